@@ -0,0 +1,106 @@
+use language_reporting::termcolor::{ColorChoice, StandardStream};
+use language_reporting::Diagnostic;
+use mltt_core::{meta, nbe, serialize, syntax};
+use mltt_elaborate::Context;
+use mltt_parse::lexer::Lexer;
+use mltt_parse::parser;
+use mltt_span::{File, FileSpan, Files};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Type check some files.
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// The files to type check.
+    #[structopt(parse(from_os_str))]
+    pub files: Vec<PathBuf>,
+    /// Write an output for each file that was checked. `core` writes the
+    /// elaborated core module alongside the file.
+    #[structopt(long = "emit")]
+    pub emit: Option<Emit>,
+    /// The format to write core modules in, either `binary` or `json`.
+    #[structopt(long = "core-format", default_value = "binary")]
+    pub core_format: serialize::Format,
+    /// The maximum number of evaluation steps to perform for each file.
+    #[structopt(long = "max-eval-steps")]
+    pub max_eval_steps: Option<u64>,
+    /// The maximum depth of nested evaluation, to avoid overflowing the stack
+    /// on pathological expressions.
+    #[structopt(long = "max-eval-depth")]
+    pub max_eval_depth: Option<u32>,
+}
+
+/// The outputs that can be written after checking.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Emit {
+    /// The elaborated core module.
+    Core,
+}
+
+impl FromStr for Emit {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Emit, String> {
+        match src {
+            "core" => Ok(Emit::Core),
+            _ => Err(format!("unknown output `{}`, expected `core`", src)),
+        }
+    }
+}
+
+/// Type check the files with the given options.
+pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let writer = StandardStream::stderr(ColorChoice::Auto);
+    let mut files = Files::new();
+    let mut context = Context::default();
+    context.set_budget(nbe::Budget::new(nbe::Limits {
+        steps: options.max_eval_steps,
+        depth: options.max_eval_depth,
+    }));
+    let mut failed = false;
+
+    for path in &options.files {
+        let contents = fs::read_to_string(path)?;
+        let file_id = files.add(path.display().to_string(), contents);
+        context.budget().reset();
+
+        match check_file(&context, &files[file_id]) {
+            Ok(module) => match options.emit {
+                None => {},
+                Some(Emit::Core) => {
+                    let bytes = serialize::encode_module(options.core_format, &module);
+                    fs::write(core_path(path, options.core_format), bytes)?;
+                },
+            },
+            Err(diagnostic) => {
+                let config = language_reporting::DefaultConfig;
+                language_reporting::emit(&mut writer.lock(), &files, &diagnostic, &config)?;
+                failed = true;
+            },
+        }
+    }
+
+    if failed {
+        Err("type checking failed".into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Parse and elaborate the given file.
+fn check_file(context: &Context, file: &File) -> Result<syntax::Module, Diagnostic<FileSpan>> {
+    let lexer = Lexer::new(&file);
+    let concrete_module = parser::parse_module(lexer)?;
+
+    mltt_elaborate::check_module(context, &mut meta::Env::new(), &concrete_module)
+}
+
+/// The path to write the core module for a source file to.
+fn core_path(path: &Path, format: serialize::Format) -> PathBuf {
+    match format {
+        serialize::Format::Binary => path.with_extension("core"),
+        serialize::Format::Json => path.with_extension("core.json"),
+    }
+}
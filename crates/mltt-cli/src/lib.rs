@@ -4,6 +4,7 @@
 
 use std::error::Error;
 
+pub mod check;
 pub mod repl;
 
 /// The MLTT command line interface.
@@ -12,7 +13,7 @@ pub mod repl;
 pub enum Options {
     /// Type check some files.
     #[structopt(name = "check")]
-    Check,
+    Check(check::Options),
     /// Runs the language server/IDE support.
     #[structopt(name = "ide")]
     Ide,
@@ -24,7 +25,7 @@ pub enum Options {
 /// Run the CLI with the given options
 pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
     match options {
-        Options::Check => Err("not yet implemented".into()),
+        Options::Check(options) => check::run(options),
         Options::Ide => Err("not yet implemented".into()),
         Options::Repl(options) => repl::run(options),
     }
@@ -1,6 +1,6 @@
 use language_reporting::termcolor::{ColorChoice, StandardStream};
 use language_reporting::Diagnostic;
-use mltt_core::{domain, meta, pretty, syntax};
+use mltt_core::{domain, meta, nbe, pretty, syntax};
 use mltt_elaborate::{Context, MetaInsertion};
 use mltt_parse::lexer::Lexer;
 use mltt_parse::parser;
@@ -21,6 +21,13 @@ pub struct Options {
     /// The prompt to display before expressions.
     #[structopt(long = "prompt", default_value = "> ")]
     pub prompt: String,
+    /// The maximum number of evaluation steps to perform for each expression.
+    #[structopt(long = "max-eval-steps")]
+    pub max_eval_steps: Option<u64>,
+    /// The maximum depth of nested evaluation, to avoid overflowing the stack
+    /// on pathological expressions.
+    #[structopt(long = "max-eval-depth")]
+    pub max_eval_depth: Option<u32>,
 }
 
 /// Run the REPL with the given options.
@@ -40,7 +47,11 @@ pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
     }
 
     let mut files = Files::new();
-    let context = Context::default();
+    let mut context = Context::default();
+    context.set_budget(nbe::Budget::new(nbe::Limits {
+        steps: options.max_eval_steps,
+        depth: options.max_eval_depth,
+    }));
     let mut metas = meta::Env::new();
 
     loop {
@@ -49,6 +60,7 @@ pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
                 let file_id = files.add("repl", line);
                 let file = &files[file_id];
                 editor.add_history_entry(file.contents());
+                context.budget().reset();
 
                 match read_eval(&context, &mut metas, file) {
                     Ok((term, ty)) => {
@@ -94,10 +106,11 @@ fn read_eval(
     file: &File,
 ) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
     let lexer = Lexer::new(&file);
-    let concrete_term = parser::parse_term(lexer)?;;
+    let concrete_term = parser::parse_term(lexer)?;
 
     let (core_term, ty) =
         mltt_elaborate::synth_term(MetaInsertion::Yes, &context, metas, &concrete_term)?;
+    context.check_postponed(metas)?;
 
     let term_span = concrete_term.span();
     let term = context.normalize_term(metas, term_span, &core_term)?;
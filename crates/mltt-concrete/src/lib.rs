@@ -26,6 +26,8 @@ pub enum Item<'file> {
     Declaration(Declaration<'file>),
     /// Term definitions.
     Definition(Definition<'file>),
+    /// Inductive data type definitions.
+    Data(Data<'file>),
 }
 
 impl<'file> Item<'file> {
@@ -33,7 +35,7 @@ impl<'file> Item<'file> {
     pub fn is_definition(&self) -> bool {
         match self {
             Item::Declaration(_) => false,
-            Item::Definition(_) => true,
+            Item::Definition(_) | Item::Data(_) => true,
         }
     }
 
@@ -41,6 +43,7 @@ impl<'file> Item<'file> {
         match self {
             Item::Declaration(declaration) => declaration.span(),
             Item::Definition(definition) => definition.span(),
+            Item::Data(data) => data.span,
         }
     }
 }
@@ -49,13 +52,17 @@ impl<'file> Item<'file> {
 #[derive(Debug, Clone, PartialEq)]
 pub struct Declaration<'file> {
     pub docs: Vec<SpannedString<'file>>,
+    /// The `auto` keyword, if unbound names in the type should become
+    /// implicit parameters.
+    pub auto: Option<SpannedString<'file>>,
     pub label: SpannedString<'file>,
     pub body_ty: Term<'file>,
 }
 
 impl<'file> Declaration<'file> {
     pub fn span(&self) -> FileSpan {
-        FileSpan::merge(self.label.span(), self.body_ty.span())
+        let start_span = self.auto.as_ref().unwrap_or(&self.label).span();
+        FileSpan::merge(start_span, self.body_ty.span())
     }
 }
 
@@ -63,6 +70,9 @@ impl<'file> Declaration<'file> {
 #[derive(Debug, Clone, PartialEq)]
 pub struct Definition<'file> {
     pub docs: Vec<SpannedString<'file>>,
+    /// The `partial` keyword, if the definition is exempt from termination
+    /// checking.
+    pub partial: Option<SpannedString<'file>>,
     pub label: SpannedString<'file>,
     pub params: Vec<IntroParam<'file>>,
     pub body_ty: Option<Term<'file>>,
@@ -71,10 +81,30 @@ pub struct Definition<'file> {
 
 impl<'file> Definition<'file> {
     pub fn span(&self) -> FileSpan {
-        FileSpan::merge(self.label.span(), self.body.span())
+        let start_span = self.partial.as_ref().unwrap_or(&self.label).span();
+        FileSpan::merge(start_span, self.body.span())
     }
 }
 
+/// Inductive data type definitions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Data<'file> {
+    pub span: FileSpan,
+    pub docs: Vec<SpannedString<'file>>,
+    pub label: SpannedString<'file>,
+    pub params: Vec<TypeParam<'file>>,
+    pub body_ty: Option<Term<'file>>,
+    pub intros: Vec<DataIntro<'file>>,
+}
+
+/// Constructors of inductive data types.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataIntro<'file> {
+    pub docs: Vec<SpannedString<'file>>,
+    pub label: SpannedString<'file>,
+    pub body_ty: Term<'file>,
+}
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub struct SpannedString<'file> {
     pub source: FileId,
@@ -140,6 +170,10 @@ pub enum Pattern<'file> {
     Var(SpannedString<'file>),
     /// Literal introductions.
     LiteralIntro(LiteralKind, SpannedString<'file>),
+    /// Constructors of data types, applied to some argument patterns.
+    DataIntro(SpannedString<'file>, Vec<Pattern<'file>>),
+    /// Tuple patterns.
+    TupleIntro(FileSpan, Vec<Pattern<'file>>),
     // TODO:
     // /// Patterns with an explicit type annotation.
     // Ann(Box<Pattern<'file>>, Box<Term<'file>>),
@@ -150,6 +184,11 @@ impl<'file> Pattern<'file> {
         match self {
             Pattern::Var(name) => name.span(),
             Pattern::LiteralIntro(_, literal) => literal.span(),
+            Pattern::DataIntro(label, arg_patterns) => match arg_patterns.last() {
+                Some(arg_pattern) => FileSpan::merge(label.span(), arg_pattern.span()),
+                None => label.span(),
+            },
+            Pattern::TupleIntro(span, _) => *span,
         }
     }
 }
@@ -160,6 +199,38 @@ impl<'file> fmt::Display for Pattern<'file> {
     }
 }
 
+/// Concrete universe levels.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Level<'file> {
+    /// Integer literals.
+    Literal(SpannedString<'file>),
+    /// Level variables.
+    Var(SpannedString<'file>),
+    /// A level shifted by some integer literal.
+    Shift(Box<Level<'file>>, SpannedString<'file>),
+    /// The maximum of two levels.
+    Max(FileSpan, Box<Level<'file>>, Box<Level<'file>>),
+    /// A parenthesized level.
+    Parens(FileSpan, Box<Level<'file>>),
+}
+
+impl<'file> Level<'file> {
+    pub fn span(&self) -> FileSpan {
+        match self {
+            Level::Literal(literal) => literal.span(),
+            Level::Var(name) => name.span(),
+            Level::Shift(level, shift) => FileSpan::merge(level.span(), shift.span()),
+            Level::Max(span, _, _) | Level::Parens(span, _) => *span,
+        }
+    }
+}
+
+impl<'file> fmt::Display for Level<'file> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_doc().group().pretty(1_000_000_000).fmt(f)
+    }
+}
+
 /// The kind of literal.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum LiteralKind {
@@ -266,10 +337,16 @@ impl<'file> fmt::Display for Arg<'file> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct RecordTypeField<'file> {
-    pub docs: Vec<SpannedString<'file>>,
-    pub label: SpannedString<'file>,
-    pub ann: Term<'file>,
+pub enum RecordTypeField<'file> {
+    /// A field with a type annotation, and an optional manifest value.
+    Explicit {
+        docs: Vec<SpannedString<'file>>,
+        label: SpannedString<'file>,
+        ann: Term<'file>,
+        value: Option<Term<'file>>,
+    },
+    /// Include the fields of another record type.
+    Include { record_ty: Term<'file> },
 }
 
 impl<'file> fmt::Display for RecordTypeField<'file> {
@@ -380,11 +457,22 @@ pub enum Term<'file> {
     RecordType(FileSpan, Vec<RecordTypeField<'file>>),
     /// Record introduction
     RecordIntro(FileSpan, Vec<RecordIntroField<'file>>),
+    /// Record update, replacing the given fields of an existing record
+    ///
+    /// This can be written as either `r with { .. }` or `record { ..r; .. }`.
+    RecordUpdate(FileSpan, Box<Term<'file>>, Vec<RecordIntroField<'file>>),
     /// Eliminate a record by projecting on it
     RecordElim(Box<Term<'file>>, SpannedString<'file>),
 
+    /// Tuple types, with optionally named components
+    ///
+    /// These are sugar for record types with the labels `_0`, `_1`, ...
+    TupleType(FileSpan, Vec<(Option<SpannedString<'file>>, Term<'file>)>),
+    /// Tuple introduction
+    TupleIntro(FileSpan, Vec<Term<'file>>),
+
     /// Universe of types
-    Universe(FileSpan, Option<SpannedString<'file>>),
+    Universe(FileSpan, Option<Level<'file>>),
 }
 
 impl<'file> Term<'file> {
@@ -413,7 +501,10 @@ impl<'file> Term<'file> {
             },
             Term::RecordType(span, _) => *span,
             Term::RecordIntro(span, _) => *span,
+            Term::RecordUpdate(span, _, _) => *span,
             Term::RecordElim(record, label) => FileSpan::merge(record.span(), label.span()),
+            Term::TupleType(span, _) => *span,
+            Term::TupleIntro(span, _) => *span,
             Term::Universe(span, _) => *span,
         }
     }
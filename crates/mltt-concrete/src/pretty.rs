@@ -1,8 +1,8 @@
 use pretty::{BoxDoc, Doc};
 
 use crate::{
-    Arg, Declaration, Definition, IntroParam, Item, Pattern, RecordIntroField, RecordTypeField,
-    SpannedString, Term, TypeParam,
+    Arg, Data, DataIntro, Declaration, Definition, IntroParam, Item, Level, Pattern,
+    RecordIntroField, RecordTypeField, SpannedString, Term, TypeParam,
 };
 
 impl<'file> Item<'file> {
@@ -11,6 +11,7 @@ impl<'file> Item<'file> {
         match self {
             Item::Declaration(declaration) => declaration.to_doc(),
             Item::Definition(definition) => definition.to_doc(),
+            Item::Data(data) => data.to_doc(),
         }
     }
 }
@@ -24,8 +25,13 @@ impl<'file> Declaration<'file> {
                 .map(|doc| doc.to_doc().append(Doc::newline())),
         );
 
+        let auto = self.auto.as_ref().map_or(Doc::nil(), |auto| {
+            Doc::nil().append(auto.to_doc()).append(Doc::space())
+        });
+
         Doc::nil()
             .append(docs)
+            .append(auto)
             .append(self.label.to_doc())
             .append(Doc::space())
             .append(":")
@@ -52,8 +58,13 @@ impl<'file> Definition<'file> {
                 .append(Doc::space())
         });
 
+        let partial = self.partial.as_ref().map_or(Doc::nil(), |partial| {
+            Doc::nil().append(partial.to_doc()).append(Doc::space())
+        });
+
         Doc::nil()
             .append(docs)
+            .append(partial)
             .append(self.label.to_doc())
             .append(Doc::space())
             .append(params)
@@ -66,6 +77,67 @@ impl<'file> Definition<'file> {
     }
 }
 
+impl<'file> Data<'file> {
+    /// Convert the data type definition into a pretty-printable document.
+    pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
+        let docs = Doc::concat(
+            self.docs
+                .iter()
+                .map(|doc| doc.to_doc().append(Doc::newline())),
+        );
+        let params = Doc::concat(
+            self.params
+                .iter()
+                .map(|param| Doc::space().append(param.to_doc())),
+        );
+        let body_ty = self.body_ty.as_ref().map_or(Doc::nil(), |body_ty| {
+            Doc::nil()
+                .append(Doc::space())
+                .append(":")
+                .append(Doc::space())
+                .append(body_ty.to_doc())
+        });
+        let intros = Doc::concat(
+            self.intros
+                .iter()
+                .map(|intro| Doc::newline().append(intro.to_doc())),
+        );
+
+        Doc::nil()
+            .append(docs)
+            .append("data")
+            .append(Doc::space())
+            .append(self.label.to_doc())
+            .append(params)
+            .append(body_ty)
+            .append(Doc::space())
+            .append("{")
+            .append(intros.nest(4))
+            .append(Doc::newline())
+            .append("};")
+    }
+}
+
+impl<'file> DataIntro<'file> {
+    /// Convert the constructor into a pretty-printable document.
+    pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
+        let docs = Doc::concat(
+            self.docs
+                .iter()
+                .map(|doc| doc.to_doc().append(Doc::newline())),
+        );
+
+        Doc::nil()
+            .append(docs)
+            .append(self.label.to_doc())
+            .append(Doc::space())
+            .append(":")
+            .append(Doc::space())
+            .append(self.body_ty.to_doc())
+            .append(";")
+    }
+}
+
 impl<'file> SpannedString<'file> {
     /// Convert the string into a pretty-printable document.
     pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
@@ -79,6 +151,41 @@ impl<'file> Pattern<'file> {
         match self {
             Pattern::Var(name) => name.to_doc(),
             Pattern::LiteralIntro(_, literal) => literal.to_doc(),
+            Pattern::DataIntro(label, arg_patterns) => label.to_doc().append(Doc::concat(
+                arg_patterns
+                    .iter()
+                    .map(|arg_pattern| Doc::space().append(arg_pattern.to_doc())),
+            )),
+            Pattern::TupleIntro(_, patterns) => Doc::nil()
+                .append("(")
+                .append(Doc::intersperse(
+                    patterns.iter().map(Pattern::to_doc),
+                    Doc::text(",").append(Doc::space()),
+                ))
+                .append(")"),
+        }
+    }
+}
+
+impl<'file> Level<'file> {
+    /// Convert the level into a pretty-printable document.
+    pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
+        match self {
+            Level::Literal(literal) => literal.to_doc(),
+            Level::Var(name) => name.to_doc(),
+            Level::Shift(level, shift) => Doc::nil()
+                .append(level.to_doc())
+                .append(Doc::space())
+                .append("+")
+                .append(Doc::space())
+                .append(shift.to_doc()),
+            Level::Max(_, lhs, rhs) => Doc::nil()
+                .append("max")
+                .append(Doc::space())
+                .append(lhs.to_doc())
+                .append(Doc::space())
+                .append(rhs.to_doc()),
+            Level::Parens(_, level) => Doc::text("(").append(level.to_doc()).append(")"),
         }
     }
 }
@@ -195,13 +302,27 @@ impl<'file> Arg<'file> {
 impl<'file> RecordTypeField<'file> {
     /// Convert the field into a pretty-printable document.
     pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
-        Doc::nil()
-            .append(self.label.to_doc())
-            .append(Doc::space())
-            .append(":")
-            .append(Doc::space())
-            .append(self.ann.to_doc())
-            .append(";")
+        match self {
+            RecordTypeField::Explicit {
+                label, ann, value, ..
+            } => Doc::nil()
+                .append(label.to_doc())
+                .append(Doc::space())
+                .append(":")
+                .append(Doc::space())
+                .append(ann.to_doc())
+                .append(match value {
+                    None => Doc::nil(),
+                    Some(value) => Doc::space()
+                        .append("=")
+                        .append(Doc::space())
+                        .append(value.to_doc()),
+                })
+                .append(";"),
+            RecordTypeField::Include { record_ty } => {
+                Doc::text("..").append(record_ty.to_doc()).append(";")
+            },
+        }
     }
 }
 
@@ -376,7 +497,48 @@ impl<'file> Term<'file> {
                     .append(Doc::newline())
                     .append("}")
             },
+            Term::RecordUpdate(_, record, intro_fields) if intro_fields.is_empty() => {
+                record.to_doc().append(" with {}")
+            },
+            Term::RecordUpdate(_, record, intro_fields) => {
+                let intro_fields = Doc::intersperse(
+                    intro_fields.iter().map(RecordIntroField::to_doc),
+                    Doc::newline(),
+                );
+
+                Doc::nil()
+                    .append(record.to_doc())
+                    .append(Doc::space())
+                    .append("with")
+                    .append(Doc::space())
+                    .append("{")
+                    .append(Doc::newline())
+                    .append(intro_fields.nest(4))
+                    .append(Doc::newline())
+                    .append("}")
+            },
             Term::RecordElim(record, label) => record.to_doc().append(".").append(label.to_doc()),
+            Term::TupleType(_, components) => Doc::intersperse(
+                components.iter().map(|(name, ty)| match name {
+                    None => ty.to_doc(),
+                    Some(name) => Doc::nil()
+                        .append("(")
+                        .append(name.to_doc())
+                        .append(Doc::space())
+                        .append(":")
+                        .append(Doc::space())
+                        .append(ty.to_doc())
+                        .append(")"),
+                }),
+                Doc::text(" * "),
+            ),
+            Term::TupleIntro(_, elements) => Doc::nil()
+                .append("(")
+                .append(Doc::intersperse(
+                    elements.iter().map(Term::to_doc),
+                    Doc::text(",").append(Doc::space()),
+                ))
+                .append(")"),
             Term::Universe(_, None) => Doc::text("Type"),
             Term::Universe(_, Some(level)) => Doc::text("Type^").append(level.to_doc()),
         }
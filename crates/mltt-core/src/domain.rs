@@ -1,10 +1,14 @@
 //! The semantic domain.
 
+use std::cell::RefCell;
+use std::mem;
 use std::rc::Rc;
 
 use super::literal::{LiteralIntro, LiteralType};
-use crate::syntax::Term;
-use crate::{meta, prim, var, AppMode, DocString, Label, UniverseLevel};
+use crate::intern::Symbol;
+use crate::nbe::EvalError;
+use crate::syntax::{Data, Guard, Term};
+use crate::{meta, prim, trim, var, AppMode, DocString, Label, UniverseLevel};
 
 /// Terms that are in _weak head normal form_.
 ///
@@ -26,19 +30,48 @@ pub enum Value {
     LiteralIntro(LiteralIntro),
 
     /// Dependent function types
-    FunType(AppMode, Option<String>, Rc<Type>, AppClosure),
+    FunType(AppMode, Option<Symbol>, Rc<Type>, AppClosure),
     /// Introduce a function
-    FunIntro(AppMode, Option<String>, AppClosure),
+    FunIntro(AppMode, Option<Symbol>, AppClosure),
 
     /// Dependent record type extension
-    RecordTypeExtend(DocString, Label, Option<String>, Rc<Type>, AppClosure),
+    ///
+    /// Fields may have a manifest value, which is definitionally known when
+    /// the field is projected from a record of this type.
+    RecordTypeExtend(
+        DocString,
+        Label,
+        Option<Symbol>,
+        Rc<Type>,
+        Option<Rc<Value>>,
+        AppClosure,
+    ),
     /// Empty record type
     RecordTypeEmpty,
     /// Introduce a record
     RecordIntro(Vec<(Label, Rc<Value>)>),
 
+    /// Inductive data types, applied to some arguments
+    DataType(DataClosure, Vec<(AppMode, Rc<Value>)>),
+    /// Introduce an inductive data type (constructors), applied to some
+    /// arguments
+    ///
+    /// The arguments begin with the parameters of the data type, followed by
+    /// the fields of the constructor.
+    DataIntro(DataClosure, Label, Vec<(AppMode, Rc<Value>)>),
+
+    /// Propositional equality types
+    EqType(Rc<Type>, Rc<Value>, Rc<Value>),
+    /// Introduce an equality (reflexivity)
+    EqIntro,
+
+    /// The type of universe levels
+    LevelType,
+    /// Universe levels that are not neutral
+    Level(Level),
+
     /// Universe of types
-    Universe(UniverseLevel),
+    Universe(Level),
 }
 
 impl Value {
@@ -47,6 +80,16 @@ impl Value {
         Value::Neutral(Head::Var(level.into()), Vec::new())
     }
 
+    /// Construct a global definition, glued to the value that it unfolds to.
+    pub fn global(level: impl Into<var::Level>, value: Rc<Value>) -> Value {
+        Value::Neutral(Head::Global(level.into(), value), Vec::new())
+    }
+
+    /// Construct a thunk, to be evaluated once it is forced.
+    pub fn thunk(thunk: Thunk) -> Value {
+        Value::Neutral(Head::Thunk(thunk), Vec::new())
+    }
+
     /// Construct a metavariable.
     pub fn meta(index: impl Into<meta::Index>) -> Value {
         Value::Neutral(Head::Meta(index.into()), Vec::new())
@@ -69,7 +112,171 @@ impl Value {
 
     /// Construct a universe.
     pub fn universe(level: impl Into<UniverseLevel>) -> Value {
-        Value::Universe(level.into())
+        Value::Universe(Level::constant(level))
+    }
+}
+
+/// Universe levels, in normal form.
+///
+/// Finite levels are represented as the maximum of a constant level and some
+/// neutral levels, each of which is shifted up by a constant amount. For
+/// example, the level `max (l + 1) 3` is represented by the constant `3` and
+/// the neutral level `l`, shifted by `1`.
+#[derive(Debug, Clone)]
+pub enum Level {
+    /// A finite level, made up of its constant part, and its neutral parts
+    /// along with the amounts that they are shifted by.
+    Finite(UniverseLevel, Vec<(Rc<Value>, u16)>),
+    /// The level of types that quantify over universe levels. This is larger
+    /// than every finite level, and can't be shifted any further.
+    Omega,
+}
+
+impl Level {
+    /// Construct a constant level.
+    pub fn constant(level: impl Into<UniverseLevel>) -> Level {
+        Level::Finite(level.into(), Vec::new())
+    }
+
+    /// Construct a level that is larger than every other level.
+    pub fn omega() -> Level {
+        Level::Omega
+    }
+
+    /// Construct a level from a neutral value.
+    pub fn neutral(value: Rc<Value>) -> Level {
+        Level::Finite(UniverseLevel(0), vec![(value, 0)])
+    }
+
+    /// The neutral parts of the level, along with the amounts that they are
+    /// shifted by.
+    pub fn neutrals(&self) -> &[(Rc<Value>, u16)] {
+        match self {
+            Level::Finite(_, neutrals) => neutrals,
+            Level::Omega => &[],
+        }
+    }
+
+    /// Return the level as a constant, if it is finite and has no neutral
+    /// parts.
+    pub fn as_constant(&self) -> Option<UniverseLevel> {
+        match self {
+            Level::Finite(constant, neutrals) if neutrals.is_empty() => Some(*constant),
+            Level::Finite(_, _) | Level::Omega => None,
+        }
+    }
+
+    /// Shift the level up by the given amount, returning `None` if the maximum
+    /// universe level has been reached, or if the level is omega.
+    pub fn shift(&self, shift: u16) -> Option<Level> {
+        match self {
+            Level::Finite(constant, neutrals) => {
+                let neutrals = neutrals
+                    .iter()
+                    .map(|(value, offset)| Some((value.clone(), offset.checked_add(shift)?)))
+                    .collect::<Option<_>>()?;
+
+                Some(Level::normalize(constant.shift(shift)?, neutrals))
+            },
+            Level::Omega => None,
+        }
+    }
+
+    /// Shift the level down by the given amount, returning `None` if this
+    /// would result in a negative level, or if the level is omega.
+    pub fn unshift(&self, shift: u16) -> Option<Level> {
+        let (constant, neutrals) = match self {
+            Level::Finite(constant, neutrals) => (constant, neutrals),
+            Level::Omega => return None,
+        };
+        let constant = match constant.0.checked_sub(shift) {
+            Some(constant) => UniverseLevel(constant),
+            // The constant can be dropped if a neutral level is already larger
+            None if !neutrals.is_empty() => UniverseLevel(0),
+            None => return None,
+        };
+        let neutrals = neutrals
+            .iter()
+            .map(|(value, offset)| Some((value.clone(), offset.checked_sub(shift)?)))
+            .collect::<Option<_>>()?;
+
+        Some(Level::normalize(constant, neutrals))
+    }
+
+    /// The maximum of two levels.
+    pub fn max(&self, other: &Level) -> Level {
+        match (self, other) {
+            (Level::Finite(constant1, neutrals1), Level::Finite(constant2, neutrals2)) => {
+                let constant = std::cmp::max(*constant1, *constant2);
+                let neutrals = Iterator::chain(neutrals1.iter(), neutrals2.iter())
+                    .cloned()
+                    .collect();
+
+                Level::normalize(constant, neutrals)
+            },
+            (Level::Omega, _) | (_, Level::Omega) => Level::Omega,
+        }
+    }
+
+    /// Check if this level is less than or equal to another level.
+    ///
+    /// This is incomplete in the presence of neutral levels, but will never
+    /// return `true` if there is a substitution for the neutral levels that
+    /// would make this level larger than the other level.
+    pub fn is_le(&self, other: &Level) -> bool {
+        let (constant, neutrals, other_constant, other_neutrals) = match (self, other) {
+            (_, Level::Omega) => return true,
+            (Level::Omega, Level::Finite(_, _)) => return false,
+            (Level::Finite(c1, n1), Level::Finite(c2, n2)) => (c1, n1, c2, n2),
+        };
+
+        // Neutral levels can't be smaller than the amount they are shifted by
+        let other_offsets = other_neutrals.iter().map(|(_, offset)| *offset);
+        let other_bound = std::cmp::max(other_constant.0, other_offsets.max().unwrap_or(0));
+
+        constant.0 <= other_bound
+            && neutrals.iter().all(|(value, offset)| {
+                other_neutrals.iter().any(|(other_value, other_offset)| {
+                    value == other_value && offset <= other_offset
+                })
+            })
+    }
+
+    /// Merge duplicate neutral levels, and drop parts of the level that are
+    /// already covered by the other parts.
+    fn normalize(constant: UniverseLevel, neutrals: Vec<(Rc<Value>, u16)>) -> Level {
+        let mut merged = Vec::<(Rc<Value>, u16)>::with_capacity(neutrals.len());
+        for (value, offset) in neutrals {
+            match merged
+                .iter_mut()
+                .find(|(other_value, _)| *other_value == value)
+            {
+                Some((_, other_offset)) => *other_offset = std::cmp::max(*other_offset, offset),
+                None => merged.push((value, offset)),
+            }
+        }
+
+        let constant = match merged.iter().map(|(_, offset)| *offset).max() {
+            Some(offset) if constant.0 <= offset => UniverseLevel(0),
+            Some(_) | None => constant,
+        };
+
+        Level::Finite(constant, merged)
+    }
+}
+
+impl PartialEq for Level {
+    fn eq(&self, other: &Level) -> bool {
+        match (self, other) {
+            (Level::Finite(constant1, neutrals1), Level::Finite(constant2, neutrals2)) => {
+                // Neutral levels are compared without regard to their order
+                constant1 == constant2
+                    && neutrals1.len() == neutrals2.len()
+                    && neutrals1.iter().all(|neutral| neutrals2.contains(neutral))
+            },
+            (Level::Omega, Level::Omega) => true,
+            (_, _) => false,
+        }
     }
 }
 
@@ -78,16 +285,52 @@ impl Value {
 pub type Type = Value;
 
 /// The head of a neutral term.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Head {
     /// Variables
     Var(var::Level),
+    /// Global definitions
+    ///
+    /// These are _glued_ to the value of the definition, allowing us to keep
+    /// them folded when pretty printing, while still being able to unfold
+    /// them on demand, for example when forcing a value. The spine is only
+    /// applied to the unfolded value once it is needed.
+    Global(var::Level, Rc<Value>),
+    /// Suspended computations
+    ///
+    /// These are used for the definitions of let bindings and the fields of
+    /// records, so that we only evaluate them if they are needed. Like global
+    /// definitions, the spine is only applied once the thunk is forced.
+    Thunk(Thunk),
+    /// Recursive definitions
+    ///
+    /// These are unfolded lazily, once the guard of the selected definition
+    /// allows it, to avoid looping forever on open terms.
+    Fix(usize, FixClosure),
     /// Metavariables
     Meta(meta::Index),
     /// Primitives
     Prim(prim::Name),
 }
 
+impl PartialEq for Head {
+    fn eq(&self, other: &Head) -> bool {
+        match (self, other) {
+            (Head::Var(level1), Head::Var(level2)) => level1 == level2,
+            // Global definitions are uniquely identified by their level, so
+            // we can avoid comparing the unfolded values
+            (Head::Global(level1, _), Head::Global(level2, _)) => level1 == level2,
+            (Head::Thunk(thunk1), Head::Thunk(thunk2)) => thunk1 == thunk2,
+            (Head::Fix(index1, closure1), Head::Fix(index2, closure2)) => {
+                index1 == index2 && closure1 == closure2
+            },
+            (Head::Meta(index1), Head::Meta(index2)) => index1 == index2,
+            (Head::Prim(name1), Head::Prim(name2)) => name1 == name2,
+            (_, _) => false,
+        }
+    }
+}
+
 /// A spine of eliminators.
 pub type Spine = Vec<Elim>;
 
@@ -100,6 +343,11 @@ pub enum Elim {
     Fun(AppMode, Rc<Value>),
     /// Record elimination (projection).
     Record(Label),
+    /// Data elimination (case split).
+    Data(CaseClosure),
+    /// Equality elimination (the J rule), with a motive and the value to
+    /// return when the proof is `refl`.
+    Eq(Rc<Value>, Rc<Value>),
 }
 
 /// A closure that binds a single variable.
@@ -115,14 +363,113 @@ pub struct AppClosure {
     pub term: Rc<Term>,
     /// The environment in which we'll run the term in.
     ///
-    /// At the moment this captures the _entire_ environment - would it be
-    /// better to only capture what the `term` needs?
+    /// This only captures the entries that the `term` refers to.
     pub values: var::Env<Rc<Value>>,
 }
 
 impl AppClosure {
+    /// Create a closure, trimming the environment down to the entries that
+    /// the term refers to.
     pub fn new(term: Rc<Term>, values: var::Env<Rc<Value>>) -> AppClosure {
-        AppClosure { term, values }
+        match trim::trim_app(&term, &values) {
+            Some((term, values)) => AppClosure { term, values },
+            None => AppClosure { term, values },
+        }
+    }
+}
+
+/// A suspended computation, which is evaluated the first time that it is
+/// forced. The resulting value is remembered, and shared between all of the
+/// clones of the thunk.
+#[derive(Debug, Clone)]
+pub struct Thunk(Rc<RefCell<ThunkState>>);
+
+/// The state of a thunk.
+#[derive(Debug)]
+enum ThunkState {
+    /// A term, waiting to be evaluated in an environment.
+    Suspended(Rc<Term>, var::Env<Rc<Value>>),
+    /// The term is currently being evaluated.
+    Forcing,
+    /// The value of the term, once it has been evaluated.
+    Forced(Rc<Value>),
+}
+
+impl Thunk {
+    /// Suspend the evaluation of a term, trimming the environment down to the
+    /// entries that the term refers to.
+    pub fn new(term: Rc<Term>, values: var::Env<Rc<Value>>) -> Thunk {
+        let (term, values) = trim::trim_term(&term, &values).unwrap_or((term, values));
+        Thunk(Rc::new(RefCell::new(ThunkState::Suspended(term, values))))
+    }
+
+    /// Returns `true` if the thunk has already been forced.
+    pub fn is_forced(&self) -> bool {
+        match *self.0.borrow() {
+            ThunkState::Suspended(_, _) | ThunkState::Forcing => false,
+            ThunkState::Forced(_) => true,
+        }
+    }
+
+    /// Force the thunk, using `eval` to evaluate the term if this has not
+    /// already been done. The environment is released once the value is
+    /// known.
+    ///
+    /// The thunk is marked as being forced while `eval` runs, so forcing it
+    /// again from inside `eval` returns an error instead of looping. If
+    /// `eval` fails, the thunk is suspended again.
+    pub fn force(
+        &self,
+        eval: impl FnOnce(&var::Env<Rc<Value>>, &Rc<Term>) -> Result<Rc<Value>, EvalError>,
+    ) -> Result<Rc<Value>, EvalError> {
+        let state = mem::replace(&mut *self.0.borrow_mut(), ThunkState::Forcing);
+        let (term, values) = match state {
+            ThunkState::Suspended(term, values) => (term, values),
+            ThunkState::Forcing => return Err(EvalError::ForcingThunk),
+            ThunkState::Forced(value) => {
+                *self.0.borrow_mut() = ThunkState::Forced(value.clone());
+                return Ok(value);
+            },
+        };
+
+        match eval(&values, &term) {
+            Ok(value) => {
+                *self.0.borrow_mut() = ThunkState::Forced(value.clone());
+                Ok(value)
+            },
+            Err(error) => {
+                *self.0.borrow_mut() = ThunkState::Suspended(term, values);
+                Err(error)
+            },
+        }
+    }
+}
+
+impl PartialEq for Thunk {
+    fn eq(&self, other: &Thunk) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// A closure that stores a group of mutually recursive definitions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixClosure {
+    /// The definitions in the group.
+    pub defns: Rc<[(Label, Guard, Rc<Term>, Rc<Term>)]>,
+    /// The environment in which we'll run the definitions in.
+    ///
+    /// This captures the entire environment. Fix closures are only created
+    /// when evaluating a recursive group, rather than each time that one of
+    /// its definitions is unfolded, so trimming them would save little.
+    pub values: var::Env<Rc<Value>>,
+}
+
+impl FixClosure {
+    pub fn new(
+        defns: Rc<[(Label, Guard, Rc<Term>, Rc<Term>)]>,
+        values: var::Env<Rc<Value>>,
+    ) -> FixClosure {
+        FixClosure { defns, values }
     }
 }
 
@@ -135,17 +482,21 @@ pub struct LiteralClosure {
     pub default: Rc<Term>,
     /// The environment in which we'll run the clauses in.
     ///
-    /// At the moment this captures the _entire_ environment - would it be
-    /// better to only capture what the `term` needs?
+    /// This only captures the entries that the clauses refer to.
     pub values: var::Env<Rc<Value>>,
 }
 
 impl LiteralClosure {
+    /// Create a closure, trimming the environment down to the entries that
+    /// the clauses refer to.
     pub fn new(
         clauses: Rc<[(LiteralIntro, Rc<Term>)]>,
         default: Rc<Term>,
         values: var::Env<Rc<Value>>,
     ) -> LiteralClosure {
+        let (clauses, default, values) =
+            trim::trim_literal(&clauses, &default, &values).unwrap_or((clauses, default, values));
+
         LiteralClosure {
             clauses,
             default,
@@ -153,3 +504,48 @@ impl LiteralClosure {
         }
     }
 }
+
+/// A closure that stores an inductive data type declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataClosure {
+    /// The data type declaration.
+    pub data: Rc<Data>,
+    /// The environment in which we'll run the declaration in.
+    ///
+    /// This captures the entire environment. Data closures are created once
+    /// for each evaluation of a data type declaration, and their parameters
+    /// and constructors are under different numbers of binders, so trimming
+    /// them is not worth the extra bookkeeping.
+    pub values: var::Env<Rc<Value>>,
+}
+
+impl DataClosure {
+    pub fn new(data: Rc<Data>, values: var::Env<Rc<Value>>) -> DataClosure {
+        DataClosure { data, values }
+    }
+}
+
+/// A closure that stores a clause for each constructor of a data type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseClosure {
+    /// The clauses.
+    pub clauses: Rc<[(Label, Vec<Option<Symbol>>, Rc<Term>)]>,
+    /// The environment in which we'll run the clauses in.
+    ///
+    /// This only captures the entries that the clauses refer to.
+    pub values: var::Env<Rc<Value>>,
+}
+
+impl CaseClosure {
+    /// Create a closure, trimming the environment down to the entries that
+    /// the clauses refer to.
+    pub fn new(
+        clauses: Rc<[(Label, Vec<Option<Symbol>>, Rc<Term>)]>,
+        values: var::Env<Rc<Value>>,
+    ) -> CaseClosure {
+        match trim::trim_case(&clauses, &values) {
+            Some((clauses, values)) => CaseClosure { clauses, values },
+            None => CaseClosure { clauses, values },
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Interned strings.
+//!
+//! Labels, primitive names, and name hints are repeated many times throughout
+//! the core syntax and the domain, so we store each distinct string once, and
+//! share it between all of the places it is used. This also lets us compare
+//! them by pointer, rather than by their contents.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::Rc;
+
+thread_local! {
+    /// The strings that have been interned on the current thread.
+    static SYMBOLS: RefCell<HashSet<Rc<str>>> = RefCell::new(HashSet::new());
+}
+
+/// An interned string.
+///
+/// Symbols are cheap to clone, and are compared for equality and hashed by
+/// pointer. They are ordered by their contents, so that sorting them is
+/// independent of the order in which they were interned.
+#[derive(Clone)]
+pub struct Symbol(Rc<str>);
+
+impl Symbol {
+    /// Intern a string, returning the existing symbol if it was already
+    /// interned.
+    pub fn intern(src: &str) -> Symbol {
+        SYMBOLS.with(|symbols| {
+            let mut symbols = symbols.borrow_mut();
+            match symbols.get(src) {
+                Some(symbol) => Symbol(symbol.clone()),
+                None => {
+                    let symbol = Rc::<str>::from(src);
+                    symbols.insert(symbol.clone());
+                    Symbol(symbol)
+                },
+            }
+        })
+    }
+
+    /// The contents of the symbol.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> From<&'a str> for Symbol {
+    fn from(src: &'a str) -> Symbol {
+        Symbol::intern(src)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(src: String) -> Symbol {
+        Symbol::intern(&src)
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Symbol) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for Symbol {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.0.as_ptr() as usize).hash(state);
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Symbol) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Symbol) -> Ordering {
+        if self == other {
+            Ordering::Equal
+        } else {
+            self.as_str().cmp(other.as_str())
+        }
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn intern_shares_strings() {
+        let symbol1 = Symbol::intern("foo");
+        let symbol2 = Symbol::from("foo".to_owned());
+        let symbol3 = Symbol::intern("bar");
+
+        assert!(Rc::ptr_eq(&symbol1.0, &symbol2.0));
+        assert_eq!(symbol1, symbol2);
+        assert_ne!(symbol1, symbol3);
+        assert_eq!(symbol1, "foo");
+    }
+
+    #[test]
+    fn order_by_contents() {
+        let mut symbols = vec![
+            Symbol::intern("b"),
+            Symbol::intern("c"),
+            Symbol::intern("a"),
+        ];
+        symbols.sort();
+
+        assert_eq!(
+            symbols,
+            vec![
+                Symbol::intern("a"),
+                Symbol::intern("b"),
+                Symbol::intern("c")
+            ]
+        );
+    }
+}
@@ -6,6 +6,9 @@ use std::fmt;
 use std::rc::Rc;
 use std::u16;
 
+use crate::intern::Symbol;
+
+pub mod intern;
 pub mod meta;
 pub mod var;
 
@@ -13,18 +16,49 @@ pub mod domain;
 pub mod literal;
 pub mod pretty;
 pub mod prim;
+pub mod serialize;
 pub mod syntax;
 
 pub mod nbe;
+pub mod optimize;
 pub mod validate;
 
+pub mod trim;
+
 /// Reference counted documentation string.
 pub type DocString = Rc<str>;
 
 /// A label. These are treated as significant when comparing terms for alpha
 /// equivalence.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Label(pub String);
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Label(pub Symbol);
+
+impl Label {
+    /// Construct a label, interning its name.
+    pub fn new(name: &str) -> Label {
+        Label(Symbol::intern(name))
+    }
+
+    /// The name of the label.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    /// The label of the element at the given position in a tuple.
+    pub fn tuple(index: usize) -> Label {
+        Label::new(&format!("_{}", index))
+    }
+
+    /// Returns `true` if the labels are those of a tuple, ie. `_0`, `_1`, ...
+    ///
+    /// Tuples must have at least two elements.
+    pub fn is_tuple<'a>(labels: impl ExactSizeIterator<Item = &'a Label>) -> bool {
+        labels.len() >= 2
+            && labels
+                .enumerate()
+                .all(|(index, label)| *label == Label::tuple(index))
+    }
+}
 
 impl fmt::Display for Label {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -33,7 +67,7 @@ impl fmt::Display for Label {
 }
 
 /// The application mode of a function.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum AppMode {
     /// Explicit application mode.
     Explicit,
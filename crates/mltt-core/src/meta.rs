@@ -2,7 +2,7 @@ use mltt_span::FileSpan;
 use std::fmt;
 use std::rc::Rc;
 
-use crate::domain;
+use crate::{domain, syntax, var};
 
 /// Metavariable index.
 ///
@@ -31,11 +31,74 @@ pub enum Solution {
     Solved(Rc<domain::Value>),
 }
 
+/// A unification problem that was postponed, because it was blocked on an
+/// unsolved metavariable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// The location where the problem was encountered.
+    pub span: FileSpan,
+    /// Names of the entries in the context (used for pretty printing).
+    pub names: var::Env<String>,
+    /// Values of the entries in the context.
+    pub values: var::Env<Rc<domain::Value>>,
+    /// Types of the entries in the context.
+    pub tys: var::Env<Rc<domain::Type>>,
+    /// The value that was expected to be a subtype of `value2`.
+    pub value1: Rc<domain::Value>,
+    /// The value that was expected to be a supertype of `value1`.
+    pub value2: Rc<domain::Value>,
+}
+
+/// Identifies when a unification problem was postponed, allowing the problems
+/// that were postponed after some point during elaboration to be found.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ProblemId(usize);
+
+/// A metavariable that stands for a type in a universe whose level is not yet
+/// known. Once the metavariable is solved, the universe of its solution will
+/// be unified with the expected universe, allowing the level to be inferred.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniverseProblem {
+    /// The location where the metavariable was created.
+    pub span: FileSpan,
+    /// Names of the entries in the context (used for pretty printing).
+    pub names: var::Env<String>,
+    /// Values of the entries in the context.
+    pub values: var::Env<Rc<domain::Value>>,
+    /// Types of the entries in the context.
+    pub tys: var::Env<Rc<domain::Type>>,
+    /// The metavariable, applied to the variables bound in the context.
+    pub term: Rc<syntax::Term>,
+    /// The universe that the solution is expected to be in.
+    pub universe: Rc<domain::Type>,
+}
+
+/// A point in the history of a metavariable environment that it can be rolled
+/// back to, undoing the metavariables that were created or solved since.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The number of metavariables in the environment.
+    solution_count: usize,
+    /// The length of the trail of solved metavariables.
+    trail_len: usize,
+}
+
 /// An environment of solved and unsolved metavariables.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Env {
-    /// The solutions.
-    solutions: Vec<(FileSpan, Solution, Rc<domain::Type>)>,
+    /// The solutions, along with the number of parameters that each
+    /// metavariable was created with, and its type. The type is in the scope
+    /// of the parameters, with the first parameter at level `0`.
+    solutions: Vec<(FileSpan, Solution, var::Size, Rc<domain::Type>)>,
+    /// The metavariables in the order that they were solved, allowing the
+    /// solutions to be undone when rolling back to a checkpoint.
+    trail: Vec<Index>,
+    /// Unification problems that are waiting on metavariables to be solved.
+    postponed: Vec<(ProblemId, Problem)>,
+    /// The id to give to the next problem that is postponed.
+    next_problem_id: ProblemId,
+    /// Metavariables that are waiting to have their universe levels checked.
+    universe_problems: Vec<UniverseProblem>,
 }
 
 impl Env {
@@ -43,27 +106,121 @@ impl Env {
     pub fn new() -> Env {
         Env {
             solutions: Vec::new(),
+            trail: Vec::new(),
+            postponed: Vec::new(),
+            next_problem_id: ProblemId::default(),
+            universe_problems: Vec::new(),
         }
     }
 
     /// Lookup a the solution for a metavariable in the environment.
-    pub fn lookup_solution(&self, index: Index) -> Option<&(FileSpan, Solution, Rc<domain::Type>)> {
+    pub fn lookup_solution(
+        &self,
+        index: Index,
+    ) -> Option<&(FileSpan, Solution, var::Size, Rc<domain::Type>)> {
         self.solutions.get(index.0 as usize)
     }
 
     /// Add a solution to the given metavariable index.
     pub fn add_solved(&mut self, index: Index, solved: Rc<domain::Value>) {
         match self.solutions.get_mut(index.0 as usize) {
-            Some((_, solution @ Solution::Unsolved, _)) => *solution = Solution::Solved(solved),
-            Some((_, Solution::Solved(_), _)) => unimplemented!("updating solved solution"),
+            Some((_, solution @ Solution::Unsolved, _, _)) => {
+                *solution = Solution::Solved(solved);
+                self.trail.push(index);
+            },
+            Some((_, Solution::Solved(_), _, _)) => unimplemented!("updating solved solution"),
             None => unimplemented!("no corresponding solution"),
         }
     }
 
-    /// Create a fresh metavariable index.
-    pub fn add_unsolved(&mut self, span: FileSpan, ty: Rc<domain::Type>) -> Index {
+    /// Create a fresh metavariable index, for a metavariable that will be
+    /// applied to `arity` parameters. The type should be in the scope of those
+    /// parameters.
+    pub fn add_unsolved(
+        &mut self,
+        span: FileSpan,
+        arity: var::Size,
+        ty: Rc<domain::Type>,
+    ) -> Index {
         let index = Index(self.solutions.len() as u32);
-        self.solutions.push((span, Solution::Unsolved, ty));
+        self.solutions.push((span, Solution::Unsolved, arity, ty));
         index
     }
+
+    /// Record the current state of the metavariables, so that it can be
+    /// returned to with `rollback`.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            solution_count: self.solutions.len(),
+            trail_len: self.trail.len(),
+        }
+    }
+
+    /// Undo the solutions to metavariables since the checkpoint was taken, and
+    /// remove the metavariables that were created since then.
+    ///
+    /// Postponed problems and universe problems are left untouched.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        for index in self.trail.drain(checkpoint.trail_len..) {
+            if let Some((_, solution, _, _)) = self.solutions.get_mut(index.0 as usize) {
+                *solution = Solution::Unsolved;
+            }
+        }
+        self.solutions.truncate(checkpoint.solution_count);
+    }
+
+    /// Unification problems that are waiting on metavariables to be solved.
+    pub fn postponed(&self) -> impl ExactSizeIterator<Item = &Problem> {
+        self.postponed.iter().map(|(_, problem)| problem)
+    }
+
+    /// Unification problems that were postponed after the problem with the
+    /// given id, including that problem, that are still waiting on
+    /// metavariables to be solved.
+    pub fn postponed_since(&self, since: ProblemId) -> impl Iterator<Item = &Problem> {
+        self.postponed
+            .iter()
+            .filter(move |(id, _)| *id >= since)
+            .map(|(_, problem)| problem)
+    }
+
+    /// The id that will be given to the next problem that is postponed.
+    pub fn next_problem_id(&self) -> ProblemId {
+        self.next_problem_id
+    }
+
+    /// Postpone a unification problem until more metavariables are solved.
+    pub fn add_postponed(&mut self, problem: Problem) {
+        let id = self.next_problem_id;
+        self.next_problem_id = ProblemId(id.0 + 1);
+        self.postponed.push((id, problem));
+    }
+
+    /// Take the postponed unification problems out of the environment, so that
+    /// they can be retried.
+    pub fn take_postponed(&mut self) -> Vec<(ProblemId, Problem)> {
+        std::mem::take(&mut self.postponed)
+    }
+
+    /// Put back a problem that was taken out of the environment, but that is
+    /// still waiting on metavariables to be solved.
+    pub fn restore_postponed(&mut self, id: ProblemId, problem: Problem) {
+        self.postponed.push((id, problem));
+    }
+
+    /// Metavariables that are waiting to have their universe levels checked.
+    pub fn universe_problems(&self) -> &[UniverseProblem] {
+        &self.universe_problems
+    }
+
+    /// Check the universe level of a metavariable once it has been solved.
+    pub fn add_universe_problem(&mut self, problem: UniverseProblem) {
+        self.universe_problems.push(problem);
+    }
+
+    /// Take the universe problems out of the environment, so that they can be
+    /// checked.
+    pub fn take_universe_problems(&mut self) -> Vec<UniverseProblem> {
+        std::mem::take(&mut self.universe_problems)
+    }
 }
@@ -0,0 +1,675 @@
+//! Conversion checking.
+//!
+//! Here we check if two values are definitionally equal, or if one type is a
+//! subtype of another, by comparing the values directly. The heads of neutral
+//! values are compared before their spines, and comparisons stop at the first
+//! mismatch, so we avoid normalizing terms that can't possibly be equal.
+
+use std::rc::Rc;
+
+use super::{
+    app_closure, data_ty, eval_fun_elim, eval_record_elim, eval_term, force_value, inst_closure,
+    Budget, EvalError,
+};
+use crate::domain::{
+    CaseClosure, DataClosure, Elim, FixClosure, Head, Level, LiteralClosure, Value,
+};
+use crate::literal::{LiteralIntro, LiteralType};
+use crate::{meta, prim, var, AppMode};
+
+/// Check whether a type is a subtype of another type.
+///
+/// If `subtype` is `false`, the types must be definitionally equal.
+pub fn check_ty(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    subtype: bool,
+    ty1: &Rc<Value>,
+    ty2: &Rc<Value>,
+) -> Result<bool, EvalError> {
+    check(prims, metas, budget, size, subtype, ty1, ty2)
+}
+
+/// Check whether two values are definitionally equal.
+pub fn check_value(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    value1: &Rc<Value>,
+    value2: &Rc<Value>,
+) -> Result<bool, EvalError> {
+    check(prims, metas, budget, size, false, value1, value2)
+}
+
+/// Check whether two closures refer to equivalent data type declarations.
+pub fn check_data(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    closure1: &DataClosure,
+    closure2: &DataClosure,
+) -> Result<bool, EvalError> {
+    let (data1, data2) = (&closure1.data, &closure2.data);
+    if data1.label != data2.label
+        || data1.param_count != data2.param_count
+        || data1.intros.len() != data2.intros.len()
+    {
+        return Ok(false);
+    }
+
+    let ty1 = data_ty(prims, metas, budget, closure1)?;
+    let ty2 = data_ty(prims, metas, budget, closure2)?;
+    if !check(prims, metas, budget, size, false, &ty1, &ty2)? {
+        return Ok(false);
+    }
+
+    // Compare the types of the constructors with the type former bound to a
+    // fresh variable
+    let data_ty_var = Rc::from(Value::var(size.next_level()));
+    let mut intro_values1 = closure1.values.clone();
+    let mut intro_values2 = closure2.values.clone();
+    intro_values1.add_entry(data_ty_var.clone());
+    intro_values2.add_entry(data_ty_var);
+
+    for ((_, label1, intro_ty1), (_, label2, intro_ty2)) in
+        Iterator::zip(data1.intros.iter(), data2.intros.iter())
+    {
+        if label1 != label2 {
+            return Ok(false);
+        }
+        let intro_ty1 = eval_term(prims, metas, budget, &intro_values1, intro_ty1)?;
+        let intro_ty2 = eval_term(prims, metas, budget, &intro_values2, intro_ty2)?;
+        if !check(
+            prims,
+            metas,
+            budget,
+            size + 1,
+            false,
+            &intro_ty1,
+            &intro_ty2,
+        )? {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Check whether two values are convertible. Subtyping is only allowed in
+/// positions where `subtype` is `true`, which only ever flows through the
+/// components of types.
+fn check(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    subtype: bool,
+    value1: &Rc<Value>,
+    value2: &Rc<Value>,
+) -> Result<bool, EvalError> {
+    budget.step()?;
+    let _depth = budget.enter()?;
+
+    // Try to avoid unfolding global definitions, by first checking if both
+    // sides refer to the same definition, applied to convertible spines
+    if let (
+        Value::Neutral(Head::Global(level1, _), spine1),
+        Value::Neutral(Head::Global(level2, _), spine2),
+    ) = (value1.as_ref(), value2.as_ref())
+    {
+        if level1 == level2 && check_spines(prims, metas, budget, size, spine1, spine2)? {
+            return Ok(true);
+        }
+    }
+
+    let value1 = &force_value(prims, metas, budget, value1)?;
+    let value2 = &force_value(prims, metas, budget, value2)?;
+
+    match (value1.as_ref(), value2.as_ref()) {
+        (Value::Neutral(head1, spine1), Value::Neutral(head2, spine2)) => {
+            Ok(check_heads(prims, metas, budget, size, head1, head2)?
+                && check_spines(prims, metas, budget, size, spine1, spine2)?)
+        },
+
+        // Literals
+        (Value::LiteralType(literal_ty1), Value::LiteralType(literal_ty2)) => {
+            Ok(LiteralType::alpha_eq(literal_ty1, literal_ty2))
+        },
+        (Value::LiteralIntro(literal_intro1), Value::LiteralIntro(literal_intro2)) => {
+            Ok(LiteralIntro::alpha_eq(literal_intro1, literal_intro2))
+        },
+
+        // Functions
+        (
+            Value::FunType(app_mode1, _, param_ty1, body_ty1),
+            Value::FunType(app_mode2, _, param_ty2, body_ty2),
+        ) if app_mode1 == app_mode2 => Ok(check(
+            prims, metas, budget, size, subtype, param_ty2, param_ty1,
+        )? && {
+            let body_ty1 = inst_closure(prims, metas, budget, size, body_ty1)?;
+            let body_ty2 = inst_closure(prims, metas, budget, size, body_ty2)?;
+            check(
+                prims,
+                metas,
+                budget,
+                size + 1,
+                subtype,
+                &body_ty1,
+                &body_ty2,
+            )?
+        }),
+        (Value::FunIntro(app_mode1, _, body1), Value::FunIntro(app_mode2, _, body2)) => {
+            if app_mode1 != app_mode2 {
+                return Ok(false);
+            }
+            let body1 = inst_closure(prims, metas, budget, size, body1)?;
+            let body2 = inst_closure(prims, metas, budget, size, body2)?;
+            check(prims, metas, budget, size + 1, false, &body1, &body2)
+        },
+
+        // Eta conversion (η-conversion) for functions:
+        //
+        // ```text
+        // (fun x => f x) == f
+        // ```
+        (Value::FunIntro(app_mode1, _, body1), _) => {
+            let param = Rc::from(Value::var(size.next_level()));
+            match eta_fun_elim(prims, metas, budget, value2, app_mode1, param.clone())? {
+                None => Ok(false),
+                Some(body2) => {
+                    let body1 = app_closure(prims, metas, budget, body1, param)?;
+                    check(prims, metas, budget, size + 1, false, &body1, &body2)
+                },
+            }
+        },
+        (_, Value::FunIntro(app_mode2, _, body2)) => {
+            let param = Rc::from(Value::var(size.next_level()));
+            match eta_fun_elim(prims, metas, budget, value1, app_mode2, param.clone())? {
+                None => Ok(false),
+                Some(body1) => {
+                    let body2 = app_closure(prims, metas, budget, body2, param)?;
+                    check(prims, metas, budget, size + 1, false, &body1, &body2)
+                },
+            }
+        },
+
+        // Records
+        (
+            Value::RecordTypeExtend(_, label1, _, term_ty1, manifest1, rest_ty1),
+            Value::RecordTypeExtend(_, label2, _, term_ty2, manifest2, rest_ty2),
+        ) if label1 == label2 => Ok(check(
+            prims, metas, budget, size, subtype, term_ty1, term_ty2,
+        )? && match (manifest1, manifest2) {
+            (Some(manifest1), Some(manifest2)) => {
+                check(prims, metas, budget, size, false, manifest1, manifest2)?
+            },
+            // Forgetting the value of a manifest field is allowed when
+            // subtyping
+            (Some(_), None) => subtype,
+            (None, Some(_)) => false,
+            (None, None) => true,
+        } && {
+            let rest_ty1 = inst_closure(prims, metas, budget, size, rest_ty1)?;
+            let rest_ty2 = inst_closure(prims, metas, budget, size, rest_ty2)?;
+            check(
+                prims,
+                metas,
+                budget,
+                size + 1,
+                subtype,
+                &rest_ty1,
+                &rest_ty2,
+            )?
+        }),
+        (Value::RecordTypeEmpty, Value::RecordTypeEmpty) => Ok(true),
+        (Value::RecordIntro(fields1), Value::RecordIntro(fields2)) => {
+            if fields1.len() != fields2.len() {
+                return Ok(false);
+            }
+            for ((label1, value1), (label2, value2)) in
+                Iterator::zip(fields1.iter(), fields2.iter())
+            {
+                if label1 != label2 || !check(prims, metas, budget, size, false, value1, value2)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        },
+
+        // Eta conversion (η-conversion) for records:
+        //
+        // ```text
+        // record { l1 = r.l1, .. } == r
+        // ```
+        (Value::RecordIntro(fields1), _) => {
+            for (label1, value1) in fields1 {
+                let value2 = match eval_record_elim(value2.clone(), label1) {
+                    Ok(value2) => value2,
+                    Err(_) => return Ok(false),
+                };
+                if !check(prims, metas, budget, size, false, value1, &value2)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        },
+        (_, Value::RecordIntro(fields2)) => {
+            for (label2, value2) in fields2 {
+                let value1 = match eval_record_elim(value1.clone(), label2) {
+                    Ok(value1) => value1,
+                    Err(_) => return Ok(false),
+                };
+                if !check(prims, metas, budget, size, false, &value1, value2)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        },
+
+        // Data types
+        (Value::DataType(closure1, args1), Value::DataType(closure2, args2)) => Ok(args1.len()
+            == args2.len()
+            && check_data(prims, metas, budget, size, closure1, closure2)?
+            && check_args(prims, metas, budget, size, args1, args2)?),
+        (Value::DataIntro(closure1, label1, args1), Value::DataIntro(closure2, label2, args2)) => {
+            Ok(label1 == label2
+                && args1.len() == args2.len()
+                && check_data(prims, metas, budget, size, closure1, closure2)?
+                && check_args(prims, metas, budget, size, args1, args2)?)
+        },
+
+        // Equality
+        (Value::EqType(ty1, lhs1, rhs1), Value::EqType(ty2, lhs2, rhs2)) => {
+            Ok(check(prims, metas, budget, size, false, ty1, ty2)?
+                && check(prims, metas, budget, size, false, lhs1, lhs2)?
+                && check(prims, metas, budget, size, false, rhs1, rhs2)?)
+        },
+        (Value::EqIntro, Value::EqIntro) => Ok(true),
+
+        // Universe levels
+        (Value::LevelType, Value::LevelType) => Ok(true),
+        (Value::Level(level1), Value::Level(level2)) => {
+            check_level(prims, metas, budget, size, level1, level2)
+        },
+
+        // Universes
+        (Value::Universe(level1), Value::Universe(level2)) if subtype => Ok(level1.is_le(level2)),
+        (Value::Universe(level1), Value::Universe(level2)) => {
+            check_level(prims, metas, budget, size, level1, level2)
+        },
+
+        (_, _) => Ok(false),
+    }
+}
+
+/// Apply a value that is being compared with a function to a fresh
+/// parameter, returning `None` if the value can't be applied in the given
+/// application mode.
+fn eta_fun_elim(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    fun: &Rc<Value>,
+    app_mode: &AppMode,
+    param: Rc<Value>,
+) -> Result<Option<Rc<Value>>, EvalError> {
+    match eval_fun_elim(prims, metas, budget, fun.clone(), app_mode, param) {
+        Ok(value) => Ok(Some(value)),
+        Err(EvalError::ExpectedFunction { .. }) | Err(EvalError::UnexpectedAppMode { .. }) => {
+            Ok(None)
+        },
+        Err(error) => Err(error),
+    }
+}
+
+/// Check whether the heads of two stuck neutral values are convertible.
+fn check_heads(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    head1: &Head,
+    head2: &Head,
+) -> Result<bool, EvalError> {
+    match (head1, head2) {
+        (Head::Fix(index1, closure1), Head::Fix(index2, closure2)) => {
+            Ok(index1 == index2 && check_fix(prims, metas, budget, size, closure1, closure2)?)
+        },
+        (_, _) => Ok(head1 == head2),
+    }
+}
+
+/// Check whether two groups of recursive definitions are convertible.
+///
+/// The bodies of the definitions are compared with the definitions in the
+/// group bound as variables, so this will always terminate.
+fn check_fix(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    closure1: &FixClosure,
+    closure2: &FixClosure,
+) -> Result<bool, EvalError> {
+    if closure1.defns.len() != closure2.defns.len() {
+        return Ok(false);
+    }
+
+    let mut body_values1 = closure1.values.clone();
+    let mut body_values2 = closure2.values.clone();
+    for defn_index in 0..closure1.defns.len() {
+        let defn = Rc::from(Value::var(size.next_level() + defn_index as u32));
+        body_values1.add_entry(defn.clone());
+        body_values2.add_entry(defn);
+    }
+    let body_size = size + closure1.defns.len() as u32;
+
+    let defns = Iterator::zip(closure1.defns.iter(), closure2.defns.iter());
+    for ((label1, guard1, ty1, body1), (label2, guard2, ty2, body2)) in defns {
+        if label1 != label2 || guard1 != guard2 {
+            return Ok(false);
+        }
+        let ty1 = eval_term(prims, metas, budget, &closure1.values, ty1)?;
+        let ty2 = eval_term(prims, metas, budget, &closure2.values, ty2)?;
+        if !check(prims, metas, budget, size, false, &ty1, &ty2)? {
+            return Ok(false);
+        }
+        let body1 = eval_term(prims, metas, budget, &body_values1, body1)?;
+        let body2 = eval_term(prims, metas, budget, &body_values2, body2)?;
+        if !check(prims, metas, budget, body_size, false, &body1, &body2)? {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Check whether the spines of two neutral values are convertible, stopping
+/// at the first eliminator that differs.
+fn check_spines(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    spine1: &[Elim],
+    spine2: &[Elim],
+) -> Result<bool, EvalError> {
+    if spine1.len() != spine2.len() {
+        return Ok(false);
+    }
+
+    for (elim1, elim2) in Iterator::zip(spine1.iter(), spine2.iter()) {
+        let is_convertible = match (elim1, elim2) {
+            (Elim::Fun(app_mode1, arg1), Elim::Fun(app_mode2, arg2)) => {
+                app_mode1 == app_mode2 && check(prims, metas, budget, size, false, arg1, arg2)?
+            },
+            (Elim::Record(label1), Elim::Record(label2)) => label1 == label2,
+            (Elim::Literal(closure1), Elim::Literal(closure2)) => {
+                check_literal_clauses(prims, metas, budget, size, closure1, closure2)?
+            },
+            (Elim::Data(closure1), Elim::Data(closure2)) => {
+                check_data_clauses(prims, metas, budget, size, closure1, closure2)?
+            },
+            (Elim::Eq(motive1, body1), Elim::Eq(motive2, body2)) => {
+                check(prims, metas, budget, size, false, motive1, motive2)?
+                    && check(prims, metas, budget, size, false, body1, body2)?
+            },
+            (_, _) => false,
+        };
+
+        if !is_convertible {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Check whether the clauses of two literal eliminators are convertible.
+fn check_literal_clauses(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    closure1: &LiteralClosure,
+    closure2: &LiteralClosure,
+) -> Result<bool, EvalError> {
+    if closure1.clauses.len() != closure2.clauses.len() {
+        return Ok(false);
+    }
+
+    let clauses = Iterator::zip(closure1.clauses.iter(), closure2.clauses.iter());
+    for ((literal_intro1, body1), (literal_intro2, body2)) in clauses {
+        if !LiteralIntro::alpha_eq(literal_intro1, literal_intro2) {
+            return Ok(false);
+        }
+        let body1 = eval_term(prims, metas, budget, &closure1.values, body1)?;
+        let body2 = eval_term(prims, metas, budget, &closure2.values, body2)?;
+        if !check(prims, metas, budget, size, false, &body1, &body2)? {
+            return Ok(false);
+        }
+    }
+
+    let default1 = eval_term(prims, metas, budget, &closure1.values, &closure1.default)?;
+    let default2 = eval_term(prims, metas, budget, &closure2.values, &closure2.default)?;
+    check(prims, metas, budget, size, false, &default1, &default2)
+}
+
+/// Check whether the clauses of two data eliminators are convertible.
+fn check_data_clauses(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    closure1: &CaseClosure,
+    closure2: &CaseClosure,
+) -> Result<bool, EvalError> {
+    if closure1.clauses.len() != closure2.clauses.len() {
+        return Ok(false);
+    }
+
+    let clauses = Iterator::zip(closure1.clauses.iter(), closure2.clauses.iter());
+    for ((label1, name_hints1, body1), (label2, name_hints2, body2)) in clauses {
+        if label1 != label2 || name_hints1.len() != name_hints2.len() {
+            return Ok(false);
+        }
+
+        // Compare the bodies of the clauses, with their fields bound to fresh
+        // variables
+        let mut values1 = closure1.values.clone();
+        let mut values2 = closure2.values.clone();
+        for index in 0..name_hints1.len() {
+            let field = Rc::from(Value::var(size.next_level() + index as u32));
+            values1.add_entry(field.clone());
+            values2.add_entry(field);
+        }
+        let body_size = size + name_hints1.len() as u32;
+        let body1 = eval_term(prims, metas, budget, &values1, body1)?;
+        let body2 = eval_term(prims, metas, budget, &values2, body2)?;
+        if !check(prims, metas, budget, body_size, false, &body1, &body2)? {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Check whether the arguments applied to two data types or constructors are
+/// convertible.
+fn check_args(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    args1: &[(AppMode, Rc<Value>)],
+    args2: &[(AppMode, Rc<Value>)],
+) -> Result<bool, EvalError> {
+    for ((app_mode1, arg1), (app_mode2, arg2)) in Iterator::zip(args1.iter(), args2.iter()) {
+        if app_mode1 != app_mode2 || !check(prims, metas, budget, size, false, arg1, arg2)? {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Check whether two levels are equal. The neutral parts of the levels are
+/// compared without regard to their order.
+fn check_level(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    level1: &Level,
+    level2: &Level,
+) -> Result<bool, EvalError> {
+    match (level1, level2) {
+        (Level::Omega, Level::Omega) => return Ok(true),
+        (Level::Finite(constant1, neutrals1), Level::Finite(constant2, neutrals2))
+            if constant1 == constant2 && neutrals1.len() == neutrals2.len() => {},
+        (_, _) => return Ok(false),
+    }
+
+    for (value1, shift1) in level1.neutrals() {
+        let mut found = false;
+        for (value2, shift2) in level2.neutrals() {
+            if shift1 == shift2 && check(prims, metas, budget, size, false, value1, value2)? {
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intern::Symbol;
+    use crate::syntax::Term;
+    use crate::{Label, UniverseLevel};
+
+    #[test]
+    fn fun_eta() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let mut values = var::Env::new();
+        values.add_entry(Rc::from(Value::var(0)));
+
+        let fun = Rc::from(Term::FunIntro(
+            AppMode::Explicit,
+            Some(Symbol::intern("x")),
+            Rc::from(Term::FunElim(
+                Rc::from(Term::var(1)),
+                AppMode::Explicit,
+                Rc::from(Term::var(0)),
+            )),
+        ));
+        let fun = eval_term(&prims, &metas, &Budget::default(), &values, &fun).unwrap();
+        let var = Rc::from(Value::var(0));
+
+        assert!(check_value(
+            &prims,
+            &metas,
+            &Budget::default(),
+            values.size(),
+            &fun,
+            &var
+        )
+        .unwrap());
+        assert!(check_value(
+            &prims,
+            &metas,
+            &Budget::default(),
+            values.size(),
+            &var,
+            &fun
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn record_eta() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let size = var::Size(1);
+
+        let var = Rc::from(Value::var(0));
+        let label = Label::new("x");
+        let record = Rc::from(Value::RecordIntro(vec![(
+            label.clone(),
+            eval_record_elim(var.clone(), &label).unwrap(),
+        )]));
+
+        assert!(check_value(&prims, &metas, &Budget::default(), size, &record, &var).unwrap());
+        assert!(check_value(&prims, &metas, &Budget::default(), size, &var, &record).unwrap());
+    }
+
+    #[test]
+    fn neutral_heads_differ() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let size = var::Size(2);
+
+        let arg = Rc::from(Value::literal_intro(1u32));
+        let value1 = Rc::from(Value::Neutral(
+            Head::Var(var::Level(0)),
+            vec![Elim::Fun(AppMode::Explicit, arg.clone())],
+        ));
+        let value2 = Rc::from(Value::Neutral(
+            Head::Var(var::Level(1)),
+            vec![Elim::Fun(AppMode::Explicit, arg)],
+        ));
+
+        assert!(!check_value(&prims, &metas, &Budget::default(), size, &value1, &value2).unwrap());
+    }
+
+    #[test]
+    fn universe_cumulativity() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let size = var::Size(0);
+
+        let universe0 = Rc::from(Value::universe(UniverseLevel(0)));
+        let universe1 = Rc::from(Value::universe(UniverseLevel(1)));
+
+        assert!(check_ty(
+            &prims,
+            &metas,
+            &Budget::default(),
+            size,
+            true,
+            &universe0,
+            &universe1
+        )
+        .unwrap());
+        assert!(!check_ty(
+            &prims,
+            &metas,
+            &Budget::default(),
+            size,
+            true,
+            &universe1,
+            &universe0
+        )
+        .unwrap());
+        assert!(!check_ty(
+            &prims,
+            &metas,
+            &Budget::default(),
+            size,
+            false,
+            &universe0,
+            &universe1
+        )
+        .unwrap());
+    }
+}
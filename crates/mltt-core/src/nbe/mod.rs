@@ -0,0 +1,2291 @@
+//! Normalization by evaluation.
+//!
+//! Here we implement a full normalization algorithm by first implementing
+//! evaluation to `Value`s in weak-head-normal-form, and then reading it back
+//! `Normal` terms.
+
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::domain::{
+    AppClosure, CaseClosure, DataClosure, Elim, FixClosure, Head, Level, LiteralClosure, Spine,
+    Thunk, Type, Value,
+};
+use crate::intern::Symbol;
+use crate::literal::LiteralIntro;
+use crate::syntax::{Data, Guard, Item, RecordTypeField, Term};
+use crate::{meta, prim, var, AppMode, DocString, Label, UniverseLevel};
+
+pub use crate::nbe::conversion::{check_data, check_ty, check_value};
+
+mod conversion;
+#[cfg(any(test, feature = "test-support"))]
+pub mod recursive;
+
+/// Controls the unfolding of global definitions during read-back.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Unfold {
+    /// Keep global definitions folded, preserving the names that they were
+    /// given by the user.
+    None,
+    /// Unfold all global definitions.
+    All,
+}
+
+/// Limits on the amount of work that normalization can perform.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum number of evaluation steps, or `None` if unlimited.
+    pub steps: Option<u64>,
+    /// The maximum depth of nested evaluation, or `None` if unlimited.
+    ///
+    /// Evaluation and read-back keep their own stacks, so the depth only grows
+    /// when they need to be run from within each other, for example when
+    /// instantiating closures or forcing values. Each level of depth
+    /// corresponds to some amount of the native stack, so this can be used to
+    /// avoid stack overflows on pathological terms.
+    pub depth: Option<u32>,
+}
+
+/// A limit that was exceeded during normalization.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Limit {
+    Steps(u64),
+    Depth(u32),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Steps(steps) => write!(f, "more than {} steps", steps),
+            Limit::Depth(depth) => write!(f, "nested deeper than {} levels", depth),
+        }
+    }
+}
+
+/// An evaluation budget, keeping track of the work performed during
+/// normalization against some limits.
+///
+/// Clones of a budget share the same counters.
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    limits: Limits,
+    steps: Rc<Cell<u64>>,
+    depth: Rc<Cell<u32>>,
+}
+
+impl Budget {
+    /// Create a new budget with the given limits.
+    pub fn new(limits: Limits) -> Budget {
+        Budget {
+            limits,
+            steps: Rc::new(Cell::new(0)),
+            depth: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// The limits of the budget.
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// The number of steps performed since the budget was last reset.
+    pub fn steps(&self) -> u64 {
+        self.steps.get()
+    }
+
+    /// Reset the number of steps performed, eg. before starting on a new
+    /// request in the REPL.
+    pub fn reset(&self) {
+        self.steps.set(0);
+    }
+
+    /// Record a step of evaluation.
+    fn step(&self) -> Result<(), EvalError> {
+        let steps = self.steps.get() + 1;
+
+        match self.limits.steps {
+            Some(max_steps) if steps > max_steps => {
+                Err(EvalError::LimitExceeded(Limit::Steps(max_steps)))
+            },
+            _ => {
+                self.steps.set(steps);
+                Ok(())
+            },
+        }
+    }
+
+    /// Enter a nested run of evaluation, returning a guard that tracks the
+    /// depth of the run until it is dropped.
+    fn enter(&self) -> Result<Depth<'_>, EvalError> {
+        let depth = self.depth.get() + 1;
+
+        match self.limits.depth {
+            Some(max_depth) if depth > max_depth => {
+                Err(EvalError::LimitExceeded(Limit::Depth(max_depth)))
+            },
+            _ => {
+                self.depth.set(depth);
+                Ok(Depth(self))
+            },
+        }
+    }
+}
+
+/// Restores the depth of a budget once a run of evaluation is complete.
+struct Depth<'budget>(&'budget Budget);
+
+impl<'budget> Drop for Depth<'budget> {
+    fn drop(&mut self) {
+        self.0.depth.set(self.0.depth.get() - 1);
+    }
+}
+
+/// An error produced during normalization.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    UnboundVariable(var::Index),
+    UnboundMeta(meta::Index),
+    UnknownPrim(prim::Name),
+    NoDefinitionInGroup(usize),
+    NoIntroInData(Label),
+    NoFieldInRecord {
+        label: Label,
+        found: Rc<Value>,
+    },
+    NoClauseForIntro {
+        label: Label,
+        found: Rc<Value>,
+    },
+    UnexpectedIntroFields {
+        label: Label,
+        found: Rc<Value>,
+    },
+    UnexpectedAppMode {
+        found: AppMode,
+        expected: AppMode,
+    },
+    ExpectedFunction {
+        found: Rc<Value>,
+    },
+    ExpectedRecord {
+        found: Rc<Value>,
+    },
+    ExpectedLiteral {
+        found: Rc<Value>,
+    },
+    ExpectedDataIntro {
+        found: Rc<Value>,
+    },
+    ExpectedEqIntro {
+        found: Rc<Value>,
+    },
+    ExpectedLevel {
+        found: Rc<Value>,
+    },
+    OverflowingUniverseLevel,
+    /// A thunk was forced again while it was still being evaluated.
+    ForcingThunk,
+    /// The evaluation budget was exhausted.
+    LimitExceeded(Limit),
+    /// Evaluation was aborted by a call to the `abort` primitive.
+    Abort(String),
+}
+
+impl Error for EvalError {}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::UnboundVariable(index) => write!(f, "unbound variable: {}", index),
+            EvalError::UnboundMeta(index) => write!(f, "unbound metavariable: `{}`", index),
+            EvalError::UnknownPrim(name) => write!(f, "unbound primitive: {}", name),
+            EvalError::NoDefinitionInGroup(index) => {
+                write!(f, "no definition at index {} in recursive group", index)
+            },
+            EvalError::NoIntroInData(label) => write!(f, "no constructor `{}` in data type", label),
+            EvalError::NoFieldInRecord { label, .. } => {
+                write!(f, "no field `{}` in record", label)
+            },
+            EvalError::NoClauseForIntro { label, .. } => {
+                write!(f, "no clause for constructor `{}`", label)
+            },
+            EvalError::UnexpectedIntroFields { label, .. } => {
+                write!(f, "unexpected number of fields for constructor `{}`", label)
+            },
+            EvalError::UnexpectedAppMode { found, expected } => write!(
+                f,
+                "unexpected application mode, found `{:?}`, but expected `{:?}`",
+                found, expected,
+            ),
+            EvalError::ExpectedFunction { .. } => write!(f, "expected function"),
+            EvalError::ExpectedRecord { .. } => write!(f, "expected record"),
+            EvalError::ExpectedLiteral { .. } => write!(f, "expected literal"),
+            EvalError::ExpectedDataIntro { .. } => write!(f, "expected constructor"),
+            EvalError::ExpectedEqIntro { .. } => write!(f, "expected proof of equality"),
+            EvalError::ExpectedLevel { .. } => write!(f, "expected level"),
+            EvalError::OverflowingUniverseLevel => write!(
+                f,
+                "cannot represent universes greater than `{}`",
+                UniverseLevel::MAX,
+            ),
+            EvalError::ForcingThunk => write!(f, "thunk forced while it was being evaluated"),
+            EvalError::LimitExceeded(limit) => {
+                write!(f, "evaluation limit exceeded: {}", limit)
+            },
+            EvalError::Abort(message) => write!(f, "aborted: {}", message),
+        }
+    }
+}
+
+/// Evaluate a primitive.
+pub fn eval_prim<'spine>(
+    prims: &prim::Env,
+    prim_name: &prim::Name,
+    spine: &'spine [Elim],
+) -> Result<(Rc<Value>, &'spine [Elim]), EvalError> {
+    let prim = prims
+        .lookup_entry(prim_name)
+        .ok_or_else(|| EvalError::UnknownPrim(prim_name.clone()))?;
+
+    match prim.interpret(spine) {
+        Some(result) => result,
+        None => Ok((Rc::from(Value::prim(prim_name.clone())), spine)),
+    }
+}
+
+/// Evaluate an eliminator.
+pub fn eval_elim(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    head: Rc<Value>,
+    elim: &Elim,
+) -> Result<Rc<Value>, EvalError> {
+    match elim {
+        Elim::Literal(closure) => eval_literal_elim(prims, metas, budget, head, closure.clone()),
+        Elim::Fun(app_mode, arg) => {
+            eval_fun_elim(prims, metas, budget, head, app_mode, arg.clone())
+        },
+        Elim::Record(label) => eval_record_elim(head, label),
+        Elim::Data(closure) => eval_data_elim(prims, metas, budget, head, closure.clone()),
+        Elim::Eq(motive, body) => eval_eq_elim(head, motive.clone(), body.clone()),
+    }
+}
+
+/// Apply a spine of eliminators to a value.
+pub fn eval_spine(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    head: Rc<Value>,
+    spine: &[Elim],
+) -> Result<Rc<Value>, EvalError> {
+    spine.iter().fold(Ok(head), |head, elim| {
+        eval_elim(prims, metas, budget, head?, elim)
+    })
+}
+
+/// Case split on a literal.
+pub fn eval_literal_elim(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    scrutinee: Rc<Value>,
+    closure: LiteralClosure,
+) -> Result<Rc<Value>, EvalError> {
+    let step = literal_elim_step(scrutinee, closure)?;
+    run_eval(prims, metas, budget, step)
+}
+
+/// Case split on a literal, returning the body of the matching clause if the
+/// scrutinee is a literal.
+fn literal_elim_step(scrutinee: Rc<Value>, closure: LiteralClosure) -> Result<EvalStep, EvalError> {
+    match scrutinee.as_ref() {
+        Value::LiteralIntro(literal_intro) => {
+            let index = closure.clauses.binary_search_by(|(l, _)| {
+                l.partial_cmp(literal_intro).unwrap() // NaN?
+            });
+
+            let clause_body = match index {
+                Ok(index) => closure.clauses[index].1.clone(),
+                Err(_) => closure.default.clone(),
+            };
+            Ok(EvalStep::Term(closure.values, clause_body))
+        },
+        Value::Neutral(head, spine) => {
+            let mut spine = spine.clone();
+            spine.push(Elim::Literal(closure));
+            Ok(EvalStep::Value(Rc::from(Value::Neutral(
+                head.clone(),
+                spine,
+            ))))
+        },
+        _ => Err(EvalError::ExpectedLiteral { found: scrutinee }),
+    }
+}
+
+/// Case split on the constructors of a data type.
+pub fn eval_data_elim(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    scrutinee: Rc<Value>,
+    closure: CaseClosure,
+) -> Result<Rc<Value>, EvalError> {
+    let step = data_elim_step(scrutinee, closure)?;
+    run_eval(prims, metas, budget, step)
+}
+
+/// Case split on the constructors of a data type, returning the body of the
+/// matching clause if the scrutinee is a constructor.
+fn data_elim_step(scrutinee: Rc<Value>, closure: CaseClosure) -> Result<EvalStep, EvalError> {
+    match scrutinee.as_ref() {
+        Value::DataIntro(data_closure, label, args) => {
+            let (_, name_hints, body) = closure
+                .clauses
+                .iter()
+                .find(|(l, _, _)| l == label)
+                .ok_or_else(|| EvalError::NoClauseForIntro {
+                    label: label.clone(),
+                    found: scrutinee.clone(),
+                })?;
+
+            let param_count = data_closure.data.param_count;
+            if args.len() != param_count + name_hints.len() {
+                return Err(EvalError::UnexpectedIntroFields {
+                    label: label.clone(),
+                    found: scrutinee.clone(),
+                });
+            }
+
+            let mut values = closure.values.clone();
+            for (_, arg) in &args[param_count..] {
+                values.add_entry(arg.clone());
+            }
+            Ok(EvalStep::Term(values, body.clone()))
+        },
+        Value::Neutral(head, spine) => {
+            let mut spine = spine.clone();
+            spine.push(Elim::Data(closure));
+            Ok(EvalStep::Value(Rc::from(Value::Neutral(
+                head.clone(),
+                spine,
+            ))))
+        },
+        _ => Err(EvalError::ExpectedDataIntro { found: scrutinee }),
+    }
+}
+
+/// Eliminate a proof of equality, returning the body if the proof is `refl`.
+pub fn eval_eq_elim(
+    proof: Rc<Value>,
+    motive: Rc<Value>,
+    body: Rc<Value>,
+) -> Result<Rc<Value>, EvalError> {
+    match proof.as_ref() {
+        Value::EqIntro => Ok(body),
+        Value::Neutral(head, spine) => {
+            let mut spine = spine.clone();
+            spine.push(Elim::Eq(motive, body));
+            Ok(Rc::from(Value::Neutral(head.clone(), spine)))
+        },
+        _ => Err(EvalError::ExpectedEqIntro { found: proof }),
+    }
+}
+
+/// Return the field in from a record.
+pub fn eval_record_elim(record: Rc<Value>, label: &Label) -> Result<Rc<Value>, EvalError> {
+    match record.as_ref() {
+        Value::RecordIntro(fields) => match fields.iter().find(|(l, _)| l == label) {
+            Some((_, term)) => Ok(term.clone()),
+            None => Err(EvalError::NoFieldInRecord {
+                label: label.clone(),
+                found: record.clone(),
+            }),
+        },
+        Value::Neutral(head, spine) => {
+            let mut spine = spine.clone();
+            spine.push(Elim::Record(label.clone()));
+            // TODO: If head is `primitive`, and arity == number of initial spine apps in NF
+            Ok(Rc::from(Value::Neutral(head.clone(), spine)))
+        },
+        _ => Err(EvalError::ExpectedRecord { found: record }),
+    }
+}
+
+/// Apply a function to an argument.
+pub fn eval_fun_elim(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    fun: Rc<Value>,
+    app_mode: &AppMode,
+    arg: Rc<Value>,
+) -> Result<Rc<Value>, EvalError> {
+    let step = fun_elim_step(fun, app_mode, arg)?;
+    run_eval(prims, metas, budget, step)
+}
+
+/// Apply a function to an argument, returning the body of the function if it
+/// needs to be evaluated further.
+fn fun_elim_step(
+    fun: Rc<Value>,
+    app_mode: &AppMode,
+    arg: Rc<Value>,
+) -> Result<EvalStep, EvalError> {
+    match fun.as_ref() {
+        Value::FunIntro(fun_app_mode, _, body) => {
+            if fun_app_mode == app_mode {
+                let mut values = body.values.clone();
+                values.add_entry(arg);
+                Ok(EvalStep::Term(values, body.term.clone()))
+            } else {
+                Err(EvalError::UnexpectedAppMode {
+                    found: app_mode.clone(),
+                    expected: fun_app_mode.clone(),
+                })
+            }
+        },
+        Value::DataType(closure, args) => {
+            let mut args = args.clone();
+            args.push((app_mode.clone(), arg));
+            Ok(EvalStep::Value(Rc::from(Value::DataType(
+                closure.clone(),
+                args,
+            ))))
+        },
+        Value::DataIntro(closure, label, args) => {
+            let mut args = args.clone();
+            args.push((app_mode.clone(), arg));
+            Ok(EvalStep::Value(Rc::from(Value::DataIntro(
+                closure.clone(),
+                label.clone(),
+                args,
+            ))))
+        },
+        Value::Neutral(head, spine) => {
+            let mut spine = spine.clone();
+            spine.push(Elim::Fun(app_mode.clone(), arg));
+            // TODO: If head is `primitive`, and arity == number of initial spine apps in NF
+            Ok(EvalStep::Value(Rc::from(Value::Neutral(
+                head.clone(),
+                spine,
+            ))))
+        },
+        _ => Err(EvalError::ExpectedFunction { found: fun }),
+    }
+}
+
+/// Apply a closure to an argument.
+pub fn app_closure(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    closure: &AppClosure,
+    arg: Rc<Value>,
+) -> Result<Rc<Value>, EvalError> {
+    let mut values = closure.values.clone();
+    values.add_entry(arg);
+    run_eval(
+        prims,
+        metas,
+        budget,
+        EvalStep::Term(values, closure.term.clone()),
+    )
+}
+
+/// Return the type of the type former of a data type.
+pub fn data_ty(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    closure: &DataClosure,
+) -> Result<Rc<Type>, EvalError> {
+    eval_term(prims, metas, budget, &closure.values, &closure.data.ty)
+}
+
+/// Return the type of a constructor of a data type.
+pub fn intro_ty(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    closure: &DataClosure,
+    label: &Label,
+) -> Result<Rc<Type>, EvalError> {
+    let ty = closure
+        .data
+        .lookup_intro(label)
+        .ok_or_else(|| EvalError::NoIntroInData(label.clone()))?;
+
+    let mut values = closure.values.clone();
+    values.add_entry(Rc::from(Value::DataType(closure.clone(), Vec::new())));
+    eval_term(prims, metas, budget, &values, ty)
+}
+
+/// Unfold a definition from a group of mutually recursive definitions.
+///
+/// The other definitions in the group are left folded, so that they can be
+/// unfolded lazily once they are needed.
+pub fn unfold_fix(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    index: usize,
+    closure: &FixClosure,
+) -> Result<Rc<Value>, EvalError> {
+    let (_, _, _, body) = closure
+        .defns
+        .get(index)
+        .ok_or(EvalError::NoDefinitionInGroup(index))?;
+
+    let mut values = closure.values.clone();
+    for defn_index in 0..closure.defns.len() {
+        values.add_entry(Rc::from(Value::Neutral(
+            Head::Fix(defn_index, closure.clone()),
+            Vec::new(),
+        )));
+    }
+    eval_term(prims, metas, budget, &values, body)
+}
+
+/// Check if the guard of a recursive definition allows it to be unfolded when
+/// applied to the given spine.
+fn is_fix_unfoldable(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    index: usize,
+    closure: &FixClosure,
+    spine: &[Elim],
+) -> Result<bool, EvalError> {
+    let is_canonical = |arg: &Rc<Value>| -> Result<bool, EvalError> {
+        match force_value(prims, metas, budget, arg)?.as_ref() {
+            Value::Neutral(_, _) => Ok(false),
+            _ => Ok(true),
+        }
+    };
+    let mut args = spine.iter().filter_map(|elim| match elim {
+        Elim::Fun(_, arg) => Some(arg),
+        Elim::Literal(_) | Elim::Record(_) | Elim::Data(_) | Elim::Eq(_, _) => None,
+    });
+
+    match closure.defns.get(index) {
+        None => Err(EvalError::NoDefinitionInGroup(index)),
+        Some((_, Guard::Always, _, _)) => Ok(true),
+        Some((_, Guard::Structural(position), _, _)) => match args.nth(*position) {
+            None => Ok(false),
+            Some(arg) => is_canonical(arg),
+        },
+        Some((_, Guard::Partial, _, _)) => {
+            for arg in args {
+                if !is_canonical(arg)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        },
+    }
+}
+
+/// Convert a value of type `Level` into a level.
+pub fn value_to_level(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    value: &Rc<Value>,
+) -> Result<Level, EvalError> {
+    let value = force_value(prims, metas, budget, value)?;
+    match value.as_ref() {
+        Value::Level(level) => Ok(level.clone()),
+        Value::Neutral(_, _) => Ok(Level::neutral(value.clone())),
+        _ => Err(EvalError::ExpectedLevel { found: value }),
+    }
+}
+
+/// Convert a level into a value of type `Level`, keeping levels that consist
+/// of a single neutral value neutral.
+pub fn level_to_value(level: Level) -> Rc<Value> {
+    match &level {
+        Level::Finite(UniverseLevel(0), neutrals) => match neutrals.as_slice() {
+            [(value, 0)] => value.clone(),
+            _ => Rc::from(Value::Level(level)),
+        },
+        Level::Finite(_, _) | Level::Omega => Rc::from(Value::Level(level)),
+    }
+}
+
+/// Evaluate the neutral parts of a level further, if it's now possible due to
+/// updates made to the metavariable solutions.
+pub fn force_level(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    level: &Level,
+) -> Result<Level, EvalError> {
+    let (constant, neutrals) = match level {
+        Level::Finite(constant, neutrals) => (Level::constant(*constant), neutrals),
+        Level::Omega => return Ok(Level::Omega),
+    };
+    neutrals.iter().try_fold(constant, |acc, (value, shift)| {
+        let level = value_to_level(prims, metas, budget, value)?;
+        let level = level
+            .shift(*shift)
+            .ok_or(EvalError::OverflowingUniverseLevel)?;
+        Ok(acc.max(&level))
+    })
+}
+
+/// Instantiate a closure in an environment of the given size.
+pub fn inst_closure(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    closure: &AppClosure,
+) -> Result<Rc<Value>, EvalError> {
+    let arg = Rc::from(Value::var(size.next_level()));
+    app_closure(prims, metas, budget, closure, arg)
+}
+
+/// Evaluate a term in the environment that corresponds to the context in which
+/// the term was typed.
+pub fn eval_term(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    values: &var::Env<Rc<Value>>,
+    term: &Rc<Term>,
+) -> Result<Rc<Value>, EvalError> {
+    run_eval(
+        prims,
+        metas,
+        budget,
+        EvalStep::Term(values.clone(), term.clone()),
+    )
+}
+
+/// A step in the evaluation of a term.
+enum EvalStep {
+    /// Evaluate a term in an environment.
+    Term(var::Env<Rc<Value>>, Rc<Term>),
+    /// Continue evaluating the subterms of a frame.
+    Frame(EvalFrame),
+    /// The term was evaluated to a value.
+    Value(Rc<Value>),
+}
+
+impl EvalStep {
+    fn frame(values: var::Env<Rc<Value>>, pending: Vec<Rc<Term>>, cont: EvalCont) -> EvalStep {
+        EvalStep::Frame(EvalFrame::new(values, pending, cont))
+    }
+}
+
+/// A term whose evaluation is waiting on the values of its subterms.
+struct EvalFrame {
+    /// The environment to evaluate the subterms in.
+    values: var::Env<Rc<Value>>,
+    /// The subterms that remain to be evaluated, in reverse order.
+    pending: Vec<Rc<Term>>,
+    /// The values of the subterms that have been evaluated so far.
+    done: Vec<Rc<Value>>,
+    /// What to do once all of the subterms have been evaluated.
+    cont: EvalCont,
+}
+
+impl EvalFrame {
+    fn new(values: var::Env<Rc<Value>>, mut pending: Vec<Rc<Term>>, cont: EvalCont) -> EvalFrame {
+        pending.reverse();
+        EvalFrame {
+            values,
+            done: Vec::with_capacity(pending.len()),
+            pending,
+            cont,
+        }
+    }
+}
+
+/// What to do with the values of the subterms of an evaluation frame.
+enum EvalCont {
+    /// Case split on the literal, using the clauses and the default term.
+    LiteralElim(Rc<[(LiteralIntro, Rc<Term>)]>, Rc<Term>),
+    /// Build a function type from the parameter type and the body type.
+    FunType(AppMode, Option<Symbol>, Rc<Term>),
+    /// Apply the function to the argument.
+    FunElim(AppMode),
+    /// Build a record type from the type and manifest value of the first
+    /// field, and the remaining fields.
+    RecordType(DocString, Label, Option<Symbol>, Vec<RecordTypeField>),
+    /// Project a field from the record.
+    RecordElim(Label),
+    /// Case split on the constructor, using the clauses.
+    DataElim(Rc<[(Label, Vec<Option<Symbol>>, Rc<Term>)]>),
+    /// Build an equality type from its type and the values being compared.
+    EqType,
+    /// Eliminate the proof of equality, using the motive and the body.
+    EqElim,
+    /// Shift the level up by a constant amount.
+    LevelShift(u16),
+    /// Take the maximum of the two levels.
+    LevelMax,
+    /// Build a universe at the level.
+    Universe,
+}
+
+/// Run an evaluation to completion.
+///
+/// Rather than recursing into the subterms of a term, we keep an explicit
+/// stack of frames that are waiting on the values of their subterms. Terms in
+/// tail position, like the bodies of functions and clauses, replace the
+/// current step, so long chains of applications won't grow the stack.
+fn run_eval(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    step: EvalStep,
+) -> Result<Rc<Value>, EvalError> {
+    let _depth = budget.enter()?;
+    let mut frames = Vec::new();
+    let mut step = step;
+
+    loop {
+        step = match step {
+            EvalStep::Term(values, term) => {
+                budget.step()?;
+                eval_step(prims, metas, values, &term)?
+            },
+            EvalStep::Frame(mut frame) => match frame.pending.pop() {
+                Some(term) => {
+                    let values = frame.values.clone();
+                    frames.push(frame);
+                    EvalStep::Term(values, term)
+                },
+                None => finish_eval(prims, metas, budget, frame)?,
+            },
+            EvalStep::Value(value) => match frames.pop() {
+                None => return Ok(value),
+                Some(mut frame) => {
+                    frame.done.push(value);
+                    EvalStep::Frame(frame)
+                },
+            },
+        };
+    }
+}
+
+/// Suspend the evaluation of a term until it is forced. Variables are looked
+/// up immediately, because there is nothing to be gained by delaying them.
+fn delay(values: &var::Env<Rc<Value>>, term: &Rc<Term>) -> Result<Rc<Value>, EvalError> {
+    match term.as_ref() {
+        Term::Var(var_index) => match values.lookup_entry(*var_index) {
+            Some(value) => Ok(value.clone()),
+            None => Err(EvalError::UnboundVariable(*var_index)),
+        },
+        _ => Ok(Rc::from(Value::thunk(Thunk::new(
+            term.clone(),
+            values.clone(),
+        )))),
+    }
+}
+
+/// Force a thunk, evaluating its term if it has not already been forced.
+fn force_thunk(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    thunk: &Thunk,
+) -> Result<Rc<Value>, EvalError> {
+    thunk.force(|values, term| eval_term(prims, metas, budget, values, term))
+}
+
+/// Force the thunks in head position of a value, leaving any global
+/// definitions folded.
+fn force_thunks(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    value: &Rc<Value>,
+) -> Result<Rc<Value>, EvalError> {
+    let mut value = value.clone();
+    while let Value::Neutral(Head::Thunk(thunk), spine) = value.as_ref() {
+        let thunk_value = force_thunk(prims, metas, budget, thunk)?;
+        value = eval_spine(prims, metas, budget, thunk_value, spine)?;
+    }
+    Ok(value)
+}
+
+/// Take a single step of evaluation on a term.
+fn eval_step(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    values: var::Env<Rc<Value>>,
+    term: &Rc<Term>,
+) -> Result<EvalStep, EvalError> {
+    match term.as_ref() {
+        Term::Var(var_index) => match values.lookup_entry(*var_index) {
+            Some(value) => Ok(EvalStep::Value(value.clone())),
+            None => Err(EvalError::UnboundVariable(*var_index)),
+        },
+        Term::Meta(meta_level) => match metas.lookup_solution(*meta_level) {
+            Some((_, meta::Solution::Solved(value), _, _)) => Ok(EvalStep::Value(value.clone())),
+            Some((_, meta::Solution::Unsolved, _, _)) => {
+                Ok(EvalStep::Value(Rc::from(Value::meta(*meta_level))))
+            },
+            None => Err(EvalError::UnboundMeta(*meta_level)),
+        },
+        Term::Prim(prim_name) => Ok(EvalStep::Value(eval_prim(prims, prim_name, &[])?.0)),
+
+        Term::Ann(term, _) => Ok(EvalStep::Term(values, term.clone())),
+        Term::Let(items, body) => {
+            let mut values = values;
+            for item in items {
+                if let Item::Definition(_, _, term) = item {
+                    let value = delay(&values, term)?;
+                    values.add_entry(value);
+                }
+            }
+
+            Ok(EvalStep::Term(values, body.clone()))
+        },
+        Term::Fix(index, defns) => {
+            let closure = FixClosure::new(defns.clone(), values);
+            Ok(EvalStep::Value(Rc::from(Value::Neutral(
+                Head::Fix(*index, closure),
+                Vec::new(),
+            ))))
+        },
+
+        // Literals
+        Term::LiteralType(ty) => Ok(EvalStep::Value(Rc::from(Value::literal_ty(ty.clone())))),
+        Term::LiteralIntro(intro) => Ok(EvalStep::Value(Rc::from(Value::literal_intro(
+            intro.clone(),
+        )))),
+        Term::LiteralElim(scrutinee, clauses, default_body) => {
+            let cont = EvalCont::LiteralElim(clauses.clone(), default_body.clone());
+            Ok(EvalStep::frame(values, vec![scrutinee.clone()], cont))
+        },
+
+        // Functions
+        Term::FunType(app_mode, name_hint, param_ty, body_ty) => {
+            let cont = EvalCont::FunType(app_mode.clone(), name_hint.clone(), body_ty.clone());
+            Ok(EvalStep::frame(values, vec![param_ty.clone()], cont))
+        },
+        Term::FunIntro(app_mode, name_hint, body) => {
+            let app_mode = app_mode.clone();
+            let name_hint = name_hint.clone();
+            let body = AppClosure::new(body.clone(), values);
+
+            Ok(EvalStep::Value(Rc::from(Value::FunIntro(
+                app_mode, name_hint, body,
+            ))))
+        },
+        Term::FunElim(fun, app_mode, arg) => {
+            let cont = EvalCont::FunElim(app_mode.clone());
+            Ok(EvalStep::frame(
+                values,
+                vec![fun.clone(), arg.clone()],
+                cont,
+            ))
+        },
+
+        // Records
+        Term::RecordType(fields) => match fields.split_first() {
+            None => Ok(EvalStep::Value(Rc::from(Value::RecordTypeEmpty))),
+            Some(((doc, label, name_hint, ty, manifest), rest)) => {
+                let mut pending = vec![ty.clone()];
+                pending.extend(manifest.clone());
+                // FIXME: Seems expensive?
+                let cont = EvalCont::RecordType(
+                    doc.clone(),
+                    label.clone(),
+                    name_hint.clone(),
+                    rest.to_vec(),
+                );
+
+                Ok(EvalStep::frame(values, pending, cont))
+            },
+        },
+        Term::RecordIntro(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(label, term)| Ok((label.clone(), delay(&values, term)?)))
+                .collect::<Result<_, EvalError>>()?;
+
+            Ok(EvalStep::Value(Rc::from(Value::RecordIntro(fields))))
+        },
+        Term::RecordElim(record, label) => {
+            let cont = EvalCont::RecordElim(label.clone());
+            Ok(EvalStep::frame(values, vec![record.clone()], cont))
+        },
+
+        // Data types
+        Term::DataType(data) => {
+            let closure = DataClosure::new(data.clone(), values);
+            Ok(EvalStep::Value(Rc::from(Value::DataType(
+                closure,
+                Vec::new(),
+            ))))
+        },
+        Term::DataIntro(data, label) => {
+            let closure = DataClosure::new(data.clone(), values);
+            Ok(EvalStep::Value(Rc::from(Value::DataIntro(
+                closure,
+                label.clone(),
+                Vec::new(),
+            ))))
+        },
+        Term::DataElim(scrutinee, clauses) => {
+            let cont = EvalCont::DataElim(clauses.clone());
+            Ok(EvalStep::frame(values, vec![scrutinee.clone()], cont))
+        },
+
+        // Equality
+        Term::EqType(ty, lhs, rhs) => {
+            let pending = vec![ty.clone(), lhs.clone(), rhs.clone()];
+            Ok(EvalStep::frame(values, pending, EvalCont::EqType))
+        },
+        Term::EqIntro => Ok(EvalStep::Value(Rc::from(Value::EqIntro))),
+        Term::EqElim(proof, motive, body) => {
+            let pending = vec![proof.clone(), motive.clone(), body.clone()];
+            Ok(EvalStep::frame(values, pending, EvalCont::EqElim))
+        },
+
+        // Universe levels
+        Term::LevelType => Ok(EvalStep::Value(Rc::from(Value::LevelType))),
+        Term::LevelIntro(level) => Ok(EvalStep::Value(Rc::from(Value::Level(Level::constant(
+            *level,
+        ))))),
+        Term::LevelOmega => Ok(EvalStep::Value(Rc::from(Value::Level(Level::omega())))),
+        Term::LevelShift(level, shift) => {
+            let cont = EvalCont::LevelShift(*shift);
+            Ok(EvalStep::frame(values, vec![level.clone()], cont))
+        },
+        Term::LevelMax(lhs, rhs) => {
+            let pending = vec![lhs.clone(), rhs.clone()];
+            Ok(EvalStep::frame(values, pending, EvalCont::LevelMax))
+        },
+
+        // Universes
+        Term::Universe(level) => Ok(EvalStep::frame(
+            values,
+            vec![level.clone()],
+            EvalCont::Universe,
+        )),
+    }
+}
+
+/// Finish evaluating a frame, once the values of all of its subterms are
+/// known.
+fn finish_eval(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    frame: EvalFrame,
+) -> Result<EvalStep, EvalError> {
+    let values = frame.values;
+    let mut done = frame.done.into_iter();
+
+    match frame.cont {
+        EvalCont::LiteralElim(clauses, default_body) => {
+            let scrutinee = next_value(&mut done);
+            let closure = LiteralClosure::new(clauses, default_body, values);
+
+            literal_elim_step(scrutinee, closure)
+        },
+        EvalCont::FunType(app_mode, name_hint, body_ty) => {
+            let param_ty = next_value(&mut done);
+            let body_ty = AppClosure::new(body_ty, values);
+
+            Ok(EvalStep::Value(Rc::from(Value::FunType(
+                app_mode, name_hint, param_ty, body_ty,
+            ))))
+        },
+        EvalCont::FunElim(app_mode) => {
+            let fun = next_value(&mut done);
+            let arg = next_value(&mut done);
+
+            fun_elim_step(fun, &app_mode, arg)
+        },
+        EvalCont::RecordType(doc, label, name_hint, rest_fields) => {
+            let ty = next_value(&mut done);
+            let manifest = done.next();
+            let rest = AppClosure::new(Rc::from(Term::RecordType(rest_fields)), values);
+
+            Ok(EvalStep::Value(Rc::from(Value::RecordTypeExtend(
+                doc, label, name_hint, ty, manifest, rest,
+            ))))
+        },
+        EvalCont::RecordElim(label) => {
+            let record = next_value(&mut done);
+            Ok(EvalStep::Value(eval_record_elim(record, &label)?))
+        },
+        EvalCont::DataElim(clauses) => {
+            let scrutinee = next_value(&mut done);
+            let closure = CaseClosure::new(clauses, values);
+
+            data_elim_step(scrutinee, closure)
+        },
+        EvalCont::EqType => {
+            let ty = next_value(&mut done);
+            let lhs = next_value(&mut done);
+            let rhs = next_value(&mut done);
+
+            Ok(EvalStep::Value(Rc::from(Value::EqType(ty, lhs, rhs))))
+        },
+        EvalCont::EqElim => {
+            let proof = next_value(&mut done);
+            let motive = next_value(&mut done);
+            let body = next_value(&mut done);
+
+            Ok(EvalStep::Value(eval_eq_elim(proof, motive, body)?))
+        },
+        EvalCont::LevelShift(shift) => {
+            let level = value_to_level(prims, metas, budget, &next_value(&mut done))?;
+            match level.shift(shift) {
+                Some(level) => Ok(EvalStep::Value(level_to_value(level))),
+                None => Err(EvalError::OverflowingUniverseLevel),
+            }
+        },
+        EvalCont::LevelMax => {
+            let lhs = value_to_level(prims, metas, budget, &next_value(&mut done))?;
+            let rhs = value_to_level(prims, metas, budget, &next_value(&mut done))?;
+
+            Ok(EvalStep::Value(level_to_value(lhs.max(&rhs))))
+        },
+        EvalCont::Universe => {
+            let level = value_to_level(prims, metas, budget, &next_value(&mut done))?;
+            Ok(EvalStep::Value(Rc::from(Value::Universe(level))))
+        },
+    }
+}
+
+/// Take the next value from the subterms of a frame.
+fn next_value(done: &mut impl Iterator<Item = Rc<Value>>) -> Rc<Value> {
+    done.next().expect("missing value for subterm")
+}
+
+/// Read a value back into the core syntax, normalizing as required.
+pub fn read_back_value(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    unfold: Unfold,
+    value: &Rc<Value>,
+) -> Result<Rc<Term>, EvalError> {
+    let step = ReadBackStep::Value(size, value.clone());
+    run_read_back(prims, metas, budget, unfold, step)
+}
+
+/// Read a level back into the core syntax.
+pub fn read_back_level(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    unfold: Unfold,
+    level: &Level,
+) -> Result<Rc<Term>, EvalError> {
+    let step = level_step(size, level, ReadBackCont::Level);
+    run_read_back(prims, metas, budget, unfold, step)
+}
+
+/// Read a data type declaration back into the core syntax.
+///
+/// The types of the constructors are read back with the type former bound as
+/// a variable, so this will always terminate.
+pub fn read_back_data(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    unfold: Unfold,
+    closure: &DataClosure,
+) -> Result<Rc<Data>, EvalError> {
+    let (shape, pending) = data_values(prims, metas, budget, size, closure)?;
+    let terms = pending
+        .into_iter()
+        .map(|(size, value)| {
+            let step = ReadBackStep::Value(size, value);
+            run_read_back(prims, metas, budget, unfold, step)
+        })
+        .collect::<Result<Vec<_>, EvalError>>()?;
+
+    Ok(finish_data(shape, &mut terms.into_iter()))
+}
+
+/// Read a neutral value back into the core syntax, normalizing as required.
+pub fn read_back_neutral(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    unfold: Unfold,
+    head: &Head,
+    spine: &Spine,
+) -> Result<Rc<Term>, EvalError> {
+    let step = neutral_step(prims, metas, budget, size, unfold, head, spine)?;
+    run_read_back(prims, metas, budget, unfold, step)
+}
+
+/// A step in the read-back of a value.
+enum ReadBackStep {
+    /// Read back a value in an environment of the given size.
+    Value(var::Size, Rc<Value>),
+    /// Continue reading back the values in a frame.
+    Frame(ReadBackFrame),
+    /// The value was read back into a term.
+    Term(Rc<Term>),
+}
+
+impl ReadBackStep {
+    fn frame(pending: Vec<(var::Size, Rc<Value>)>, cont: ReadBackCont) -> ReadBackStep {
+        ReadBackStep::Frame(ReadBackFrame::new(pending, cont))
+    }
+}
+
+/// A value whose read-back is waiting on the terms of the values it contains.
+struct ReadBackFrame {
+    /// The values that remain to be read back, in reverse order.
+    pending: Vec<(var::Size, Rc<Value>)>,
+    /// The terms that have been read back so far.
+    done: Vec<Rc<Term>>,
+    /// What to do once all of the values have been read back.
+    cont: ReadBackCont,
+}
+
+impl ReadBackFrame {
+    fn new(mut pending: Vec<(var::Size, Rc<Value>)>, cont: ReadBackCont) -> ReadBackFrame {
+        pending.reverse();
+        ReadBackFrame {
+            done: Vec::with_capacity(pending.len()),
+            pending,
+            cont,
+        }
+    }
+}
+
+/// What to do with the terms that were read back in a read-back frame.
+enum ReadBackCont {
+    /// Build a function type from the parameter type and the body type.
+    FunType(AppMode, Option<Symbol>),
+    /// Build a function from its body.
+    FunIntro(AppMode, Option<Symbol>),
+    /// Build a record type from the types and manifest values of its fields.
+    /// The flag records whether the field has a manifest value.
+    RecordType(Vec<(DocString, Label, Option<Symbol>, bool)>),
+    /// Build a record from the values of its fields.
+    RecordIntro(Vec<Label>),
+    /// Build a data type from its declaration, applied to some arguments.
+    DataType(DataShape, Vec<AppMode>),
+    /// Build a constructor from its data type declaration, applied to some
+    /// arguments.
+    DataIntro(DataShape, Label, Vec<AppMode>),
+    /// Build an equality type from its type and the values being compared.
+    EqType,
+    /// Build a level from the terms that were read back from its neutral
+    /// parts.
+    Level(Level),
+    /// Build a universe at a level.
+    Universe(Level),
+    /// Apply a spine of eliminators to a neutral head.
+    Neutral(NeutralHead, Vec<ElimShape>),
+}
+
+/// The parts of a data type declaration that aren't read back from values.
+struct DataShape {
+    label: Label,
+    param_count: usize,
+    intros: Vec<(DocString, Label)>,
+}
+
+/// The head of a neutral value that is being read back.
+enum NeutralHead {
+    Term(Rc<Term>),
+    /// A group of recursive definitions, with the types and bodies of the
+    /// definitions being read back.
+    Fix(usize, Vec<(Label, Guard)>),
+}
+
+/// The parts of an eliminator that aren't read back from values.
+enum ElimShape {
+    /// The literals of the clauses, followed by the default clause.
+    Literal(Vec<LiteralIntro>),
+    Fun(AppMode),
+    Record(Label),
+    Data(Vec<(Label, Vec<Option<Symbol>>)>),
+    Eq,
+}
+
+/// Run a read-back to completion.
+///
+/// Like `run_eval`, this keeps an explicit stack of frames rather than
+/// recursing into the values being read back. Closures and clauses are still
+/// evaluated with nested calls to `run_eval`.
+fn run_read_back(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    unfold: Unfold,
+    step: ReadBackStep,
+) -> Result<Rc<Term>, EvalError> {
+    let _depth = budget.enter()?;
+    let mut frames = Vec::new();
+    let mut step = step;
+
+    loop {
+        step = match step {
+            ReadBackStep::Value(size, value) => {
+                budget.step()?;
+                read_back_step(prims, metas, budget, size, unfold, &value)?
+            },
+            ReadBackStep::Frame(mut frame) => match frame.pending.pop() {
+                Some((size, value)) => {
+                    frames.push(frame);
+                    ReadBackStep::Value(size, value)
+                },
+                None => ReadBackStep::Term(finish_read_back(frame)),
+            },
+            ReadBackStep::Term(term) => match frames.pop() {
+                None => return Ok(term),
+                Some(mut frame) => {
+                    frame.done.push(term);
+                    ReadBackStep::Frame(frame)
+                },
+            },
+        };
+    }
+}
+
+/// Take a single step of read-back on a value.
+fn read_back_step(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    unfold: Unfold,
+    value: &Rc<Value>,
+) -> Result<ReadBackStep, EvalError> {
+    match value.as_ref() {
+        Value::Neutral(head, spine) => {
+            neutral_step(prims, metas, budget, size, unfold, head, spine)
+        },
+
+        // Literals
+        Value::LiteralType(literal_ty) => Ok(ReadBackStep::Term(Rc::from(Term::literal_ty(
+            literal_ty.clone(),
+        )))),
+        Value::LiteralIntro(literal_intro) => Ok(ReadBackStep::Term(Rc::from(
+            Term::literal_intro(literal_intro.clone()),
+        ))),
+
+        // Functions
+        Value::FunType(app_mode, name_hint, param_ty, body_ty) => {
+            let body_ty = inst_closure(prims, metas, budget, size, body_ty)?;
+            let pending = vec![(size, param_ty.clone()), (size + 1, body_ty)];
+            let cont = ReadBackCont::FunType(app_mode.clone(), name_hint.clone());
+
+            Ok(ReadBackStep::frame(pending, cont))
+        },
+        Value::FunIntro(app_mode, name_hint, body) => {
+            let body = inst_closure(prims, metas, budget, size, body)?;
+            let cont = ReadBackCont::FunIntro(app_mode.clone(), name_hint.clone());
+
+            Ok(ReadBackStep::frame(vec![(size + 1, body)], cont))
+        },
+
+        // Records
+        Value::RecordTypeExtend(..) => {
+            let mut size = size;
+            let mut rest_ty = value.clone();
+            let mut pending = Vec::new();
+            let mut fields = Vec::new();
+
+            while let Value::RecordTypeExtend(
+                doc,
+                label,
+                name_hint,
+                term_ty,
+                manifest,
+                next_rest_ty,
+            ) = rest_ty.as_ref()
+            {
+                pending.push((size, term_ty.clone()));
+                if let Some(manifest) = manifest {
+                    pending.push((size, manifest.clone()));
+                }
+                fields.push((
+                    doc.clone(),
+                    label.clone(),
+                    name_hint.clone(),
+                    manifest.is_some(),
+                ));
+                rest_ty = inst_closure(prims, metas, budget, size, next_rest_ty)?;
+                size += 1;
+            }
+
+            Ok(ReadBackStep::frame(
+                pending,
+                ReadBackCont::RecordType(fields),
+            ))
+        },
+        Value::RecordTypeEmpty => Ok(ReadBackStep::Term(Rc::from(Term::RecordType(Vec::new())))),
+        Value::RecordIntro(fields) => {
+            let labels = fields.iter().map(|(label, _)| label.clone()).collect();
+            let pending = fields
+                .iter()
+                .map(|(_, value)| (size, value.clone()))
+                .collect();
+
+            Ok(ReadBackStep::frame(
+                pending,
+                ReadBackCont::RecordIntro(labels),
+            ))
+        },
+
+        // Data types
+        Value::DataType(closure, args) => {
+            let (shape, mut pending) = data_values(prims, metas, budget, size, closure)?;
+            pending.extend(args.iter().map(|(_, arg)| (size, arg.clone())));
+            let app_modes = args.iter().map(|(app_mode, _)| app_mode.clone()).collect();
+
+            Ok(ReadBackStep::frame(
+                pending,
+                ReadBackCont::DataType(shape, app_modes),
+            ))
+        },
+        Value::DataIntro(closure, label, args) => {
+            let (shape, mut pending) = data_values(prims, metas, budget, size, closure)?;
+            pending.extend(args.iter().map(|(_, arg)| (size, arg.clone())));
+            let app_modes = args.iter().map(|(app_mode, _)| app_mode.clone()).collect();
+            let cont = ReadBackCont::DataIntro(shape, label.clone(), app_modes);
+
+            Ok(ReadBackStep::frame(pending, cont))
+        },
+
+        // Equality
+        Value::EqType(ty, lhs, rhs) => {
+            let pending = vec![(size, ty.clone()), (size, lhs.clone()), (size, rhs.clone())];
+            Ok(ReadBackStep::frame(pending, ReadBackCont::EqType))
+        },
+        Value::EqIntro => Ok(ReadBackStep::Term(Rc::from(Term::EqIntro))),
+
+        // Universe levels
+        Value::LevelType => Ok(ReadBackStep::Term(Rc::from(Term::LevelType))),
+        Value::Level(level) => Ok(level_step(size, level, ReadBackCont::Level)),
+
+        // Universes
+        Value::Universe(level) => Ok(level_step(size, level, ReadBackCont::Universe)),
+    }
+}
+
+/// Begin reading back the neutral parts of a level.
+fn level_step(
+    size: var::Size,
+    level: &Level,
+    cont: impl FnOnce(Level) -> ReadBackCont,
+) -> ReadBackStep {
+    let pending = level
+        .neutrals()
+        .iter()
+        .map(|(value, _)| (size, value.clone()))
+        .collect();
+
+    ReadBackStep::frame(pending, cont(level.clone()))
+}
+
+/// Evaluate the type of a data type declaration and the types of its
+/// constructors, ready to be read back.
+fn data_values(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    closure: &DataClosure,
+) -> Result<(DataShape, Vec<(var::Size, Rc<Value>)>), EvalError> {
+    let mut pending = Vec::with_capacity(closure.data.intros.len() + 1);
+    pending.push((size, data_ty(prims, metas, budget, closure)?));
+
+    let mut intro_values = closure.values.clone();
+    intro_values.add_entry(Rc::from(Value::var(size.next_level())));
+    for (_, _, intro_ty) in &closure.data.intros {
+        let intro_ty = eval_term(prims, metas, budget, &intro_values, intro_ty)?;
+        pending.push((size + 1, intro_ty));
+    }
+
+    let shape = DataShape {
+        label: closure.data.label.clone(),
+        param_count: closure.data.param_count,
+        intros: closure
+            .data
+            .intros
+            .iter()
+            .map(|(docs, label, _)| (docs.clone(), label.clone()))
+            .collect(),
+    };
+
+    Ok((shape, pending))
+}
+
+/// Resolve the head of a neutral value, beginning the read-back of its spine
+/// if it can't be unfolded any further.
+fn neutral_step(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    unfold: Unfold,
+    head: &Head,
+    spine: &Spine,
+) -> Result<ReadBackStep, EvalError> {
+    let mut pending = Vec::new();
+    let head = match head {
+        Head::Var(var_level) => NeutralHead::Term(Rc::from(Term::var(size.index(*var_level)))),
+        Head::Global(var_level, value) => match unfold {
+            Unfold::None => NeutralHead::Term(Rc::from(Term::var(size.index(*var_level)))),
+            Unfold::All => {
+                let value = eval_spine(prims, metas, budget, value.clone(), spine)?;
+                return Ok(ReadBackStep::Value(size, value));
+            },
+        },
+        // Thunks have no name to fold them back to, so they are always forced
+        Head::Thunk(_) => {
+            let value = Rc::from(Value::Neutral(head.clone(), spine.clone()));
+            let value = match unfold {
+                Unfold::All => force_value(prims, metas, budget, &value)?,
+                Unfold::None => force_thunks(prims, metas, budget, &value)?,
+            };
+            return Ok(ReadBackStep::Value(size, value));
+        },
+        Head::Fix(index, closure) => {
+            if unfold == Unfold::All
+                && is_fix_unfoldable(prims, metas, budget, *index, closure, spine)?
+            {
+                let value = unfold_fix(prims, metas, budget, *index, closure)?;
+                let value = eval_spine(prims, metas, budget, value, spine)?;
+                return Ok(ReadBackStep::Value(size, value));
+            }
+
+            // The bodies of the definitions are read back with the
+            // definitions in the group bound as variables, so this will
+            // always terminate.
+            let mut body_values = closure.values.clone();
+            for defn_index in 0..closure.defns.len() {
+                body_values.add_entry(Rc::from(Value::var(size.next_level() + defn_index as u32)));
+            }
+            let body_size = size + closure.defns.len() as u32;
+
+            let mut defns = Vec::with_capacity(closure.defns.len());
+            for (label, guard, ty, body) in closure.defns.iter() {
+                let ty = eval_term(prims, metas, budget, &closure.values, ty)?;
+                let body = eval_term(prims, metas, budget, &body_values, body)?;
+                pending.push((size, ty));
+                pending.push((body_size, body));
+                defns.push((label.clone(), guard.clone()));
+            }
+
+            NeutralHead::Fix(*index, defns)
+        },
+        Head::Meta(meta_index) => match metas.lookup_solution(*meta_index) {
+            // Unfold solved metavariables, so that they don't appear in the
+            // resulting normal forms
+            Some((_, meta::Solution::Solved(value), _, _)) => {
+                let value = eval_spine(prims, metas, budget, value.clone(), spine)?;
+                return Ok(ReadBackStep::Value(size, value));
+            },
+            Some((_, meta::Solution::Unsolved, _, _)) | None => {
+                NeutralHead::Term(Rc::from(Term::meta(*meta_index)))
+            },
+        },
+        Head::Prim(prim_name) => match interpret_prim(prims, metas, budget, prim_name, spine)? {
+            Some(value) => return Ok(ReadBackStep::Value(size, value)),
+            None => NeutralHead::Term(Rc::from(Term::prim(prim_name.clone()))),
+        },
+    };
+
+    let mut elims = Vec::with_capacity(spine.len());
+    for elim in spine {
+        elims.push(match elim {
+            Elim::Literal(closure) => {
+                for (_, body) in closure.clauses.iter() {
+                    let body = eval_term(prims, metas, budget, &closure.values, body)?;
+                    pending.push((size, body));
+                }
+                let default_body =
+                    eval_term(prims, metas, budget, &closure.values, &closure.default)?;
+                pending.push((size, default_body));
+
+                let literal_intros = closure
+                    .clauses
+                    .iter()
+                    .map(|(literal_intro, _)| literal_intro.clone())
+                    .collect();
+                ElimShape::Literal(literal_intros)
+            },
+            Elim::Fun(app_mode, arg) => {
+                pending.push((size, arg.clone()));
+                ElimShape::Fun(app_mode.clone())
+            },
+            Elim::Record(label) => ElimShape::Record(label.clone()),
+            Elim::Data(closure) => {
+                for (_, name_hints, body) in closure.clauses.iter() {
+                    let mut values = closure.values.clone();
+                    for index in 0..name_hints.len() {
+                        values.add_entry(Rc::from(Value::var(size.next_level() + index as u32)));
+                    }
+                    let body_size = size + name_hints.len() as u32;
+                    let body = eval_term(prims, metas, budget, &values, body)?;
+                    pending.push((body_size, body));
+                }
+
+                let clauses = closure
+                    .clauses
+                    .iter()
+                    .map(|(label, name_hints, _)| (label.clone(), name_hints.clone()))
+                    .collect();
+                ElimShape::Data(clauses)
+            },
+            Elim::Eq(motive, body) => {
+                pending.push((size, motive.clone()));
+                pending.push((size, body.clone()));
+                ElimShape::Eq
+            },
+        });
+    }
+
+    Ok(ReadBackStep::frame(
+        pending,
+        ReadBackCont::Neutral(head, elims),
+    ))
+}
+
+/// Finish reading back a frame, once the terms of all of its values are
+/// known.
+fn finish_read_back(frame: ReadBackFrame) -> Rc<Term> {
+    let mut done = frame.done.into_iter();
+
+    match frame.cont {
+        ReadBackCont::FunType(app_mode, name_hint) => {
+            let param_ty = next_term(&mut done);
+            let body_ty = next_term(&mut done);
+
+            Rc::from(Term::FunType(app_mode, name_hint, param_ty, body_ty))
+        },
+        ReadBackCont::FunIntro(app_mode, name_hint) => {
+            Rc::from(Term::FunIntro(app_mode, name_hint, next_term(&mut done)))
+        },
+        ReadBackCont::RecordType(fields) => {
+            let field_tys = fields
+                .into_iter()
+                .map(|(doc, label, name_hint, has_manifest)| {
+                    let term_ty = next_term(&mut done);
+                    let manifest = if has_manifest {
+                        Some(next_term(&mut done))
+                    } else {
+                        None
+                    };
+                    (doc, label, name_hint, term_ty, manifest)
+                })
+                .collect();
+
+            Rc::from(Term::RecordType(field_tys))
+        },
+        ReadBackCont::RecordIntro(labels) => {
+            Rc::from(Term::RecordIntro(labels.into_iter().zip(done).collect()))
+        },
+        ReadBackCont::DataType(shape, app_modes) => {
+            let head = Rc::from(Term::DataType(finish_data(shape, &mut done)));
+            finish_args(head, app_modes, done)
+        },
+        ReadBackCont::DataIntro(shape, label, app_modes) => {
+            let head = Rc::from(Term::DataIntro(finish_data(shape, &mut done), label));
+            finish_args(head, app_modes, done)
+        },
+        ReadBackCont::EqType => {
+            let ty = next_term(&mut done);
+            let lhs = next_term(&mut done);
+            let rhs = next_term(&mut done);
+
+            Rc::from(Term::EqType(ty, lhs, rhs))
+        },
+        ReadBackCont::Level(level) => finish_level(&level, done),
+        ReadBackCont::Universe(level) => Rc::from(Term::Universe(finish_level(&level, done))),
+        ReadBackCont::Neutral(head, elims) => {
+            let head = match head {
+                NeutralHead::Term(term) => term,
+                NeutralHead::Fix(index, defns) => {
+                    let defns = defns
+                        .into_iter()
+                        .map(|(label, guard)| {
+                            let ty = next_term(&mut done);
+                            let body = next_term(&mut done);
+                            (label, guard, ty, body)
+                        })
+                        .collect::<Vec<_>>();
+
+                    Rc::from(Term::Fix(index, Rc::from(defns)))
+                },
+            };
+
+            elims.into_iter().fold(head, |acc, elim| match elim {
+                ElimShape::Literal(literal_intros) => {
+                    let clauses = literal_intros
+                        .into_iter()
+                        .map(|literal_intro| (literal_intro, next_term(&mut done)))
+                        .collect::<Vec<_>>();
+                    let default_body = next_term(&mut done);
+
+                    Rc::from(Term::LiteralElim(acc, Rc::from(clauses), default_body))
+                },
+                ElimShape::Fun(app_mode) => {
+                    Rc::from(Term::FunElim(acc, app_mode, next_term(&mut done)))
+                },
+                ElimShape::Record(label) => Rc::from(Term::RecordElim(acc, label)),
+                ElimShape::Data(clauses) => {
+                    let clauses = clauses
+                        .into_iter()
+                        .map(|(label, name_hints)| (label, name_hints, next_term(&mut done)))
+                        .collect::<Vec<_>>();
+
+                    Rc::from(Term::DataElim(acc, Rc::from(clauses)))
+                },
+                ElimShape::Eq => {
+                    let motive = next_term(&mut done);
+                    let body = next_term(&mut done);
+
+                    Rc::from(Term::EqElim(acc, motive, body))
+                },
+            })
+        },
+    }
+}
+
+/// Take the next term from the values that were read back in a frame.
+fn next_term(done: &mut impl Iterator<Item = Rc<Term>>) -> Rc<Term> {
+    done.next().expect("missing term for value")
+}
+
+/// Rebuild a data type declaration from its shape and the terms that were
+/// read back from its type and the types of its constructors.
+fn finish_data(shape: DataShape, done: &mut impl Iterator<Item = Rc<Term>>) -> Rc<Data> {
+    let ty = next_term(done);
+    let intros = shape
+        .intros
+        .into_iter()
+        .map(|(docs, label)| (docs, label, next_term(done)))
+        .collect();
+
+    Rc::from(Data {
+        label: shape.label,
+        param_count: shape.param_count,
+        ty,
+        intros,
+    })
+}
+
+/// Apply a head to the arguments that were read back.
+fn finish_args(
+    head: Rc<Term>,
+    app_modes: Vec<AppMode>,
+    args: impl Iterator<Item = Rc<Term>>,
+) -> Rc<Term> {
+    app_modes
+        .into_iter()
+        .zip(args)
+        .fold(head, |acc, (app_mode, arg)| {
+            Rc::from(Term::FunElim(acc, app_mode, arg))
+        })
+}
+
+/// Rebuild a level from the terms that were read back from its neutral parts.
+fn finish_level(level: &Level, neutrals: impl Iterator<Item = Rc<Term>>) -> Rc<Term> {
+    let (constant, shifts) = match level {
+        Level::Finite(constant, shifts) => (*constant, shifts.iter().map(|(_, shift)| *shift)),
+        Level::Omega => return Rc::from(Term::LevelOmega),
+    };
+
+    // The constant part can be omitted if it's zero and there are some
+    // neutral parts
+    let mut term = match constant {
+        UniverseLevel(0) if !level.neutrals().is_empty() => None,
+        constant => Some(Rc::from(Term::LevelIntro(constant))),
+    };
+
+    for (neutral, shift) in neutrals.zip(shifts) {
+        let neutral = match neutral {
+            neutral if shift == 0 => neutral,
+            neutral => Rc::from(Term::LevelShift(neutral, shift)),
+        };
+        term = Some(match term {
+            None => neutral,
+            Some(term) => Rc::from(Term::LevelMax(term, neutral)),
+        });
+    }
+
+    term.unwrap_or_else(|| Rc::from(Term::LevelIntro(UniverseLevel(0))))
+}
+
+/// Remove the `amount` innermost entries from the scope of a level that was
+/// found in a context of the given size, returning `None` if any of them are
+/// referred to in the level.
+pub fn strengthen_level(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    amount: u32,
+    level: &Level,
+) -> Result<Option<Level>, EvalError> {
+    let level = force_level(prims, metas, budget, level)?;
+    for (value, _) in level.neutrals() {
+        let term = read_back_value(prims, metas, budget, size, Unfold::None, value)?;
+        if term.strengthen(amount).is_none() {
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(level))
+}
+
+/// Fully normalize a term by first evaluating it, then reading it back.
+pub fn normalize_term(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    values: &var::Env<Rc<Value>>,
+    term: &Rc<Term>,
+) -> Result<Rc<Term>, EvalError> {
+    let value = eval_term(prims, metas, budget, values, term)?;
+    read_back_value(prims, metas, budget, values.size(), Unfold::All, &value)
+}
+
+/// Evaluate a value further, if it's now possible due to updates made to the
+/// metavariable solutions, unfolding any global definitions, thunks,
+/// recursive definitions, or primitive operations in head position. Thunks
+/// are only forced here, or by `force_thunks` when reading back values without
+/// unfolding global definitions.
+///
+/// Recursive definitions are only unfolded once their guards allow it, but
+/// note that this may still loop forever on definitions that were marked as
+/// `partial`.
+pub fn force_value(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    value: &Rc<Value>,
+) -> Result<Rc<Value>, EvalError> {
+    let mut value = value.clone();
+
+    loop {
+        value = match value.as_ref() {
+            Value::Neutral(Head::Global(_, global_value), spine) => {
+                eval_spine(prims, metas, budget, global_value.clone(), spine)?
+            },
+            Value::Neutral(Head::Thunk(thunk), spine) => {
+                let thunk_value = force_thunk(prims, metas, budget, thunk)?;
+                eval_spine(prims, metas, budget, thunk_value, spine)?
+            },
+            Value::Neutral(Head::Fix(index, closure), spine) => {
+                if !is_fix_unfoldable(prims, metas, budget, *index, closure, spine)? {
+                    return Ok(value.clone());
+                }
+                let fix_value = unfold_fix(prims, metas, budget, *index, closure)?;
+                eval_spine(prims, metas, budget, fix_value, spine)?
+            },
+            Value::Neutral(Head::Prim(prim_name), spine) => {
+                match interpret_prim(prims, metas, budget, prim_name, spine)? {
+                    Some(prim_value) => prim_value,
+                    None => return Ok(value.clone()),
+                }
+            },
+            Value::Neutral(Head::Meta(meta_level), spine) => {
+                match metas.lookup_solution(*meta_level) {
+                    Some((_, meta::Solution::Solved(meta_value), _, _)) => {
+                        eval_spine(prims, metas, budget, meta_value.clone(), spine)?
+                    },
+                    Some((_, meta::Solution::Unsolved, _, _)) | None => return Ok(value.clone()),
+                }
+            },
+            Value::Level(level) => {
+                return Ok(level_to_value(force_level(prims, metas, budget, level)?));
+            },
+            Value::Universe(level) => {
+                let level = force_level(prims, metas, budget, level)?;
+                return Ok(Rc::from(Value::Universe(level)));
+            },
+            _ => return Ok(value.clone()),
+        };
+    }
+}
+
+/// Apply a primitive to a spine of eliminators, returning `None` if the
+/// primitive is stuck.
+fn interpret_prim(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    prim_name: &prim::Name,
+    spine: &[Elim],
+) -> Result<Option<Rc<Value>>, EvalError> {
+    let prim = prims
+        .lookup_entry(prim_name)
+        .ok_or_else(|| EvalError::UnknownPrim(prim_name.clone()))?;
+    // Force the arguments, so that the primitive can inspect them
+    let forced_spine = force_spine(prims, metas, budget, spine)?;
+
+    match prim.interpret(&forced_spine) {
+        Some(result) => {
+            let (value, rest_spine) = result?;
+            Ok(Some(eval_spine(prims, metas, budget, value, rest_spine)?))
+        },
+        None => Ok(None),
+    }
+}
+
+/// Force the arguments of the function eliminators in a spine.
+fn force_spine(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    spine: &[Elim],
+) -> Result<Spine, EvalError> {
+    spine
+        .iter()
+        .map(|elim| match elim {
+            Elim::Fun(app_mode, arg) => Ok(Elim::Fun(
+                app_mode.clone(),
+                force_value(prims, metas, budget, arg)?,
+            )),
+            Elim::Literal(_) | Elim::Record(_) | Elim::Data(_) | Elim::Eq(_, _) => Ok(elim.clone()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::literal::{LiteralIntro, LiteralType};
+
+    /// A partial definition that counts down to zero:
+    ///
+    /// ```text
+    /// count-down : U32 -> U32;
+    /// partial count-down n = case n {
+    ///     0 => 0;
+    ///     _ => count-down (u32-sub n 1);
+    /// };
+    /// ```
+    fn count_down() -> Rc<Term> {
+        let u32_ty = Rc::from(Term::literal_ty(LiteralType::U32));
+        let ty = Rc::from(Term::FunType(
+            AppMode::Explicit,
+            None,
+            u32_ty.clone(),
+            u32_ty,
+        ));
+        let pred = Rc::from(Term::FunElim(
+            Rc::from(Term::FunElim(
+                Rc::from(Term::prim("u32-sub")),
+                AppMode::Explicit,
+                Rc::from(Term::var(0)),
+            )),
+            AppMode::Explicit,
+            Rc::from(Term::literal_intro(1u32)),
+        ));
+        let body = Rc::from(Term::FunIntro(
+            AppMode::Explicit,
+            Some(Symbol::intern("n")),
+            Rc::from(Term::LiteralElim(
+                Rc::from(Term::var(0)),
+                Rc::from(vec![(
+                    LiteralIntro::from(0u32),
+                    Rc::from(Term::literal_intro(0u32)),
+                )]),
+                Rc::from(Term::FunElim(
+                    Rc::from(Term::var(1)),
+                    AppMode::Explicit,
+                    pred,
+                )),
+            )),
+        ));
+        let label = Label::new("count-down");
+
+        Rc::from(Term::Fix(
+            0,
+            Rc::from(vec![(label, Guard::Partial, ty, body)]),
+        ))
+    }
+
+    #[test]
+    fn fix_unfold_closed() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let values = var::Env::new();
+
+        let arg = Rc::from(Term::literal_intro(3u32));
+        let term = Rc::from(Term::FunElim(count_down(), AppMode::Explicit, arg));
+
+        assert_eq!(
+            normalize_term(&prims, &metas, &Budget::default(), &values, &term).unwrap(),
+            Rc::from(Term::literal_intro(0u32)),
+        );
+    }
+
+    #[test]
+    fn fix_folded_open() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let mut values = var::Env::new();
+        values.add_entry(Rc::from(Value::var(0)));
+
+        let arg = Rc::from(Term::var(0));
+        let term = Rc::from(Term::FunElim(count_down(), AppMode::Explicit, arg));
+        let value = eval_term(&prims, &metas, &Budget::default(), &values, &term).unwrap();
+
+        assert_eq!(
+            force_value(&prims, &metas, &Budget::default(), &value).unwrap(),
+            value
+        );
+        assert!(Term::alpha_eq(
+            &normalize_term(&prims, &metas, &Budget::default(), &values, &term).unwrap(),
+            &term,
+        ));
+    }
+
+    #[test]
+    fn eq_elim_refl() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let mut values = var::Env::new();
+        values.add_entry(Rc::from(Value::var(0)));
+        values.add_entry(Rc::from(Value::var(1)));
+
+        let motive = Rc::from(Term::var(1));
+        let body = Rc::from(Term::literal_intro(1u32));
+        let refl_term = Rc::from(Term::EqElim(
+            Rc::from(Term::EqIntro),
+            motive.clone(),
+            body.clone(),
+        ));
+        let neutral_term = Rc::from(Term::EqElim(Rc::from(Term::var(0)), motive, body.clone()));
+
+        assert_eq!(
+            normalize_term(&prims, &metas, &Budget::default(), &values, &refl_term).unwrap(),
+            body,
+        );
+        assert!(Term::alpha_eq(
+            &normalize_term(&prims, &metas, &Budget::default(), &values, &neutral_term).unwrap(),
+            &neutral_term,
+        ));
+    }
+
+    #[test]
+    fn level_max_normalize() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let mut values = var::Env::new();
+        values.add_entry(Rc::from(Value::var(0)));
+
+        let shifted = Rc::from(Term::LevelShift(Rc::from(Term::var(0)), 1));
+        let term = Rc::from(Term::LevelMax(
+            shifted.clone(),
+            Rc::from(Term::LevelIntro(UniverseLevel(1))),
+        ));
+
+        assert_eq!(
+            normalize_term(&prims, &metas, &Budget::default(), &values, &term).unwrap(),
+            shifted,
+        );
+    }
+
+    #[test]
+    fn let_unused_definition_is_not_evaluated() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let values = var::Env::new();
+
+        // Evaluating the definition would fail, because the primitive is unknown
+        let defn = Item::Definition(
+            DocString::from(""),
+            Label::new("x"),
+            Rc::from(Term::prim("unknown-prim")),
+        );
+        let body = Rc::from(Term::literal_intro(1u32));
+        let term = Rc::from(Term::Let(vec![defn], body.clone()));
+
+        assert_eq!(
+            normalize_term(&prims, &metas, &Budget::default(), &values, &term).unwrap(),
+            body,
+        );
+    }
+
+    #[test]
+    fn record_unprojected_field_is_not_evaluated() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let values = var::Env::new();
+
+        // Evaluating the first field would fail, because the primitive is unknown
+        let field = Rc::from(Term::literal_intro(1u32));
+        let record = Rc::from(Term::RecordIntro(vec![
+            (Label::new("x"), Rc::from(Term::prim("unknown-prim"))),
+            (Label::new("y"), field.clone()),
+        ]));
+        let term = Rc::from(Term::RecordElim(record, Label::new("y")));
+
+        assert_eq!(
+            normalize_term(&prims, &metas, &Budget::default(), &values, &term).unwrap(),
+            field,
+        );
+    }
+
+    #[test]
+    fn thunk_is_forced_once() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let budget = Budget::default();
+
+        let thunk = Thunk::new(Rc::from(Term::literal_intro(1u32)), var::Env::new());
+        let eval_count = Cell::new(0);
+        let eval = |values: &var::Env<Rc<Value>>, term: &Rc<Term>| {
+            eval_count.set(eval_count.get() + 1);
+            eval_term(&prims, &metas, &budget, values, term)
+        };
+
+        assert!(!thunk.is_forced());
+        let value1 = thunk.force(&eval).unwrap();
+        let value2 = thunk.clone().force(&eval).unwrap();
+
+        assert!(thunk.is_forced());
+        assert!(Rc::ptr_eq(&value1, &value2));
+        assert_eq!(eval_count.get(), 1);
+        assert_eq!(value1, Rc::from(Value::literal_intro(1u32)));
+    }
+
+    #[test]
+    fn thunk_reentry_is_an_error() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let budget = Budget::default();
+
+        let thunk = Thunk::new(Rc::from(Term::literal_intro(1u32)), var::Env::new());
+        let eval = |values: &var::Env<Rc<Value>>, term: &Rc<Term>| {
+            eval_term(&prims, &metas, &budget, values, term)
+        };
+
+        assert_eq!(
+            thunk.force(|_, _| thunk.force(&eval)),
+            Err(EvalError::ForcingThunk),
+        );
+        assert!(!thunk.is_forced());
+        assert_eq!(thunk.force(&eval), Ok(Rc::from(Value::literal_intro(1u32))));
+    }
+
+    #[test]
+    fn eval_unbound_var() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let values = var::Env::new();
+
+        assert_eq!(
+            eval_term(
+                &prims,
+                &metas,
+                &Budget::default(),
+                &values,
+                &Rc::from(Term::var(0))
+            ),
+            Err(EvalError::UnboundVariable(var::Index(0))),
+        );
+    }
+
+    #[test]
+    fn force_abort() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let values = var::Env::new();
+
+        let term = Rc::from(Term::FunElim(
+            Rc::from(Term::prim("abort")),
+            AppMode::Explicit,
+            Rc::from(Term::literal_intro(Rc::<str>::from("oops"))),
+        ));
+        let value = eval_term(&prims, &metas, &Budget::default(), &values, &term).unwrap();
+
+        assert_eq!(
+            force_value(&prims, &metas, &Budget::default(), &value),
+            Err(EvalError::Abort("oops".to_owned())),
+        );
+    }
+
+    #[test]
+    fn fix_unfold_step_limit() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let values = var::Env::new();
+        let budget = Budget::new(Limits {
+            steps: Some(100),
+            depth: None,
+        });
+
+        let arg = Rc::from(Term::literal_intro(1_000_000u32));
+        let term = Rc::from(Term::FunElim(count_down(), AppMode::Explicit, arg));
+
+        assert_eq!(
+            normalize_term(&prims, &metas, &budget, &values, &term),
+            Err(EvalError::LimitExceeded(Limit::Steps(100))),
+        );
+    }
+
+    #[test]
+    fn depth_limit() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let values = var::Env::new();
+        let budget = Budget::new(Limits {
+            steps: None,
+            depth: Some(1),
+        });
+
+        let u32_ty = Rc::from(Term::literal_ty(LiteralType::U32));
+        let shallow = Rc::from(Term::Ann(
+            Rc::from(Term::literal_intro(1u32)),
+            u32_ty.clone(),
+        ));
+        let fun_ty = Rc::from(Term::FunType(
+            AppMode::Explicit,
+            None,
+            u32_ty.clone(),
+            u32_ty,
+        ));
+
+        assert!(eval_term(&prims, &metas, &budget, &values, &shallow).is_ok());
+        assert_eq!(budget.steps(), 2);
+        // Reading back the body of the function type requires a nested
+        // evaluation of its closure
+        assert!(eval_term(&prims, &metas, &budget, &values, &fun_ty).is_ok());
+        assert_eq!(
+            normalize_term(&prims, &metas, &budget, &values, &fun_ty),
+            Err(EvalError::LimitExceeded(Limit::Depth(1))),
+        );
+
+        budget.reset();
+        assert_eq!(budget.steps(), 0);
+        assert!(eval_term(&prims, &metas, &budget, &values, &shallow).is_ok());
+    }
+
+    /// Apply a variable to a literal argument `count` times.
+    fn nested_apps(count: usize) -> Rc<Term> {
+        (0..count).fold(Rc::from(Term::var(0)), |acc, _| {
+            let arg = Rc::from(Term::literal_intro(1u32));
+            Rc::from(Term::FunElim(acc, AppMode::Explicit, arg))
+        })
+    }
+
+    #[test]
+    fn nested_apps_stack_safe() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let mut values = var::Env::new();
+        values.add_entry(Rc::from(Value::var(0)));
+        let budget = Budget::new(Limits {
+            steps: None,
+            depth: Some(2),
+        });
+
+        let mut term =
+            normalize_term(&prims, &metas, &budget, &values, &nested_apps(5_000)).unwrap();
+
+        // Walk down the spine in a loop, to avoid overflowing the stack
+        for _ in 0..5_000 {
+            term = match term.as_ref() {
+                Term::FunElim(head, AppMode::Explicit, arg) => {
+                    assert_eq!(*arg.as_ref(), Term::literal_intro(1u32));
+                    head.clone()
+                },
+                term => panic!("unexpected term: {:?}", term),
+            };
+        }
+        assert_eq!(*term, Term::var(0));
+    }
+
+    #[test]
+    fn normalize_matches_recursive() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let mut values = var::Env::new();
+        values.add_entry(Rc::from(Value::var(0)));
+
+        let terms = vec![
+            Rc::from(Term::FunElim(
+                count_down(),
+                AppMode::Explicit,
+                Rc::from(Term::literal_intro(3u32)),
+            )),
+            Rc::from(Term::FunElim(
+                count_down(),
+                AppMode::Explicit,
+                Rc::from(Term::var(0)),
+            )),
+            Rc::from(Term::LevelMax(
+                Rc::from(Term::LevelShift(Rc::from(Term::var(0)), 1)),
+                Rc::from(Term::LevelIntro(UniverseLevel(2))),
+            )),
+            nested_apps(100),
+        ];
+
+        for term in terms {
+            assert_eq!(
+                normalize_term(&prims, &metas, &Budget::default(), &values, &term),
+                recursive::normalize_term(&prims, &metas, &Budget::default(), &values, &term),
+            );
+        }
+    }
+
+    #[test]
+    fn read_back_globals_matches_recursive() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let budget = Budget::default();
+        let mut values = var::Env::new();
+        values.add_entry(Rc::from(Value::var(0)));
+        let count_down_value = eval_term(&prims, &metas, &budget, &values, &count_down()).unwrap();
+        values.add_entry(Rc::from(Value::global(1, count_down_value)));
+
+        let terms = vec![
+            Rc::from(Term::var(0)),
+            Rc::from(Term::FunElim(
+                Rc::from(Term::var(0)),
+                AppMode::Explicit,
+                Rc::from(Term::literal_intro(3u32)),
+            )),
+            Rc::from(Term::FunElim(
+                Rc::from(Term::var(0)),
+                AppMode::Explicit,
+                Rc::from(Term::var(1)),
+            )),
+        ];
+
+        for term in terms {
+            let value = eval_term(&prims, &metas, &budget, &values, &term).unwrap();
+            let recursive_value =
+                recursive::eval_term(&prims, &metas, &budget, &values, &term).unwrap();
+
+            for &unfold in &[Unfold::None, Unfold::All] {
+                assert_eq!(
+                    read_back_value(&prims, &metas, &budget, values.size(), unfold, &value),
+                    recursive::read_back_value(
+                        &prims,
+                        &metas,
+                        &budget,
+                        values.size(),
+                        unfold,
+                        &recursive_value,
+                    ),
+                );
+            }
+        }
+    }
+}
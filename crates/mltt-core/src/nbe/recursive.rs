@@ -0,0 +1,762 @@
+//! A direct recursive implementation of evaluation and read-back.
+//!
+//! This was the original implementation of normalization, before it was
+//! replaced by the stack-safe one in the parent module. It is much easier to
+//! follow, so we keep it around as a reference to test the stack-safe
+//! implementation against. It should produce identical results, but may
+//! overflow the stack on large terms.
+//!
+//! Let bindings and the fields of records are evaluated eagerly here, rather
+//! than being suspended in thunks, but this should not change the results of
+//! read-back. Forcing values and unfolding recursive definitions is shared
+//! with the parent module.
+
+use std::rc::Rc;
+
+use super::{
+    eval_eq_elim, eval_prim, eval_record_elim, force_thunk, interpret_prim, is_fix_unfoldable,
+    level_to_value, unfold_fix, value_to_level, Budget, EvalError, Unfold,
+};
+use crate::domain::{
+    AppClosure, CaseClosure, DataClosure, Elim, FixClosure, Head, Level, LiteralClosure, Spine,
+    Value,
+};
+use crate::syntax::{Data, Guard, Item, Term};
+use crate::{meta, prim, var, AppMode, Label, UniverseLevel};
+
+/// Evaluate an eliminator.
+fn eval_elim(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    head: Rc<Value>,
+    elim: &Elim,
+) -> Result<Rc<Value>, EvalError> {
+    match elim {
+        Elim::Literal(closure) => eval_literal_elim(prims, metas, budget, head, closure.clone()),
+        Elim::Fun(app_mode, arg) => {
+            eval_fun_elim(prims, metas, budget, head, app_mode, arg.clone())
+        },
+        Elim::Record(label) => eval_record_elim(head, label),
+        Elim::Data(closure) => eval_data_elim(prims, metas, budget, head, closure.clone()),
+        Elim::Eq(motive, body) => eval_eq_elim(head, motive.clone(), body.clone()),
+    }
+}
+
+/// Apply a spine of eliminators to a value.
+fn eval_spine(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    head: Rc<Value>,
+    spine: &[Elim],
+) -> Result<Rc<Value>, EvalError> {
+    spine.iter().fold(Ok(head), |head, elim| {
+        eval_elim(prims, metas, budget, head?, elim)
+    })
+}
+
+/// Case split on a literal.
+fn eval_literal_elim(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    scrutinee: Rc<Value>,
+    closure: LiteralClosure,
+) -> Result<Rc<Value>, EvalError> {
+    match scrutinee.as_ref() {
+        Value::LiteralIntro(literal_intro) => {
+            let index = closure.clauses.binary_search_by(|(l, _)| {
+                l.partial_cmp(literal_intro).unwrap() // NaN?
+            });
+
+            let clause_body = match index {
+                Ok(index) => &closure.clauses.get(index).unwrap().1,
+                Err(_) => &closure.default,
+            };
+            eval_term(prims, metas, budget, &closure.values, clause_body)
+        },
+        Value::Neutral(head, spine) => {
+            let mut spine = spine.clone();
+            spine.push(Elim::Literal(closure));
+            Ok(Rc::from(Value::Neutral(head.clone(), spine)))
+        },
+        _ => Err(EvalError::ExpectedLiteral { found: scrutinee }),
+    }
+}
+
+/// Case split on the constructors of a data type.
+fn eval_data_elim(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    scrutinee: Rc<Value>,
+    closure: CaseClosure,
+) -> Result<Rc<Value>, EvalError> {
+    match scrutinee.as_ref() {
+        Value::DataIntro(data_closure, label, args) => {
+            let (_, name_hints, body) = closure
+                .clauses
+                .iter()
+                .find(|(l, _, _)| l == label)
+                .ok_or_else(|| EvalError::NoClauseForIntro {
+                    label: label.clone(),
+                    found: scrutinee.clone(),
+                })?;
+
+            let param_count = data_closure.data.param_count;
+            if args.len() != param_count + name_hints.len() {
+                return Err(EvalError::UnexpectedIntroFields {
+                    label: label.clone(),
+                    found: scrutinee.clone(),
+                });
+            }
+
+            let mut values = closure.values.clone();
+            for (_, arg) in &args[param_count..] {
+                values.add_entry(arg.clone());
+            }
+            eval_term(prims, metas, budget, &values, body)
+        },
+        Value::Neutral(head, spine) => {
+            let mut spine = spine.clone();
+            spine.push(Elim::Data(closure));
+            Ok(Rc::from(Value::Neutral(head.clone(), spine)))
+        },
+        _ => Err(EvalError::ExpectedDataIntro { found: scrutinee }),
+    }
+}
+
+/// Apply a function to an argument.
+fn eval_fun_elim(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    fun: Rc<Value>,
+    app_mode: &AppMode,
+    arg: Rc<Value>,
+) -> Result<Rc<Value>, EvalError> {
+    match fun.as_ref() {
+        Value::FunIntro(fun_app_mode, _, body) => {
+            if fun_app_mode == app_mode {
+                app_closure(prims, metas, budget, body, arg)
+            } else {
+                Err(EvalError::UnexpectedAppMode {
+                    found: app_mode.clone(),
+                    expected: fun_app_mode.clone(),
+                })
+            }
+        },
+        Value::DataType(closure, args) => {
+            let mut args = args.clone();
+            args.push((app_mode.clone(), arg));
+            Ok(Rc::from(Value::DataType(closure.clone(), args)))
+        },
+        Value::DataIntro(closure, label, args) => {
+            let mut args = args.clone();
+            args.push((app_mode.clone(), arg));
+            Ok(Rc::from(Value::DataIntro(
+                closure.clone(),
+                label.clone(),
+                args,
+            )))
+        },
+        Value::Neutral(head, spine) => {
+            let mut spine = spine.clone();
+            spine.push(Elim::Fun(app_mode.clone(), arg));
+            // TODO: If head is `primitive`, and arity == number of initial spine apps in NF
+            Ok(Rc::from(Value::Neutral(head.clone(), spine)))
+        },
+        _ => Err(EvalError::ExpectedFunction { found: fun }),
+    }
+}
+
+/// Apply a closure to an argument.
+fn app_closure(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    closure: &AppClosure,
+    arg: Rc<Value>,
+) -> Result<Rc<Value>, EvalError> {
+    let mut values = closure.values.clone();
+    values.add_entry(arg);
+    eval_term(prims, metas, budget, &values, &closure.term)
+}
+
+/// Evaluate a term of type `Level` into a level.
+fn eval_level(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    values: &var::Env<Rc<Value>>,
+    term: &Rc<Term>,
+) -> Result<Level, EvalError> {
+    value_to_level(
+        prims,
+        metas,
+        budget,
+        &eval_term(prims, metas, budget, values, term)?,
+    )
+}
+
+/// Instantiate a closure in an environment of the given size.
+fn inst_closure(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    closure: &AppClosure,
+) -> Result<Rc<Value>, EvalError> {
+    let arg = Rc::from(Value::var(size.next_level()));
+    app_closure(prims, metas, budget, closure, arg)
+}
+
+/// Evaluate a term in the environment that corresponds to the context in which
+/// the term was typed.
+pub fn eval_term(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    values: &var::Env<Rc<Value>>,
+    term: &Rc<Term>,
+) -> Result<Rc<Value>, EvalError> {
+    budget.step()?;
+    let _depth = budget.enter()?;
+
+    match term.as_ref() {
+        Term::Var(var_index) => match values.lookup_entry(*var_index) {
+            Some(value) => Ok(value.clone()),
+            None => Err(EvalError::UnboundVariable(*var_index)),
+        },
+        Term::Meta(meta_level) => match metas.lookup_solution(*meta_level) {
+            Some((_, meta::Solution::Solved(value), _, _)) => Ok(value.clone()),
+            Some((_, meta::Solution::Unsolved, _, _)) => Ok(Rc::from(Value::meta(*meta_level))),
+            None => Err(EvalError::UnboundMeta(*meta_level)),
+        },
+        Term::Prim(prim_name) => Ok(eval_prim(prims, prim_name, &[])?.0),
+
+        Term::Ann(term, _) => eval_term(prims, metas, budget, values, term),
+        Term::Let(items, body) => {
+            let mut values = values.clone();
+            for item in items {
+                if let Item::Definition(_, _, term) = item {
+                    values.add_entry(eval_term(prims, metas, budget, &values, term)?);
+                }
+            }
+            eval_term(prims, metas, budget, &values, body)
+        },
+        Term::Fix(index, defns) => {
+            let closure = FixClosure::new(defns.clone(), values.clone());
+            Ok(Rc::from(Value::Neutral(
+                Head::Fix(*index, closure),
+                Vec::new(),
+            )))
+        },
+
+        // Literals
+        Term::LiteralType(ty) => Ok(Rc::from(Value::literal_ty(ty.clone()))),
+        Term::LiteralIntro(intro) => Ok(Rc::from(Value::literal_intro(intro.clone()))),
+        Term::LiteralElim(scrutinee, clauses, default_body) => {
+            let scrutinee = eval_term(prims, metas, budget, values, scrutinee)?;
+            let closure =
+                LiteralClosure::new(clauses.clone(), default_body.clone(), values.clone());
+
+            eval_literal_elim(prims, metas, budget, scrutinee, closure)
+        },
+
+        // Functions
+        Term::FunType(app_mode, name_hint, param_ty, body_ty) => {
+            let app_mode = app_mode.clone();
+            let name_hint = name_hint.clone();
+            let param_ty = eval_term(prims, metas, budget, values, param_ty)?;
+            let body_ty = AppClosure::new(body_ty.clone(), values.clone());
+
+            Ok(Rc::from(Value::FunType(
+                app_mode, name_hint, param_ty, body_ty,
+            )))
+        },
+        Term::FunIntro(app_mode, name_hint, body) => {
+            let app_mode = app_mode.clone();
+            let name_hint = name_hint.clone();
+            let body = AppClosure::new(body.clone(), values.clone());
+
+            Ok(Rc::from(Value::FunIntro(app_mode, name_hint, body)))
+        },
+        Term::FunElim(fun, app_mode, arg) => {
+            let fun = eval_term(prims, metas, budget, values, fun)?;
+            let arg = eval_term(prims, metas, budget, values, arg)?;
+
+            eval_fun_elim(prims, metas, budget, fun, app_mode, arg)
+        },
+
+        // Records
+        Term::RecordType(fields) => match fields.split_first() {
+            None => Ok(Rc::from(Value::RecordTypeEmpty)),
+            Some(((doc, label, name_hint, ty, manifest), rest)) => {
+                let doc = doc.clone();
+                let label = label.clone();
+                let name_hint = name_hint.clone();
+                let ty = eval_term(prims, metas, budget, values, ty)?;
+                let manifest = match manifest {
+                    None => None,
+                    Some(manifest) => Some(eval_term(prims, metas, budget, values, manifest)?),
+                };
+                let rest_fields = rest.iter().cloned().collect(); // FIXME: Seems expensive?
+                let rest = AppClosure::new(Rc::from(Term::RecordType(rest_fields)), values.clone());
+
+                Ok(Rc::from(Value::RecordTypeExtend(
+                    doc, label, name_hint, ty, manifest, rest,
+                )))
+            },
+        },
+        Term::RecordIntro(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(label, term)| {
+                    Ok((
+                        label.clone(),
+                        eval_term(prims, metas, budget, values, term)?,
+                    ))
+                })
+                .collect::<Result<_, EvalError>>()?;
+
+            Ok(Rc::from(Value::RecordIntro(fields)))
+        },
+        Term::RecordElim(record, label) => {
+            eval_record_elim(eval_term(prims, metas, budget, values, record)?, label)
+        },
+
+        // Data types
+        Term::DataType(data) => {
+            let closure = DataClosure::new(data.clone(), values.clone());
+            Ok(Rc::from(Value::DataType(closure, Vec::new())))
+        },
+        Term::DataIntro(data, label) => {
+            let closure = DataClosure::new(data.clone(), values.clone());
+            Ok(Rc::from(Value::DataIntro(
+                closure,
+                label.clone(),
+                Vec::new(),
+            )))
+        },
+        Term::DataElim(scrutinee, clauses) => {
+            let scrutinee = eval_term(prims, metas, budget, values, scrutinee)?;
+            let closure = CaseClosure::new(clauses.clone(), values.clone());
+
+            eval_data_elim(prims, metas, budget, scrutinee, closure)
+        },
+
+        // Equality
+        Term::EqType(ty, lhs, rhs) => Ok(Rc::from(Value::EqType(
+            eval_term(prims, metas, budget, values, ty)?,
+            eval_term(prims, metas, budget, values, lhs)?,
+            eval_term(prims, metas, budget, values, rhs)?,
+        ))),
+        Term::EqIntro => Ok(Rc::from(Value::EqIntro)),
+        Term::EqElim(proof, motive, body) => {
+            let proof = eval_term(prims, metas, budget, values, proof)?;
+            let motive = eval_term(prims, metas, budget, values, motive)?;
+            let body = eval_term(prims, metas, budget, values, body)?;
+
+            eval_eq_elim(proof, motive, body)
+        },
+
+        // Universe levels
+        Term::LevelType => Ok(Rc::from(Value::LevelType)),
+        Term::LevelIntro(level) => Ok(Rc::from(Value::Level(Level::constant(*level)))),
+        Term::LevelOmega => Ok(Rc::from(Value::Level(Level::omega()))),
+        Term::LevelShift(level, shift) => {
+            let level = eval_level(prims, metas, budget, values, level)?;
+            match level.shift(*shift) {
+                Some(level) => Ok(level_to_value(level)),
+                None => Err(EvalError::OverflowingUniverseLevel),
+            }
+        },
+        Term::LevelMax(lhs, rhs) => {
+            let lhs = eval_level(prims, metas, budget, values, lhs)?;
+            let rhs = eval_level(prims, metas, budget, values, rhs)?;
+
+            Ok(level_to_value(lhs.max(&rhs)))
+        },
+
+        // Universes
+        Term::Universe(level) => Ok(Rc::from(Value::Universe(eval_level(
+            prims, metas, budget, values, level,
+        )?))),
+    }
+}
+
+/// Read a value back into the core syntax, normalizing as required.
+pub fn read_back_value(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    unfold: Unfold,
+    term: &Rc<Value>,
+) -> Result<Rc<Term>, EvalError> {
+    budget.step()?;
+    let _depth = budget.enter()?;
+
+    match term.as_ref() {
+        Value::Neutral(head, spine) => {
+            read_back_neutral(prims, metas, budget, size, unfold, head, spine)
+        },
+
+        // Literals
+        Value::LiteralType(literal_ty) => Ok(Rc::from(Term::literal_ty(literal_ty.clone()))),
+        Value::LiteralIntro(literal_intro) => {
+            Ok(Rc::from(Term::literal_intro(literal_intro.clone())))
+        },
+
+        // Functions
+        Value::FunType(app_mode, name_hint, param_ty, body_ty) => {
+            let app_mode = app_mode.clone();
+            let name_hint = name_hint.clone();
+            let body_ty = inst_closure(prims, metas, budget, size, body_ty)?;
+            let param_ty = read_back_value(prims, metas, budget, size, unfold, param_ty)?;
+            let body_ty = read_back_value(prims, metas, budget, size + 1, unfold, &body_ty)?;
+
+            Ok(Rc::from(Term::FunType(
+                app_mode, name_hint, param_ty, body_ty,
+            )))
+        },
+        Value::FunIntro(app_mode, name_hint, body) => {
+            let app_mode = app_mode.clone();
+            let name_hint = name_hint.clone();
+            let body = inst_closure(prims, metas, budget, size, body)?;
+            let body = read_back_value(prims, metas, budget, size + 1, unfold, &body)?;
+
+            Ok(Rc::from(Term::FunIntro(app_mode, name_hint, body)))
+        },
+
+        // Records
+        Value::RecordTypeExtend(..) => {
+            let mut size = size;
+            let mut rest_ty = term.clone();
+            let mut field_tys = Vec::new();
+
+            while let Value::RecordTypeExtend(
+                doc,
+                label,
+                name_hint,
+                term_ty,
+                manifest,
+                next_rest_ty,
+            ) = rest_ty.as_ref()
+            {
+                let term_ty = read_back_value(prims, metas, budget, size, unfold, term_ty)?;
+                let manifest = match manifest {
+                    None => None,
+                    Some(manifest) => Some(read_back_value(
+                        prims, metas, budget, size, unfold, manifest,
+                    )?),
+                };
+                field_tys.push((
+                    doc.clone(),
+                    label.clone(),
+                    name_hint.clone(),
+                    term_ty,
+                    manifest,
+                ));
+                rest_ty = inst_closure(prims, metas, budget, size, next_rest_ty)?;
+                size += 1;
+            }
+
+            Ok(Rc::from(Term::RecordType(field_tys)))
+        },
+        Value::RecordTypeEmpty => Ok(Rc::from(Term::RecordType(Vec::new()))),
+        Value::RecordIntro(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(label, term)| {
+                    Ok((
+                        label.clone(),
+                        read_back_value(prims, metas, budget, size, unfold, term)?,
+                    ))
+                })
+                .collect::<Result<_, EvalError>>()?;
+
+            Ok(Rc::from(Term::RecordIntro(fields)))
+        },
+
+        // Data types
+        Value::DataType(closure, args) => {
+            let data = read_back_data(prims, metas, budget, size, unfold, closure)?;
+            read_back_args(
+                prims,
+                metas,
+                budget,
+                size,
+                unfold,
+                Term::DataType(data),
+                args,
+            )
+        },
+        Value::DataIntro(closure, label, args) => {
+            let data = read_back_data(prims, metas, budget, size, unfold, closure)?;
+            let head = Term::DataIntro(data, label.clone());
+            read_back_args(prims, metas, budget, size, unfold, head, args)
+        },
+
+        // Equality
+        Value::EqType(ty, lhs, rhs) => Ok(Rc::from(Term::EqType(
+            read_back_value(prims, metas, budget, size, unfold, ty)?,
+            read_back_value(prims, metas, budget, size, unfold, lhs)?,
+            read_back_value(prims, metas, budget, size, unfold, rhs)?,
+        ))),
+        Value::EqIntro => Ok(Rc::from(Term::EqIntro)),
+
+        // Universe levels
+        Value::LevelType => Ok(Rc::from(Term::LevelType)),
+        Value::Level(level) => read_back_level(prims, metas, budget, size, unfold, level),
+
+        // Universes
+        Value::Universe(level) => Ok(Rc::from(Term::Universe(read_back_level(
+            prims, metas, budget, size, unfold, level,
+        )?))),
+    }
+}
+
+/// Read a level back into the core syntax.
+fn read_back_level(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    unfold: Unfold,
+    level: &Level,
+) -> Result<Rc<Term>, EvalError> {
+    // The constant part can be omitted if it's zero and there are some
+    // neutral parts
+    let mut term = match level {
+        Level::Finite(UniverseLevel(0), neutrals) if !neutrals.is_empty() => None,
+        Level::Finite(constant, _) => Some(Rc::from(Term::LevelIntro(*constant))),
+        Level::Omega => return Ok(Rc::from(Term::LevelOmega)),
+    };
+
+    for (value, shift) in level.neutrals() {
+        let neutral = match read_back_value(prims, metas, budget, size, unfold, value)? {
+            neutral if *shift == 0 => neutral,
+            neutral => Rc::from(Term::LevelShift(neutral, *shift)),
+        };
+        term = Some(match term {
+            None => neutral,
+            Some(term) => Rc::from(Term::LevelMax(term, neutral)),
+        });
+    }
+
+    Ok(term.unwrap_or_else(|| Rc::from(Term::LevelIntro(UniverseLevel(0)))))
+}
+
+/// Read the arguments of a function application back into the core syntax.
+fn read_back_args(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    unfold: Unfold,
+    head: Term,
+    args: &[(AppMode, Rc<Value>)],
+) -> Result<Rc<Term>, EvalError> {
+    args.iter()
+        .fold(Ok(Rc::from(head)), |acc, (app_mode, arg)| {
+            let arg = read_back_value(prims, metas, budget, size, unfold, arg)?;
+            Ok(Rc::from(Term::FunElim(acc?, app_mode.clone(), arg)))
+        })
+}
+
+/// Read a data type declaration back into the core syntax.
+///
+/// The types of the constructors are read back with the type former bound as
+/// a variable, so this will always terminate.
+fn read_back_data(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    unfold: Unfold,
+    closure: &DataClosure,
+) -> Result<Rc<Data>, EvalError> {
+    let ty = eval_term(prims, metas, budget, &closure.values, &closure.data.ty)?;
+    let ty = read_back_value(prims, metas, budget, size, unfold, &ty)?;
+
+    let mut intro_values = closure.values.clone();
+    intro_values.add_entry(Rc::from(Value::var(size.next_level())));
+    let intros = closure
+        .data
+        .intros
+        .iter()
+        .map(|(docs, label, intro_ty)| {
+            let intro_ty = eval_term(prims, metas, budget, &intro_values, intro_ty)?;
+            let intro_ty = read_back_value(prims, metas, budget, size + 1, unfold, &intro_ty)?;
+            Ok((docs.clone(), label.clone(), intro_ty))
+        })
+        .collect::<Result<_, EvalError>>()?;
+
+    Ok(Rc::from(Data {
+        label: closure.data.label.clone(),
+        param_count: closure.data.param_count,
+        ty,
+        intros,
+    }))
+}
+
+/// Read a neutral value back into the core syntax, normalizing as required.
+pub fn read_back_neutral(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    unfold: Unfold,
+    head: &Head,
+    spine: &Spine,
+) -> Result<Rc<Term>, EvalError> {
+    let (head, spine) = match head {
+        Head::Var(var_level) => (
+            Rc::from(Term::var(size.index(*var_level))),
+            spine.as_slice(),
+        ),
+        Head::Global(var_level, value) => match unfold {
+            Unfold::None => (
+                Rc::from(Term::var(size.index(*var_level))),
+                spine.as_slice(),
+            ),
+            Unfold::All => {
+                let value = eval_spine(prims, metas, budget, value.clone(), spine)?;
+                return read_back_value(prims, metas, budget, size, unfold, &value);
+            },
+        },
+        Head::Thunk(thunk) => {
+            let value = force_thunk(prims, metas, budget, thunk)?;
+            let value = eval_spine(prims, metas, budget, value, spine)?;
+            return read_back_value(prims, metas, budget, size, unfold, &value);
+        },
+        Head::Fix(index, closure) => {
+            if unfold == Unfold::All
+                && is_fix_unfoldable(prims, metas, budget, *index, closure, spine)?
+            {
+                let value = unfold_fix(prims, metas, budget, *index, closure)?;
+                let value = eval_spine(prims, metas, budget, value, spine)?;
+                return read_back_value(prims, metas, budget, size, unfold, &value);
+            }
+
+            let defns = read_back_fix(prims, metas, budget, size, unfold, closure)?;
+            (Rc::from(Term::Fix(*index, defns)), spine.as_slice())
+        },
+        Head::Meta(meta_index) => match metas.lookup_solution(*meta_index) {
+            // Unfold solved metavariables, so that they don't appear in the
+            // resulting normal forms
+            Some((_, meta::Solution::Solved(value), _, _)) => {
+                let value = eval_spine(prims, metas, budget, value.clone(), spine)?;
+                return read_back_value(prims, metas, budget, size, unfold, &value);
+            },
+            Some((_, meta::Solution::Unsolved, _, _)) | None => {
+                (Rc::from(Term::meta(*meta_index)), spine.as_slice())
+            },
+        },
+        Head::Prim(prim_name) => match interpret_prim(prims, metas, budget, prim_name, spine)? {
+            Some(value) => return read_back_value(prims, metas, budget, size, unfold, &value),
+            None => (Rc::from(Term::prim(prim_name.clone())), spine.as_slice()),
+        },
+    };
+
+    spine.iter().fold(Ok(head), |acc, elim| match elim {
+        Elim::Literal(closure) => {
+            let clauses = Rc::from(
+                closure
+                    .clauses
+                    .iter()
+                    .map(|(literal_intro, body)| {
+                        let body = eval_term(prims, metas, budget, &closure.values, body)?;
+                        let body = read_back_value(prims, metas, budget, size, unfold, &body)?;
+                        Ok((literal_intro.clone(), body))
+                    })
+                    .collect::<Result<Vec<_>, EvalError>>()?,
+            );
+            let default_body = eval_term(prims, metas, budget, &closure.values, &closure.default)?;
+            let default_body = read_back_value(prims, metas, budget, size, unfold, &default_body)?;
+
+            Ok(Rc::from(Term::LiteralElim(acc?, clauses, default_body)))
+        },
+        Elim::Fun(app_mode, arg) => {
+            let arg = read_back_value(prims, metas, budget, size, unfold, &arg)?;
+
+            Ok(Rc::from(Term::FunElim(acc?, app_mode.clone(), arg)))
+        },
+        Elim::Record(label) => Ok(Rc::from(Term::RecordElim(acc?, label.clone()))),
+        Elim::Data(closure) => {
+            let clauses = Rc::from(
+                closure
+                    .clauses
+                    .iter()
+                    .map(|(label, name_hints, body)| {
+                        let mut values = closure.values.clone();
+                        for index in 0..name_hints.len() {
+                            values
+                                .add_entry(Rc::from(Value::var(size.next_level() + index as u32)));
+                        }
+                        let body_size = size + name_hints.len() as u32;
+                        let body = eval_term(prims, metas, budget, &values, body)?;
+                        let body = read_back_value(prims, metas, budget, body_size, unfold, &body)?;
+                        Ok((label.clone(), name_hints.clone(), body))
+                    })
+                    .collect::<Result<Vec<_>, EvalError>>()?,
+            );
+
+            Ok(Rc::from(Term::DataElim(acc?, clauses)))
+        },
+        Elim::Eq(motive, body) => {
+            let motive = read_back_value(prims, metas, budget, size, unfold, motive)?;
+            let body = read_back_value(prims, metas, budget, size, unfold, body)?;
+
+            Ok(Rc::from(Term::EqElim(acc?, motive, body)))
+        },
+    })
+}
+
+/// Read a group of mutually recursive definitions back into the core syntax.
+///
+/// The bodies of the definitions are read back with the definitions in the
+/// group bound as variables, so this will always terminate.
+fn read_back_fix(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    size: var::Size,
+    unfold: Unfold,
+    closure: &FixClosure,
+) -> Result<Rc<[(Label, Guard, Rc<Term>, Rc<Term>)]>, EvalError> {
+    let mut body_values = closure.values.clone();
+    for defn_index in 0..closure.defns.len() {
+        body_values.add_entry(Rc::from(Value::var(size.next_level() + defn_index as u32)));
+    }
+    let body_size = size + closure.defns.len() as u32;
+
+    let defns = closure
+        .defns
+        .iter()
+        .map(|(label, guard, ty, body)| {
+            let ty = eval_term(prims, metas, budget, &closure.values, ty)?;
+            let ty = read_back_value(prims, metas, budget, size, unfold, &ty)?;
+            let body = eval_term(prims, metas, budget, &body_values, body)?;
+            let body = read_back_value(prims, metas, budget, body_size, unfold, &body)?;
+            Ok((label.clone(), guard.clone(), ty, body))
+        })
+        .collect::<Result<Vec<_>, EvalError>>()?;
+
+    Ok(Rc::from(defns))
+}
+
+/// Fully normalize a term by first evaluating it, then reading it back.
+pub fn normalize_term(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    values: &var::Env<Rc<Value>>,
+    term: &Rc<Term>,
+) -> Result<Rc<Term>, EvalError> {
+    let value = eval_term(prims, metas, budget, values, term)?;
+    read_back_value(prims, metas, budget, values.size(), Unfold::All, &value)
+}
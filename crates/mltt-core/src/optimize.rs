@@ -0,0 +1,803 @@
+//! Optimization of the core syntax.
+//!
+//! The elaborator keeps the let bindings, annotations, and redexes that it
+//! produces along the way, so that they can be validated. This pass cleans
+//! them up before the core syntax is used elsewhere, performing:
+//!
+//! - inlining of small definitions
+//! - beta reduction of known function applications, binding large arguments
+//!   that are used more than once
+//! - projection of fields from known records
+//! - case-of-known-literal folding
+//! - removal of unused let bindings
+//! - removal of annotations where they are not needed for type checking
+//!
+//! The optimizations preserve the types of well-typed terms. Annotations are
+//! added to substituted terms wherever they might otherwise lose the ability
+//! to synthesize their type, and are only removed from terms that are checked
+//! against a type in `validate::check_term`.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::intern::Symbol;
+use crate::syntax::{Item, Module, Term};
+use crate::{var, DocString, Label};
+
+/// The maximum number of nodes in a definition or argument for it to be
+/// inlined.
+const INLINE_SIZE: usize = 16;
+
+/// Optimize a module.
+///
+/// The items of the module are kept, but small definitions are inlined into
+/// the items that follow them.
+pub fn optimize_module(module: &Module) -> Module {
+    Module {
+        items: Optimizer::new().optimize_items(&module.items),
+    }
+}
+
+/// Optimize a term.
+///
+/// The optimized term will synthesize the same type as the original term, if
+/// the original term synthesized a type.
+pub fn optimize_term(term: &Rc<Term>) -> Rc<Term> {
+    Optimizer::new().optimize(term, Position::Synth)
+}
+
+/// How the type of a term is found by `validate`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Position {
+    /// The term is checked against a known type, so annotations can be removed.
+    Check,
+    /// The type of the term is synthesized, so annotations must be kept.
+    Synth,
+}
+
+struct Optimizer {
+    /// The definitions that can be inlined, for each entry in scope, with the
+    /// innermost entry last. Each definition is in the scope of the entries
+    /// before it.
+    defns: Vec<Option<Rc<Term>>>,
+}
+
+impl Optimizer {
+    fn new() -> Optimizer {
+        Optimizer { defns: Vec::new() }
+    }
+
+    /// Optimize a term under `count` new parameters.
+    fn optimize_under(&mut self, count: usize, term: &Rc<Term>, position: Position) -> Rc<Term> {
+        let len = self.defns.len();
+        self.defns.extend((0..count).map(|_| None));
+        let term = self.optimize(term, position);
+        self.defns.truncate(len);
+        term
+    }
+
+    /// Lookup a definition that can be inlined in place of a variable.
+    fn lookup_defn(&self, var_index: var::Index) -> Option<Rc<Term>> {
+        let position = self.defns.len().checked_sub(var_index.0 as usize + 1)?;
+        let defn = self.defns[position].as_ref()?;
+        Some(defn.shift(var_index.0 + 1))
+    }
+
+    /// Optimize some items, adding their definitions to the scope.
+    fn optimize_items(&mut self, items: &[Item]) -> Vec<Item> {
+        // The optimized types of pending declarations, along with the number of
+        // entries that were in scope when they were declared
+        let mut declarations = HashMap::<&Label, (Rc<Term>, usize)>::new();
+
+        items
+            .iter()
+            .map(|item| match item {
+                Item::Declaration(docs, label, ty) => {
+                    let ty = self.optimize(ty, Position::Synth);
+                    declarations.insert(label, (ty.clone(), self.defns.len()));
+                    Item::Declaration(docs.clone(), label.clone(), ty)
+                },
+                Item::Definition(docs, label, term) => {
+                    let (term, defn) = match declarations.remove(label) {
+                        // Declared definitions are checked against their
+                        // declared types, so they need to be annotated with
+                        // them when they are inlined
+                        Some((ty, len)) => {
+                            let term = self.optimize(term, Position::Check);
+                            let ty = ty.shift((self.defns.len() - len) as u32);
+                            (term.clone(), Rc::from(Term::Ann(term, ty)))
+                        },
+                        None => {
+                            let term = self.optimize(term, Position::Synth);
+                            (term.clone(), term)
+                        },
+                    };
+
+                    self.defns
+                        .push(if is_small(&defn) { Some(defn) } else { None });
+                    Item::Definition(docs.clone(), label.clone(), term)
+                },
+            })
+            .collect()
+    }
+
+    fn optimize(&mut self, term: &Rc<Term>, position: Position) -> Rc<Term> {
+        match term.as_ref() {
+            Term::Var(var_index) => match self.lookup_defn(*var_index) {
+                None => term.clone(),
+                Some(defn) => match (position, defn.as_ref()) {
+                    (Position::Check, Term::Ann(defn, _)) => defn.clone(),
+                    (_, _) => defn,
+                },
+            },
+            Term::Meta(_) | Term::Prim(_) => term.clone(),
+
+            Term::Ann(term, term_ty) => {
+                let term = self.optimize(term, Position::Check);
+                match position {
+                    Position::Check => term,
+                    Position::Synth => {
+                        let term_ty = self.optimize(term_ty, Position::Synth);
+                        Rc::from(Term::Ann(term, term_ty))
+                    },
+                }
+            },
+            Term::Let(items, body) => {
+                let len = self.defns.len();
+                let items = self.optimize_items(items);
+                let body = self.optimize(body, position);
+                self.defns.truncate(len);
+
+                remove_unused_items(items, body)
+            },
+            Term::Fix(index, defns) => {
+                let defns = defns
+                    .iter()
+                    .map(|(label, guard, ty, body)| {
+                        let ty = self.optimize(ty, Position::Synth);
+                        let body = self.optimize_under(defns.len(), body, Position::Check);
+                        (label.clone(), guard.clone(), ty, body)
+                    })
+                    .collect::<Vec<_>>();
+
+                Rc::from(Term::Fix(*index, Rc::from(defns)))
+            },
+
+            Term::LiteralType(_) | Term::LiteralIntro(_) => term.clone(),
+            Term::LiteralElim(scrutinee, clauses, default_body) => {
+                let scrutinee = self.optimize(scrutinee, Position::Synth);
+
+                if let Term::LiteralIntro(literal_intro) = strip_ann(&scrutinee).as_ref() {
+                    // Avoid folding literals that could never match, like `NaN`
+                    if literal_intro.partial_cmp(literal_intro) == Some(Ordering::Equal) {
+                        let body = clauses
+                            .iter()
+                            .find(|(l, _)| l.partial_cmp(literal_intro) == Some(Ordering::Equal))
+                            .map_or(default_body, |(_, body)| body);
+
+                        return self.optimize(body, Position::Check);
+                    }
+                }
+
+                let clauses = clauses
+                    .iter()
+                    .map(|(literal_intro, body)| {
+                        let body = self.optimize(body, Position::Check);
+                        (literal_intro.clone(), body)
+                    })
+                    .collect::<Vec<_>>();
+                let default_body = self.optimize(default_body, Position::Check);
+
+                Rc::from(Term::LiteralElim(
+                    scrutinee,
+                    Rc::from(clauses),
+                    default_body,
+                ))
+            },
+
+            Term::FunType(app_mode, name_hint, param_ty, body_ty) => {
+                let param_ty = self.optimize(param_ty, Position::Synth);
+                let body_ty = self.optimize_under(1, body_ty, Position::Synth);
+
+                Rc::from(Term::FunType(
+                    app_mode.clone(),
+                    name_hint.clone(),
+                    param_ty,
+                    body_ty,
+                ))
+            },
+            Term::FunIntro(app_mode, name_hint, body) => {
+                let body = self.optimize_under(1, body, Position::Check);
+                Rc::from(Term::FunIntro(app_mode.clone(), name_hint.clone(), body))
+            },
+            Term::FunElim(fun, app_mode, arg) => {
+                let fun = self.optimize(fun, Position::Synth);
+                let arg = self.optimize(arg, Position::Check);
+
+                if let Term::Ann(intro, ty) = fun.as_ref() {
+                    match (intro.as_ref(), ty.as_ref()) {
+                        (
+                            Term::FunIntro(intro_app_mode, name_hint, body),
+                            Term::FunType(ty_app_mode, _, param_ty, body_ty),
+                        ) if intro_app_mode == app_mode && ty_app_mode == app_mode => {
+                            // The argument might not be able to synthesize its
+                            // type in place of the parameter
+                            let param = Rc::from(Term::Ann(arg.clone(), param_ty.clone()));
+                            let uses = body.count_free_var(var::Index(0))
+                                + body_ty.count_free_var(var::Index(0));
+
+                            let term = if uses <= 1 || is_small(&param) {
+                                Term::Ann(body.subst(&param), body_ty.subst(&arg))
+                            } else {
+                                // Bind the argument, rather than duplicating it
+                                let label =
+                                    Label(name_hint.clone().unwrap_or_else(|| Symbol::intern("_")));
+                                let defn = Item::Definition(DocString::from(""), label, param);
+                                let body = Rc::from(Term::Ann(body.clone(), body_ty.clone()));
+                                Term::Let(vec![defn], body)
+                            };
+
+                            return self.optimize(&Rc::from(term), position);
+                        },
+                        (_, _) => {},
+                    }
+                }
+
+                Rc::from(Term::FunElim(fun, app_mode.clone(), arg))
+            },
+
+            Term::RecordType(ty_fields) => {
+                let len = self.defns.len();
+                let ty_fields = ty_fields
+                    .iter()
+                    .map(|(docs, label, name_hint, ty, manifest)| {
+                        let ty = self.optimize(ty, Position::Synth);
+                        let manifest = manifest
+                            .as_ref()
+                            .map(|manifest| self.optimize(manifest, Position::Check));
+                        self.defns.push(None);
+
+                        (docs.clone(), label.clone(), name_hint.clone(), ty, manifest)
+                    })
+                    .collect();
+                self.defns.truncate(len);
+
+                Rc::from(Term::RecordType(ty_fields))
+            },
+            Term::RecordIntro(intro_fields) => {
+                let intro_fields = intro_fields
+                    .iter()
+                    .map(|(label, term)| (label.clone(), self.optimize(term, Position::Check)))
+                    .collect();
+
+                Rc::from(Term::RecordIntro(intro_fields))
+            },
+            Term::RecordElim(record, label) => {
+                let record = self.optimize(record, Position::Synth);
+
+                if let Term::Ann(intro, ty) = record.as_ref() {
+                    if let (Term::RecordIntro(intro_fields), Term::RecordType(ty_fields)) =
+                        (intro.as_ref(), ty.as_ref())
+                    {
+                        if let Some(term) = project_field(intro_fields, ty_fields, label) {
+                            return self.optimize(&term, position);
+                        }
+                    }
+                }
+
+                Rc::from(Term::RecordElim(record, label.clone()))
+            },
+
+            // Data types are left as they are
+            Term::DataType(_) | Term::DataIntro(_, _) => term.clone(),
+            Term::DataElim(scrutinee, clauses) => {
+                let scrutinee = self.optimize(scrutinee, Position::Synth);
+                let clauses = clauses
+                    .iter()
+                    .map(|(label, name_hints, body)| {
+                        let body = self.optimize_under(name_hints.len(), body, Position::Check);
+                        (label.clone(), name_hints.clone(), body)
+                    })
+                    .collect::<Vec<_>>();
+
+                Rc::from(Term::DataElim(scrutinee, Rc::from(clauses)))
+            },
+
+            Term::EqType(ty, lhs, rhs) => Rc::from(Term::EqType(
+                self.optimize(ty, Position::Synth),
+                self.optimize(lhs, Position::Check),
+                self.optimize(rhs, Position::Check),
+            )),
+            Term::EqIntro => term.clone(),
+            Term::EqElim(proof, motive, body) => Rc::from(Term::EqElim(
+                self.optimize(proof, Position::Synth),
+                self.optimize(motive, Position::Synth),
+                self.optimize(body, Position::Check),
+            )),
+
+            Term::LevelType | Term::LevelIntro(_) | Term::LevelOmega => term.clone(),
+            Term::LevelShift(level, shift) => Rc::from(Term::LevelShift(
+                self.optimize(level, Position::Check),
+                *shift,
+            )),
+            Term::LevelMax(lhs, rhs) => Rc::from(Term::LevelMax(
+                self.optimize(lhs, Position::Check),
+                self.optimize(rhs, Position::Check),
+            )),
+
+            Term::Universe(level) => {
+                Rc::from(Term::Universe(self.optimize(level, Position::Check)))
+            },
+        }
+    }
+}
+
+/// Remove any annotations from the outside of a term.
+fn strip_ann(term: &Rc<Term>) -> &Rc<Term> {
+    match term.as_ref() {
+        Term::Ann(term, _) => strip_ann(term),
+        _ => term,
+    }
+}
+
+/// Project a field from a record with a known type, annotating the field
+/// with its type.
+///
+/// Returns `None` if the fields of the record do not line up with its type.
+fn project_field(
+    intro_fields: &[(Label, Rc<Term>)],
+    ty_fields: &[crate::syntax::RecordTypeField],
+    label: &Label,
+) -> Option<Rc<Term>> {
+    if intro_fields.len() != ty_fields.len()
+        || Iterator::zip(intro_fields.iter(), ty_fields.iter())
+            .any(|((intro_label, _), (_, ty_label, _, _, _))| intro_label != ty_label)
+    {
+        return None;
+    }
+
+    let index = intro_fields.iter().position(|(l, _)| l == label)?;
+
+    // The type of each field is in the scope of the fields before it, so we
+    // substitute the values of those fields, starting from the innermost. The
+    // values are annotated, in case they can't synthesize their types.
+    let mut values = Vec::<Rc<Term>>::with_capacity(index + 1);
+    for ((_, term), (_, _, _, ty, _)) in intro_fields.iter().zip(ty_fields).take(index + 1) {
+        let ty = (0..values.len()).rev().fold(ty.clone(), |ty, field_index| {
+            ty.subst(&values[field_index].shift(field_index as u32))
+        });
+        values.push(Rc::from(Term::Ann(term.clone(), ty)));
+    }
+
+    values.pop()
+}
+
+/// Remove the definitions that are not referred to by the items following
+/// them or by the body, along with their declarations.
+fn remove_unused_items(items: Vec<Item>, body: Rc<Term>) -> Rc<Term> {
+    let defn_count = items
+        .iter()
+        .filter(|item| match item {
+            Item::Declaration(_, _, _) => false,
+            Item::Definition(_, _, _) => true,
+        })
+        .count();
+
+    // Find the definitions that are used, working backwards from the body. The
+    // entry at index `i` in the scope of an item that follows `count`
+    // definitions refers to the definition at `count - i - 1`.
+    let mut is_used = vec![false; defn_count];
+    let mark_used = |is_used: &mut Vec<bool>, count: usize, term: &Rc<Term>| {
+        for var::Index(index) in term.free_vars(0) {
+            if let Some(position) = count.checked_sub(index as usize + 1) {
+                is_used[position] = true;
+            }
+        }
+    };
+    let mut unused_labels = HashSet::new();
+
+    mark_used(&mut is_used, defn_count, &body);
+    let mut count = defn_count;
+    for item in items.iter().rev() {
+        match item {
+            Item::Declaration(_, label, ty) => {
+                if !unused_labels.contains(label) {
+                    mark_used(&mut is_used, count, ty);
+                }
+            },
+            Item::Definition(_, label, term) => {
+                count -= 1;
+                if is_used[count] {
+                    mark_used(&mut is_used, count, term);
+                } else {
+                    unused_labels.insert(label);
+                }
+            },
+        }
+    }
+
+    if items.is_empty() {
+        return body;
+    } else if unused_labels.is_empty() {
+        return Rc::from(Term::Let(items, body));
+    }
+
+    // The number of used definitions before each position
+    let mut used_counts = Vec::with_capacity(defn_count + 1);
+    used_counts.push(0);
+    for &is_used in &is_used {
+        used_counts.push(used_counts[used_counts.len() - 1] + is_used as u32);
+    }
+    // Rename the variables of a term that follows `count` definitions, skipping
+    // over the entries that were removed
+    let rename = |count: usize, term: &Rc<Term>| {
+        term.rename_free_vars(0, |var::Index(index)| {
+            let used_count = used_counts[count];
+            match count.checked_sub(index as usize + 1) {
+                Some(position) => var::Index(used_count - used_counts[position + 1]),
+                None => var::Index(index - (count as u32 - used_count)),
+            }
+        })
+    };
+
+    let mut count = 0;
+    let mut rest_items = Vec::with_capacity(items.len());
+    for item in &items {
+        match item {
+            Item::Declaration(docs, label, ty) => {
+                if !unused_labels.contains(label) {
+                    rest_items.push(Item::Declaration(
+                        docs.clone(),
+                        label.clone(),
+                        rename(count, ty),
+                    ));
+                }
+            },
+            Item::Definition(docs, label, term) => {
+                if is_used[count] {
+                    rest_items.push(Item::Definition(
+                        docs.clone(),
+                        label.clone(),
+                        rename(count, term),
+                    ));
+                }
+                count += 1;
+            },
+        }
+    }
+    let body = rename(count, &body);
+
+    if rest_items.is_empty() {
+        body
+    } else {
+        Rc::from(Term::Let(rest_items, body))
+    }
+}
+
+/// Returns `true` if a term is small enough to be duplicated when inlining.
+fn is_small(term: &Term) -> bool {
+    size(term, INLINE_SIZE) <= INLINE_SIZE
+}
+
+/// The number of nodes in a term, stopping early once `limit` is exceeded.
+fn size(term: &Term, limit: usize) -> usize {
+    let children = match term {
+        Term::Var(_)
+        | Term::Prim(_)
+        | Term::LiteralType(_)
+        | Term::LiteralIntro(_)
+        | Term::EqIntro
+        | Term::LevelType
+        | Term::LevelIntro(_)
+        | Term::LevelOmega => return 1,
+        Term::Ann(term1, term2)
+        | Term::FunType(_, _, term1, term2)
+        | Term::FunElim(term1, _, term2)
+        | Term::LevelMax(term1, term2) => vec![term1, term2],
+        Term::FunIntro(_, _, term)
+        | Term::RecordElim(term, _)
+        | Term::LevelShift(term, _)
+        | Term::Universe(term) => vec![term],
+        Term::EqType(term1, term2, term3) | Term::EqElim(term1, term2, term3) => {
+            vec![term1, term2, term3]
+        },
+        // These are assumed to be too large to duplicate
+        _ => return limit + 1,
+    };
+
+    children.into_iter().fold(1, |total, child| {
+        if total > limit {
+            total
+        } else {
+            total + size(child, limit - total)
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::literal::{LiteralIntro, LiteralType};
+    use crate::validate::{self, Context};
+    use crate::{meta, AppMode};
+
+    fn string_ty() -> Rc<Term> {
+        Rc::from(Term::literal_ty(LiteralType::String))
+    }
+
+    fn string(value: &str) -> Rc<Term> {
+        Rc::from(Term::literal_intro(LiteralIntro::String(Rc::from(value))))
+    }
+
+    fn ann(term: Rc<Term>, term_ty: Rc<Term>) -> Rc<Term> {
+        Rc::from(Term::Ann(term, term_ty))
+    }
+
+    fn definition(label: &str, term: Rc<Term>) -> Item {
+        Item::Definition(Rc::from(""), Label::new(label), term)
+    }
+
+    fn string_fun_ty() -> Rc<Term> {
+        Rc::from(Term::FunType(
+            AppMode::Explicit,
+            None,
+            string_ty(),
+            string_ty(),
+        ))
+    }
+
+    fn string_fun(body: Rc<Term>) -> Rc<Term> {
+        ann(
+            Rc::from(Term::FunIntro(AppMode::Explicit, None, body)),
+            string_fun_ty(),
+        )
+    }
+
+    /// A term that is too large to be inlined, and can't be folded away.
+    fn large(scrutinee: Rc<Term>) -> Rc<Term> {
+        Rc::from(Term::LiteralElim(
+            scrutinee,
+            Rc::from(vec![(LiteralIntro::String(Rc::from("a")), string("b"))]),
+            string("c"),
+        ))
+    }
+
+    /// Optimize a term, checking that the optimized term has the same type as
+    /// the original term.
+    fn check_optimize(term: &Rc<Term>, expected_ty: &Rc<Term>) -> Rc<Term> {
+        let context = Context::empty();
+        let metas = meta::Env::new();
+        let expected_ty = context.eval_term(&metas, expected_ty).unwrap();
+
+        validate::check_term(&context, &metas, term, &expected_ty).unwrap();
+        let optimized_term = optimize_term(term);
+        validate::check_term(&context, &metas, &optimized_term, &expected_ty).unwrap();
+
+        optimized_term
+    }
+
+    #[test]
+    fn beta_reduce() {
+        let fun_ty = Rc::from(Term::FunType(
+            AppMode::Explicit,
+            None,
+            string_ty(),
+            string_ty(),
+        ));
+        let fun = ann(
+            Rc::from(Term::FunIntro(
+                AppMode::Explicit,
+                None,
+                Rc::from(Term::var(0)),
+            )),
+            fun_ty,
+        );
+        let term = Rc::from(Term::FunElim(fun, AppMode::Explicit, string("hello")));
+
+        assert_eq!(
+            check_optimize(&term, &string_ty()),
+            ann(string("hello"), string_ty()),
+        );
+    }
+
+    #[test]
+    fn beta_reduce_large_arg_used_once() {
+        // fun y => (fun x => x) (case y { "a" => "b", _ => "c" })
+        let fun = string_fun(Rc::from(Term::var(0)));
+        let arg = large(Rc::from(Term::var(0)));
+        let term = string_fun(Rc::from(Term::FunElim(fun, AppMode::Explicit, arg.clone())));
+
+        assert_eq!(check_optimize(&term, &string_fun_ty()), string_fun(arg));
+    }
+
+    #[test]
+    fn bind_large_arg_used_twice() {
+        // fun y => (fun x => case x { "a" => "b", _ => x }) (case y { "a" => "b", _ => "c" })
+        let body = Rc::from(Term::LiteralElim(
+            Rc::from(Term::var(0)),
+            Rc::from(vec![(LiteralIntro::String(Rc::from("a")), string("b"))]),
+            Rc::from(Term::var(0)),
+        ));
+        let fun = string_fun(body.clone());
+        let arg = large(Rc::from(Term::var(0)));
+        let term = string_fun(Rc::from(Term::FunElim(fun, AppMode::Explicit, arg.clone())));
+
+        assert_eq!(
+            check_optimize(&term, &string_fun_ty()),
+            string_fun(Rc::from(Term::Let(
+                vec![definition("_", ann(arg, string_ty()))],
+                body,
+            ))),
+        );
+    }
+
+    #[test]
+    fn inline_small_definitions() {
+        let term = Rc::from(Term::Let(
+            vec![
+                definition("a", string("hello")),
+                definition("b", Rc::from(Term::var(0))),
+            ],
+            Rc::from(Term::var(0)),
+        ));
+
+        assert_eq!(check_optimize(&term, &string_ty()), string("hello"));
+    }
+
+    #[test]
+    fn inline_declared_items() {
+        let module = Module {
+            items: vec![
+                Item::Declaration(Rc::from(""), Label::new("greeting"), string_ty()),
+                definition("greeting", string("hello")),
+                definition("message", Rc::from(Term::var(0))),
+            ],
+        };
+
+        let context = Context::empty();
+        let metas = meta::Env::new();
+        validate::check_module(&context, &metas, &module).unwrap();
+        let optimized_module = optimize_module(&module);
+        validate::check_module(&context, &metas, &optimized_module).unwrap();
+
+        assert_eq!(
+            optimized_module.items,
+            vec![
+                Item::Declaration(Rc::from(""), Label::new("greeting"), string_ty()),
+                definition("greeting", string("hello")),
+                definition("message", ann(string("hello"), string_ty())),
+            ],
+        );
+    }
+
+    #[test]
+    fn remove_unused_definitions() {
+        let record_ty = Rc::from(Term::RecordType(vec![(
+            Rc::from(""),
+            Label::new("x"),
+            None,
+            string_ty(),
+            None,
+        )]));
+        let record = Rc::from(Term::RecordIntro(vec![(Label::new("x"), string("hello"))]));
+        let term = Rc::from(Term::Let(
+            vec![
+                definition("R", record_ty.clone()),
+                definition("unused", string("unused")),
+            ],
+            ann(record.clone(), Rc::from(Term::var(1))),
+        ));
+
+        assert_eq!(
+            check_optimize(&term, &record_ty),
+            Rc::from(Term::Let(
+                vec![definition("R", record_ty.clone())],
+                ann(record, Rc::from(Term::var(0))),
+            )),
+        );
+    }
+
+    #[test]
+    fn remove_transitively_unused_definitions() {
+        // fun y => let a = ...y...; b = ...a...; c = ...y...; in c
+        let term = string_fun(Rc::from(Term::Let(
+            vec![
+                definition("a", ann(large(Rc::from(Term::var(0))), string_ty())),
+                definition("b", ann(large(Rc::from(Term::var(0))), string_ty())),
+                definition("c", ann(large(Rc::from(Term::var(2))), string_ty())),
+            ],
+            Rc::from(Term::var(0)),
+        )));
+
+        assert_eq!(
+            check_optimize(&term, &string_fun_ty()),
+            string_fun(Rc::from(Term::Let(
+                vec![definition(
+                    "c",
+                    ann(large(Rc::from(Term::var(0))), string_ty())
+                )],
+                Rc::from(Term::var(0)),
+            ))),
+        );
+    }
+
+    #[test]
+    fn fold_known_literal() {
+        let term = Rc::from(Term::LiteralElim(
+            ann(
+                Rc::from(Term::literal_intro(LiteralIntro::U8(2))),
+                Rc::from(Term::literal_ty(LiteralType::U8)),
+            ),
+            Rc::from(vec![
+                (LiteralIntro::U8(1), string("one")),
+                (LiteralIntro::U8(2), string("two")),
+            ]),
+            string("other"),
+        ));
+
+        assert_eq!(check_optimize(&term, &string_ty()), string("two"));
+    }
+
+    #[test]
+    fn project_known_field() {
+        // Record { A : Type, a : A }
+        let record_ty = Rc::from(Term::RecordType(vec![
+            (
+                Rc::from(""),
+                Label::new("A"),
+                None,
+                Rc::from(Term::universe(0)),
+                None,
+            ),
+            (
+                Rc::from(""),
+                Label::new("a"),
+                None,
+                Rc::from(Term::var(0)),
+                None,
+            ),
+        ]));
+        let record = Rc::from(Term::RecordIntro(vec![
+            (Label::new("A"), string_ty()),
+            (Label::new("a"), string("hello")),
+        ]));
+        let term = Rc::from(Term::RecordElim(ann(record, record_ty), Label::new("a")));
+
+        assert_eq!(
+            check_optimize(&term, &string_ty()),
+            ann(
+                string("hello"),
+                ann(string_ty(), Rc::from(Term::universe(0))),
+            ),
+        );
+    }
+
+    #[test]
+    fn strip_checked_annotations() {
+        let fun_ty = Rc::from(Term::FunType(
+            AppMode::Explicit,
+            None,
+            string_ty(),
+            string_ty(),
+        ));
+        let body = ann(Rc::from(Term::var(0)), string_ty());
+        let term = ann(
+            Rc::from(Term::FunIntro(AppMode::Explicit, None, body)),
+            fun_ty.clone(),
+        );
+
+        assert_eq!(
+            check_optimize(&term, &fun_ty),
+            ann(
+                Rc::from(Term::FunIntro(
+                    AppMode::Explicit,
+                    None,
+                    Rc::from(Term::var(0)),
+                )),
+                fun_ty,
+            ),
+        );
+    }
+}
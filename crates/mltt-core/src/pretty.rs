@@ -3,7 +3,8 @@
 use pretty::{BoxDoc, Doc};
 use std::borrow::Cow;
 
-use super::{syntax, var, AppMode, UniverseLevel};
+use super::intern::Symbol;
+use super::{syntax, var, AppMode, Label, UniverseLevel};
 
 pub fn parens<'doc, A>(
     inner: impl Into<Doc<'doc, BoxDoc<'doc, A>, A>>,
@@ -49,6 +50,19 @@ pub fn definition<'doc, A>(
         )
 }
 
+/// The label of a recursive definition, marked as `partial` if needed.
+fn fix_label<'doc, A>(
+    guard: &syntax::Guard,
+    label: impl Into<Doc<'doc, BoxDoc<'doc, A>, A>>,
+) -> Doc<'doc, BoxDoc<'doc, A>, A> {
+    match guard {
+        syntax::Guard::Partial => Doc::text("partial")
+            .append(Doc::space())
+            .append(label.into()),
+        syntax::Guard::Always | syntax::Guard::Structural(_) => label.into(),
+    }
+}
+
 pub fn clause<'doc, A>(
     patterns: impl Into<Doc<'doc, BoxDoc<'doc, A>, A>>,
     body: impl Into<Doc<'doc, BoxDoc<'doc, A>, A>>,
@@ -93,6 +107,34 @@ pub fn record_elim<'doc, A>(
         .append(label.into())
 }
 
+pub fn tuple_ty<'doc, A>(
+    components: impl IntoIterator<Item = Doc<'doc, BoxDoc<'doc, A>, A>>,
+) -> Doc<'doc, BoxDoc<'doc, A>, A> {
+    Doc::intersperse(components, Doc::space().append("*").append(Doc::space()))
+}
+
+pub fn tuple_intro<'doc, A>(
+    elements: impl IntoIterator<Item = Doc<'doc, BoxDoc<'doc, A>, A>>,
+) -> Doc<'doc, BoxDoc<'doc, A>, A> {
+    parens(Doc::intersperse(
+        elements,
+        Doc::text(",").append(Doc::space()),
+    ))
+}
+
+/// Returns `true` if the fields of a record type can be printed as a tuple.
+fn is_tuple_ty(ty_fields: &[syntax::RecordTypeField]) -> bool {
+    Label::is_tuple(ty_fields.iter().map(|(_, label, _, _, _)| label))
+        && ty_fields
+            .iter()
+            .all(|(_, _, _, _, manifest)| manifest.is_none())
+}
+
+/// Returns `true` if the fields of a record can be printed as a tuple.
+fn is_tuple_intro(intro_fields: &[(Label, std::rc::Rc<syntax::Term>)]) -> bool {
+    Label::is_tuple(intro_fields.iter().map(|(label, _)| label))
+}
+
 pub fn universe0<'doc, A>() -> Doc<'doc, BoxDoc<'doc, A>, A> {
     Doc::text("Type")
 }
@@ -141,7 +183,7 @@ impl Env {
     /// Generate a fresh name based on the names that have already been
     /// used in the environment. We try to get close to the `name_hint`,
     /// adding a number if necessary.
-    fn fresh_name(&mut self, name_hint: Option<&str>) -> String {
+    pub fn fresh_name(&mut self, name_hint: Option<&str>) -> String {
         // Use `x` as our default name, for lack of anything better...
         const DEFAULT_NAME: &str = "x";
 
@@ -210,7 +252,7 @@ pub fn items_to_display_doc(
                 let doc = definition(Doc::as_string(label), term.to_display_doc(env))
                     .append(Doc::newline())
                     .append(Doc::newline());
-                env.fresh_name(Some(&label.0));
+                env.fresh_name(Some(label.as_str()));
                 doc
             },
         }
@@ -236,6 +278,30 @@ impl syntax::Term {
                 })))
                 .append("in")
                 .append(Doc::space().append(body.to_debug_doc()).group().nest(4)),
+            syntax::Term::Fix(index, defns) => {
+                let declarations = Doc::concat(defns.iter().map(|(label, _, ty, _)| {
+                    declaration(Doc::as_string(label), ty.to_debug_doc())
+                        .append(Doc::newline())
+                        .append(Doc::newline())
+                }));
+                let definitions = Doc::concat(defns.iter().map(|(label, guard, _, body)| {
+                    definition(fix_label(guard, Doc::as_string(label)), body.to_debug_doc())
+                        .append(Doc::newline())
+                        .append(Doc::newline())
+                }));
+                let label = match defns.get(*index) {
+                    Some((label, _, _, _)) => Doc::as_string(label),
+                    None => Doc::text("<missing definition>"),
+                };
+
+                Doc::nil()
+                    .append("let")
+                    .append(Doc::space())
+                    .append(declarations)
+                    .append(definitions)
+                    .append("in")
+                    .append(Doc::space().append(label).group().nest(4))
+            },
 
             syntax::Term::LiteralType(literal_ty) => Doc::as_string(literal_ty),
             syntax::Term::LiteralIntro(literal_intro) => Doc::as_string(literal_intro),
@@ -369,6 +435,11 @@ impl syntax::Term {
             },
 
             syntax::Term::RecordType(ty_fields) if ty_fields.is_empty() => Doc::text("Record {}"),
+            syntax::Term::RecordType(ty_fields) if is_tuple_ty(ty_fields) => tuple_ty(
+                ty_fields
+                    .iter()
+                    .map(|(_, _, _, ty, _)| ty.to_debug_arg_doc()),
+            ),
             syntax::Term::RecordType(ty_fields) => Doc::nil()
                 .append("Record")
                 .append(Doc::space())
@@ -378,7 +449,7 @@ impl syntax::Term {
                     Doc::nil()
                         .append(Doc::space())
                         .append(Doc::intersperse(
-                            ty_fields.iter().map(|(_, label, _, ty)| {
+                            ty_fields.iter().map(|(_, label, _, ty, manifest)| {
                                 Doc::nil()
                                     .append(Doc::as_string(label))
                                     .append(Doc::space())
@@ -386,6 +457,13 @@ impl syntax::Term {
                                     .append(
                                         Doc::space()
                                             .append(ty.to_debug_doc())
+                                            .append(match manifest {
+                                                None => Doc::nil(),
+                                                Some(manifest) => Doc::space()
+                                                    .append("=")
+                                                    .append(Doc::space())
+                                                    .append(manifest.to_debug_doc()),
+                                            })
                                             .append(";")
                                             .group()
                                             .nest(4),
@@ -401,6 +479,9 @@ impl syntax::Term {
             syntax::Term::RecordIntro(intro_fields) if intro_fields.is_empty() => {
                 Doc::text("record {}")
             },
+            syntax::Term::RecordIntro(intro_fields) if is_tuple_intro(intro_fields) => {
+                tuple_intro(intro_fields.iter().map(|(_, term)| term.to_debug_doc()))
+            },
             syntax::Term::RecordIntro(intro_fields) => Doc::nil()
                 .append("record")
                 .append(Doc::space())
@@ -435,7 +516,81 @@ impl syntax::Term {
                 record_elim(record.to_debug_doc(), Doc::as_string(label))
             },
 
-            syntax::Term::Universe(level) => universe(Doc::as_string(level)),
+            syntax::Term::DataType(data) => Doc::as_string(&data.label),
+            syntax::Term::DataIntro(_, label) => Doc::as_string(label),
+            syntax::Term::DataElim(scrutinee, clauses) => {
+                let clauses = Doc::intersperse(
+                    clauses.iter().map(|(label, name_hints, body)| {
+                        let pattern = Doc::as_string(label).append(Doc::concat(
+                            name_hints.iter().map(|_| Doc::space().append("_")),
+                        ));
+                        clause(pattern, body.to_debug_doc()).group()
+                    }),
+                    Doc::space(),
+                );
+
+                Doc::nil()
+                    .append("case")
+                    .append(Doc::space())
+                    .append(scrutinee.to_debug_arg_doc())
+                    .append(Doc::space())
+                    .append("{")
+                    .group()
+                    .append(
+                        Doc::nil()
+                            .append(Doc::space())
+                            .append(clauses)
+                            .group()
+                            .nest(4),
+                    )
+                    .append(Doc::space())
+                    .append("}")
+            },
+
+            syntax::Term::EqType(ty, lhs, rhs) => Doc::nil()
+                .append("Eq")
+                .append(Doc::space())
+                .append(
+                    Doc::nil()
+                        .append("{A")
+                        .append(Doc::space())
+                        .append("=")
+                        .group()
+                        .append(Doc::space().append(ty.to_debug_doc()).nest(4))
+                        .append("}")
+                        .group(),
+                )
+                .append(Doc::space())
+                .append(lhs.to_debug_arg_doc())
+                .append(Doc::space())
+                .append(rhs.to_debug_arg_doc()),
+            syntax::Term::EqIntro => Doc::text("refl"),
+            syntax::Term::EqElim(proof, motive, body) => Doc::nil()
+                .append("J")
+                .append(Doc::space())
+                .append(motive.to_debug_arg_doc())
+                .append(Doc::space())
+                .append(body.to_debug_arg_doc())
+                .append(Doc::space())
+                .append(proof.to_debug_arg_doc()),
+
+            syntax::Term::LevelType => Doc::text("Level"),
+            syntax::Term::LevelIntro(level) => Doc::as_string(level),
+            syntax::Term::LevelOmega => Doc::text("omega"),
+            syntax::Term::LevelShift(level, shift) => Doc::nil()
+                .append(level.to_debug_arg_doc())
+                .append(Doc::space())
+                .append("+")
+                .append(Doc::space())
+                .append(Doc::as_string(shift)),
+            syntax::Term::LevelMax(lhs, rhs) => Doc::nil()
+                .append("max")
+                .append(Doc::space())
+                .append(lhs.to_debug_arg_doc())
+                .append(Doc::space())
+                .append(rhs.to_debug_arg_doc()),
+
+            syntax::Term::Universe(level) => universe(level.to_debug_arg_doc()),
         }
     }
 
@@ -446,7 +601,16 @@ impl syntax::Term {
             | syntax::Term::LiteralIntro(_)
             | syntax::Term::LiteralType(_)
             | syntax::Term::RecordElim(_, _)
+            | syntax::Term::DataType(_)
+            | syntax::Term::DataIntro(_, _)
+            | syntax::Term::EqIntro
+            | syntax::Term::LevelType
+            | syntax::Term::LevelIntro(_)
+            | syntax::Term::LevelOmega
             | syntax::Term::Universe(_) => self.to_debug_doc(),
+            syntax::Term::RecordIntro(intro_fields) if is_tuple_intro(intro_fields) => {
+                self.to_debug_doc()
+            },
             _ => parens(self.to_debug_doc()),
         }
     }
@@ -478,6 +642,41 @@ impl syntax::Term {
                             .nest(4),
                     )
             },
+            syntax::Term::Fix(index, defns) => {
+                let mut body_env = env.clone();
+                let names = defns
+                    .iter()
+                    .map(|(label, _, _, _)| body_env.fresh_name(Some(label.as_str())))
+                    .collect::<Vec<_>>();
+
+                let declarations = Doc::concat(Iterator::zip(defns.iter(), names.iter()).map(
+                    |((_, _, ty, _), name)| {
+                        declaration(Doc::as_string(name), ty.to_display_doc(env))
+                            .append(Doc::newline())
+                            .append(Doc::newline())
+                    },
+                ));
+                let definitions = Doc::concat(Iterator::zip(defns.iter(), names.iter()).map(
+                    |((_, guard, _, body), name)| {
+                        let label = fix_label(guard, Doc::as_string(name));
+                        definition(label, body.to_display_doc(&body_env))
+                            .append(Doc::newline())
+                            .append(Doc::newline())
+                    },
+                ));
+                let name = match names.get(*index) {
+                    Some(name) => Doc::as_string(name),
+                    None => Doc::text("<missing definition>"),
+                };
+
+                Doc::nil()
+                    .append("let")
+                    .append(Doc::space())
+                    .append(declarations)
+                    .append(definitions)
+                    .append("in")
+                    .append(Doc::space().append(name).group().nest(4))
+            },
 
             syntax::Term::LiteralType(literal_ty) => Doc::as_string(literal_ty),
             syntax::Term::LiteralIntro(literal_intro) => Doc::as_string(literal_intro),
@@ -528,7 +727,7 @@ impl syntax::Term {
                         let param_ty_doc = param_ty.to_display_doc(&env);
                         match app_mode {
                             AppMode::Explicit => {
-                                let name_hint = name_hint.as_ref().map(String::as_str);
+                                let name_hint = name_hint.as_ref().map(Symbol::as_str);
                                 let param_name = env.fresh_name(name_hint);
 
                                 Doc::nil()
@@ -543,12 +742,12 @@ impl syntax::Term {
                             },
                             AppMode::Implicit(label) => {
                                 let param_name = match name_hint {
-                                    None => env.fresh_name(Some(&label.0)),
+                                    None => env.fresh_name(Some(label.as_str())),
                                     Some(name_hint) => env.fresh_name(Some(name_hint.as_str())),
                                 };
 
                                 Doc::nil()
-                                    .append(if label.0 == param_name {
+                                    .append(if label.as_str() == param_name {
                                         Doc::text("{").append(Doc::as_string(label)).group()
                                     } else {
                                         Doc::nil()
@@ -568,12 +767,12 @@ impl syntax::Term {
                             },
                             AppMode::Instance(label) => {
                                 let param_name = match name_hint {
-                                    None => env.fresh_name(Some(&label.0)),
+                                    None => env.fresh_name(Some(label.as_str())),
                                     Some(name_hint) => env.fresh_name(Some(name_hint.as_str())),
                                 };
 
                                 Doc::nil()
-                                    .append(if label.0 == param_name {
+                                    .append(if label.as_str() == param_name {
                                         Doc::text("{{").append(Doc::as_string(label)).group()
                                     } else {
                                         Doc::nil()
@@ -625,17 +824,17 @@ impl syntax::Term {
                         .iter()
                         .map(|(app_mode, name_hint)| match app_mode {
                             AppMode::Explicit => {
-                                let name_hint = name_hint.as_ref().map(String::as_str);
+                                let name_hint = name_hint.as_ref().map(Symbol::as_str);
                                 Doc::as_string(env.fresh_name(name_hint)).group()
                             },
                             AppMode::Implicit(label) => {
                                 let param_name = match name_hint {
-                                    None => env.fresh_name(Some(&label.0)),
+                                    None => env.fresh_name(Some(label.as_str())),
                                     Some(name_hint) => env.fresh_name(Some(name_hint.as_str())),
                                 };
 
                                 Doc::nil()
-                                    .append(if label.0 == param_name {
+                                    .append(if label.as_str() == param_name {
                                         Doc::text("{").append(Doc::as_string(label)).group()
                                     } else {
                                         Doc::nil()
@@ -651,12 +850,12 @@ impl syntax::Term {
                             },
                             AppMode::Instance(label) => {
                                 let param_name = match name_hint {
-                                    None => env.fresh_name(Some(&label.0)),
+                                    None => env.fresh_name(Some(label.as_str())),
                                     Some(name_hint) => env.fresh_name(Some(name_hint.as_str())),
                                 };
 
                                 Doc::nil()
-                                    .append(if label.0 == param_name {
+                                    .append(if label.as_str() == param_name {
                                         Doc::text("{{").append(Doc::as_string(label)).group()
                                     } else {
                                         Doc::nil()
@@ -727,20 +926,57 @@ impl syntax::Term {
             },
 
             syntax::Term::RecordType(ty_fields) if ty_fields.is_empty() => Doc::text("Record {}"),
+            syntax::Term::RecordType(ty_fields) if is_tuple_ty(ty_fields) => {
+                let mut env = env.clone();
+                let components = ty_fields
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (_, _, name_hint, ty, _))| {
+                        // Only name the components that later components depend on
+                        let is_dependent = ty_fields[index + 1..].iter().enumerate().any(
+                            |(offset, (_, _, _, next_ty, _))| {
+                                next_ty.has_free_var(var::Index(offset as u32))
+                            },
+                        );
+                        let ty_doc = if is_dependent {
+                            ty.to_display_doc(&env)
+                        } else {
+                            ty.to_display_arg_doc(&env)
+                        };
+                        let name = env.fresh_name(name_hint.as_ref().map(Symbol::as_str));
+
+                        if is_dependent {
+                            parens(ann(Doc::as_string(name), ty_doc))
+                        } else {
+                            ty_doc
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                tuple_ty(components)
+            },
             syntax::Term::RecordType(ty_fields) => {
                 let mut env = env.clone();
 
                 let fields_doc = {
                     Doc::intersperse(
-                        ty_fields.iter().map(|(_, label, name_hint, ty)| {
-                            let ty_doc = ty.to_display_doc(&env);
+                        ty_fields.iter().map(|(_, label, name_hint, ty, manifest)| {
+                            let ty_doc = match manifest {
+                                None => ty.to_display_doc(&env),
+                                Some(manifest) => Doc::nil()
+                                    .append(ty.to_display_doc(&env))
+                                    .append(Doc::space())
+                                    .append("=")
+                                    .append(Doc::space())
+                                    .append(manifest.to_display_doc(&env)),
+                            };
                             let field_name = match name_hint {
-                                None => env.fresh_name(Some(&label.0)),
+                                None => env.fresh_name(Some(label.as_str())),
                                 Some(name_hint) => env.fresh_name(Some(name_hint.as_str())),
                             };
 
                             Doc::nil()
-                                .append(if label.0 == field_name {
+                                .append(if label.as_str() == field_name {
                                     Doc::as_string(label)
                                 } else {
                                     Doc::nil()
@@ -773,6 +1009,11 @@ impl syntax::Term {
             syntax::Term::RecordIntro(intro_fields) if intro_fields.is_empty() => {
                 Doc::text("record {}")
             },
+            syntax::Term::RecordIntro(intro_fields) if is_tuple_intro(intro_fields) => tuple_intro(
+                intro_fields
+                    .iter()
+                    .map(|(_, term)| term.to_display_doc(env)),
+            ),
             syntax::Term::RecordIntro(intro_fields) => Doc::nil()
                 .append("record")
                 .append(Doc::space())
@@ -808,8 +1049,84 @@ impl syntax::Term {
                 record_elim(record.to_display_doc(env), Doc::as_string(label))
             },
 
-            syntax::Term::Universe(UniverseLevel(0)) => universe0(),
-            syntax::Term::Universe(level) => universe(Doc::as_string(level)),
+            syntax::Term::DataType(data) => Doc::as_string(&data.label),
+            syntax::Term::DataIntro(_, label) => Doc::as_string(label),
+            syntax::Term::DataElim(scrutinee, clauses) => {
+                let scrutinee = scrutinee.to_display_arg_doc(env);
+                let clauses = Doc::intersperse(
+                    clauses.iter().map(|(label, name_hints, body)| {
+                        let mut body_env = env.clone();
+                        let names = name_hints
+                            .iter()
+                            .map(|name_hint| {
+                                body_env.fresh_name(name_hint.as_ref().map(Symbol::as_str))
+                            })
+                            .collect::<Vec<_>>();
+                        let pattern = Doc::as_string(label).append(Doc::concat(
+                            names.into_iter().map(|name| Doc::space().append(name)),
+                        ));
+                        clause(pattern, body.to_display_doc(&body_env)).group()
+                    }),
+                    Doc::space(),
+                );
+
+                Doc::nil()
+                    .append("case")
+                    .append(Doc::space())
+                    .append(scrutinee)
+                    .append(Doc::space())
+                    .append("{")
+                    .group()
+                    .append(
+                        Doc::nil()
+                            .append(Doc::space())
+                            .append(clauses)
+                            .group()
+                            .nest(4),
+                    )
+                    .append(Doc::space())
+                    .append("}")
+            },
+
+            syntax::Term::EqType(_, lhs, rhs) => Doc::nil().append("Eq").append(
+                Doc::space()
+                    .append(lhs.to_display_arg_doc(env))
+                    .append(Doc::space())
+                    .append(rhs.to_display_arg_doc(env))
+                    .nest(4),
+            ),
+            syntax::Term::EqIntro => Doc::text("refl"),
+            syntax::Term::EqElim(proof, motive, body) => Doc::nil().append("J").append(
+                Doc::space()
+                    .append(motive.to_display_arg_doc(env))
+                    .append(Doc::space())
+                    .append(body.to_display_arg_doc(env))
+                    .append(Doc::space())
+                    .append(proof.to_display_arg_doc(env))
+                    .nest(4),
+            ),
+
+            syntax::Term::LevelType => Doc::text("Level"),
+            syntax::Term::LevelIntro(level) => Doc::as_string(level),
+            syntax::Term::LevelOmega => Doc::text("omega"),
+            syntax::Term::LevelShift(level, shift) => Doc::nil()
+                .append(level.to_display_arg_doc(env))
+                .append(Doc::space())
+                .append("+")
+                .append(Doc::space())
+                .append(Doc::as_string(shift)),
+            syntax::Term::LevelMax(lhs, rhs) => Doc::nil().append("max").append(
+                Doc::space()
+                    .append(lhs.to_display_arg_doc(env))
+                    .append(Doc::space())
+                    .append(rhs.to_display_arg_doc(env))
+                    .nest(4),
+            ),
+
+            syntax::Term::Universe(level) => match level.as_ref() {
+                syntax::Term::LevelIntro(UniverseLevel(0)) => universe0(),
+                _ => universe(level.to_display_arg_doc(env)),
+            },
         }
     }
 
@@ -820,7 +1137,16 @@ impl syntax::Term {
             | syntax::Term::LiteralIntro(_)
             | syntax::Term::LiteralType(_)
             | syntax::Term::RecordElim(_, _)
+            | syntax::Term::DataType(_)
+            | syntax::Term::DataIntro(_, _)
+            | syntax::Term::EqIntro
+            | syntax::Term::LevelType
+            | syntax::Term::LevelIntro(_)
+            | syntax::Term::LevelOmega
             | syntax::Term::Universe(_) => self.to_display_doc(env),
+            syntax::Term::RecordIntro(intro_fields) if is_tuple_intro(intro_fields) => {
+                self.to_display_doc(env)
+            },
             _ => parens(self.to_display_doc(env)),
         }
     }
@@ -3,20 +3,22 @@ use std::rc::Rc;
 
 use super::literal::LiteralIntro;
 use crate::domain::{Elim, Value};
+use crate::intern::Symbol;
+use crate::nbe::EvalError;
 
 /// The name of a primitive.
 #[derive(Debug, Clone, PartialEq, Hash, Eq, PartialOrd, Ord)]
-pub struct Name(pub String);
+pub struct Name(pub Symbol);
 
 impl<'a> From<&'a str> for Name {
     fn from(src: &'a str) -> Name {
-        Name(src.to_owned())
+        Name(Symbol::intern(src))
     }
 }
 
 impl From<String> for Name {
     fn from(src: String) -> Name {
-        Name(src)
+        Name(Symbol::from(src))
     }
 }
 
@@ -39,7 +41,7 @@ pub struct Entry {
     /// - `Some(Ok(_))`: if the primitive returned a value
     /// - `Some(Err(_))`: if the primitive resulted in an evaluation error
     /// - `None`: if the primitive is stuck on an argument
-    pub interpretation: fn(Vec<Rc<Value>>) -> Option<Result<Rc<Value>, String>>,
+    pub interpretation: fn(Vec<Rc<Value>>) -> Option<Result<Rc<Value>, EvalError>>,
 }
 
 impl Entry {
@@ -52,7 +54,7 @@ impl Entry {
     pub fn interpret<'spine>(
         &self,
         spine: &'spine [Elim],
-    ) -> Option<Result<(Rc<Value>, &'spine [Elim]), String>> {
+    ) -> Option<Result<(Rc<Value>, &'spine [Elim]), EvalError>> {
         // Prevent `split_at` from panicking if we don't have enough eliminators
         // in the spine.
         if spine.len() < self.arity as usize {
@@ -65,7 +67,7 @@ impl Entry {
         for arg_elim in arg_spine {
             match arg_elim {
                 Elim::Fun(_, arg) => args.push(arg.clone()),
-                Elim::Literal(_) | Elim::Record(_) => return None, // Return String?
+                Elim::Literal(_) | Elim::Record(_) | Elim::Data(_) | Elim::Eq(_, _) => return None, // Return String?
             }
         }
 
@@ -151,7 +153,7 @@ impl Default for Env {
                 Entry {
                     arity: 0,
                     interpretation: {
-                        fn interpretation(params: Vec<Rc<Value>>) -> Option<Result<Rc<Value>, String>> {
+                        fn interpretation(params: Vec<Rc<Value>>) -> Option<Result<Rc<Value>, EvalError>> {
                             match params.as_slice() {
                                 [] => Some($body),
                                 _ => None,
@@ -165,7 +167,7 @@ impl Default for Env {
                 Entry {
                     arity: count!($($param_name)*),
                     interpretation: {
-                        fn interpretation(params: Vec<Rc<Value>>) -> Option<Result<Rc<Value>, String>> {
+                        fn interpretation(params: Vec<Rc<Value>>) -> Option<Result<Rc<Value>, EvalError>> {
                             match params.as_slice() {
                                 [$(ref $param_name),*] => {
                                     $(let $param_name = <$PType>::try_from_value($param_name)?;)*
@@ -182,7 +184,7 @@ impl Default for Env {
 
         Env {
             entries: im::hashmap! {
-                Name::from("abort") => prim!(|message: Rc<str>| Err(message.to_string())),
+                Name::from("abort") => prim!(|message: Rc<str>| Err(EvalError::Abort(message.to_string()))),
 
                 Name::from("string-eq") => prim!(|lhs: Rc<str>, rhs: Rc<str>| Ok(Rc::from(Value::literal_intro(lhs == rhs)))),
                 Name::from("char-eq") => prim!(|lhs: char, rhs: char| Ok(Rc::from(Value::literal_intro(lhs == rhs)))),
@@ -0,0 +1,209 @@
+//! The binary encoding of nodes.
+//!
+//! The encoding begins with a magic number, followed by the root node. Each
+//! node is encoded as a tag byte followed by its contents. Numbers and lengths
+//! are encoded as variable-length integers (LEB128), with negative integers
+//! being zigzag encoded.
+
+use super::{DecodeError, Node};
+
+/// The magic number at the start of the binary encoding.
+const MAGIC: &[u8] = b"\0mltt-core";
+
+const TAG_NULL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_NAT: u8 = 3;
+const TAG_INT: u8 = 4;
+const TAG_STRING: u8 = 5;
+const TAG_ARRAY: u8 = 6;
+const TAG_OBJECT: u8 = 7;
+
+/// Encode a node in the binary format.
+pub fn write(node: &Node) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    write_node(&mut bytes, node);
+    bytes
+}
+
+fn write_node(bytes: &mut Vec<u8>, node: &Node) {
+    match node {
+        Node::Null => bytes.push(TAG_NULL),
+        Node::Bool(false) => bytes.push(TAG_FALSE),
+        Node::Bool(true) => bytes.push(TAG_TRUE),
+        Node::Nat(value) => {
+            bytes.push(TAG_NAT);
+            write_varint(bytes, *value);
+        },
+        Node::Int(value) => {
+            bytes.push(TAG_INT);
+            write_varint(bytes, ((value << 1) ^ (value >> 63)) as u64);
+        },
+        Node::String(value) => {
+            bytes.push(TAG_STRING);
+            write_str(bytes, value);
+        },
+        Node::Array(nodes) => {
+            bytes.push(TAG_ARRAY);
+            write_varint(bytes, nodes.len() as u64);
+            for node in nodes {
+                write_node(bytes, node);
+            }
+        },
+        Node::Object(fields) => {
+            bytes.push(TAG_OBJECT);
+            write_varint(bytes, fields.len() as u64);
+            for (name, node) in fields {
+                write_str(bytes, name);
+                write_node(bytes, node);
+            }
+        },
+    }
+}
+
+fn write_str(bytes: &mut Vec<u8>, value: &str) {
+    write_varint(bytes, value.len() as u64);
+    bytes.extend_from_slice(value.as_bytes());
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Decode a node from the binary format.
+pub fn read(bytes: &[u8]) -> Result<Node, DecodeError> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(DecodeError::InvalidHeader);
+    }
+
+    let mut reader = Reader {
+        bytes,
+        offset: MAGIC.len(),
+    };
+    let node = reader.read_node()?;
+
+    if reader.offset == bytes.len() {
+        Ok(node)
+    } else {
+        Err(DecodeError::TrailingData)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn read_byte(&mut self) -> Result<u8, DecodeError> {
+        let byte = *self
+            .bytes
+            .get(self.offset)
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn read_varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_byte()?;
+            // The last byte of a 64-bit integer can only hold a single bit
+            if shift == 63 && byte > 1 {
+                return Err(DecodeError::Expected("variable-length integer"));
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Read a length, ensuring that there is enough input remaining for it
+    /// to be plausible, so that we don't allocate huge amounts of memory for
+    /// corrupted input.
+    fn read_len(&mut self) -> Result<usize, DecodeError> {
+        let len = self.read_varint()?;
+        if len > (self.bytes.len() - self.offset) as u64 {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        Ok(len as usize)
+    }
+
+    fn read_str(&mut self) -> Result<String, DecodeError> {
+        let len = self.read_len()?;
+        let bytes = &self.bytes[self.offset..self.offset + len];
+        self.offset += len;
+
+        String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::InvalidUtf8)
+    }
+
+    fn read_node(&mut self) -> Result<Node, DecodeError> {
+        match self.read_byte()? {
+            TAG_NULL => Ok(Node::Null),
+            TAG_FALSE => Ok(Node::Bool(false)),
+            TAG_TRUE => Ok(Node::Bool(true)),
+            TAG_NAT => Ok(Node::Nat(self.read_varint()?)),
+            TAG_INT => {
+                let value = self.read_varint()?;
+                Ok(Node::Int((value >> 1) as i64 ^ -((value & 1) as i64)))
+            },
+            TAG_STRING => Ok(Node::String(self.read_str()?)),
+            TAG_ARRAY => {
+                let len = self.read_len()?;
+                let mut nodes = Vec::with_capacity(len);
+                for _ in 0..len {
+                    nodes.push(self.read_node()?);
+                }
+                Ok(Node::Array(nodes))
+            },
+            TAG_OBJECT => {
+                let len = self.read_len()?;
+                let mut fields = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let name = self.read_str()?;
+                    fields.push((name, self.read_node()?));
+                }
+                Ok(Node::Object(fields))
+            },
+            tag => Err(DecodeError::InvalidTag(tag)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip_numbers() {
+        let node = Node::Array(vec![
+            Node::Nat(0),
+            Node::Nat(127),
+            Node::Nat(128),
+            Node::Nat(u64::max_value()),
+            Node::Int(-1),
+            Node::Int(-64),
+            Node::Int(-65),
+            Node::Int(i64::min_value()),
+        ]);
+
+        assert_eq!(read(&write(&node)), Ok(node));
+    }
+
+    #[test]
+    fn read_truncated() {
+        let bytes = write(&Node::String("hello".to_owned()));
+
+        assert_eq!(
+            read(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::UnexpectedEnd),
+        );
+    }
+}
@@ -0,0 +1,317 @@
+//! The JSON encoding of nodes.
+//!
+//! Only the subset of JSON that is needed to represent nodes is supported.
+//! Numbers must be integers, so that they can be read back exactly.
+
+use std::fmt::Write;
+
+use super::{DecodeError, Node};
+
+/// Encode a node as JSON.
+pub fn write(node: &Node) -> String {
+    let mut output = String::new();
+    write_node(&mut output, node);
+    output.push('\n');
+    output
+}
+
+fn write_node(output: &mut String, node: &Node) {
+    match node {
+        Node::Null => output.push_str("null"),
+        Node::Bool(value) => output.push_str(if *value { "true" } else { "false" }),
+        Node::Nat(value) => write!(output, "{}", value).unwrap(),
+        Node::Int(value) => write!(output, "{}", value).unwrap(),
+        Node::String(value) => write_str(output, value),
+        Node::Array(nodes) => {
+            output.push('[');
+            for (index, node) in nodes.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_node(output, node);
+            }
+            output.push(']');
+        },
+        Node::Object(fields) => {
+            output.push('{');
+            for (index, (name, node)) in fields.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_str(output, name);
+                output.push(':');
+                write_node(output, node);
+            }
+            output.push('}');
+        },
+    }
+}
+
+fn write_str(output: &mut String, value: &str) {
+    output.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            ch if ch < ' ' => write!(output, "\\u{:04x}", ch as u32).unwrap(),
+            ch => output.push(ch),
+        }
+    }
+    output.push('"');
+}
+
+/// Decode a node from JSON.
+pub fn read(bytes: &[u8]) -> Result<Node, DecodeError> {
+    let mut reader = Reader { bytes, offset: 0 };
+    reader.skip_whitespace();
+    let node = reader.read_node()?;
+    reader.skip_whitespace();
+
+    if reader.offset == bytes.len() {
+        Ok(node)
+    } else {
+        Err(DecodeError::TrailingData)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self) -> DecodeError {
+        if self.offset >= self.bytes.len() {
+            DecodeError::UnexpectedEnd
+        } else {
+            DecodeError::InvalidJson(self.offset)
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.offset).cloned()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), DecodeError> {
+        if self.peek() == Some(byte) {
+            self.offset += 1;
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), DecodeError> {
+        if self.bytes[self.offset..].starts_with(keyword.as_bytes()) {
+            self.offset += keyword.len();
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\n') | Some(b'\r') | Some(b'\t') = self.peek() {
+            self.offset += 1;
+        }
+    }
+
+    fn read_node(&mut self) -> Result<Node, DecodeError> {
+        match self.peek() {
+            Some(b'n') => self.expect_keyword("null").map(|()| Node::Null),
+            Some(b't') => self.expect_keyword("true").map(|()| Node::Bool(true)),
+            Some(b'f') => self.expect_keyword("false").map(|()| Node::Bool(false)),
+            Some(b'"') => Ok(Node::String(self.read_str()?)),
+            Some(b'-') | Some(b'0'..=b'9') => self.read_number(),
+            Some(b'[') => {
+                self.offset += 1;
+                let mut nodes = Vec::new();
+                self.read_sequence(b']', |reader| {
+                    nodes.push(reader.read_node()?);
+                    Ok(())
+                })?;
+                Ok(Node::Array(nodes))
+            },
+            Some(b'{') => {
+                self.offset += 1;
+                let mut fields = Vec::new();
+                self.read_sequence(b'}', |reader| {
+                    let name = reader.read_str()?;
+                    reader.skip_whitespace();
+                    reader.expect(b':')?;
+                    reader.skip_whitespace();
+                    fields.push((name, reader.read_node()?));
+                    Ok(())
+                })?;
+                Ok(Node::Object(fields))
+            },
+            _ => Err(self.error()),
+        }
+    }
+
+    /// Read comma separated elements, up to the closing delimiter.
+    fn read_sequence(
+        &mut self,
+        close: u8,
+        mut read_element: impl FnMut(&mut Reader<'a>) -> Result<(), DecodeError>,
+    ) -> Result<(), DecodeError> {
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.offset += 1;
+            return Ok(());
+        }
+
+        loop {
+            read_element(self)?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => {
+                    self.offset += 1;
+                    self.skip_whitespace();
+                },
+                Some(byte) if byte == close => {
+                    self.offset += 1;
+                    return Ok(());
+                },
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    fn read_number(&mut self) -> Result<Node, DecodeError> {
+        let start = self.offset;
+        if self.peek() == Some(b'-') {
+            self.offset += 1;
+        }
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.offset += 1;
+        }
+        if let Some(b'.') | Some(b'e') | Some(b'E') = self.peek() {
+            // Fractional numbers are not used by the format
+            return Err(self.error());
+        }
+
+        let src = std::str::from_utf8(&self.bytes[start..self.offset]).unwrap();
+        let node = if src.starts_with('-') {
+            src.parse().map(Node::int)
+        } else {
+            src.parse().map(Node::Nat)
+        };
+
+        node.map_err(|_| DecodeError::InvalidJson(start))
+    }
+
+    fn read_str(&mut self) -> Result<String, DecodeError> {
+        self.expect(b'"')?;
+        let mut value = Vec::new();
+
+        loop {
+            match self.peek() {
+                None => return Err(DecodeError::UnexpectedEnd),
+                Some(b'"') => {
+                    self.offset += 1;
+                    return String::from_utf8(value).map_err(|_| DecodeError::InvalidUtf8);
+                },
+                Some(b'\\') => {
+                    self.offset += 1;
+                    let escape = self.peek().ok_or(DecodeError::UnexpectedEnd)?;
+                    self.offset += 1;
+                    let ch = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.read_unicode_escape()?,
+                        _ => return Err(DecodeError::InvalidJson(self.offset - 1)),
+                    };
+                    let mut buffer = [0; 4];
+                    value.extend_from_slice(ch.encode_utf8(&mut buffer).as_bytes());
+                },
+                Some(byte) if byte < b' ' => return Err(self.error()),
+                Some(byte) => {
+                    self.offset += 1;
+                    value.push(byte);
+                },
+            }
+        }
+    }
+
+    /// Read the hexadecimal digits of a `\u` escape, combining surrogate
+    /// pairs into a single character.
+    fn read_unicode_escape(&mut self) -> Result<char, DecodeError> {
+        let high = self.read_hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            self.expect_keyword("\\u")?;
+            let low = self.read_hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(DecodeError::InvalidJson(self.offset));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+
+        std::char::from_u32(code).ok_or(DecodeError::InvalidJson(self.offset))
+    }
+
+    fn read_hex4(&mut self) -> Result<u32, DecodeError> {
+        let digits = self
+            .bytes
+            .get(self.offset..self.offset + 4)
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        let digits = std::str::from_utf8(digits).map_err(|_| self.error())?;
+        let code = u32::from_str_radix(digits, 16).map_err(|_| self.error())?;
+        self.offset += 4;
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip_strings() {
+        let node = Node::Array(vec![
+            Node::String("".to_owned()),
+            Node::String("\"quoted\" \\ /".to_owned()),
+            Node::String("line\nbreak\ttab\u{0}\u{1f}".to_owned()),
+            Node::String("λ 🦀".to_owned()),
+        ]);
+
+        assert_eq!(read(write(&node).as_bytes()), Ok(node));
+    }
+
+    #[test]
+    fn read_whitespace_and_escapes() {
+        let src = " { \"a\" : [ 1 , -2 , null , true ] , \"b\" : \"\\u03bb\\ud83e\\udd80\" } ";
+
+        assert_eq!(
+            read(src.as_bytes()),
+            Ok(Node::Object(vec![
+                (
+                    "a".to_owned(),
+                    Node::Array(vec![
+                        Node::Nat(1),
+                        Node::Int(-2),
+                        Node::Null,
+                        Node::Bool(true),
+                    ]),
+                ),
+                ("b".to_owned(), Node::String("λ🦀".to_owned())),
+            ])),
+        );
+    }
+
+    #[test]
+    fn read_fractional_number() {
+        assert_eq!(read(b"1.5"), Err(DecodeError::InvalidJson(1)));
+    }
+}
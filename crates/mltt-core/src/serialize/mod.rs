@@ -0,0 +1,971 @@
+//! Serialization of the core syntax.
+//!
+//! Elaborated modules can be written out in either a compact binary format, or
+//! in JSON, allowing them to be cached, or consumed by other tools without
+//! linking the elaborator. Both formats share the same structure: terms are
+//! first converted to a tree of [`Node`]s, which is then encoded in the chosen
+//! format. Terms are encoded as arrays that begin with the name of their
+//! variant, followed by their fields, for example `["Var", 0]`.
+//!
+//! Encoded modules begin with a header that includes the version of the
+//! format, and are rejected if the version does not match [`FORMAT_VERSION`].
+//! Decoded modules should not be trusted until they have been checked, which
+//! can be done by loading them with [`load_module`].
+//!
+//! [`Node`]: enum.Node.html
+//! [`FORMAT_VERSION`]: constant.FORMAT_VERSION.html
+//! [`load_module`]: fn.load_module.html
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::intern::Symbol;
+use crate::literal::{LiteralIntro, LiteralType};
+use crate::syntax::{Data, Guard, Item, Module, RecordTypeField, Term};
+use crate::validate::{self, TypeError};
+use crate::{meta, AppMode, DocString, Label, UniverseLevel};
+
+mod binary;
+mod json;
+
+/// The name of the format, recorded in the header of encoded modules.
+const FORMAT_NAME: &str = "mltt-core";
+
+/// The version of the format. This should be incremented whenever the
+/// encoding of modules changes.
+pub const FORMAT_VERSION: u64 = 1;
+
+/// The formats that modules can be encoded in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// A compact binary format.
+    Binary,
+    /// A JSON format, for consumption by other tools.
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Format, String> {
+        match src {
+            "binary" => Ok(Format::Binary),
+            "json" => Ok(Format::Json),
+            _ => Err(format!(
+                "unknown format `{}`, expected `binary` or `json`",
+                src
+            )),
+        }
+    }
+}
+
+/// A tree of serialized data, shared between the binary and JSON formats.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Null,
+    Bool(bool),
+    /// Natural numbers.
+    Nat(u64),
+    /// Negative integers.
+    Int(i64),
+    String(String),
+    Array(Vec<Node>),
+    Object(Vec<(String, Node)>),
+}
+
+impl Node {
+    /// Construct an integer node, using `Node::Nat` for non-negative integers
+    /// so that each number has a unique representation.
+    fn int(value: i64) -> Node {
+        if value < 0 {
+            Node::Int(value)
+        } else {
+            Node::Nat(value as u64)
+        }
+    }
+
+    /// Construct a node for a variant of an enum.
+    fn variant(tag: &str, fields: Vec<Node>) -> Node {
+        let mut nodes = Vec::with_capacity(fields.len() + 1);
+        nodes.push(Node::String(tag.to_owned()));
+        nodes.extend(fields);
+        Node::Array(nodes)
+    }
+}
+
+/// An error that occurred while decoding a module.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    /// The input ended unexpectedly.
+    UnexpectedEnd,
+    /// The input continued after the end of the module.
+    TrailingData,
+    /// The input did not begin with the expected header.
+    InvalidHeader,
+    /// The module was encoded with an unsupported version of the format.
+    UnsupportedVersion(u64),
+    /// A string was not valid UTF-8.
+    InvalidUtf8,
+    /// An unknown tag was found in the binary format.
+    InvalidTag(u8),
+    /// Malformed JSON was found at the given byte offset.
+    InvalidJson(usize),
+    /// The data did not have the expected structure.
+    Expected(&'static str),
+}
+
+impl Error for DecodeError {}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            DecodeError::TrailingData => write!(f, "unexpected data after the end of the module"),
+            DecodeError::InvalidHeader => write!(f, "not an encoded core module"),
+            DecodeError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported format version {}, expected version {}",
+                version, FORMAT_VERSION,
+            ),
+            DecodeError::InvalidUtf8 => write!(f, "invalid UTF-8 in string"),
+            DecodeError::InvalidTag(tag) => write!(f, "invalid tag: {:#04x}", tag),
+            DecodeError::InvalidJson(offset) => write!(f, "invalid JSON at byte {}", offset),
+            DecodeError::Expected(expected) => write!(f, "expected {}", expected),
+        }
+    }
+}
+
+/// An error that occurred while loading a module.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+    /// The module could not be decoded.
+    Decode(DecodeError),
+    /// The decoded module was not well typed.
+    Type(TypeError),
+}
+
+impl From<DecodeError> for LoadError {
+    fn from(error: DecodeError) -> LoadError {
+        LoadError::Decode(error)
+    }
+}
+
+impl From<TypeError> for LoadError {
+    fn from(error: TypeError) -> LoadError {
+        LoadError::Type(error)
+    }
+}
+
+impl Error for LoadError {}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Decode(error) => write!(f, "failed to decode module: {}", error),
+            LoadError::Type(error) => write!(f, "failed to validate module: {}", error),
+        }
+    }
+}
+
+/// Encode a module in the given format.
+pub fn encode_module(format: Format, module: &Module) -> Vec<u8> {
+    let node = Node::Object(vec![
+        ("format".to_owned(), Node::String(FORMAT_NAME.to_owned())),
+        ("version".to_owned(), Node::Nat(FORMAT_VERSION)),
+        ("items".to_owned(), encode_items(&module.items)),
+    ]);
+
+    match format {
+        Format::Binary => binary::write(&node),
+        Format::Json => json::write(&node).into_bytes(),
+    }
+}
+
+/// Decode a module that was encoded in the given format.
+///
+/// Note that the module is not checked - use `load_module` if it might not
+/// be well typed.
+pub fn decode_module(format: Format, bytes: &[u8]) -> Result<Module, DecodeError> {
+    let node = match format {
+        Format::Binary => binary::read(bytes)?,
+        Format::Json => json::read(bytes)?,
+    };
+
+    let fields = match &node {
+        Node::Object(fields) => fields.as_slice(),
+        _ => return Err(DecodeError::InvalidHeader),
+    };
+    let field = |name| lookup_field(fields, name);
+
+    match field("format") {
+        Some(Node::String(format_name)) if format_name == FORMAT_NAME => {},
+        _ => return Err(DecodeError::InvalidHeader),
+    }
+    match field("version") {
+        Some(Node::Nat(FORMAT_VERSION)) => {},
+        Some(Node::Nat(version)) => return Err(DecodeError::UnsupportedVersion(*version)),
+        _ => return Err(DecodeError::InvalidHeader),
+    }
+    match field("items") {
+        Some(items) => Ok(Module {
+            items: decode_items(items)?,
+        }),
+        None => Err(DecodeError::Expected("items")),
+    }
+}
+
+/// Lookup the value of a field in an object.
+fn lookup_field<'a>(fields: &'a [(String, Node)], name: &str) -> Option<&'a Node> {
+    fields
+        .iter()
+        .find(|(field_name, _)| field_name == name)
+        .map(|(_, node)| node)
+}
+
+/// Decode a module, checking that it is well typed in the given context
+/// before it is used.
+pub fn load_module(
+    context: &validate::Context,
+    format: Format,
+    bytes: &[u8],
+) -> Result<Module, LoadError> {
+    let module = decode_module(format, bytes)?;
+    // Modules are zonked before being encoded, so they should not refer to
+    // any metavariables
+    validate::check_module(context, &meta::Env::new(), &module)?;
+    Ok(module)
+}
+
+// Encoding
+
+fn encode_label(label: &Label) -> Node {
+    Node::String(label.as_str().to_owned())
+}
+
+fn encode_docs(docs: &DocString) -> Node {
+    Node::String(docs.to_string())
+}
+
+fn encode_name_hint(name_hint: &Option<Symbol>) -> Node {
+    match name_hint {
+        None => Node::Null,
+        Some(name_hint) => Node::String(name_hint.as_str().to_owned()),
+    }
+}
+
+fn encode_app_mode(app_mode: &AppMode) -> Node {
+    match app_mode {
+        AppMode::Explicit => Node::variant("Explicit", vec![]),
+        AppMode::Implicit(label) => Node::variant("Implicit", vec![encode_label(label)]),
+        AppMode::Instance(label) => Node::variant("Instance", vec![encode_label(label)]),
+    }
+}
+
+fn encode_guard(guard: &Guard) -> Node {
+    match guard {
+        Guard::Always => Node::variant("Always", vec![]),
+        Guard::Structural(position) => {
+            Node::variant("Structural", vec![Node::Nat(*position as u64)])
+        },
+        Guard::Partial => Node::variant("Partial", vec![]),
+    }
+}
+
+fn encode_universe_level(level: UniverseLevel) -> Node {
+    Node::Nat(u64::from(level.0))
+}
+
+fn encode_literal_ty(literal_ty: &LiteralType) -> Node {
+    let tag = match literal_ty {
+        LiteralType::String => "String",
+        LiteralType::Char => "Char",
+        LiteralType::Bool => "Bool",
+        LiteralType::U8 => "U8",
+        LiteralType::U16 => "U16",
+        LiteralType::U32 => "U32",
+        LiteralType::U64 => "U64",
+        LiteralType::S8 => "S8",
+        LiteralType::S16 => "S16",
+        LiteralType::S32 => "S32",
+        LiteralType::S64 => "S64",
+        LiteralType::F32 => "F32",
+        LiteralType::F64 => "F64",
+    };
+
+    Node::String(tag.to_owned())
+}
+
+/// Encode a literal. Floating point numbers are encoded using their bits, so
+/// that they survive the round trip exactly.
+fn encode_literal_intro(literal_intro: &LiteralIntro) -> Node {
+    match literal_intro {
+        LiteralIntro::String(value) => {
+            Node::variant("String", vec![Node::String(value.to_string())])
+        },
+        LiteralIntro::Char(value) => Node::variant("Char", vec![Node::String(value.to_string())]),
+        LiteralIntro::Bool(value) => Node::variant("Bool", vec![Node::Bool(*value)]),
+        LiteralIntro::U8(value) => Node::variant("U8", vec![Node::Nat(u64::from(*value))]),
+        LiteralIntro::U16(value) => Node::variant("U16", vec![Node::Nat(u64::from(*value))]),
+        LiteralIntro::U32(value) => Node::variant("U32", vec![Node::Nat(u64::from(*value))]),
+        LiteralIntro::U64(value) => Node::variant("U64", vec![Node::Nat(*value)]),
+        LiteralIntro::S8(value) => Node::variant("S8", vec![Node::int(i64::from(*value))]),
+        LiteralIntro::S16(value) => Node::variant("S16", vec![Node::int(i64::from(*value))]),
+        LiteralIntro::S32(value) => Node::variant("S32", vec![Node::int(i64::from(*value))]),
+        LiteralIntro::S64(value) => Node::variant("S64", vec![Node::int(*value)]),
+        LiteralIntro::F32(value) => {
+            Node::variant("F32", vec![Node::Nat(u64::from(value.to_bits()))])
+        },
+        LiteralIntro::F64(value) => Node::variant("F64", vec![Node::Nat(value.to_bits())]),
+    }
+}
+
+fn encode_items(items: &[Item]) -> Node {
+    Node::Array(items.iter().map(encode_item).collect())
+}
+
+fn encode_item(item: &Item) -> Node {
+    match item {
+        Item::Declaration(docs, label, ty) => Node::variant(
+            "Declaration",
+            vec![encode_docs(docs), encode_label(label), encode_term(ty)],
+        ),
+        Item::Definition(docs, label, term) => Node::variant(
+            "Definition",
+            vec![encode_docs(docs), encode_label(label), encode_term(term)],
+        ),
+    }
+}
+
+fn encode_data(data: &Data) -> Node {
+    let intros = data
+        .intros
+        .iter()
+        .map(|(docs, label, ty)| {
+            Node::Array(vec![
+                encode_docs(docs),
+                encode_label(label),
+                encode_term(ty),
+            ])
+        })
+        .collect();
+
+    Node::Array(vec![
+        encode_label(&data.label),
+        Node::Nat(data.param_count as u64),
+        encode_term(&data.ty),
+        Node::Array(intros),
+    ])
+}
+
+fn encode_term(term: &Term) -> Node {
+    match term {
+        Term::Var(index) => Node::variant("Var", vec![Node::Nat(u64::from(index.0))]),
+        Term::Meta(index) => Node::variant("Meta", vec![Node::Nat(u64::from(index.0))]),
+        Term::Prim(name) => Node::variant("Prim", vec![Node::String(name.0.to_string())]),
+
+        Term::Ann(term, term_ty) => {
+            Node::variant("Ann", vec![encode_term(term), encode_term(term_ty)])
+        },
+        Term::Let(items, body) => {
+            Node::variant("Let", vec![encode_items(items), encode_term(body)])
+        },
+        Term::Fix(index, defns) => {
+            let defns = defns
+                .iter()
+                .map(|(label, guard, ty, body)| {
+                    Node::Array(vec![
+                        encode_label(label),
+                        encode_guard(guard),
+                        encode_term(ty),
+                        encode_term(body),
+                    ])
+                })
+                .collect();
+
+            Node::variant("Fix", vec![Node::Nat(*index as u64), Node::Array(defns)])
+        },
+
+        Term::LiteralType(literal_ty) => {
+            Node::variant("LiteralType", vec![encode_literal_ty(literal_ty)])
+        },
+        Term::LiteralIntro(literal_intro) => {
+            Node::variant("LiteralIntro", vec![encode_literal_intro(literal_intro)])
+        },
+        Term::LiteralElim(scrutinee, clauses, default_body) => {
+            let clauses = clauses
+                .iter()
+                .map(|(literal_intro, body)| {
+                    Node::Array(vec![encode_literal_intro(literal_intro), encode_term(body)])
+                })
+                .collect();
+
+            Node::variant(
+                "LiteralElim",
+                vec![
+                    encode_term(scrutinee),
+                    Node::Array(clauses),
+                    encode_term(default_body),
+                ],
+            )
+        },
+
+        Term::FunType(app_mode, name_hint, param_ty, body_ty) => Node::variant(
+            "FunType",
+            vec![
+                encode_app_mode(app_mode),
+                encode_name_hint(name_hint),
+                encode_term(param_ty),
+                encode_term(body_ty),
+            ],
+        ),
+        Term::FunIntro(app_mode, name_hint, body) => Node::variant(
+            "FunIntro",
+            vec![
+                encode_app_mode(app_mode),
+                encode_name_hint(name_hint),
+                encode_term(body),
+            ],
+        ),
+        Term::FunElim(fun, app_mode, arg) => Node::variant(
+            "FunElim",
+            vec![
+                encode_term(fun),
+                encode_app_mode(app_mode),
+                encode_term(arg),
+            ],
+        ),
+
+        Term::RecordType(ty_fields) => {
+            let ty_fields = ty_fields
+                .iter()
+                .map(|(docs, label, name_hint, ty, manifest)| {
+                    Node::Array(vec![
+                        encode_docs(docs),
+                        encode_label(label),
+                        encode_name_hint(name_hint),
+                        encode_term(ty),
+                        match manifest {
+                            None => Node::Null,
+                            Some(manifest) => encode_term(manifest),
+                        },
+                    ])
+                })
+                .collect();
+
+            Node::variant("RecordType", vec![Node::Array(ty_fields)])
+        },
+        Term::RecordIntro(intro_fields) => {
+            let intro_fields = intro_fields
+                .iter()
+                .map(|(label, term)| Node::Array(vec![encode_label(label), encode_term(term)]))
+                .collect();
+
+            Node::variant("RecordIntro", vec![Node::Array(intro_fields)])
+        },
+        Term::RecordElim(record, label) => {
+            Node::variant("RecordElim", vec![encode_term(record), encode_label(label)])
+        },
+
+        Term::DataType(data) => Node::variant("DataType", vec![encode_data(data)]),
+        Term::DataIntro(data, label) => {
+            Node::variant("DataIntro", vec![encode_data(data), encode_label(label)])
+        },
+        Term::DataElim(scrutinee, clauses) => {
+            let clauses = clauses
+                .iter()
+                .map(|(label, name_hints, body)| {
+                    let name_hints = name_hints.iter().map(encode_name_hint).collect();
+                    Node::Array(vec![
+                        encode_label(label),
+                        Node::Array(name_hints),
+                        encode_term(body),
+                    ])
+                })
+                .collect();
+
+            Node::variant(
+                "DataElim",
+                vec![encode_term(scrutinee), Node::Array(clauses)],
+            )
+        },
+
+        Term::EqType(ty, lhs, rhs) => Node::variant(
+            "EqType",
+            vec![encode_term(ty), encode_term(lhs), encode_term(rhs)],
+        ),
+        Term::EqIntro => Node::variant("EqIntro", vec![]),
+        Term::EqElim(proof, motive, body) => Node::variant(
+            "EqElim",
+            vec![encode_term(proof), encode_term(motive), encode_term(body)],
+        ),
+
+        Term::LevelType => Node::variant("LevelType", vec![]),
+        Term::LevelIntro(level) => Node::variant("LevelIntro", vec![encode_universe_level(*level)]),
+        Term::LevelOmega => Node::variant("LevelOmega", vec![]),
+        Term::LevelShift(level, shift) => Node::variant(
+            "LevelShift",
+            vec![encode_term(level), Node::Nat(u64::from(*shift))],
+        ),
+        Term::LevelMax(lhs, rhs) => {
+            Node::variant("LevelMax", vec![encode_term(lhs), encode_term(rhs)])
+        },
+
+        Term::Universe(level) => Node::variant("Universe", vec![encode_term(level)]),
+    }
+}
+
+// Decoding
+
+fn decode_array<'a>(node: &'a Node, expected: &'static str) -> Result<&'a [Node], DecodeError> {
+    match node {
+        Node::Array(nodes) => Ok(nodes.as_slice()),
+        _ => Err(DecodeError::Expected(expected)),
+    }
+}
+
+/// Decode an array that begins with the name of a variant.
+fn decode_variant<'a>(
+    node: &'a Node,
+    expected: &'static str,
+) -> Result<(&'a str, &'a [Node]), DecodeError> {
+    match decode_array(node, expected)?.split_first() {
+        Some((Node::String(tag), fields)) => Ok((tag.as_str(), fields)),
+        _ => Err(DecodeError::Expected(expected)),
+    }
+}
+
+fn decode_str<'a>(node: &'a Node, expected: &'static str) -> Result<&'a str, DecodeError> {
+    match node {
+        Node::String(value) => Ok(value.as_str()),
+        _ => Err(DecodeError::Expected(expected)),
+    }
+}
+
+fn decode_nat<T: TryFrom<u64>>(node: &Node, expected: &'static str) -> Result<T, DecodeError> {
+    match node {
+        Node::Nat(value) => T::try_from(*value).map_err(|_| DecodeError::Expected(expected)),
+        _ => Err(DecodeError::Expected(expected)),
+    }
+}
+
+fn decode_int<T: TryFrom<i64>>(node: &Node, expected: &'static str) -> Result<T, DecodeError> {
+    let value = match node {
+        Node::Nat(value) => i64::try_from(*value).map_err(|_| DecodeError::Expected(expected))?,
+        Node::Int(value) => *value,
+        _ => return Err(DecodeError::Expected(expected)),
+    };
+
+    T::try_from(value).map_err(|_| DecodeError::Expected(expected))
+}
+
+fn decode_label(node: &Node) -> Result<Label, DecodeError> {
+    Ok(Label::new(decode_str(node, "label")?))
+}
+
+fn decode_docs(node: &Node) -> Result<DocString, DecodeError> {
+    Ok(DocString::from(decode_str(node, "documentation")?))
+}
+
+fn decode_name_hint(node: &Node) -> Result<Option<Symbol>, DecodeError> {
+    match node {
+        Node::Null => Ok(None),
+        Node::String(name_hint) => Ok(Some(Symbol::intern(name_hint))),
+        _ => Err(DecodeError::Expected("name hint")),
+    }
+}
+
+fn decode_app_mode(node: &Node) -> Result<AppMode, DecodeError> {
+    match decode_variant(node, "application mode")? {
+        ("Explicit", []) => Ok(AppMode::Explicit),
+        ("Implicit", [label]) => Ok(AppMode::Implicit(decode_label(label)?)),
+        ("Instance", [label]) => Ok(AppMode::Instance(decode_label(label)?)),
+        (_, _) => Err(DecodeError::Expected("application mode")),
+    }
+}
+
+fn decode_guard(node: &Node) -> Result<Guard, DecodeError> {
+    match decode_variant(node, "guard")? {
+        ("Always", []) => Ok(Guard::Always),
+        ("Structural", [position]) => Ok(Guard::Structural(decode_nat(position, "position")?)),
+        ("Partial", []) => Ok(Guard::Partial),
+        (_, _) => Err(DecodeError::Expected("guard")),
+    }
+}
+
+fn decode_universe_level(node: &Node) -> Result<UniverseLevel, DecodeError> {
+    Ok(UniverseLevel(decode_nat(node, "universe level")?))
+}
+
+fn decode_literal_ty(node: &Node) -> Result<LiteralType, DecodeError> {
+    match decode_str(node, "literal type")? {
+        "String" => Ok(LiteralType::String),
+        "Char" => Ok(LiteralType::Char),
+        "Bool" => Ok(LiteralType::Bool),
+        "U8" => Ok(LiteralType::U8),
+        "U16" => Ok(LiteralType::U16),
+        "U32" => Ok(LiteralType::U32),
+        "U64" => Ok(LiteralType::U64),
+        "S8" => Ok(LiteralType::S8),
+        "S16" => Ok(LiteralType::S16),
+        "S32" => Ok(LiteralType::S32),
+        "S64" => Ok(LiteralType::S64),
+        "F32" => Ok(LiteralType::F32),
+        "F64" => Ok(LiteralType::F64),
+        _ => Err(DecodeError::Expected("literal type")),
+    }
+}
+
+fn decode_literal_intro(node: &Node) -> Result<LiteralIntro, DecodeError> {
+    const EXPECTED: &str = "literal";
+
+    match decode_variant(node, EXPECTED)? {
+        ("String", [value]) => Ok(LiteralIntro::String(Rc::from(decode_str(value, EXPECTED)?))),
+        ("Char", [value]) => {
+            let mut chars = decode_str(value, EXPECTED)?.chars();
+            match (chars.next(), chars.next()) {
+                (Some(value), None) => Ok(LiteralIntro::Char(value)),
+                (_, _) => Err(DecodeError::Expected(EXPECTED)),
+            }
+        },
+        ("Bool", [Node::Bool(value)]) => Ok(LiteralIntro::Bool(*value)),
+        ("U8", [value]) => Ok(LiteralIntro::U8(decode_nat(value, EXPECTED)?)),
+        ("U16", [value]) => Ok(LiteralIntro::U16(decode_nat(value, EXPECTED)?)),
+        ("U32", [value]) => Ok(LiteralIntro::U32(decode_nat(value, EXPECTED)?)),
+        ("U64", [value]) => Ok(LiteralIntro::U64(decode_nat(value, EXPECTED)?)),
+        ("S8", [value]) => Ok(LiteralIntro::S8(decode_int(value, EXPECTED)?)),
+        ("S16", [value]) => Ok(LiteralIntro::S16(decode_int(value, EXPECTED)?)),
+        ("S32", [value]) => Ok(LiteralIntro::S32(decode_int(value, EXPECTED)?)),
+        ("S64", [value]) => Ok(LiteralIntro::S64(decode_int(value, EXPECTED)?)),
+        ("F32", [bits]) => Ok(LiteralIntro::F32(f32::from_bits(decode_nat(
+            bits, EXPECTED,
+        )?))),
+        ("F64", [bits]) => Ok(LiteralIntro::F64(f64::from_bits(decode_nat(
+            bits, EXPECTED,
+        )?))),
+        (_, _) => Err(DecodeError::Expected(EXPECTED)),
+    }
+}
+
+fn decode_items(node: &Node) -> Result<Vec<Item>, DecodeError> {
+    decode_array(node, "items")?
+        .iter()
+        .map(decode_item)
+        .collect()
+}
+
+fn decode_item(node: &Node) -> Result<Item, DecodeError> {
+    match decode_variant(node, "item")? {
+        ("Declaration", [docs, label, ty]) => Ok(Item::Declaration(
+            decode_docs(docs)?,
+            decode_label(label)?,
+            decode_term(ty)?,
+        )),
+        ("Definition", [docs, label, term]) => Ok(Item::Definition(
+            decode_docs(docs)?,
+            decode_label(label)?,
+            decode_term(term)?,
+        )),
+        (_, _) => Err(DecodeError::Expected("item")),
+    }
+}
+
+fn decode_data(node: &Node) -> Result<Rc<Data>, DecodeError> {
+    match decode_array(node, "data type")? {
+        [label, param_count, ty, intros] => {
+            let intros = decode_array(intros, "constructors")?
+                .iter()
+                .map(|intro| match decode_array(intro, "constructor")? {
+                    [docs, label, ty] => {
+                        Ok((decode_docs(docs)?, decode_label(label)?, decode_term(ty)?))
+                    },
+                    _ => Err(DecodeError::Expected("constructor")),
+                })
+                .collect::<Result<_, _>>()?;
+
+            Ok(Rc::from(Data {
+                label: decode_label(label)?,
+                param_count: decode_nat(param_count, "parameter count")?,
+                ty: decode_term(ty)?,
+                intros,
+            }))
+        },
+        _ => Err(DecodeError::Expected("data type")),
+    }
+}
+
+fn decode_term(node: &Node) -> Result<Rc<Term>, DecodeError> {
+    const EXPECTED: &str = "term";
+
+    let term = match decode_variant(node, EXPECTED)? {
+        ("Var", [index]) => Term::var(decode_nat::<u32>(index, "variable index")?),
+        ("Meta", [index]) => Term::meta(decode_nat::<u32>(index, "metavariable index")?),
+        ("Prim", [name]) => Term::prim(decode_str(name, "primitive name")?),
+
+        ("Ann", [term, term_ty]) => Term::Ann(decode_term(term)?, decode_term(term_ty)?),
+        ("Let", [items, body]) => Term::Let(decode_items(items)?, decode_term(body)?),
+        ("Fix", [index, defns]) => {
+            let defns = decode_array(defns, "recursive definitions")?
+                .iter()
+                .map(|defn| match decode_array(defn, "recursive definition")? {
+                    [label, guard, ty, body] => Ok((
+                        decode_label(label)?,
+                        decode_guard(guard)?,
+                        decode_term(ty)?,
+                        decode_term(body)?,
+                    )),
+                    _ => Err(DecodeError::Expected("recursive definition")),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Term::Fix(decode_nat(index, "definition index")?, Rc::from(defns))
+        },
+
+        ("LiteralType", [literal_ty]) => Term::LiteralType(decode_literal_ty(literal_ty)?),
+        ("LiteralIntro", [literal_intro]) => {
+            Term::LiteralIntro(decode_literal_intro(literal_intro)?)
+        },
+        ("LiteralElim", [scrutinee, clauses, default_body]) => {
+            let clauses = decode_array(clauses, "clauses")?
+                .iter()
+                .map(|clause| match decode_array(clause, "clause")? {
+                    [literal_intro, body] => {
+                        Ok((decode_literal_intro(literal_intro)?, decode_term(body)?))
+                    },
+                    _ => Err(DecodeError::Expected("clause")),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Term::LiteralElim(
+                decode_term(scrutinee)?,
+                Rc::from(clauses),
+                decode_term(default_body)?,
+            )
+        },
+
+        ("FunType", [app_mode, name_hint, param_ty, body_ty]) => Term::FunType(
+            decode_app_mode(app_mode)?,
+            decode_name_hint(name_hint)?,
+            decode_term(param_ty)?,
+            decode_term(body_ty)?,
+        ),
+        ("FunIntro", [app_mode, name_hint, body]) => Term::FunIntro(
+            decode_app_mode(app_mode)?,
+            decode_name_hint(name_hint)?,
+            decode_term(body)?,
+        ),
+        ("FunElim", [fun, app_mode, arg]) => Term::FunElim(
+            decode_term(fun)?,
+            decode_app_mode(app_mode)?,
+            decode_term(arg)?,
+        ),
+
+        ("RecordType", [ty_fields]) => {
+            let ty_fields = decode_array(ty_fields, "record type fields")?
+                .iter()
+                .map(decode_record_ty_field)
+                .collect::<Result<_, _>>()?;
+
+            Term::RecordType(ty_fields)
+        },
+        ("RecordIntro", [intro_fields]) => {
+            let intro_fields = decode_array(intro_fields, "record fields")?
+                .iter()
+                .map(
+                    |intro_field| match decode_array(intro_field, "record field")? {
+                        [label, term] => Ok((decode_label(label)?, decode_term(term)?)),
+                        _ => Err(DecodeError::Expected("record field")),
+                    },
+                )
+                .collect::<Result<_, _>>()?;
+
+            Term::RecordIntro(intro_fields)
+        },
+        ("RecordElim", [record, label]) => {
+            Term::RecordElim(decode_term(record)?, decode_label(label)?)
+        },
+
+        ("DataType", [data]) => Term::DataType(decode_data(data)?),
+        ("DataIntro", [data, label]) => Term::DataIntro(decode_data(data)?, decode_label(label)?),
+        ("DataElim", [scrutinee, clauses]) => {
+            let clauses = decode_array(clauses, "clauses")?
+                .iter()
+                .map(|clause| match decode_array(clause, "clause")? {
+                    [label, name_hints, body] => {
+                        let name_hints = decode_array(name_hints, "name hints")?
+                            .iter()
+                            .map(decode_name_hint)
+                            .collect::<Result<Vec<_>, _>>()?;
+
+                        Ok((decode_label(label)?, name_hints, decode_term(body)?))
+                    },
+                    _ => Err(DecodeError::Expected("clause")),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Term::DataElim(decode_term(scrutinee)?, Rc::from(clauses))
+        },
+
+        ("EqType", [ty, lhs, rhs]) => {
+            Term::EqType(decode_term(ty)?, decode_term(lhs)?, decode_term(rhs)?)
+        },
+        ("EqIntro", []) => Term::EqIntro,
+        ("EqElim", [proof, motive, body]) => Term::EqElim(
+            decode_term(proof)?,
+            decode_term(motive)?,
+            decode_term(body)?,
+        ),
+
+        ("LevelType", []) => Term::LevelType,
+        ("LevelIntro", [level]) => Term::LevelIntro(decode_universe_level(level)?),
+        ("LevelOmega", []) => Term::LevelOmega,
+        ("LevelShift", [level, shift]) => {
+            Term::LevelShift(decode_term(level)?, decode_nat(shift, "level shift")?)
+        },
+        ("LevelMax", [lhs, rhs]) => Term::LevelMax(decode_term(lhs)?, decode_term(rhs)?),
+
+        ("Universe", [level]) => Term::Universe(decode_term(level)?),
+
+        (_, _) => return Err(DecodeError::Expected(EXPECTED)),
+    };
+
+    Ok(Rc::from(term))
+}
+
+fn decode_record_ty_field(node: &Node) -> Result<RecordTypeField, DecodeError> {
+    match decode_array(node, "record type field")? {
+        [docs, label, name_hint, ty, manifest] => Ok((
+            decode_docs(docs)?,
+            decode_label(label)?,
+            decode_name_hint(name_hint)?,
+            decode_term(ty)?,
+            match manifest {
+                Node::Null => None,
+                manifest => Some(decode_term(manifest)?),
+            },
+        )),
+        _ => Err(DecodeError::Expected("record type field")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn module() -> Module {
+        let u32_ty = Rc::from(Term::literal_ty(LiteralType::U32));
+        let ty = Rc::from(Term::FunType(
+            AppMode::Implicit(Label::new("A")),
+            Some(Symbol::intern("A")),
+            Rc::from(Term::universe(0)),
+            Rc::from(Term::FunType(
+                AppMode::Explicit,
+                None,
+                Rc::from(Term::var(0)),
+                Rc::from(Term::var(1)),
+            )),
+        ));
+        let body = Rc::from(Term::FunIntro(
+            AppMode::Implicit(Label::new("A")),
+            Some(Symbol::intern("A")),
+            Rc::from(Term::FunIntro(
+                AppMode::Explicit,
+                Some(Symbol::intern("a")),
+                Rc::from(Term::var(0)),
+            )),
+        ));
+        let literals = Rc::from(Term::RecordIntro(vec![
+            (
+                Label::new("string"),
+                Rc::from(Term::literal_intro("hello \"world\"\n")),
+            ),
+            (Label::new("char"), Rc::from(Term::literal_intro('λ'))),
+            (Label::new("s64"), Rc::from(Term::literal_intro(-42i64))),
+            (Label::new("f64"), Rc::from(Term::literal_intro(-0.0f64))),
+        ]));
+        let elim = Rc::from(Term::LiteralElim(
+            Rc::from(Term::literal_intro(1u32)),
+            Rc::from(vec![(LiteralIntro::U32(0), u32_ty.clone())]),
+            Rc::from(Term::RecordType(vec![(
+                DocString::from("A field."),
+                Label::new("x"),
+                None,
+                u32_ty,
+                Some(Rc::from(Term::literal_intro(1u32))),
+            )])),
+        ));
+
+        Module {
+            items: vec![
+                Item::Declaration(
+                    DocString::from("The identity function."),
+                    Label::new("id"),
+                    ty,
+                ),
+                Item::Definition(DocString::from(""), Label::new("id"), body),
+                Item::Definition(DocString::from(""), Label::new("literals"), literals),
+                Item::Definition(DocString::from(""), Label::new("elim"), elim),
+            ],
+        }
+    }
+
+    #[test]
+    fn round_trip_binary() {
+        let module = module();
+        let bytes = encode_module(Format::Binary, &module);
+
+        assert_eq!(decode_module(Format::Binary, &bytes), Ok(module));
+    }
+
+    #[test]
+    fn round_trip_json() {
+        let module = module();
+        let bytes = encode_module(Format::Json, &module);
+
+        assert_eq!(decode_module(Format::Json, &bytes), Ok(module));
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let node = Node::Object(vec![
+            ("format".to_owned(), Node::String(FORMAT_NAME.to_owned())),
+            ("version".to_owned(), Node::Nat(FORMAT_VERSION + 1)),
+            ("items".to_owned(), Node::Array(Vec::new())),
+        ]);
+
+        assert_eq!(
+            decode_module(Format::Json, json::write(&node).as_bytes()),
+            Err(DecodeError::UnsupportedVersion(FORMAT_VERSION + 1)),
+        );
+    }
+
+    #[test]
+    fn decode_wrong_format() {
+        let bytes = encode_module(Format::Json, &module());
+
+        assert_eq!(
+            decode_module(Format::Binary, &bytes),
+            Err(DecodeError::InvalidHeader),
+        );
+    }
+
+    #[test]
+    fn load_ill_typed_module() {
+        let context = validate::Context::empty();
+        let module = Module {
+            items: vec![Item::Definition(
+                DocString::from(""),
+                Label::new("x"),
+                Rc::from(Term::var(0)),
+            )],
+        };
+        let bytes = encode_module(Format::Binary, &module);
+
+        match load_module(&context, Format::Binary, &bytes) {
+            Err(LoadError::Type(_)) => {},
+            result => panic!("expected a type error, found: {:?}", result),
+        }
+    }
+}
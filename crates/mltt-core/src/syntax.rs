@@ -1,9 +1,14 @@
 //! The core syntax of the language.
 
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashSet};
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
 use std::rc::Rc;
 
 use super::literal::{LiteralIntro, LiteralType};
+use crate::intern::Symbol;
 use crate::{meta, prim, var, AppMode, DocString, Label, UniverseLevel};
 
 /// Top-level module.
@@ -35,6 +40,10 @@ impl fmt::Debug for Item {
     }
 }
 
+/// A field of a record type, made up of its documentation, label, name hint,
+/// type, and an optional manifest value.
+pub type RecordTypeField = (DocString, Label, Option<Symbol>, Rc<Term>, Option<Rc<Term>>);
+
 /// Core terms.
 // TODO: explicitly annotate with types
 #[derive(Clone, PartialEq)]
@@ -50,6 +59,14 @@ pub enum Term {
     Ann(Rc<Term>, Rc<Term>),
     /// Let bindings
     Let(Vec<Item>, Rc<Term>),
+    /// Select a definition from a group of mutually recursive definitions
+    ///
+    /// Each definition in the group includes a label, a guard that restricts
+    /// when it may be unfolded, a type, and a body. The types are in the scope
+    /// of the surrounding term, while the bodies are extended with a variable
+    /// for each definition in the group, with the last definition bound
+    /// innermost.
+    Fix(usize, Rc<[(Label, Guard, Rc<Term>, Rc<Term>)]>),
 
     /// Literal types
     LiteralType(LiteralType),
@@ -63,21 +80,148 @@ pub enum Term {
     LiteralElim(Rc<Term>, Rc<[(LiteralIntro, Rc<Term>)]>, Rc<Term>),
 
     /// Dependent function types
-    FunType(AppMode, Option<String>, Rc<Term>, Rc<Term>),
+    FunType(AppMode, Option<Symbol>, Rc<Term>, Rc<Term>),
     /// Introduce a function
-    FunIntro(AppMode, Option<String>, Rc<Term>),
+    FunIntro(AppMode, Option<Symbol>, Rc<Term>),
     /// Eliminate a function (application)
     FunElim(Rc<Term>, AppMode, Rc<Term>),
 
     /// Dependent record types
-    RecordType(Vec<(DocString, Label, Option<String>, Rc<Term>)>),
+    ///
+    /// Each field includes a type, and an optional manifest value.
+    RecordType(Vec<RecordTypeField>),
     /// Introduce a record
     RecordIntro(Vec<(Label, Rc<Term>)>),
     /// Eliminate a record (projection)
     RecordElim(Rc<Term>, Label),
 
-    /// Universe of types
-    Universe(UniverseLevel),
+    /// Inductive data types
+    DataType(Rc<Data>),
+    /// Introduce an inductive data type (constructors)
+    DataIntro(Rc<Data>, Label),
+    /// Eliminate an inductive data type (case split on constructors)
+    ///
+    /// We include a scrutinee, and a clause for each of the constructors of
+    /// the data type, in the order that they were declared. The body of each
+    /// clause is extended with a variable for each of the fields of the
+    /// constructor, not including the parameters of the data type.
+    DataElim(Rc<Term>, Rc<[(Label, Vec<Option<Symbol>>, Rc<Term>)]>),
+
+    /// Propositional equality types
+    ///
+    /// We include the type of the terms being compared, followed by the terms
+    /// on the left and right hand sides of the equality.
+    EqType(Rc<Term>, Rc<Term>, Rc<Term>),
+    /// Introduce an equality (reflexivity)
+    EqIntro,
+    /// Eliminate an equality (the J rule)
+    ///
+    /// We include the proof of the equality being eliminated, a motive, and a
+    /// term to return when the proof is `refl`. For a proof of `Eq {A} a b`,
+    /// the motive is a function of type `Fun (b : A) -> Eq a b -> Type`.
+    EqElim(Rc<Term>, Rc<Term>, Rc<Term>),
+
+    /// The type of universe levels
+    LevelType,
+    /// Constant universe levels
+    LevelIntro(UniverseLevel),
+    /// The level of types that quantify over universe levels, which is larger
+    /// than every constant level
+    LevelOmega,
+    /// Shift a universe level up by a constant amount
+    LevelShift(Rc<Term>, u16),
+    /// The maximum of two universe levels
+    LevelMax(Rc<Term>, Rc<Term>),
+
+    /// Universe of types, at the given level
+    Universe(Rc<Term>),
+}
+
+/// An inductive data type declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Data {
+    /// The name of the data type.
+    pub label: Label,
+    /// The number of parameters that the type former takes before its
+    /// indices. These are fixed for every constructor of the data type.
+    pub param_count: usize,
+    /// The type of the type former.
+    pub ty: Rc<Term>,
+    /// The constructors of the data type.
+    ///
+    /// The types of the constructors are extended with a variable that refers
+    /// to the type former, and begin by taking each of the parameters of the
+    /// data type as implicit arguments.
+    pub intros: Vec<(DocString, Label, Rc<Term>)>,
+}
+
+impl Data {
+    /// Lookup the type of a constructor.
+    pub fn lookup_intro(&self, label: &Label) -> Option<&Rc<Term>> {
+        self.intros
+            .iter()
+            .find(|(_, intro_label, _)| intro_label == label)
+            .map(|(_, _, ty)| ty)
+    }
+
+    /// Checks if a data type declaration is _alpha equivalent_ to another
+    /// data type declaration.
+    pub fn alpha_eq(&self, other: &Data) -> bool {
+        if std::ptr::eq(self, other) {
+            return true;
+        }
+
+        self.label == other.label
+            && self.param_count == other.param_count
+            && Term::alpha_eq(&self.ty, &other.ty)
+            && self.intros.len() == other.intros.len()
+            && Iterator::zip(self.intros.iter(), other.intros.iter())
+                .all(|((_, l1, t1), (_, l2, t2))| l1 == l2 && Term::alpha_eq(t1, t2))
+    }
+
+    fn map_free_vars(
+        &self,
+        depth: u32,
+        f: &impl Fn(u32) -> Option<FreeVar>,
+        g: &impl Fn(meta::Index) -> Option<u32>,
+    ) -> Option<Rc<Data>> {
+        Some(Rc::from(Data {
+            label: self.label.clone(),
+            param_count: self.param_count,
+            ty: self.ty.map_free_vars(depth, f, g)?,
+            intros: self
+                .intros
+                .iter()
+                .map(|(docs, label, ty)| {
+                    Some((
+                        docs.clone(),
+                        label.clone(),
+                        ty.map_free_vars(depth + 1, f, g)?,
+                    ))
+                })
+                .collect::<Option<_>>()?,
+        }))
+    }
+}
+
+/// Restricts when a recursive definition may be unfolded during evaluation.
+///
+/// Unfolding recursive definitions eagerly would cause evaluation to loop
+/// forever on open terms, so we wait until the arguments that the recursion
+/// depends on are in a canonical form.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Guard {
+    /// The definition never refers back to its group, so it is always safe
+    /// to unfold.
+    Always,
+    /// The definition is structurally recursive on the parameter at the given
+    /// position, so we can unfold it once the corresponding argument is
+    /// canonical.
+    Structural(usize),
+    /// The definition was marked as `partial`, so we only unfold it once all
+    /// of the arguments that it is applied to are canonical. Note that this
+    /// may still loop forever if the definition does not terminate!
+    Partial,
 }
 
 impl Term {
@@ -113,7 +257,7 @@ impl Term {
 
     /// Construct a universe.
     pub fn universe(level: impl Into<UniverseLevel>) -> Term {
-        Term::Universe(level.into())
+        Term::Universe(Rc::from(Term::LevelIntro(level.into())))
     }
 
     /// Checks if a term is _alpha equivalent_ to another term.
@@ -135,6 +279,13 @@ impl Term {
         // are already using De Bruijn indices, so we just need to compare
         // variables using regular equality, while avoiding the comparison of
         // metadata, such as variable name hints and doc strings.
+        //
+        // Terms that have been shared through an `Arena` are often identical,
+        // in which case we can skip comparing them entirely.
+        if std::ptr::eq(self, other) {
+            return true;
+        }
+
         match (self, other) {
             (Term::Var(index1), Term::Var(index2)) => index1 == index2,
             (Term::Prim(name1), Term::Prim(name2)) => name1 == name2,
@@ -157,6 +308,18 @@ impl Term {
                     && Term::alpha_eq(body1, body2)
             },
 
+            (Term::Fix(index1, defns1), Term::Fix(index2, defns2)) => {
+                index1 == index2
+                    && defns1.len() == defns2.len()
+                    && Iterator::zip(defns1.iter(), defns2.iter()).all(
+                        |((_, guard1, ty1, body1), (_, guard2, ty2, body2))| {
+                            guard1 == guard2
+                                && Term::alpha_eq(ty1, ty2)
+                                && Term::alpha_eq(body1, body2)
+                        },
+                    )
+            },
+
             (Term::LiteralType(literal_ty1), Term::LiteralType(literal_ty2)) => {
                 LiteralType::alpha_eq(literal_ty1, literal_ty2)
             },
@@ -191,8 +354,17 @@ impl Term {
 
             (Term::RecordType(ty_fields1), Term::RecordType(ty_fields2)) => {
                 ty_fields1.len() == ty_fields2.len()
-                    && Iterator::zip(ty_fields1.iter(), ty_fields2.iter())
-                        .all(|((_, l1, _, t1), (_, l2, _, t2))| l1 == l2 && Term::alpha_eq(t1, t2))
+                    && Iterator::zip(ty_fields1.iter(), ty_fields2.iter()).all(
+                        |((_, l1, _, t1, m1), (_, l2, _, t2, m2))| {
+                            l1 == l2
+                                && Term::alpha_eq(t1, t2)
+                                && match (m1, m2) {
+                                    (Some(m1), Some(m2)) => Term::alpha_eq(m1, m2),
+                                    (None, None) => true,
+                                    (_, _) => false,
+                                }
+                        },
+                    )
             },
             (Term::RecordIntro(intro_fields1), Term::RecordIntro(intro_fields2)) => {
                 intro_fields1.len() == intro_fields2.len()
@@ -203,11 +375,341 @@ impl Term {
                 Term::alpha_eq(record1, record2) && label1 == label2
             },
 
-            (Term::Universe(level1), Term::Universe(level2)) => level1 == level2,
+            (Term::DataType(data1), Term::DataType(data2)) => Data::alpha_eq(data1, data2),
+            (Term::DataIntro(data1, label1), Term::DataIntro(data2, label2)) => {
+                label1 == label2 && Data::alpha_eq(data1, data2)
+            },
+            (Term::DataElim(scrutinee1, clauses1), Term::DataElim(scrutinee2, clauses2)) => {
+                Term::alpha_eq(scrutinee1, scrutinee2)
+                    && clauses1.len() == clauses2.len()
+                    && Iterator::zip(clauses1.iter(), clauses2.iter()).all(
+                        |((l1, ns1, b1), (l2, ns2, b2))| {
+                            l1 == l2 && ns1.len() == ns2.len() && Term::alpha_eq(b1, b2)
+                        },
+                    )
+            },
+
+            (Term::EqType(ty1, lhs1, rhs1), Term::EqType(ty2, lhs2, rhs2)) => {
+                Term::alpha_eq(ty1, ty2) && Term::alpha_eq(lhs1, lhs2) && Term::alpha_eq(rhs1, rhs2)
+            },
+            (Term::EqIntro, Term::EqIntro) => true,
+            (Term::EqElim(proof1, motive1, body1), Term::EqElim(proof2, motive2, body2)) => {
+                Term::alpha_eq(proof1, proof2)
+                    && Term::alpha_eq(motive1, motive2)
+                    && Term::alpha_eq(body1, body2)
+            },
+
+            (Term::LevelType, Term::LevelType) => true,
+            (Term::LevelIntro(level1), Term::LevelIntro(level2)) => level1 == level2,
+            (Term::LevelOmega, Term::LevelOmega) => true,
+            (Term::LevelShift(level1, shift1), Term::LevelShift(level2, shift2)) => {
+                Term::alpha_eq(level1, level2) && shift1 == shift2
+            },
+            (Term::LevelMax(lhs1, rhs1), Term::LevelMax(lhs2, rhs2)) => {
+                Term::alpha_eq(lhs1, lhs2) && Term::alpha_eq(rhs1, rhs2)
+            },
+
+            (Term::Universe(level1), Term::Universe(level2)) => Term::alpha_eq(level1, level2),
 
             (_, _) => false,
         }
     }
+
+    /// Shift the free variables in the term, allowing it to be used in a
+    /// context that has been extended with `amount` new entries.
+    pub fn shift(&self, amount: u32) -> Rc<Term> {
+        self.map_free_vars(0, &|index| Some(FreeVar::Var(index + amount)), &|_| None)
+            .expect("shifting should always succeed")
+    }
+
+    /// Remove the `amount` innermost entries from the scope of the term,
+    /// returning `None` if any of them are referred to in the term.
+    pub fn strengthen(&self, amount: u32) -> Option<Rc<Term>> {
+        self.map_free_vars(
+            0,
+            &|index| index.checked_sub(amount).map(FreeVar::Var),
+            &|_| None,
+        )
+    }
+
+    /// Substitute `arg` for the innermost entry in the scope of the term,
+    /// removing that entry from the scope. The argument should be in the scope
+    /// of the remaining entries.
+    pub fn subst(&self, arg: &Rc<Term>) -> Rc<Term> {
+        let f = |index: u32| match index {
+            0 => Some(FreeVar::Term(arg.clone())),
+            index => Some(FreeVar::Var(index - 1)),
+        };
+        self.map_free_vars(0, &f, &|_| None)
+            .expect("substitution should always succeed")
+    }
+
+    /// Collect the indices of the variables that are free in the term,
+    /// ignoring the `binders` innermost entries of its scope.
+    pub fn free_vars(&self, binders: u32) -> BTreeSet<var::Index> {
+        let free_vars = RefCell::new(BTreeSet::new());
+        let _ = self.map_free_vars(
+            binders,
+            &|index| {
+                free_vars.borrow_mut().insert(var::Index(index));
+                Some(FreeVar::Var(index))
+            },
+            &|_| None,
+        );
+        free_vars.into_inner()
+    }
+
+    /// Rename the variables that are free in the term, ignoring the `binders`
+    /// innermost entries of its scope.
+    pub fn rename_free_vars(&self, binders: u32, f: impl Fn(var::Index) -> var::Index) -> Rc<Term> {
+        let f = |index| Some(FreeVar::Var(f(var::Index(index)).0));
+        self.map_free_vars(binders, &f, &|_| None)
+            .expect("renaming should always succeed")
+    }
+
+    /// Check if the variable at the given index is referred to in the term.
+    pub fn has_free_var(&self, var_index: var::Index) -> bool {
+        let var_index = var_index.0;
+        self.map_free_vars(
+            0,
+            &|index| {
+                if index == var_index {
+                    None
+                } else {
+                    Some(FreeVar::Var(index))
+                }
+            },
+            &|_| None,
+        )
+        .is_none()
+    }
+
+    /// Count the number of references to the variable at the given index in
+    /// the term.
+    pub fn count_free_var(&self, var_index: var::Index) -> usize {
+        let count = Cell::new(0);
+        let _ = self.map_free_vars(
+            0,
+            &|index| {
+                if index == var_index.0 {
+                    count.set(count.get() + 1);
+                }
+                Some(FreeVar::Var(index))
+            },
+            &|_| None,
+        );
+        count.get()
+    }
+
+    /// Replace the given metavariables with variables bound by new entries in
+    /// the context, one for each metavariable, with the last metavariable
+    /// being bound innermost. The other free variables are shifted past the
+    /// new entries.
+    ///
+    /// Returns `None` if any of the metavariables are applied to arguments.
+    pub fn abstract_metas(&self, metas: &[meta::Index]) -> Option<Rc<Term>> {
+        let amount = metas.len() as u32;
+        self.map_free_vars(
+            0,
+            &|index| Some(FreeVar::Var(index + amount)),
+            &|meta_index| {
+                let position = metas.iter().position(|m| *m == meta_index)?;
+                Some(amount - (position as u32 + 1))
+            },
+        )
+    }
+
+    /// Returns `true` if the term is a metavariable that is replaced by `g`.
+    fn is_mapped_meta(term: &Term, g: &impl Fn(meta::Index) -> Option<u32>) -> bool {
+        match term {
+            Term::Meta(meta_index) => g(*meta_index).is_some(),
+            _ => false,
+        }
+    }
+
+    /// Map the indices of the variables that are free in the term, where
+    /// `depth` is the number of binders that we have passed under so far.
+    ///
+    /// Metavariables for which `g` returns an index are replaced with the free
+    /// variable at that index. These metavariables must not be applied to any
+    /// arguments, otherwise `None` is returned.
+    fn map_free_vars(
+        &self,
+        depth: u32,
+        f: &impl Fn(u32) -> Option<FreeVar>,
+        g: &impl Fn(meta::Index) -> Option<u32>,
+    ) -> Option<Rc<Term>> {
+        let map_items = |items: &[Item]| {
+            let mut depth = depth;
+            let items = items
+                .iter()
+                .map(|item| match item {
+                    Item::Declaration(docs, label, ty) => {
+                        let ty = ty.map_free_vars(depth, f, g)?;
+                        Some(Item::Declaration(docs.clone(), label.clone(), ty))
+                    },
+                    Item::Definition(docs, label, term) => {
+                        let term = term.map_free_vars(depth, f, g)?;
+                        depth += 1;
+                        Some(Item::Definition(docs.clone(), label.clone(), term))
+                    },
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some((items, depth))
+        };
+
+        let term = match self {
+            Term::Var(var::Index(index)) if *index < depth => self.clone(),
+            Term::Var(var::Index(index)) => match f(index - depth)? {
+                FreeVar::Var(index) => Term::var(index + depth),
+                // Move the replacement under the binders that we have passed
+                FreeVar::Term(term) if depth == 0 => return Some(term),
+                FreeVar::Term(term) => return Some(term.shift(depth)),
+            },
+            Term::Meta(meta_index) => match g(*meta_index) {
+                Some(index) => Term::var(index + depth),
+                None => self.clone(),
+            },
+            Term::Prim(_) => self.clone(),
+
+            Term::Ann(term, term_ty) => Term::Ann(
+                term.map_free_vars(depth, f, g)?,
+                term_ty.map_free_vars(depth, f, g)?,
+            ),
+            Term::Let(items, body) => {
+                let (items, body_depth) = map_items(items)?;
+                Term::Let(items, body.map_free_vars(body_depth, f, g)?)
+            },
+            Term::Fix(index, defns) => {
+                let body_depth = depth + defns.len() as u32;
+                let defns = defns
+                    .iter()
+                    .map(|(label, guard, ty, body)| {
+                        Some((
+                            label.clone(),
+                            guard.clone(),
+                            ty.map_free_vars(depth, f, g)?,
+                            body.map_free_vars(body_depth, f, g)?,
+                        ))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+
+                Term::Fix(*index, Rc::from(defns))
+            },
+
+            Term::LiteralType(_) | Term::LiteralIntro(_) => self.clone(),
+            Term::LiteralElim(scrutinee, clauses, default_body) => {
+                let clauses = clauses
+                    .iter()
+                    .map(|(literal_intro, body)| {
+                        Some((literal_intro.clone(), body.map_free_vars(depth, f, g)?))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+
+                Term::LiteralElim(
+                    scrutinee.map_free_vars(depth, f, g)?,
+                    Rc::from(clauses),
+                    default_body.map_free_vars(depth, f, g)?,
+                )
+            },
+
+            Term::FunType(app_mode, name_hint, param_ty, body_ty) => Term::FunType(
+                app_mode.clone(),
+                name_hint.clone(),
+                param_ty.map_free_vars(depth, f, g)?,
+                body_ty.map_free_vars(depth + 1, f, g)?,
+            ),
+            Term::FunIntro(app_mode, name_hint, body) => Term::FunIntro(
+                app_mode.clone(),
+                name_hint.clone(),
+                body.map_free_vars(depth + 1, f, g)?,
+            ),
+            Term::FunElim(fun, _, _) if Term::is_mapped_meta(fun, g) => return None,
+            Term::FunElim(fun, app_mode, arg) => Term::FunElim(
+                fun.map_free_vars(depth, f, g)?,
+                app_mode.clone(),
+                arg.map_free_vars(depth, f, g)?,
+            ),
+
+            Term::RecordType(ty_fields) => Term::RecordType(
+                ty_fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (docs, label, name_hint, ty, manifest))| {
+                        let ty = ty.map_free_vars(depth + i as u32, f, g)?;
+                        let manifest = match manifest {
+                            None => None,
+                            Some(manifest) => {
+                                Some(manifest.map_free_vars(depth + i as u32, f, g)?)
+                            },
+                        };
+                        Some((docs.clone(), label.clone(), name_hint.clone(), ty, manifest))
+                    })
+                    .collect::<Option<_>>()?,
+            ),
+            Term::RecordIntro(intro_fields) => Term::RecordIntro(
+                intro_fields
+                    .iter()
+                    .map(|(label, term)| Some((label.clone(), term.map_free_vars(depth, f, g)?)))
+                    .collect::<Option<_>>()?,
+            ),
+            Term::RecordElim(record, label) => {
+                Term::RecordElim(record.map_free_vars(depth, f, g)?, label.clone())
+            },
+
+            Term::DataType(data) => Term::DataType(data.map_free_vars(depth, f, g)?),
+            Term::DataIntro(data, label) => {
+                Term::DataIntro(data.map_free_vars(depth, f, g)?, label.clone())
+            },
+            Term::DataElim(scrutinee, clauses) => {
+                let clauses = clauses
+                    .iter()
+                    .map(|(label, name_hints, body)| {
+                        let body_depth = depth + name_hints.len() as u32;
+                        let body = body.map_free_vars(body_depth, f, g)?;
+                        Some((label.clone(), name_hints.clone(), body))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+
+                Term::DataElim(scrutinee.map_free_vars(depth, f, g)?, Rc::from(clauses))
+            },
+
+            Term::EqType(ty, lhs, rhs) => Term::EqType(
+                ty.map_free_vars(depth, f, g)?,
+                lhs.map_free_vars(depth, f, g)?,
+                rhs.map_free_vars(depth, f, g)?,
+            ),
+            Term::EqIntro => self.clone(),
+            Term::EqElim(proof, motive, body) => Term::EqElim(
+                proof.map_free_vars(depth, f, g)?,
+                motive.map_free_vars(depth, f, g)?,
+                body.map_free_vars(depth, f, g)?,
+            ),
+
+            Term::LevelType | Term::LevelIntro(_) | Term::LevelOmega => self.clone(),
+            Term::LevelShift(level, shift) => {
+                Term::LevelShift(level.map_free_vars(depth, f, g)?, *shift)
+            },
+            Term::LevelMax(lhs, rhs) => Term::LevelMax(
+                lhs.map_free_vars(depth, f, g)?,
+                rhs.map_free_vars(depth, f, g)?,
+            ),
+
+            Term::Universe(level) => Term::Universe(level.map_free_vars(depth, f, g)?),
+        };
+
+        Some(Rc::from(term))
+    }
+}
+
+/// The replacement for a free variable, when mapping over the free variables
+/// of a term.
+enum FreeVar {
+    /// Replace the variable with the variable at the given index.
+    Var(u32),
+    /// Replace the variable with a term, in the scope outside of the binders
+    /// that the variable occurred under.
+    Term(Rc<Term>),
 }
 
 impl fmt::Debug for Term {
@@ -216,3 +718,404 @@ impl fmt::Debug for Term {
         fmt::Display::fmt(&doc.pretty(1_000_000_000), f)
     }
 }
+
+/// An arena for hash-consing terms.
+///
+/// Sharing a term through the arena replaces structurally identical subterms
+/// with a single allocation, reducing the memory used by large modules, and
+/// allowing `Term::alpha_eq` to return early on identical pointers. Terms that
+/// bind items, records, and data types are rebuilt using shared subterms, but
+/// are not themselves shared.
+#[derive(Default)]
+pub struct Arena {
+    terms: HashSet<Shallow>,
+}
+
+impl Arena {
+    /// Create a new, empty arena.
+    pub fn new() -> Arena {
+        Arena::default()
+    }
+
+    /// Share the terms in a module.
+    pub fn share_module(&mut self, module: &Module) -> Module {
+        Module {
+            items: self.share_items(&module.items),
+        }
+    }
+
+    fn share_items(&mut self, items: &[Item]) -> Vec<Item> {
+        items
+            .iter()
+            .map(|item| match item {
+                Item::Declaration(docs, label, ty) => {
+                    Item::Declaration(docs.clone(), label.clone(), self.share(ty))
+                },
+                Item::Definition(docs, label, term) => {
+                    Item::Definition(docs.clone(), label.clone(), self.share(term))
+                },
+            })
+            .collect()
+    }
+
+    fn share_data(&mut self, data: &Data) -> Rc<Data> {
+        Rc::from(Data {
+            label: data.label.clone(),
+            param_count: data.param_count,
+            ty: self.share(&data.ty),
+            intros: data
+                .intros
+                .iter()
+                .map(|(docs, label, ty)| (docs.clone(), label.clone(), self.share(ty)))
+                .collect(),
+        })
+    }
+
+    /// Share a term, returning a term that is equal to it, where each of its
+    /// subterms have been replaced with the equivalent term in the arena.
+    pub fn share(&mut self, term: &Rc<Term>) -> Rc<Term> {
+        let term = match term.as_ref() {
+            Term::Var(_)
+            | Term::Meta(_)
+            | Term::Prim(_)
+            | Term::LiteralType(_)
+            | Term::LiteralIntro(_)
+            | Term::EqIntro
+            | Term::LevelType
+            | Term::LevelIntro(_)
+            | Term::LevelOmega => return self.intern(term.clone()),
+
+            Term::Let(items, body) => {
+                return Rc::from(Term::Let(self.share_items(items), self.share(body)));
+            },
+            Term::Fix(index, defns) => {
+                let defns = defns
+                    .iter()
+                    .map(|(label, guard, ty, body)| {
+                        let ty = self.share(ty);
+                        (label.clone(), guard.clone(), ty, self.share(body))
+                    })
+                    .collect::<Vec<_>>();
+
+                return Rc::from(Term::Fix(*index, Rc::from(defns)));
+            },
+            Term::LiteralElim(scrutinee, clauses, default_body) => {
+                let clauses = clauses
+                    .iter()
+                    .map(|(literal_intro, body)| (literal_intro.clone(), self.share(body)))
+                    .collect::<Vec<_>>();
+                let scrutinee = self.share(scrutinee);
+                let default_body = self.share(default_body);
+
+                return Rc::from(Term::LiteralElim(
+                    scrutinee,
+                    Rc::from(clauses),
+                    default_body,
+                ));
+            },
+            Term::RecordType(ty_fields) => {
+                let ty_fields = ty_fields
+                    .iter()
+                    .map(|(docs, label, name_hint, ty, manifest)| {
+                        let ty = self.share(ty);
+                        let manifest = manifest.as_ref().map(|manifest| self.share(manifest));
+                        (docs.clone(), label.clone(), name_hint.clone(), ty, manifest)
+                    })
+                    .collect();
+
+                return Rc::from(Term::RecordType(ty_fields));
+            },
+            Term::RecordIntro(intro_fields) => {
+                let intro_fields = intro_fields
+                    .iter()
+                    .map(|(label, term)| (label.clone(), self.share(term)))
+                    .collect();
+
+                return Rc::from(Term::RecordIntro(intro_fields));
+            },
+            Term::DataType(data) => return Rc::from(Term::DataType(self.share_data(data))),
+            Term::DataIntro(data, label) => {
+                return Rc::from(Term::DataIntro(self.share_data(data), label.clone()));
+            },
+            Term::DataElim(scrutinee, clauses) => {
+                let clauses = clauses
+                    .iter()
+                    .map(|(label, name_hints, body)| {
+                        (label.clone(), name_hints.clone(), self.share(body))
+                    })
+                    .collect::<Vec<_>>();
+
+                return Rc::from(Term::DataElim(self.share(scrutinee), Rc::from(clauses)));
+            },
+
+            Term::Ann(term, term_ty) => Term::Ann(self.share(term), self.share(term_ty)),
+            Term::FunType(app_mode, name_hint, param_ty, body_ty) => Term::FunType(
+                app_mode.clone(),
+                name_hint.clone(),
+                self.share(param_ty),
+                self.share(body_ty),
+            ),
+            Term::FunIntro(app_mode, name_hint, body) => {
+                Term::FunIntro(app_mode.clone(), name_hint.clone(), self.share(body))
+            },
+            Term::FunElim(fun, app_mode, arg) => {
+                Term::FunElim(self.share(fun), app_mode.clone(), self.share(arg))
+            },
+            Term::RecordElim(record, label) => Term::RecordElim(self.share(record), label.clone()),
+            Term::EqType(ty, lhs, rhs) => {
+                Term::EqType(self.share(ty), self.share(lhs), self.share(rhs))
+            },
+            Term::EqElim(proof, motive, body) => {
+                Term::EqElim(self.share(proof), self.share(motive), self.share(body))
+            },
+            Term::LevelShift(level, shift) => Term::LevelShift(self.share(level), *shift),
+            Term::LevelMax(lhs, rhs) => Term::LevelMax(self.share(lhs), self.share(rhs)),
+            Term::Universe(level) => Term::Universe(self.share(level)),
+        };
+
+        self.intern(Rc::from(term))
+    }
+
+    /// Lookup a term whose subterms have already been shared, adding it to
+    /// the arena if it has not been seen before.
+    fn intern(&mut self, term: Rc<Term>) -> Rc<Term> {
+        let term = Shallow(term);
+        match self.terms.get(&term) {
+            Some(existing) => existing.0.clone(),
+            None => {
+                let shared = term.0.clone();
+                self.terms.insert(term);
+                shared
+            },
+        }
+    }
+}
+
+/// A term in an arena, which is hashed and compared using the identity of its
+/// immediate subterms, rather than by traversing them.
+struct Shallow(Rc<Term>);
+
+impl Hash for Shallow {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        fn ptr(term: &Rc<Term>) -> *const Term {
+            &**term
+        }
+
+        mem::discriminant(self.0.as_ref()).hash(state);
+        match self.0.as_ref() {
+            Term::Var(index) => index.0.hash(state),
+            Term::Meta(index) => index.0.hash(state),
+            Term::Prim(name) => name.0.hash(state),
+            Term::Ann(term, term_ty) => (ptr(term), ptr(term_ty)).hash(state),
+            Term::LiteralType(_) => {},
+            Term::LiteralIntro(literal_intro) => hash_literal_intro(literal_intro, state),
+            Term::FunType(app_mode, name_hint, param_ty, body_ty) => {
+                (app_mode, name_hint, ptr(param_ty), ptr(body_ty)).hash(state)
+            },
+            Term::FunIntro(app_mode, name_hint, body) => {
+                (app_mode, name_hint, ptr(body)).hash(state)
+            },
+            Term::FunElim(fun, app_mode, arg) => (ptr(fun), app_mode, ptr(arg)).hash(state),
+            Term::RecordElim(record, label) => (ptr(record), label).hash(state),
+            Term::EqType(ty, lhs, rhs) => (ptr(ty), ptr(lhs), ptr(rhs)).hash(state),
+            Term::EqIntro | Term::LevelType | Term::LevelOmega => {},
+            Term::EqElim(proof, motive, body) => (ptr(proof), ptr(motive), ptr(body)).hash(state),
+            Term::LevelIntro(level) => level.0.hash(state),
+            Term::LevelShift(level, shift) => (ptr(level), shift).hash(state),
+            Term::LevelMax(lhs, rhs) => (ptr(lhs), ptr(rhs)).hash(state),
+            Term::Universe(level) => ptr(level).hash(state),
+            _ => ptr(&self.0).hash(state),
+        }
+    }
+}
+
+impl PartialEq for Shallow {
+    fn eq(&self, other: &Shallow) -> bool {
+        match (self.0.as_ref(), other.0.as_ref()) {
+            (Term::Var(index1), Term::Var(index2)) => index1 == index2,
+            (Term::Meta(index1), Term::Meta(index2)) => index1 == index2,
+            (Term::Prim(name1), Term::Prim(name2)) => name1 == name2,
+            (Term::Ann(term1, term_ty1), Term::Ann(term2, term_ty2)) => {
+                Rc::ptr_eq(term1, term2) && Rc::ptr_eq(term_ty1, term_ty2)
+            },
+            (Term::LiteralType(literal_ty1), Term::LiteralType(literal_ty2)) => {
+                literal_ty1 == literal_ty2
+            },
+            (Term::LiteralIntro(literal_intro1), Term::LiteralIntro(literal_intro2)) => {
+                identical_literal_intros(literal_intro1, literal_intro2)
+            },
+            (
+                Term::FunType(app_mode1, name_hint1, param_ty1, body_ty1),
+                Term::FunType(app_mode2, name_hint2, param_ty2, body_ty2),
+            ) => {
+                app_mode1 == app_mode2
+                    && name_hint1 == name_hint2
+                    && Rc::ptr_eq(param_ty1, param_ty2)
+                    && Rc::ptr_eq(body_ty1, body_ty2)
+            },
+            (
+                Term::FunIntro(app_mode1, name_hint1, body1),
+                Term::FunIntro(app_mode2, name_hint2, body2),
+            ) => app_mode1 == app_mode2 && name_hint1 == name_hint2 && Rc::ptr_eq(body1, body2),
+            (Term::FunElim(fun1, app_mode1, arg1), Term::FunElim(fun2, app_mode2, arg2)) => {
+                Rc::ptr_eq(fun1, fun2) && app_mode1 == app_mode2 && Rc::ptr_eq(arg1, arg2)
+            },
+            (Term::RecordElim(record1, label1), Term::RecordElim(record2, label2)) => {
+                Rc::ptr_eq(record1, record2) && label1 == label2
+            },
+            (Term::EqType(ty1, lhs1, rhs1), Term::EqType(ty2, lhs2, rhs2)) => {
+                Rc::ptr_eq(ty1, ty2) && Rc::ptr_eq(lhs1, lhs2) && Rc::ptr_eq(rhs1, rhs2)
+            },
+            (Term::EqIntro, Term::EqIntro)
+            | (Term::LevelType, Term::LevelType)
+            | (Term::LevelOmega, Term::LevelOmega) => true,
+            (Term::EqElim(proof1, motive1, body1), Term::EqElim(proof2, motive2, body2)) => {
+                Rc::ptr_eq(proof1, proof2)
+                    && Rc::ptr_eq(motive1, motive2)
+                    && Rc::ptr_eq(body1, body2)
+            },
+            (Term::LevelIntro(level1), Term::LevelIntro(level2)) => level1 == level2,
+            (Term::LevelShift(level1, shift1), Term::LevelShift(level2, shift2)) => {
+                Rc::ptr_eq(level1, level2) && shift1 == shift2
+            },
+            (Term::LevelMax(lhs1, rhs1), Term::LevelMax(lhs2, rhs2)) => {
+                Rc::ptr_eq(lhs1, lhs2) && Rc::ptr_eq(rhs1, rhs2)
+            },
+            (Term::Universe(level1), Term::Universe(level2)) => Rc::ptr_eq(level1, level2),
+            (_, _) => Rc::ptr_eq(&self.0, &other.0),
+        }
+    }
+}
+
+impl Eq for Shallow {}
+
+/// Hash a literal in a way that is consistent with `identical_literal_intros`.
+fn hash_literal_intro<H: Hasher>(literal_intro: &LiteralIntro, state: &mut H) {
+    mem::discriminant(literal_intro).hash(state);
+    match literal_intro {
+        LiteralIntro::String(value) => value.hash(state),
+        LiteralIntro::Char(value) => value.hash(state),
+        LiteralIntro::Bool(value) => value.hash(state),
+        LiteralIntro::U8(value) => value.hash(state),
+        LiteralIntro::U16(value) => value.hash(state),
+        LiteralIntro::U32(value) => value.hash(state),
+        LiteralIntro::U64(value) => value.hash(state),
+        LiteralIntro::S8(value) => value.hash(state),
+        LiteralIntro::S16(value) => value.hash(state),
+        LiteralIntro::S32(value) => value.hash(state),
+        LiteralIntro::S64(value) => value.hash(state),
+        LiteralIntro::F32(value) => value.to_bits().hash(state),
+        LiteralIntro::F64(value) => value.to_bits().hash(state),
+    }
+}
+
+/// Returns `true` if two literals can be shared without changing the meaning
+/// of the program. Floating point numbers are compared by their bits, so that
+/// `0.0` and `-0.0` are kept apart, and so that `NaN` is equal to itself.
+fn identical_literal_intros(literal_intro1: &LiteralIntro, literal_intro2: &LiteralIntro) -> bool {
+    match (literal_intro1, literal_intro2) {
+        (LiteralIntro::F32(value1), LiteralIntro::F32(value2)) => {
+            value1.to_bits() == value2.to_bits()
+        },
+        (LiteralIntro::F64(value1), LiteralIntro::F64(value2)) => {
+            value1.to_bits() == value2.to_bits()
+        },
+        (_, _) => literal_intro1 == literal_intro2,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn share_identical_subterms() {
+        let fun_ty = || {
+            let param_ty = Rc::from(Term::universe(0));
+            let body_ty = Rc::from(Term::universe(0));
+            Rc::from(Term::FunType(AppMode::Explicit, None, param_ty, body_ty))
+        };
+        let term = Rc::from(Term::Ann(fun_ty(), fun_ty()));
+
+        let mut arena = Arena::new();
+        let shared_term = arena.share(&term);
+
+        assert_eq!(shared_term, term);
+        match shared_term.as_ref() {
+            Term::Ann(term, term_ty) => {
+                assert!(Rc::ptr_eq(term, term_ty));
+                match term.as_ref() {
+                    Term::FunType(_, _, param_ty, body_ty) => {
+                        assert!(Rc::ptr_eq(param_ty, body_ty))
+                    },
+                    _ => panic!("expected a function type"),
+                }
+            },
+            _ => panic!("expected an annotated term"),
+        }
+        assert!(Rc::ptr_eq(&arena.share(&term), &shared_term));
+    }
+
+    #[test]
+    fn share_distinguishes_name_hints() {
+        let body = Rc::from(Term::var(0));
+        let fun_intro = |name: &str| {
+            let name_hint = Some(Symbol::intern(name));
+            Rc::from(Term::FunIntro(AppMode::Explicit, name_hint, body.clone()))
+        };
+
+        let mut arena = Arena::new();
+        let fun_intro1 = arena.share(&fun_intro("x"));
+        let fun_intro2 = arena.share(&fun_intro("y"));
+
+        assert!(Term::alpha_eq(&fun_intro1, &fun_intro2));
+        assert!(!Rc::ptr_eq(&fun_intro1, &fun_intro2));
+    }
+
+    #[test]
+    fn share_distinguishes_signed_zeros() {
+        let mut arena = Arena::new();
+        let zero = arena.share(&Rc::from(Term::literal_intro(0.0f32)));
+        let neg_zero = arena.share(&Rc::from(Term::literal_intro(-0.0f32)));
+
+        assert!(!Rc::ptr_eq(&zero, &neg_zero));
+        assert!(!Term::alpha_eq(&zero, &neg_zero));
+
+        let zero = arena.share(&Rc::from(Term::literal_intro(0.0f64)));
+        let neg_zero = arena.share(&Rc::from(Term::literal_intro(-0.0f64)));
+
+        assert!(!Rc::ptr_eq(&zero, &neg_zero));
+        assert!(!Term::alpha_eq(&zero, &neg_zero));
+    }
+
+    #[test]
+    fn share_nan() {
+        let mut arena = Arena::new();
+        let nan1 = arena.share(&Rc::from(Term::literal_intro(std::f64::NAN)));
+        let nan2 = arena.share(&Rc::from(Term::literal_intro(std::f64::NAN)));
+
+        assert!(Rc::ptr_eq(&nan1, &nan2));
+    }
+
+    #[test]
+    fn subst_under_binders() {
+        let fun_intro =
+            |body: Term| Rc::from(Term::FunIntro(AppMode::Explicit, None, Rc::from(body)));
+        let fun_elim =
+            |fun: Term, arg: Rc<Term>| Term::FunElim(Rc::from(fun), AppMode::Explicit, arg);
+
+        let term = fun_elim(
+            Term::var(0),
+            fun_intro(fun_elim(Term::var(1), Rc::from(Term::var(2)))),
+        );
+        let arg = Rc::from(Term::var(3));
+
+        assert_eq!(
+            term.subst(&arg),
+            Rc::from(fun_elim(
+                Term::var(3),
+                fun_intro(fun_elim(Term::var(4), Rc::from(Term::var(1))))
+            )),
+        );
+    }
+}
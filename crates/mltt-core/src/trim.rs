@@ -0,0 +1,442 @@
+//! Trimming of closure environments.
+//!
+//! Rather than capturing the entire environment, closures only capture the
+//! entries that their terms refer to, with the free variables of the terms
+//! renamed to point into the trimmed environment. This keeps closures small,
+//! and avoids retaining values that will never be used.
+//!
+//! Finding the free variables of a term requires a traversal, so the trimmed
+//! terms are cached, keyed by the address of the original terms. The cache
+//! holds weak references to the original terms, which prevents their
+//! addresses from being reused while the entries are in the cache.
+//!
+//! The caches and the setting that enables trimming belong to the current
+//! thread. They can be replaced for the duration of a `Scope`, for example to
+//! disable trimming as a baseline when benchmarking, or to keep the cached
+//! terms of one test from being seen by another.
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::rc::{Rc, Weak};
+
+use crate::intern::Symbol;
+use crate::literal::LiteralIntro;
+use crate::syntax::Term;
+use crate::{var, Label};
+
+/// The clauses of a literal case split.
+type LiteralClauses = Rc<[(LiteralIntro, Rc<Term>)]>;
+
+/// The clauses of a data type case split.
+type CaseClauses = Rc<[(Label, Vec<Option<Symbol>>, Rc<Term>)]>;
+
+/// Terms whose free variables have been renamed to point into a trimmed
+/// environment.
+#[derive(Debug, Clone)]
+struct Trimmed<T> {
+    /// The indices of the captured entries in the original environment, in
+    /// ascending order.
+    captures: Rc<[var::Index]>,
+    /// The renamed terms.
+    terms: T,
+}
+
+impl<T> Trimmed<T> {
+    /// Rename the free variables of the terms, based on the captured entries.
+    fn new(
+        free_vars: BTreeSet<var::Index>,
+        rename: impl FnOnce(&dyn Fn(var::Index) -> var::Index) -> T,
+    ) -> Trimmed<T> {
+        let captures = free_vars.into_iter().collect::<Rc<[_]>>();
+        let terms = rename(&|index| {
+            let position = captures
+                .binary_search(&index)
+                .expect("free variable not captured");
+            var::Index(position as u32)
+        });
+
+        Trimmed { captures, terms }
+    }
+
+    /// Returns `true` if every entry in the environment is captured, in which
+    /// case the renaming is the identity.
+    fn captures_all<Entry: Clone>(&self, values: &var::Env<Entry>) -> bool {
+        self.captures.len() == values.size().0 as usize
+    }
+
+    /// Select the captured entries from the environment, returning `None` if
+    /// any of them are missing.
+    fn env<Entry: Clone>(&self, values: &var::Env<Entry>) -> Option<var::Env<Entry>> {
+        let mut trimmed = var::Env::new();
+        for index in self.captures.iter().rev() {
+            trimmed.add_entry(values.lookup_entry(*index)?.clone());
+        }
+        Some(trimmed)
+    }
+}
+
+/// A cache of trimmed terms, keyed by the address of the original terms.
+struct Cache<Key: ?Sized, T> {
+    entries: HashMap<*const (), (Weak<Key>, T)>,
+    /// The number of entries at which we next prune the cache.
+    prune_len: usize,
+}
+
+impl<Key: ?Sized, T: Clone> Cache<Key, T> {
+    /// The minimum number of entries at which we prune the cache.
+    const MIN_PRUNE_LEN: usize = 1024;
+
+    fn new() -> Cache<Key, T> {
+        Cache {
+            entries: HashMap::new(),
+            prune_len: Self::MIN_PRUNE_LEN,
+        }
+    }
+
+    fn lookup(&self, key: &Rc<Key>) -> Option<T> {
+        let (_, value) = self.entries.get(&address(key))?;
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, key: &Rc<Key>, value: T) {
+        if self.entries.len() >= self.prune_len {
+            // Remove the entries for terms that are no longer in use
+            self.entries.retain(|_, (key, _)| key.upgrade().is_some());
+            self.prune_len = usize::max(self.entries.len() * 2, Self::MIN_PRUNE_LEN);
+        }
+        self.entries
+            .insert(address(key), (Rc::downgrade(key), value));
+    }
+}
+
+/// The address of a reference counted value.
+fn address<Key: ?Sized>(key: &Rc<Key>) -> *const () {
+    &**key as *const Key as *const ()
+}
+
+/// Cached terms that do not bind any variables.
+type TermCache = Cache<Term, Trimmed<Rc<Term>>>;
+/// Cached terms for closures that bind a single variable.
+type AppCache = Cache<Term, Trimmed<Rc<Term>>>;
+/// Cached clauses for literal case splits, along with the default terms that
+/// they were trimmed with.
+type LiteralCache =
+    Cache<[(LiteralIntro, Rc<Term>)], (Weak<Term>, Trimmed<(LiteralClauses, Rc<Term>)>)>;
+
+/// Cached clauses for data type case splits.
+type CaseCache = Cache<[(Label, Vec<Option<Symbol>>, Rc<Term>)], Trimmed<CaseClauses>>;
+
+/// The trimming state of a thread.
+struct State {
+    /// Closures capture their entire environment when trimming is disabled.
+    is_enabled: bool,
+    term_cache: TermCache,
+    app_cache: AppCache,
+    literal_cache: LiteralCache,
+    case_cache: CaseCache,
+}
+
+impl State {
+    fn new(is_enabled: bool) -> State {
+        State {
+            is_enabled,
+            term_cache: Cache::new(),
+            app_cache: Cache::new(),
+            literal_cache: Cache::new(),
+            case_cache: Cache::new(),
+        }
+    }
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::new(true));
+}
+
+/// Replaces the trimming state of the current thread with empty caches until
+/// it is dropped, at which point the previous state is restored.
+#[must_use = "the previous trimming state is restored when the scope is dropped"]
+pub struct Scope {
+    previous: Option<State>,
+}
+
+/// Enable or disable trimming on the current thread, with empty caches, for
+/// the lifetime of the returned scope.
+pub fn scope(is_enabled: bool) -> Scope {
+    let previous = STATE.with(|state| state.replace(State::new(is_enabled)));
+    Scope {
+        previous: Some(previous),
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            STATE.with(|state| state.replace(previous));
+        }
+    }
+}
+
+/// Returns `true` if trimming is enabled on the current thread.
+fn is_enabled() -> bool {
+    STATE.with(|state| state.borrow().is_enabled)
+}
+
+/// Trim the environment of a term that does not bind any variables.
+///
+/// Returns `None` if the environment does not need to be trimmed, if it is
+/// missing entries that are referred to, or if trimming is disabled.
+pub fn trim_term<Entry: Clone>(
+    term: &Rc<Term>,
+    values: &var::Env<Entry>,
+) -> Option<(Rc<Term>, var::Env<Entry>)> {
+    if !is_enabled() {
+        return None;
+    }
+
+    let trimmed = STATE.with(|state| {
+        if let Some(trimmed) = state.borrow().term_cache.lookup(term) {
+            return trimmed;
+        }
+
+        let trimmed = Trimmed::new(term.free_vars(0), |rename| term.rename_free_vars(0, rename));
+        state.borrow_mut().term_cache.insert(term, trimmed.clone());
+        trimmed
+    });
+
+    if trimmed.captures_all(values) {
+        None
+    } else {
+        let values = trimmed.env(values)?;
+        Some((trimmed.terms, values))
+    }
+}
+
+/// Trim the environment of a closure that binds a single variable.
+///
+/// Returns `None` if the environment does not need to be trimmed, if it is
+/// missing entries that are referred to, or if trimming is disabled.
+pub fn trim_app<Entry: Clone>(
+    term: &Rc<Term>,
+    values: &var::Env<Entry>,
+) -> Option<(Rc<Term>, var::Env<Entry>)> {
+    if !is_enabled() {
+        return None;
+    }
+
+    let trimmed = STATE.with(|state| {
+        if let Some(trimmed) = state.borrow().app_cache.lookup(term) {
+            return trimmed;
+        }
+
+        let trimmed = Trimmed::new(term.free_vars(1), |rename| term.rename_free_vars(1, rename));
+        state.borrow_mut().app_cache.insert(term, trimmed.clone());
+        trimmed
+    });
+
+    if trimmed.captures_all(values) {
+        None
+    } else {
+        let values = trimmed.env(values)?;
+        Some((trimmed.terms, values))
+    }
+}
+
+/// Trim the environment of a closure over the clauses of a literal case split.
+///
+/// Returns `None` if the environment does not need to be trimmed, if it is
+/// missing entries that are referred to, or if trimming is disabled.
+pub fn trim_literal<Entry: Clone>(
+    clauses: &LiteralClauses,
+    default: &Rc<Term>,
+    values: &var::Env<Entry>,
+) -> Option<(LiteralClauses, Rc<Term>, var::Env<Entry>)> {
+    if !is_enabled() {
+        return None;
+    }
+
+    let trimmed = STATE.with(|state| {
+        if let Some((cached_default, trimmed)) = state.borrow().literal_cache.lookup(clauses) {
+            if Weak::ptr_eq(&cached_default, &Rc::downgrade(default)) {
+                return trimmed;
+            }
+        }
+
+        let mut free_vars = default.free_vars(0);
+        for (_, body) in clauses.iter() {
+            free_vars.extend(body.free_vars(0));
+        }
+        let trimmed = Trimmed::new(free_vars, |rename| {
+            let clauses = clauses
+                .iter()
+                .map(|(literal_intro, body)| {
+                    (literal_intro.clone(), body.rename_free_vars(0, rename))
+                })
+                .collect();
+            (clauses, default.rename_free_vars(0, rename))
+        });
+        let entry = (Rc::downgrade(default), trimmed.clone());
+        state.borrow_mut().literal_cache.insert(clauses, entry);
+        trimmed
+    });
+
+    if trimmed.captures_all(values) {
+        None
+    } else {
+        let (clauses, default) = trimmed.terms.clone();
+        Some((clauses, default, trimmed.env(values)?))
+    }
+}
+
+/// Trim the environment of a closure over the clauses of a data type case
+/// split, where each clause binds the fields of its constructor.
+///
+/// Returns `None` if the environment does not need to be trimmed, if it is
+/// missing entries that are referred to, or if trimming is disabled.
+pub fn trim_case<Entry: Clone>(
+    clauses: &CaseClauses,
+    values: &var::Env<Entry>,
+) -> Option<(CaseClauses, var::Env<Entry>)> {
+    if !is_enabled() {
+        return None;
+    }
+
+    let trimmed = STATE.with(|state| {
+        if let Some(trimmed) = state.borrow().case_cache.lookup(clauses) {
+            return trimmed;
+        }
+
+        let mut free_vars = BTreeSet::new();
+        for (_, name_hints, body) in clauses.iter() {
+            free_vars.extend(body.free_vars(name_hints.len() as u32));
+        }
+        let trimmed = Trimmed::new(free_vars, |rename| {
+            clauses
+                .iter()
+                .map(|(label, name_hints, body)| {
+                    let body = body.rename_free_vars(name_hints.len() as u32, rename);
+                    (label.clone(), name_hints.clone(), body)
+                })
+                .collect()
+        });
+        state
+            .borrow_mut()
+            .case_cache
+            .insert(clauses, trimmed.clone());
+        trimmed
+    });
+
+    if trimmed.captures_all(values) {
+        None
+    } else {
+        Some((trimmed.terms.clone(), trimmed.env(values)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::{AppClosure, CaseClosure, LiteralClosure, Value};
+    use crate::{meta, nbe, prim, AppMode};
+
+    fn values(size: u32) -> var::Env<Rc<Value>> {
+        let mut values = var::Env::new();
+        for level in 0..size {
+            values.add_entry(Rc::from(Value::var(level)));
+        }
+        values
+    }
+
+    #[test]
+    fn app_closure_captures_free_vars() {
+        let _scope = scope(true);
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let budget = nbe::Budget::default();
+
+        // Refers to the variable at level 1, skipping past the argument
+        let term = Rc::from(Term::var(2));
+        let closure = AppClosure::new(term, values(3));
+
+        assert_eq!(closure.values.size(), var::Size(1));
+        assert_eq!(closure.term, Rc::from(Term::var(1)));
+        assert_eq!(
+            nbe::app_closure(&prims, &metas, &budget, &closure, Rc::from(Value::var(3))).unwrap(),
+            Rc::from(Value::var(1)),
+        );
+    }
+
+    #[test]
+    fn app_closure_captures_all() {
+        let _scope = scope(true);
+        let term = Rc::from(Term::FunElim(
+            Rc::from(Term::var(1)),
+            AppMode::Explicit,
+            Rc::from(Term::var(2)),
+        ));
+        let closure = AppClosure::new(term.clone(), values(2));
+
+        assert_eq!(closure.values.size(), var::Size(2));
+        assert_eq!(closure.term, term);
+    }
+
+    #[test]
+    fn literal_closure_captures_free_vars() {
+        let _scope = scope(true);
+        let clauses = Rc::from(vec![(LiteralIntro::from(0u32), Rc::from(Term::var(3)))]);
+        let default = Rc::from(Term::var(1));
+        let closure = LiteralClosure::new(clauses, default, values(4));
+
+        assert_eq!(closure.values.size(), var::Size(2));
+        assert_eq!(closure.clauses[0].1, Rc::from(Term::var(1)));
+        assert_eq!(closure.default, Rc::from(Term::var(0)));
+        assert_eq!(
+            closure.values.lookup_entry(var::Index(0)),
+            Some(&Rc::from(Value::var(2))),
+        );
+    }
+
+    #[test]
+    fn case_closure_captures_free_vars() {
+        let _scope = scope(true);
+        // The first clause binds a field, which shadows the captured entries
+        let clauses = Rc::from(vec![
+            (Label::new("a"), vec![None], Rc::from(Term::var(3))),
+            (Label::new("b"), vec![], Rc::from(Term::var(0))),
+        ]);
+        let closure = CaseClosure::new(clauses, values(4));
+
+        assert_eq!(closure.values.size(), var::Size(2));
+        assert_eq!(closure.clauses[0].2, Rc::from(Term::var(2)));
+        assert_eq!(closure.clauses[1].2, Rc::from(Term::var(0)));
+        assert_eq!(
+            closure.values.lookup_entry(var::Index(1)),
+            Some(&Rc::from(Value::var(1))),
+        );
+    }
+
+    #[test]
+    fn disabled() {
+        let _scope = scope(false);
+        let term = Rc::from(Term::var(2));
+        let closure = AppClosure::new(term.clone(), values(3));
+
+        assert_eq!(closure.values.size(), var::Size(3));
+        assert_eq!(closure.term, term);
+    }
+
+    #[test]
+    fn scope_restores_previous_state() {
+        let _outer = scope(false);
+        let term = Rc::from(Term::var(2));
+
+        {
+            let _inner = scope(true);
+            let closure = AppClosure::new(term.clone(), values(3));
+            assert_eq!(closure.values.size(), var::Size(1));
+        }
+
+        let closure = AppClosure::new(term.clone(), values(3));
+        assert_eq!(closure.values.size(), var::Size(3));
+        assert_eq!(closure.term, term);
+    }
+}
@@ -10,8 +10,8 @@ use std::fmt;
 use std::rc::Rc;
 
 use super::literal::{LiteralIntro, LiteralType};
-use crate::domain::{AppClosure, Type, Value};
-use crate::syntax::{Item, Module, Term};
+use crate::domain::{AppClosure, DataClosure, Elim, Head, Level, Type, Value};
+use crate::syntax::{Data, Item, Module, Term};
 use crate::{meta, nbe, prim, var, AppMode, Label, UniverseLevel};
 
 /// Local type checking context.
@@ -23,6 +23,8 @@ pub struct Context {
     values: var::Env<Rc<Value>>,
     /// Types of the entries in the context.
     tys: var::Env<Rc<Type>>,
+    /// The budget to use during normalization.
+    budget: nbe::Budget,
 }
 
 impl Context {
@@ -30,7 +32,12 @@ impl Context {
     ///
     /// We assume that the value and type environments are of the same length.
     pub fn new(prims: prim::Env, values: var::Env<Rc<Value>>, tys: var::Env<Rc<Type>>) -> Context {
-        Context { prims, values, tys }
+        Context {
+            prims,
+            values,
+            tys,
+            budget: nbe::Budget::default(),
+        }
     }
 
     /// Create a new, empty context.
@@ -48,6 +55,16 @@ impl Context {
         &self.values
     }
 
+    /// The budget to use during normalization.
+    pub fn budget(&self) -> &nbe::Budget {
+        &self.budget
+    }
+
+    /// Set the budget to use during normalization.
+    pub fn set_budget(&mut self, budget: nbe::Budget) {
+        self.budget = budget;
+    }
+
     /// Lookup the type of a variable in the context.
     pub fn lookup_ty(&self, var_index: var::Index) -> Option<&Rc<Type>> {
         self.tys.lookup_entry(var_index)
@@ -77,12 +94,36 @@ impl Context {
         closure: &AppClosure,
         arg: Rc<Value>,
     ) -> Result<Rc<Value>, TypeError> {
-        nbe::app_closure(self.prims(), metas, closure, arg).map_err(TypeError::Nbe)
+        nbe::app_closure(self.prims(), metas, self.budget(), closure, arg).map_err(TypeError::Nbe)
     }
 
     /// Evaluate a term using the evaluation environment.
     pub fn eval_term(&self, metas: &meta::Env, term: &Rc<Term>) -> Result<Rc<Value>, TypeError> {
-        nbe::eval_term(self.prims(), metas, self.values(), term).map_err(TypeError::Nbe)
+        nbe::eval_term(self.prims(), metas, self.budget(), self.values(), term)
+            .map_err(TypeError::Nbe)
+    }
+
+    /// Evaluate a value further, unfolding any solved metavariables or global
+    /// definitions in head position.
+    pub fn force_value(
+        &self,
+        metas: &meta::Env,
+        value: &Rc<Value>,
+    ) -> Result<Rc<Value>, TypeError> {
+        nbe::force_value(self.prims(), metas, self.budget(), value).map_err(TypeError::Nbe)
+    }
+
+    /// Remove the `amount` innermost entries from the scope of a level,
+    /// returning `None` if any of them are referred to in the level.
+    pub fn strengthen_level(
+        &self,
+        metas: &meta::Env,
+        amount: u32,
+        level: &Level,
+    ) -> Result<Option<Level>, TypeError> {
+        let size = self.values().size();
+        nbe::strengthen_level(self.prims(), metas, self.budget(), size, amount, level)
+            .map_err(TypeError::Nbe)
     }
 
     /// Expect that `ty1` is a subtype of `ty2` in the current context.
@@ -92,14 +133,46 @@ impl Context {
         ty1: &Rc<Type>,
         ty2: &Rc<Type>,
     ) -> Result<(), TypeError> {
-        if nbe::check_ty(self.prims(), metas, self.values().size(), true, ty1, ty2)
-            .map_err(TypeError::Nbe)?
+        if nbe::check_ty(
+            self.prims(),
+            metas,
+            self.budget(),
+            self.values().size(),
+            true,
+            ty1,
+            ty2,
+        )
+        .map_err(TypeError::Nbe)?
         {
             Ok(())
         } else {
             Err(TypeError::ExpectedSubtype(ty1.clone(), ty2.clone()))
         }
     }
+
+    /// Expect that `value1` is definitionally equal to `value2` in the
+    /// current context.
+    pub fn check_equal(
+        &self,
+        metas: &meta::Env,
+        value1: &Rc<Value>,
+        value2: &Rc<Value>,
+    ) -> Result<(), TypeError> {
+        if nbe::check_value(
+            self.prims(),
+            metas,
+            self.budget(),
+            self.values().size(),
+            value1,
+            value2,
+        )
+        .map_err(TypeError::Nbe)?
+        {
+            Ok(())
+        } else {
+            Err(TypeError::ExpectedEqual(value1.clone(), value2.clone()))
+        }
+    }
 }
 
 /// An error produced during type checking.
@@ -116,14 +189,26 @@ pub enum TypeError {
     UnboundMeta(meta::Index),
     UnsolvedMeta(meta::Index),
     UnknownPrim(prim::Name),
+    NoDefinitionInGroup(usize),
     BadLiteralPatterns(Vec<LiteralIntro>),
     NoFieldInType(Label),
     UnexpectedField { found: Label, expected: Label },
     UnexpectedAppMode { found: AppMode, expected: AppMode },
     TooManyFieldsFound,
     NotEnoughFieldsProvided,
+    ExpectedDataType { found: Rc<Type> },
+    NotEnoughDataParams(Label),
+    DataLevelDependsOnIndices(Label),
+    NoIntroInData(Label),
+    UnexpectedIntroReturnType(Label),
+    IntroTooLarge(Label),
+    UnexpectedClause { found: Label, expected: Label },
+    TooManyClausesFound,
+    NotEnoughClausesProvided,
+    ExpectedEqType { found: Rc<Type> },
+    ExpectedEqual(Rc<Value>, Rc<Value>),
     OverflowingUniverseLevel,
-    Nbe(String),
+    Nbe(nbe::EvalError),
 }
 
 impl Error for TypeError {}
@@ -142,6 +227,9 @@ impl fmt::Display for TypeError {
             TypeError::UnboundMeta(level) => write!(f, "unbound metavariable: `{}`", level),
             TypeError::UnsolvedMeta(level) => write!(f, "unsolved metavariable `{}`", level),
             TypeError::UnknownPrim(name) => write!(f, "unbound primitive: {}", name),
+            TypeError::NoDefinitionInGroup(index) => {
+                write!(f, "no definition at index {} in recursive group", index)
+            },
             TypeError::BadLiteralPatterns(literal_intros) => write!(
                 f,
                 "literal patterns are not sorted properly: {}",
@@ -160,6 +248,35 @@ impl fmt::Display for TypeError {
             ),
             TypeError::TooManyFieldsFound => write!(f, "too many fields found"),
             TypeError::NotEnoughFieldsProvided => write!(f, "not enough fields provided"),
+            TypeError::ExpectedDataType { .. } => write!(f, "expected data type"),
+            TypeError::NotEnoughDataParams(label) => {
+                write!(
+                    f,
+                    "not enough parameters in the type of data type `{}`",
+                    label
+                )
+            },
+            TypeError::DataLevelDependsOnIndices(label) => write!(
+                f,
+                "the universe level of data type `{}` depends on its indices",
+                label,
+            ),
+            TypeError::NoIntroInData(label) => write!(f, "no constructor `{}` in data type", label),
+            TypeError::UnexpectedIntroReturnType(label) => {
+                write!(f, "unexpected return type for constructor `{}`", label)
+            },
+            TypeError::IntroTooLarge(label) => {
+                write!(f, "constructor `{}` is too large for its data type", label)
+            },
+            TypeError::UnexpectedClause { found, expected } => write!(
+                f,
+                "unexpected clause, found `{}`, but expected `{}`",
+                found, expected,
+            ),
+            TypeError::TooManyClausesFound => write!(f, "too many clauses found"),
+            TypeError::NotEnoughClausesProvided => write!(f, "not enough clauses provided"),
+            TypeError::ExpectedEqType { .. } => write!(f, "expected equality type"),
+            TypeError::ExpectedEqual(..) => write!(f, "not definitionally equal"),
             TypeError::OverflowingUniverseLevel => write!(
                 f,
                 "cannot represent universes greater than `{}`",
@@ -253,6 +370,156 @@ fn check_items(context: &mut Context, metas: &meta::Env, items: &[Item]) -> Resu
     Ok(())
 }
 
+/// Check that a data type declaration is well formed.
+pub fn check_data(context: &Context, metas: &meta::Env, data: &Data) -> Result<(), TypeError> {
+    synth_universe(context, metas, &data.ty)?;
+    let ty = context.eval_term(metas, &data.ty)?;
+
+    let self_level = context.values().size().next_level();
+    let mut intro_context = context.clone();
+    intro_context.add_param(ty.clone());
+
+    // Find the universe at the end of the type of the type former. The
+    // parameters are bound after the type former, in the same way as they are
+    // in the types of the constructors, allowing the level to refer to them.
+    let mut ty_context = intro_context.clone();
+    let mut arity = 0;
+    let mut current_ty = ty;
+    let level = loop {
+        match ty_context.force_value(metas, &current_ty)?.as_ref() {
+            Value::FunType(_, _, param_ty, body_ty) => {
+                let param = ty_context.add_param(param_ty.clone());
+                current_ty = ty_context.app_closure(metas, body_ty, param)?;
+                arity += 1;
+            },
+            Value::Universe(level) => break level.clone(),
+            _ => return Err(TypeError::ExpectedUniverse { found: current_ty }),
+        }
+    };
+    if arity < data.param_count {
+        return Err(TypeError::NotEnoughDataParams(data.label.clone()));
+    }
+    let index_count = (arity - data.param_count) as u32;
+    let level = match ty_context.strengthen_level(metas, index_count, &level)? {
+        Some(level) => level,
+        None => return Err(TypeError::DataLevelDependsOnIndices(data.label.clone())),
+    };
+
+    for (_, label, intro_ty) in &data.intros {
+        // Check the fields of the constructor, making sure that they fit in
+        // the universe of the data type
+        let mut field_context = intro_context.clone();
+        let mut params = Vec::new();
+        let mut intro_ty = intro_ty;
+        while let Term::FunType(_, _, param_ty, body_ty) = intro_ty.as_ref() {
+            let param_level = synth_universe(&field_context, metas, param_ty)?;
+            if params.len() >= data.param_count && !param_level.is_le(&level) {
+                return Err(TypeError::IntroTooLarge(label.clone()));
+            }
+            let param_ty = field_context.eval_term(metas, param_ty)?;
+            params.push(field_context.add_param(param_ty));
+            intro_ty = body_ty;
+        }
+
+        // Check that the constructor returns the data type, applied to its
+        // parameters and some indices
+        synth_universe(&field_context, metas, intro_ty)?;
+        let return_ty = field_context.eval_term(metas, intro_ty)?;
+        match field_context.force_value(metas, &return_ty)?.as_ref() {
+            Value::Neutral(Head::Var(var_level), spine)
+                if *var_level == self_level
+                    && spine.len() == arity
+                    && params.len() >= data.param_count
+                    && Iterator::zip(spine.iter(), params.iter())
+                        .take(data.param_count)
+                        .all(|(elim, param)| match elim {
+                            Elim::Fun(_, arg) => arg == param,
+                            _ => false,
+                        }) => {},
+            _ => return Err(TypeError::UnexpectedIntroReturnType(label.clone())),
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that the motive of an equality elimination is a type family, indexed
+/// by the right hand side of the equality and by the proof of the equality.
+fn check_eq_motive(
+    context: &Context,
+    metas: &meta::Env,
+    motive: &Rc<Term>,
+    ty: &Rc<Type>,
+    lhs: &Rc<Value>,
+) -> Result<(), TypeError> {
+    let mut context = context.clone();
+
+    // Motives are usually functions of two parameters, which we can't
+    // synthesize the type of, so we check their bodies directly instead
+    if let Term::FunIntro(AppMode::Explicit, _, motive_body) = motive.as_ref() {
+        if let Term::FunIntro(AppMode::Explicit, _, motive_body) = motive_body.as_ref() {
+            let rhs = context.add_param(ty.clone());
+            context.add_param(Rc::from(Value::EqType(ty.clone(), lhs.clone(), rhs)));
+            synth_universe(&context, metas, motive_body)?;
+            return Ok(());
+        }
+    }
+
+    let motive_ty = synth_term(&context, metas, motive)?;
+    let (rhs, proof_ty) = match context.force_value(metas, &motive_ty)?.as_ref() {
+        Value::FunType(AppMode::Explicit, _, param_ty, body_ty) => {
+            context.check_subtype(metas, ty, param_ty)?;
+            let rhs = context.add_param(ty.clone());
+            (rhs.clone(), context.app_closure(metas, body_ty, rhs)?)
+        },
+        _ => return Err(TypeError::ExpectedFunType { found: motive_ty }),
+    };
+    let motive_body_ty = match context.force_value(metas, &proof_ty)?.as_ref() {
+        Value::FunType(AppMode::Explicit, _, param_ty, body_ty) => {
+            let eq_ty = Rc::from(Value::EqType(ty.clone(), lhs.clone(), rhs));
+            context.check_subtype(metas, &eq_ty, param_ty)?;
+            let proof = context.add_param(eq_ty);
+            context.app_closure(metas, body_ty, proof)?
+        },
+        _ => return Err(TypeError::ExpectedFunType { found: proof_ty }),
+    };
+    match context.force_value(metas, &motive_body_ty)?.as_ref() {
+        Value::Universe(_) => Ok(()),
+        _ => Err(TypeError::ExpectedUniverse {
+            found: motive_body_ty,
+        }),
+    }
+}
+
+/// Apply the motive of an equality elimination to the right hand side of the
+/// equality and a proof of the equality.
+fn app_eq_motive(
+    context: &Context,
+    metas: &meta::Env,
+    motive: &Rc<Value>,
+    rhs: Rc<Value>,
+    proof: Rc<Value>,
+) -> Result<Rc<Type>, TypeError> {
+    let motive = nbe::eval_fun_elim(
+        context.prims(),
+        metas,
+        context.budget(),
+        motive.clone(),
+        &AppMode::Explicit,
+        rhs,
+    )
+    .map_err(TypeError::Nbe)?;
+    nbe::eval_fun_elim(
+        context.prims(),
+        metas,
+        context.budget(),
+        motive,
+        &AppMode::Explicit,
+        proof,
+    )
+    .map_err(TypeError::Nbe)
+}
+
 /// Check that a literal conforms to a given type.
 pub fn check_literal(
     context: &Context,
@@ -287,10 +554,10 @@ pub fn synth_universe(
     context: &Context,
     metas: &meta::Env,
     term: &Rc<Term>,
-) -> Result<UniverseLevel, TypeError> {
+) -> Result<Level, TypeError> {
     let ty = synth_term(context, metas, term)?;
-    match ty.as_ref() {
-        Value::Universe(level) => Ok(*level),
+    match context.force_value(metas, &ty)?.as_ref() {
+        Value::Universe(level) => Ok(level.clone()),
         _ => Err(TypeError::ExpectedUniverse { found: ty.clone() }),
     }
 }
@@ -339,7 +606,77 @@ pub fn check_term(
             check_term(context, metas, default_body, expected_ty)
         },
 
-        Term::FunIntro(intro_app_mode, _, body) => match expected_ty.as_ref() {
+        Term::DataElim(scrutinee, clauses) => {
+            let scrutinee_ty = synth_term(context, metas, scrutinee)?;
+            let (closure, args) = match context.force_value(metas, &scrutinee_ty)?.as_ref() {
+                Value::DataType(closure, args) => (closure.clone(), args.clone()),
+                _ => {
+                    return Err(TypeError::ExpectedDataType {
+                        found: scrutinee_ty,
+                    })
+                },
+            };
+
+            if clauses.len() > closure.data.intros.len() {
+                return Err(TypeError::TooManyClausesFound);
+            } else if clauses.len() < closure.data.intros.len() {
+                return Err(TypeError::NotEnoughClausesProvided);
+            }
+
+            for ((label, name_hints, body), (_, expected_label, _)) in
+                Iterator::zip(clauses.iter(), closure.data.intros.iter())
+            {
+                if label != expected_label {
+                    return Err(TypeError::UnexpectedClause {
+                        found: label.clone(),
+                        expected: expected_label.clone(),
+                    });
+                }
+
+                let mut body_context = context.clone();
+                let mut intro_ty =
+                    nbe::intro_ty(context.prims(), metas, context.budget(), &closure, label)
+                        .map_err(TypeError::Nbe)?;
+
+                // Apply the constructor type to the parameters of the data type,
+                // and then bind each of the fields
+                let params = args.iter().take(closure.data.param_count).cloned();
+                let fields = name_hints.iter().map(|_| None);
+                for arg in params.map(Some).chain(fields) {
+                    intro_ty = match body_context.force_value(metas, &intro_ty)?.as_ref() {
+                        Value::FunType(_, _, param_ty, body_ty) => {
+                            let arg = match arg {
+                                Some((_, arg)) => arg,
+                                None => body_context.add_param(param_ty.clone()),
+                            };
+                            body_context.app_closure(metas, body_ty, arg)?
+                        },
+                        _ => return Err(TypeError::ExpectedFunType { found: intro_ty }),
+                    };
+                }
+                if let Value::FunType(_, _, _, _) =
+                    body_context.force_value(metas, &intro_ty)?.as_ref()
+                {
+                    return Err(TypeError::NotEnoughFieldsProvided);
+                }
+
+                check_term(&body_context, metas, body, expected_ty)?;
+            }
+
+            Ok(())
+        },
+
+        Term::EqIntro => match context.force_value(metas, expected_ty)?.as_ref() {
+            Value::EqType(_, lhs, rhs) => context.check_equal(metas, lhs, rhs),
+            _ => Err(TypeError::ExpectedEqType {
+                found: expected_ty.clone(),
+            }),
+        },
+
+        Term::FunIntro(intro_app_mode, _, body) => match context
+            .force_value(metas, expected_ty)?
+            .as_ref()
+        {
             Value::FunType(ty_app_mode, _, param_ty, body_ty) if intro_app_mode == ty_app_mode => {
                 let mut body_context = context.clone();
                 let param = body_context.add_param(param_ty.clone());
@@ -357,12 +694,19 @@ pub fn check_term(
         },
 
         Term::RecordIntro(intro_fields) => {
-            let mut context = context.clone();
+            // The fields of a record are not in scope of each other, but the
+            // types of later fields are instantiated with the earlier values
             let mut expected_ty = expected_ty.clone();
 
             for (label, term) in intro_fields {
-                if let Value::RecordTypeExtend(_, expected_label, _, expected_term_ty, rest) =
-                    expected_ty.as_ref()
+                if let Value::RecordTypeExtend(
+                    _,
+                    expected_label,
+                    _,
+                    expected_term_ty,
+                    expected_manifest,
+                    rest,
+                ) = context.force_value(metas, &expected_ty)?.as_ref()
                 {
                     if label != expected_label {
                         return Err(TypeError::UnexpectedField {
@@ -371,17 +715,19 @@ pub fn check_term(
                         });
                     }
 
-                    check_term(&context, metas, term, expected_term_ty)?;
+                    check_term(context, metas, term, expected_term_ty)?;
                     let term_value = context.eval_term(metas, term)?;
+                    if let Some(expected_manifest) = expected_manifest {
+                        context.check_equal(metas, &term_value, expected_manifest)?;
+                    }
 
-                    context.add_defn(term_value.clone(), expected_term_ty.clone());
                     expected_ty = context.app_closure(metas, &rest, term_value)?;
                 } else {
                     return Err(TypeError::TooManyFieldsFound);
                 }
             }
 
-            if let Value::RecordTypeEmpty = expected_ty.as_ref() {
+            if let Value::RecordTypeEmpty = context.force_value(metas, &expected_ty)?.as_ref() {
                 Ok(())
             } else {
                 Err(TypeError::NotEnoughFieldsProvided)
@@ -401,8 +747,6 @@ pub fn synth_term(
     metas: &meta::Env,
     term: &Rc<Term>,
 ) -> Result<Rc<Type>, TypeError> {
-    use std::cmp;
-
     log::trace!("synthesizing term:\t{:?}", term);
 
     match term.as_ref() {
@@ -411,8 +755,8 @@ pub fn synth_term(
             Some(var_ty) => Ok(var_ty.clone()),
         },
         Term::Meta(meta_level) => match metas.lookup_solution(*meta_level) {
-            Some((_, meta::Solution::Solved(_value), meta_ty)) => Ok(meta_ty.clone()),
-            Some((_, meta::Solution::Unsolved, _)) => Err(TypeError::UnsolvedMeta(*meta_level)),
+            Some((_, meta::Solution::Solved(_value), _, meta_ty)) => Ok(meta_ty.clone()),
+            Some((_, meta::Solution::Unsolved, _, _)) => Err(TypeError::UnsolvedMeta(*meta_level)),
             None => Err(TypeError::UnboundMeta(*meta_level)),
         },
         Term::Prim(prim_name) => match context.prims().lookup_entry(prim_name) {
@@ -431,6 +775,26 @@ pub fn synth_term(
             check_items(&mut context, metas, items)?;
             synth_term(&context, metas, body)
         },
+        Term::Fix(index, defns) => {
+            let mut body_context = context.clone();
+            let mut tys = Vec::with_capacity(defns.len());
+
+            for (_, _, ty, _) in defns.iter() {
+                synth_universe(context, metas, ty)?;
+                let ty = context.eval_term(metas, ty)?;
+                body_context.add_param(ty.clone());
+                tys.push(ty);
+            }
+
+            for ((_, _, _, body), ty) in Iterator::zip(defns.iter(), tys.iter()) {
+                check_term(&body_context, metas, body, ty)?;
+            }
+
+            match tys.get(*index) {
+                Some(ty) => Ok(ty.clone()),
+                None => Err(TypeError::NoDefinitionInGroup(*index)),
+            }
+        },
 
         Term::LiteralType(_) => Ok(Rc::from(Value::universe(0))),
         Term::LiteralIntro(literal_intro) => Ok(synth_literal(literal_intro)),
@@ -443,15 +807,20 @@ pub fn synth_term(
             let mut body_ty_context = context.clone();
             body_ty_context.add_param(param_ty_value);
 
+            // Function types that quantify over universe levels are placed in
+            // the largest universe
             let body_level = synth_universe(&body_ty_context, metas, body_ty)?;
+            let body_level = body_ty_context
+                .strengthen_level(metas, 1, &body_level)?
+                .unwrap_or_else(Level::omega);
 
-            Ok(Rc::from(Value::universe(cmp::max(param_level, body_level))))
+            Ok(Rc::from(Value::Universe(param_level.max(&body_level))))
         },
         Term::FunIntro(_, _, _) => Err(TypeError::AmbiguousTerm(term.clone())),
 
         Term::FunElim(fun, arg_app_mode, arg) => {
             let fun_ty = synth_term(context, metas, fun)?;
-            match fun_ty.as_ref() {
+            match context.force_value(metas, &fun_ty)?.as_ref() {
                 Value::FunType(ty_app_mode, _, arg_ty, body_ty) if arg_app_mode == ty_app_mode => {
                     check_term(context, metas, arg, arg_ty)?;
                     let arg_value = context.eval_term(metas, arg)?;
@@ -469,15 +838,27 @@ pub fn synth_term(
 
         Term::RecordType(ty_fields) => {
             let mut context = context.clone();
-            let mut max_level = UniverseLevel(0);
+            let mut max_level = Level::constant(0);
 
-            for (_, _, _, ty) in ty_fields {
+            for (index, (_, _, _, ty, manifest)) in ty_fields.iter().enumerate() {
                 let ty_level = synth_universe(&context, metas, &ty)?;
-                context.add_param(context.eval_term(metas, &ty)?);
-                max_level = cmp::max(max_level, ty_level);
+                let ty_level = context
+                    .strengthen_level(metas, index as u32, &ty_level)?
+                    .unwrap_or_else(Level::omega);
+                let ty = context.eval_term(metas, &ty)?;
+                match manifest {
+                    None => {
+                        context.add_param(ty);
+                    },
+                    Some(manifest) => {
+                        check_term(&context, metas, manifest, &ty)?;
+                        context.add_defn(context.eval_term(metas, manifest)?, ty);
+                    },
+                }
+                max_level = max_level.max(&ty_level);
             }
 
-            Ok(Rc::from(Value::universe(max_level)))
+            Ok(Rc::from(Value::Universe(max_level)))
         },
         Term::RecordIntro(intro_fields) => {
             if intro_fields.is_empty() {
@@ -489,25 +870,88 @@ pub fn synth_term(
         Term::RecordElim(record, label) => {
             let mut record_ty = synth_term(context, metas, record)?;
 
-            while let Value::RecordTypeExtend(_, current_label, _, current_ty, rest) =
-                record_ty.as_ref()
+            while let Value::RecordTypeExtend(_, current_label, _, current_ty, manifest, rest) =
+                context.force_value(metas, &record_ty)?.as_ref()
             {
                 if label == current_label {
                     return Ok(current_ty.clone());
                 } else {
-                    let label = current_label.clone();
-                    let expr = Rc::from(Term::RecordElim(record.clone(), label));
-                    record_ty =
-                        context.app_closure(metas, rest, context.eval_term(metas, &expr)?)?;
+                    let value = match manifest {
+                        Some(manifest) => manifest.clone(),
+                        None => {
+                            let label = current_label.clone();
+                            let expr = Rc::from(Term::RecordElim(record.clone(), label));
+                            context.eval_term(metas, &expr)?
+                        },
+                    };
+                    record_ty = context.app_closure(metas, rest, value)?;
                 }
             }
 
             Err(TypeError::NoFieldInType(label.clone()))
         },
 
-        Term::Universe(level) => match level.shift(1) {
-            None => Err(TypeError::OverflowingUniverseLevel),
-            Some(level) => Ok(Rc::from(Value::universe(level))),
+        Term::DataType(data) => {
+            check_data(context, metas, data)?;
+            context.eval_term(metas, &data.ty)
+        },
+        Term::DataIntro(data, label) => {
+            check_data(context, metas, data)?;
+            if data.lookup_intro(label).is_none() {
+                return Err(TypeError::NoIntroInData(label.clone()));
+            }
+            let closure = DataClosure::new(data.clone(), context.values().clone());
+            nbe::intro_ty(context.prims(), metas, context.budget(), &closure, label)
+                .map_err(TypeError::Nbe)
+        },
+        Term::DataElim(_, _) => Err(TypeError::AmbiguousTerm(term.clone())),
+
+        Term::EqType(ty, lhs, rhs) => {
+            let level = synth_universe(context, metas, ty)?;
+            let ty = context.eval_term(metas, ty)?;
+            check_term(context, metas, lhs, &ty)?;
+            check_term(context, metas, rhs, &ty)?;
+            Ok(Rc::from(Value::Universe(level)))
+        },
+        Term::EqIntro => Err(TypeError::AmbiguousTerm(term.clone())),
+        Term::EqElim(proof, motive, body) => {
+            let proof_ty = synth_term(context, metas, proof)?;
+            let (ty, lhs, rhs) = match context.force_value(metas, &proof_ty)?.as_ref() {
+                Value::EqType(ty, lhs, rhs) => (ty.clone(), lhs.clone(), rhs.clone()),
+                _ => return Err(TypeError::ExpectedEqType { found: proof_ty }),
+            };
+
+            check_eq_motive(context, metas, motive, &ty, &lhs)?;
+            let motive = context.eval_term(metas, motive)?;
+            let refl = Rc::from(Value::EqIntro);
+            let body_ty = app_eq_motive(context, metas, &motive, lhs, refl)?;
+            check_term(context, metas, body, &body_ty)?;
+
+            let proof = context.eval_term(metas, proof)?;
+            app_eq_motive(context, metas, &motive, rhs, proof)
+        },
+
+        Term::LevelType => Ok(Rc::from(Value::universe(0))),
+        Term::LevelIntro(_) | Term::LevelOmega => Ok(Rc::from(Value::LevelType)),
+        Term::LevelShift(level, _) => {
+            check_term(context, metas, level, &Rc::from(Value::LevelType))?;
+            Ok(Rc::from(Value::LevelType))
+        },
+        Term::LevelMax(lhs, rhs) => {
+            check_term(context, metas, lhs, &Rc::from(Value::LevelType))?;
+            check_term(context, metas, rhs, &Rc::from(Value::LevelType))?;
+            Ok(Rc::from(Value::LevelType))
+        },
+
+        Term::Universe(level) => {
+            check_term(context, metas, level, &Rc::from(Value::LevelType))?;
+            let level = context.eval_term(metas, level)?;
+            let level = nbe::value_to_level(context.prims(), metas, context.budget(), &level)
+                .map_err(TypeError::Nbe)?;
+            match level.shift(1) {
+                None => Err(TypeError::OverflowingUniverseLevel),
+                Some(level) => Ok(Rc::from(Value::Universe(level))),
+            }
         },
     }
 }
@@ -53,6 +53,11 @@ impl Size {
     pub fn index(self, level: Level) -> Index {
         Index(self.0 - (level.0 + 1)) // FIXME: Check for over/underflow?
     }
+
+    /// Convert a variable index in the current environment to a variable level.
+    pub fn level(self, index: Index) -> Level {
+        Level(self.0 - (index.0 + 1)) // FIXME: Check for over/underflow?
+    }
 }
 
 impl From<u32> for Size {
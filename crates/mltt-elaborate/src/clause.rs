@@ -2,12 +2,13 @@
 
 use language_reporting::{Diagnostic, Label as DiagnosticLabel};
 use mltt_concrete::{IntroParam, LiteralKind, Pattern, SpannedString, Term};
+use mltt_core::intern::Symbol;
 use mltt_core::literal::LiteralIntro;
-use mltt_core::{domain, meta, syntax, AppMode, DocString, Label};
+use mltt_core::{domain, meta, syntax, var, AppMode, DocString, Label};
 use mltt_span::FileSpan;
 use std::rc::Rc;
 
-use super::{check_term, literal, synth_term, synth_universe, Context, MetaInsertion};
+use super::{check_term, literal, nbe, synth_term, synth_universe, Context, MetaInsertion};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 // Top-level Implementation
@@ -49,10 +50,11 @@ pub fn check_clause(
 ) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
     let mut context = context.clone();
     let mut params = Vec::new();
+    let mut tuple_params = Vec::new();
     let mut expected_ty = expected_ty.clone();
 
     while let (Some((app_mode, param_ty, next_body_ty)), Some((head_param, rest_params))) = (
-        next_expected_param(&expected_ty),
+        next_expected_param(&context, metas, &expected_ty)?,
         clause.params.split_first(),
     ) {
         let (param_var, name_hint) = match check_param_app_mode(head_param, &app_mode)? {
@@ -61,7 +63,7 @@ pub fn check_clause(
                 clause.params = rest_params;
                 (
                     context.add_param(var_name, param_ty),
-                    Some(var_name.to_string()),
+                    Some(Symbol::intern(var_name.slice)),
                 )
             },
             CheckedPattern::LiteralIntro(_, literal) => {
@@ -70,13 +72,47 @@ pub fn check_clause(
                         .with_message("use a case expression for matching on literals"),
                 ));
             },
+            CheckedPattern::DataIntro(span) => {
+                return Err(Diagnostic::new_error("non-exhaustive patterns").with_label(
+                    DiagnosticLabel::new_primary(span)
+                        .with_message("use a case expression for matching on constructors"),
+                ));
+            },
+            CheckedPattern::TupleIntro(span, patterns) => {
+                clause.params = rest_params;
+                let param_level = context.values().size().next_level();
+                let param_var = context.add_fresh_param(param_ty.clone());
+                tuple_params.push((span, param_level, param_ty, patterns));
+                (param_var, None)
+            },
         };
 
         params.push((app_mode, name_hint));
-        expected_ty = context.app_closure(metas, next_body_ty, param_var)?;
+        expected_ty = context.app_closure(metas, &next_body_ty, param_var)?;
+    }
+
+    // Bind the components of the tuple patterns once all of the parameters
+    // are in scope, so that they can be referred to in the body
+    let mut items = Vec::new();
+    for (span, param_level, param_ty, patterns) in tuple_params {
+        let ty = &param_ty;
+        bind_tuple_pattern(
+            &mut context,
+            metas,
+            span,
+            param_level,
+            ty,
+            &patterns,
+            &mut items,
+        )?;
     }
 
     let body = check_clause_body(&context, metas, &clause, &expected_ty)?;
+    let body = if items.is_empty() {
+        body
+    } else {
+        Rc::from(syntax::Term::Let(items, body))
+    };
 
     Ok(done(Vec::new(), params, body))
 }
@@ -109,24 +145,68 @@ pub fn check_case<'file>(
     // TODO: Zero or more scrutinees
     // TODO: One-or-more patterns per case clause
 
+    let mut context = context.clone();
+
+    let (checked_scrutinee, (param_level, param_ty)) = {
+        let scrutinee_level = context.values().size().next_level();
+        let (scrutinee_term, scrutinee_ty) =
+            synth_term(MetaInsertion::Yes, &context, metas, scrutinee)?;
+        let scrutinee_value = context.eval_term(metas, scrutinee.span(), &scrutinee_term)?;
+        context.add_fresh_defn(scrutinee_value, scrutinee_ty.clone());
+
+        ((scrutinee_term, None), (scrutinee_level, scrutinee_ty))
+    };
+
+    let body = match context
+        .force_value(metas, scrutinee.span(), &param_ty)?
+        .as_ref()
+    {
+        domain::Value::DataType(data_closure, args) => {
+            let scrutinee = Scrutinee {
+                level: param_level,
+                data_closure,
+                args,
+            };
+            check_data_case(&context, metas, span, scrutinee, &clauses, expected_ty)?
+        },
+        domain::Value::RecordTypeExtend(..) => check_tuple_case(
+            &context,
+            metas,
+            span,
+            param_level,
+            &param_ty,
+            &clauses,
+            expected_ty,
+        )?,
+        _ => check_literal_case(
+            &context,
+            metas,
+            span,
+            param_level,
+            &param_ty,
+            &clauses,
+            expected_ty,
+        )?,
+    };
+
+    Ok(done(vec![checked_scrutinee], Vec::new(), body))
+}
+
+/// Elaborate a case expression with literal patterns.
+fn check_literal_case(
+    context: &Context,
+    metas: &mut meta::Env,
+    span: FileSpan,
+    param_level: var::Level,
+    param_ty: &Rc<domain::Type>,
+    clauses: &[CaseClause<'_>],
+    expected_ty: &Rc<domain::Type>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
     match clauses.split_last() {
         None => Err(Diagnostic::new_error("non-exhaustive patterns").with_label(
             DiagnosticLabel::new_primary(span).with_message("empty patterns are not yet supported"),
         )),
         Some((default_clause, literal_clauses)) => {
-            let mut context = context.clone();
-
-            let (checked_scrutinee, (param_level, param_ty)) = {
-                let scrutinee_level = context.values().size().next_level();
-                let (scrutinee_term, scrutinee_ty) =
-                    synth_term(MetaInsertion::Yes, &context, metas, scrutinee)?;
-                let scrutinee_value =
-                    context.eval_term(metas, scrutinee.span(), &scrutinee_term)?;
-                context.add_fresh_defn(scrutinee_value, scrutinee_ty.clone());
-
-                ((scrutinee_term, None), (scrutinee_level, scrutinee_ty))
-            };
-
             let mut literal_branches =
                 Vec::<(LiteralIntro, Rc<syntax::Term>)>::with_capacity(literal_clauses.len());
 
@@ -134,8 +214,8 @@ pub fn check_case<'file>(
                 match literal_clause.pattern {
                     Pattern::LiteralIntro(kind, literal) => {
                         let literal_intro =
-                            literal::check(&context, metas, *kind, literal, &param_ty)?;
-                        let body = check_term(&context, metas, &literal_clause.body, expected_ty)?;
+                            literal::check(context, metas, *kind, literal, param_ty)?;
+                        let body = check_term(context, metas, &literal_clause.body, expected_ty)?;
 
                         match literal_branches
                             .binary_search_by(|(l, _)| l.partial_cmp(&literal_intro).unwrap()) // NaN?
@@ -171,19 +251,371 @@ pub fn check_case<'file>(
                 },
             };
 
-            let body = Rc::from(syntax::Term::LiteralElim(
+            Ok(Rc::from(syntax::Term::LiteralElim(
                 Rc::from(syntax::Term::var(
                     context.values().size().index(param_level),
                 )),
                 Rc::from(literal_branches),
                 default_body,
+            )))
+        },
+    }
+}
+
+/// Elaborate a case expression with a tuple pattern.
+///
+/// Tuples only have a single introduction form, so the first clause covers
+/// every possible value of the scrutinee.
+fn check_tuple_case(
+    context: &Context,
+    metas: &mut meta::Env,
+    span: FileSpan,
+    param_level: var::Level,
+    param_ty: &Rc<domain::Type>,
+    clauses: &[CaseClause<'_>],
+    expected_ty: &Rc<domain::Type>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    let (clause, unreachable_clauses) = match clauses.split_first() {
+        Some(clauses) => clauses,
+        None => {
+            return Err(Diagnostic::new_error("non-exhaustive patterns").with_label(
+                DiagnosticLabel::new_primary(span)
+                    .with_message("empty patterns are not yet supported"),
             ));
+        },
+    };
+
+    if let Some(unreachable_clause) = unreachable_clauses.first() {
+        return Err(Diagnostic::new_error("unreachable pattern").with_label(
+            DiagnosticLabel::new_primary(unreachable_clause.pattern.span())
+                .with_message("this pattern is covered by the preceding case"),
+        ));
+    }
 
-            Ok(done(vec![checked_scrutinee], Vec::new(), body))
+    let mut context = context.clone();
+    match clause.pattern {
+        Pattern::Var(name) => {
+            context.add_name(name, param_level);
+            check_term(&context, metas, clause.body, expected_ty)
+        },
+        Pattern::TupleIntro(span, patterns) => {
+            let mut items = Vec::new();
+            bind_tuple_pattern(
+                &mut context,
+                metas,
+                *span,
+                param_level,
+                param_ty,
+                patterns,
+                &mut items,
+            )?;
+            let body = check_term(&context, metas, clause.body, expected_ty)?;
+
+            Ok(Rc::from(syntax::Term::Let(items, body)))
+        },
+        Pattern::LiteralIntro(_, _) | Pattern::DataIntro(_, _) => {
+            Err(Diagnostic::new_error("expected tuple pattern").with_label(
+                DiagnosticLabel::new_primary(clause.pattern.span())
+                    .with_message("tuple pattern expected here"),
+            ))
         },
     }
 }
 
+/// Bind the components of a tuple to the given patterns, by adding a
+/// definition to the context for each of them. The definitions are also
+/// added to `items`, to be bound using a let expression around the body.
+fn bind_tuple_pattern(
+    context: &mut Context,
+    metas: &mut meta::Env,
+    span: FileSpan,
+    tuple_level: var::Level,
+    tuple_ty: &Rc<domain::Type>,
+    patterns: &[Pattern<'_>],
+    items: &mut Vec<syntax::Item>,
+) -> Result<(), Diagnostic<FileSpan>> {
+    let mut tuple_ty = tuple_ty.clone();
+
+    for (index, pattern) in patterns.iter().enumerate() {
+        let forced_ty = context.force_value(metas, span, &tuple_ty)?;
+        let (label, component_ty, rest) = match forced_ty.as_ref() {
+            domain::Value::RecordTypeExtend(_, label, _, ty, _, rest)
+                if *label == Label::tuple(index) =>
+            {
+                (label, ty, rest)
+            },
+            domain::Value::RecordTypeEmpty => {
+                return Err(Diagnostic::new_error("too many patterns").with_label(
+                    DiagnosticLabel::new_primary(pattern.span())
+                        .with_message("unexpected component of tuple"),
+                ));
+            },
+            _ => {
+                return Err(
+                    Diagnostic::new_error("mismatched tuple pattern").with_label(
+                        DiagnosticLabel::new_primary(span)
+                            .with_message("the type of the parameter is not a tuple type"),
+                    ),
+                );
+            },
+        };
+
+        let tuple = syntax::Term::var(context.values().size().index(tuple_level));
+        let component = Rc::from(syntax::Term::RecordElim(Rc::from(tuple), label.clone()));
+        let component_value = context.eval_term(metas, pattern.span(), &component)?;
+        let doc = DocString::from("");
+
+        match pattern {
+            Pattern::Var(name) => {
+                items.push(syntax::Item::Definition(
+                    doc,
+                    Label::new(name.slice),
+                    component,
+                ));
+                context.add_defn(*name, component_value.clone(), component_ty.clone());
+            },
+            Pattern::TupleIntro(span, patterns) => {
+                items.push(syntax::Item::Definition(doc, Label::new("_"), component));
+                let component_level = context.values().size().next_level();
+                context.add_fresh_defn(component_value.clone(), component_ty.clone());
+                bind_tuple_pattern(
+                    context,
+                    metas,
+                    *span,
+                    component_level,
+                    component_ty,
+                    patterns,
+                    items,
+                )?;
+            },
+            Pattern::LiteralIntro(_, _) | Pattern::DataIntro(_, _) => {
+                return Err(
+                    Diagnostic::new_error("nested patterns are not yet supported")
+                        .with_label(DiagnosticLabel::new_primary(pattern.span())),
+                );
+            },
+        }
+
+        tuple_ty = context.app_closure(metas, rest, component_value)?;
+    }
+
+    match context.force_value(metas, span, &tuple_ty)?.as_ref() {
+        domain::Value::RecordTypeEmpty => Ok(()),
+        _ => Err(Diagnostic::new_error("not enough patterns").with_label(
+            DiagnosticLabel::new_primary(span)
+                .with_message("expected more components for the tuple"),
+        )),
+    }
+}
+
+/// A scrutinee whose type is an inductive data type.
+struct Scrutinee<'a> {
+    /// The level of the variable that the scrutinee is bound to
+    level: var::Level,
+    /// The data type declaration
+    data_closure: &'a domain::DataClosure,
+    /// The arguments applied to the data type, beginning with its parameters
+    args: &'a [(AppMode, Rc<domain::Value>)],
+}
+
+/// Elaborate a case expression with constructor patterns.
+///
+/// Variable patterns that do not refer to a constructor of the data type are
+/// used as a default case for any constructors that were not covered by the
+/// preceding clauses.
+fn check_data_case(
+    context: &Context,
+    metas: &mut meta::Env,
+    span: FileSpan,
+    scrutinee: Scrutinee<'_>,
+    clauses: &[CaseClause<'_>],
+    expected_ty: &Rc<domain::Type>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    // TODO: Refine the indices of the scrutinee in each clause
+    let data = &scrutinee.data_closure.data;
+    let find_intro = |name: &str| {
+        data.intros
+            .iter()
+            .position(|(_, label, _)| label.as_str() == name)
+    };
+
+    let mut intro_clauses = vec![None; data.intros.len()];
+    let mut default_clause = None;
+
+    for clause in clauses {
+        if default_clause.is_some() {
+            return Err(Diagnostic::new_error("unreachable pattern").with_label(
+                DiagnosticLabel::new_primary(clause.pattern.span())
+                    .with_message("this pattern is covered by the preceding default case"),
+            ));
+        }
+
+        let (index, arg_patterns) = match clause.pattern {
+            Pattern::Var(name) => match find_intro(name.slice) {
+                Some(index) => (index, &[][..]),
+                None => {
+                    let mut context = context.clone();
+                    context.add_name(*name, scrutinee.level);
+                    let body = check_term(&context, metas, clause.body, expected_ty)?;
+                    default_clause = Some(body);
+                    continue;
+                },
+            },
+            Pattern::DataIntro(label, arg_patterns) => match find_intro(label.slice) {
+                Some(index) => (index, &arg_patterns[..]),
+                None => {
+                    return Err(Diagnostic::new_error("unknown constructor").with_label(
+                        DiagnosticLabel::new_primary(label.span()).with_message(format!(
+                            "`{}` is not a constructor of `{}`",
+                            label, data.label,
+                        )),
+                    ));
+                },
+            },
+            Pattern::LiteralIntro(_, literal) => {
+                return Err(
+                    Diagnostic::new_error("unexpected literal pattern").with_label(
+                        DiagnosticLabel::new_primary(literal.span())
+                            .with_message("constructor pattern expected here"),
+                    ),
+                );
+            },
+            Pattern::TupleIntro(span, _) => {
+                return Err(
+                    Diagnostic::new_error("unexpected tuple pattern").with_label(
+                        DiagnosticLabel::new_primary(*span)
+                            .with_message("constructor pattern expected here"),
+                    ),
+                );
+            },
+        };
+
+        let (_, label, _) = &data.intros[index];
+        let span = clause.pattern.span();
+        let (intro_context, name_hints) =
+            bind_fields(context, metas, span, &scrutinee, label, Some(arg_patterns))?;
+        let body = check_term(&intro_context, metas, clause.body, expected_ty)?;
+
+        // TODO: Warn about duplicated patterns?
+        if intro_clauses[index].is_none() {
+            intro_clauses[index] = Some((name_hints, body));
+        }
+    }
+
+    let mut data_clauses = Vec::with_capacity(data.intros.len());
+    let mut missing_labels = Vec::new();
+
+    for ((_, label, _), intro_clause) in Iterator::zip(data.intros.iter(), intro_clauses) {
+        match (intro_clause, &default_clause) {
+            (Some((name_hints, body)), _) => data_clauses.push((label.clone(), name_hints, body)),
+            (None, Some(default_body)) => {
+                // Move the default body past the fields of the constructor
+                let (_, name_hints) = bind_fields(context, metas, span, &scrutinee, label, None)?;
+                let body = default_body.shift(name_hints.len() as u32);
+                data_clauses.push((label.clone(), name_hints, body));
+            },
+            (None, None) => missing_labels.push(format!("`{}`", label)),
+        }
+    }
+
+    if !missing_labels.is_empty() {
+        return Err(Diagnostic::new_error("non-exhaustive patterns").with_label(
+            DiagnosticLabel::new_primary(span).with_message(format!(
+                "missing patterns for {}",
+                missing_labels.join(", "),
+            )),
+        ));
+    }
+
+    Ok(Rc::from(syntax::Term::DataElim(
+        Rc::from(syntax::Term::var(
+            context.values().size().index(scrutinee.level),
+        )),
+        Rc::from(data_clauses),
+    )))
+}
+
+/// Bind the fields of a constructor of the scrutinee's data type to the
+/// given argument patterns, or to fresh variables if no patterns are given.
+///
+/// Returns the context extended with the fields, and the names of the fields.
+fn bind_fields(
+    context: &Context,
+    metas: &mut meta::Env,
+    span: FileSpan,
+    scrutinee: &Scrutinee<'_>,
+    label: &Label,
+    arg_patterns: Option<&[Pattern<'_>]>,
+) -> Result<(Context, Vec<Option<Symbol>>), Diagnostic<FileSpan>> {
+    let mut context = context.clone();
+    let mut name_hints = Vec::new();
+    let mut arg_patterns = arg_patterns.map(<[_]>::iter);
+
+    // Instantiate the constructor with the parameters of the data type
+    let mut intro_ty = nbe::intro_ty(
+        context.prims(),
+        metas,
+        context.budget(),
+        scrutinee.data_closure,
+        label,
+    )?;
+    for (_, param) in scrutinee
+        .args
+        .iter()
+        .take(scrutinee.data_closure.data.param_count)
+    {
+        intro_ty = match context.force_value(metas, span, &intro_ty)?.as_ref() {
+            domain::Value::FunType(_, _, _, body_ty) => {
+                context.app_closure(metas, body_ty, param.clone())?
+            },
+            _ => return Err(Diagnostic::new_bug("expected a data type parameter")),
+        };
+    }
+
+    while let domain::Value::FunType(app_mode, _, field_ty, body_ty) =
+        context.force_value(metas, span, &intro_ty)?.as_ref()
+    {
+        let field_ty = field_ty.clone();
+        let field = match app_mode {
+            AppMode::Implicit(field_label) | AppMode::Instance(field_label) => {
+                name_hints.push(Some(field_label.0.clone()));
+                context.add_fresh_param(field_ty)
+            },
+            AppMode::Explicit => match arg_patterns.as_mut().map(Iterator::next) {
+                None => {
+                    name_hints.push(None);
+                    context.add_fresh_param(field_ty)
+                },
+                Some(Some(Pattern::Var(name))) => {
+                    name_hints.push(Some(Symbol::intern(name.slice)));
+                    context.add_param(*name, field_ty)
+                },
+                Some(Some(pattern)) => {
+                    return Err(
+                        Diagnostic::new_error("nested patterns are not yet supported")
+                            .with_label(DiagnosticLabel::new_primary(pattern.span())),
+                    );
+                },
+                Some(None) => {
+                    return Err(Diagnostic::new_error("not enough patterns").with_label(
+                        DiagnosticLabel::new_primary(span)
+                            .with_message(format!("expected more fields for `{}`", label)),
+                    ));
+                },
+            },
+        };
+        intro_ty = context.app_closure(metas, body_ty, field)?;
+    }
+
+    if let Some(pattern) = arg_patterns.and_then(|mut arg_patterns| arg_patterns.next()) {
+        return Err(Diagnostic::new_error("too many patterns").with_label(
+            DiagnosticLabel::new_primary(pattern.span())
+                .with_message(format!("unexpected field for `{}`", label)),
+        ));
+    }
+
+    Ok((context, name_hints))
+}
 /// Synthesize the type of the clauses, elaborating them into a case tree.
 ///
 /// Returns the elaborated term and its synthesized type.
@@ -208,20 +640,24 @@ pub fn synth_clause(
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Get the next expected parameter
-fn next_expected_param<'ty>(
-    expected_ty: &'ty Rc<domain::Type>,
-) -> Option<(AppMode, Rc<domain::Value>, &'ty domain::AppClosure)> {
-    match expected_ty.as_ref() {
+fn next_expected_param(
+    context: &Context,
+    metas: &meta::Env,
+    expected_ty: &Rc<domain::Type>,
+) -> Result<Option<(AppMode, Rc<domain::Value>, domain::AppClosure)>, Diagnostic<FileSpan>> {
+    match context.force_value(metas, None, expected_ty)?.as_ref() {
         domain::Value::FunType(app_mode, _, param_ty, body_ty) => {
-            Some((app_mode.clone(), param_ty.clone(), body_ty))
+            Ok(Some((app_mode.clone(), param_ty.clone(), body_ty.clone())))
         },
-        _ => None,
+        _ => Ok(None),
     }
 }
 
 enum CheckedPattern<'file> {
     Var(Option<SpannedString<'file>>),
     LiteralIntro(LiteralKind, SpannedString<'file>),
+    DataIntro(FileSpan),
+    TupleIntro(FileSpan, Vec<Pattern<'file>>),
 }
 
 impl<'file> From<&Pattern<'file>> for CheckedPattern<'file> {
@@ -229,6 +665,10 @@ impl<'file> From<&Pattern<'file>> for CheckedPattern<'file> {
         match src {
             Pattern::Var(name) => CheckedPattern::Var(Some(name.clone())),
             Pattern::LiteralIntro(kind, src) => CheckedPattern::LiteralIntro(*kind, src.clone()),
+            Pattern::DataIntro(_, _) => CheckedPattern::DataIntro(src.span()),
+            Pattern::TupleIntro(span, patterns) => {
+                CheckedPattern::TupleIntro(*span, patterns.clone())
+            },
         }
     }
 }
@@ -243,7 +683,7 @@ fn check_param_app_mode<'param, 'file>(
         (IntroParam::Explicit(pattern), AppMode::Explicit) => Ok(CheckedPattern::from(pattern)),
         (IntroParam::Implicit(_, intro_label, pattern), AppMode::Implicit(ty_label))
         | (IntroParam::Instance(_, intro_label, pattern), AppMode::Instance(ty_label))
-            if intro_label.slice == ty_label.0 =>
+            if intro_label.slice == ty_label.as_str() =>
         {
             match pattern {
                 None => Ok(CheckedPattern::Var(Some(intro_label.clone()))),
@@ -306,7 +746,7 @@ fn synth_clause_body(
 /// Finish elaborating the patterns into a case tree.
 fn done(
     scrutinees: Vec<(Rc<syntax::Term>, Option<Rc<syntax::Term>>)>,
-    params: Vec<(AppMode, Option<String>)>,
+    params: Vec<(AppMode, Option<Symbol>)>,
     body: Rc<syntax::Term>,
 ) -> Rc<syntax::Term> {
     use mltt_core::syntax::Item::{Declaration, Definition};
@@ -315,7 +755,7 @@ fn done(
 
     for (scrutinee, scrutinee_ty) in scrutinees {
         let doc = DocString::from("");
-        let label = Label("_".to_owned());
+        let label = Label::new("_");
         if let Some(scrutinee_ty) = scrutinee_ty {
             items.push(Declaration(doc.clone(), label.clone(), scrutinee_ty));
         }
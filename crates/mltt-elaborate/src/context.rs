@@ -1,12 +1,16 @@
 //! The elaboration context.
 
 use language_reporting::Diagnostic;
-use mltt_core::{domain, meta, prim, syntax, validate, var, AppMode};
+use mltt_core::intern::Symbol;
+use mltt_core::nbe::{Budget, Unfold};
+use mltt_core::{domain, meta, prim, syntax, validate, var, AppMode, Label};
 use mltt_span::FileSpan;
 use pretty::{BoxDoc, Doc};
 use std::rc::Rc;
 
-use crate::{nbe, unify};
+use crate::generalize::AutoBinds;
+use crate::unify::{self, UnifyError};
+use crate::{nbe, zonk};
 
 /// Local elaboration context.
 ///
@@ -20,6 +24,8 @@ use crate::{nbe, unify};
 pub struct Context {
     /// Primitive entries.
     prims: prim::Env,
+    /// The budget to use during normalization.
+    budget: Budget,
     /// Values to be used during evaluation.
     values: var::Env<Rc<domain::Value>>,
     /// Types of the entries in the context.
@@ -38,6 +44,9 @@ pub struct Context {
     ///
     /// This is used for making spines for fresh metas.
     bound_levels: im::Vector<var::Level>,
+    /// Names that are bound automatically when they are not found in the
+    /// context, while elaborating the type of an `auto` declaration.
+    auto_binds: Option<Rc<AutoBinds>>,
 }
 
 impl Context {
@@ -45,11 +54,36 @@ impl Context {
     pub fn empty() -> Context {
         Context {
             prims: prim::Env::new(),
+            budget: Budget::default(),
             values: var::Env::new(),
             tys: var::Env::new(),
             names: var::Env::new(),
             names_to_levels: im::HashMap::new(),
             bound_levels: im::Vector::new(),
+            auto_binds: None,
+        }
+    }
+
+    /// Create a context from the entries that a unification problem was posed
+    /// in, for reporting errors once the problem has been postponed. Names
+    /// can't be looked up in the resulting context, but they are still used
+    /// when pretty printing.
+    pub fn from_entries(
+        prims: prim::Env,
+        budget: Budget,
+        names: var::Env<String>,
+        values: var::Env<Rc<domain::Value>>,
+        tys: var::Env<Rc<domain::Type>>,
+    ) -> Context {
+        Context {
+            prims,
+            budget,
+            values,
+            tys,
+            names,
+            names_to_levels: im::HashMap::new(),
+            bound_levels: im::Vector::new(),
+            auto_binds: None,
         }
     }
 
@@ -58,14 +92,49 @@ impl Context {
         &self.prims
     }
 
+    /// The budget to use during normalization.
+    pub fn budget(&self) -> &Budget {
+        &self.budget
+    }
+
+    /// Set the budget to use during normalization. Clones of the context that
+    /// are made after this will share the budget.
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.budget = budget;
+    }
+
     /// Values to be used during evaluation.
     pub fn values(&self) -> &var::Env<Rc<domain::Value>> {
         &self.values
     }
 
+    /// Types of the entries in the context.
+    pub fn tys(&self) -> &var::Env<Rc<domain::Type>> {
+        &self.tys
+    }
+
+    /// Returns `true` if any parameters are bound in the context.
+    pub fn has_bound_params(&self) -> bool {
+        !self.bound_levels.is_empty()
+    }
+
+    /// Names that are bound automatically when they are not found in the
+    /// context.
+    pub fn auto_binds(&self) -> Option<&Rc<AutoBinds>> {
+        self.auto_binds.as_ref()
+    }
+
+    /// Bind names automatically when they are not found in the context.
+    pub fn set_auto_binds(&mut self, auto_binds: Rc<AutoBinds>) {
+        self.auto_binds = Some(auto_binds);
+    }
+
     /// Convert the context into a validation context.
     pub fn validation_context(&self) -> validate::Context {
-        validate::Context::new(self.prims.clone(), self.values.clone(), self.tys.clone())
+        let mut context =
+            validate::Context::new(self.prims.clone(), self.values.clone(), self.tys.clone());
+        context.set_budget(self.budget.clone());
+        context
     }
 
     /// Convert the context into a pretty printing environment.
@@ -89,6 +158,9 @@ impl Context {
     }
 
     /// Add a definition to the context.
+    ///
+    /// The value is glued to the definition, allowing it to be kept folded
+    /// when pretty printing.
     pub fn add_defn(
         &mut self,
         name: impl Into<String>,
@@ -100,7 +172,8 @@ impl Context {
 
         let var_level = self.values.size().next_level();
         self.add_name(name, var_level);
-        self.values.add_entry(value);
+        self.values
+            .add_entry(Rc::from(domain::Value::global(var_level, value)));
         self.tys.add_entry(ty);
     }
 
@@ -117,6 +190,20 @@ impl Context {
         value
     }
 
+    /// Add fresh names to the context for binders that were introduced while
+    /// comparing values, until the context is of the given size. The types of
+    /// these binders are not known, so this should only be used for pretty
+    /// printing the values that were found underneath them.
+    pub fn add_unknown_params(&mut self, size: var::Size) {
+        let mut pretty_env = self.pretty_env();
+        while self.values.size().0 < size.0 {
+            let var_level = self.values.size().next_level();
+            self.names.add_entry(pretty_env.fresh_name(None));
+            self.values
+                .add_entry(Rc::from(domain::Value::var(var_level)));
+        }
+    }
+
     /// Add a parameter the context, returning a variable that points to
     /// the introduced binder.
     pub fn add_param(
@@ -136,6 +223,28 @@ impl Context {
         value
     }
 
+    /// Add a recursive definition to the context, returning a variable that
+    /// stands in for it while the bodies of its group are being elaborated.
+    ///
+    /// Unlike parameters, fresh metas are not applied to these variables
+    /// (unless their types depend on them), so that the bodies can be
+    /// strengthened again if they turn out not to be recursive.
+    pub fn add_recursive_defn(
+        &mut self,
+        name: impl Into<String>,
+        ty: Rc<domain::Type>,
+    ) -> Rc<domain::Value> {
+        let name = name.into();
+        log::trace!("add recursive definition: {}", name);
+
+        let var_level = self.values.size().next_level();
+        self.add_name(name, var_level);
+        let value = Rc::from(domain::Value::var(var_level));
+        self.values.add_entry(value.clone());
+        self.tys.add_entry(ty);
+        value
+    }
+
     /// Create a fresh meta and return the meta applied to all of the currently
     /// bound vars.
     pub fn new_meta(
@@ -143,16 +252,69 @@ impl Context {
         metas: &mut meta::Env,
         span: FileSpan,
         ty: Rc<domain::Type>,
-    ) -> Rc<syntax::Term> {
-        let args = self.bound_levels.iter().map(|var_level| {
-            let var_index = self.values().size().index(*var_level);
+    ) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+        let size = self.values().size();
+        let mut param_levels = self.bound_levels.iter().cloned().collect::<Vec<_>>();
+
+        // The type of the meta needs to be in the scope of its parameters, so
+        // we unfold any definitions that it refers to. If it still refers to
+        // recursive definitions, the meta is applied to those as well.
+        let mut ty_term = self.read_back_value(metas, span, &ty)?;
+        let is_param = |levels: &[var::Level], index| levels.contains(&size.level(index));
+        if !ty_term
+            .free_vars(0)
+            .into_iter()
+            .all(|index| is_param(&param_levels, index))
+        {
+            let (prims, budget) = (self.prims(), self.budget());
+            ty_term = nbe::read_back_value(prims, metas, budget, size, Unfold::All, span, &ty)?;
+            for var_index in ty_term.free_vars(0) {
+                if !is_param(&param_levels, var_index) {
+                    param_levels.push(size.level(var_index));
+                }
+            }
+            param_levels.sort();
+        }
+
+        let arity = var::Size(param_levels.len() as u32);
+        let param_ty = {
+            let ty_term = ty_term.rename_free_vars(0, |var_index| {
+                let position = param_levels.binary_search(&size.level(var_index));
+                arity.index(var::Level(position.expect("parameter not found") as u32))
+            });
+            let values = zonk::neutral_env(arity);
+            nbe::eval_term(self.prims(), metas, self.budget(), &values, span, &ty_term)?
+        };
+
+        let args = param_levels.iter().map(|var_level| {
+            let var_index = size.index(*var_level);
             Rc::from(syntax::Term::var(var_index))
         });
 
-        args.fold(
-            Rc::from(syntax::Term::Meta(metas.add_unsolved(span, ty))),
+        let term = args.fold(
+            Rc::from(syntax::Term::Meta(
+                metas.add_unsolved(span, arity, param_ty),
+            )),
             |acc, arg| Rc::from(syntax::Term::FunElim(acc, AppMode::Explicit, arg)),
-        )
+        );
+
+        // Metas that stand for types in universes of an unknown level need to
+        // have the universes of their solutions checked, so that the level can
+        // be inferred
+        if let domain::Value::Universe(level) = self.force_value(metas, span, &ty)?.as_ref() {
+            if level.as_constant().is_none() {
+                metas.add_universe_problem(meta::UniverseProblem {
+                    span,
+                    names: self.names.clone(),
+                    values: self.values.clone(),
+                    tys: self.tys.clone(),
+                    term: term.clone(),
+                    universe: ty,
+                });
+            }
+        }
+
+        Ok(term)
     }
 
     /// Lookup the de-bruijn index and the type annotation of a binder in the
@@ -165,6 +327,15 @@ impl Context {
         Some((var_index, ty))
     }
 
+    /// Lookup the name that refers to the binder at the given level, if it
+    /// has not been shadowed.
+    pub fn lookup_name(&self, var_level: var::Level) -> Option<&str> {
+        self.names_to_levels
+            .iter()
+            .find(|(_, level)| **level == var_level)
+            .map(|(name, _)| name.as_str())
+    }
+
     /// Apply a closure to an argument.
     pub fn app_closure(
         &self,
@@ -172,7 +343,7 @@ impl Context {
         closure: &domain::AppClosure,
         arg: Rc<domain::Value>,
     ) -> Result<Rc<domain::Value>, Diagnostic<FileSpan>> {
-        nbe::app_closure(self.prims(), metas, closure, arg)
+        nbe::app_closure(self.prims(), metas, self.budget(), closure, arg)
     }
 
     /// Evaluate a term using the evaluation environment
@@ -182,17 +353,34 @@ impl Context {
         span: impl Into<Option<FileSpan>>,
         term: &Rc<syntax::Term>,
     ) -> Result<Rc<domain::Value>, Diagnostic<FileSpan>> {
-        nbe::eval_term(self.prims(), metas, self.values(), span, term)
+        nbe::eval_term(
+            self.prims(),
+            metas,
+            self.budget(),
+            self.values(),
+            span,
+            term,
+        )
     }
 
-    /// Read a value back into the core syntax, normalizing as required.
+    /// Read a value back into the core syntax, normalizing as required, but
+    /// keeping global definitions folded.
     pub fn read_back_value(
         &self,
         metas: &meta::Env,
         span: impl Into<Option<FileSpan>>,
         value: &Rc<domain::Value>,
     ) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
-        nbe::read_back_value(self.prims(), metas, self.values().size(), span, value)
+        let size = self.values().size();
+        nbe::read_back_value(
+            self.prims(),
+            metas,
+            self.budget(),
+            size,
+            Unfold::None,
+            span,
+            value,
+        )
     }
 
     /// Fully normalize a term by first evaluating it, then reading it back.
@@ -202,21 +390,43 @@ impl Context {
         span: impl Into<Option<FileSpan>>,
         term: &Rc<syntax::Term>,
     ) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
-        nbe::normalize_term(self.prims(), metas, self.values(), span, term)
+        nbe::normalize_term(
+            self.prims(),
+            metas,
+            self.budget(),
+            self.values(),
+            span,
+            term,
+        )
     }
 
     /// Evaluate a value further, if it's now possible due to updates made to the
-    /// metavariable solutions.
+    /// metavariable solutions, unfolding any global definitions in head position.
     pub fn force_value(
         &self,
         metas: &meta::Env,
         span: impl Into<Option<FileSpan>>,
         value: &Rc<domain::Value>,
     ) -> Result<Rc<domain::Value>, Diagnostic<FileSpan>> {
-        nbe::force_value(self.prims(), metas, span, value)
+        nbe::force_value(self.prims(), metas, self.budget(), span, value)
+    }
+
+    /// Remove the `amount` innermost entries from the scope of a level,
+    /// returning `None` if any of them are referred to in the level.
+    pub fn strengthen_level(
+        &self,
+        metas: &meta::Env,
+        amount: u32,
+        level: &domain::Level,
+    ) -> Result<Option<domain::Level>, Diagnostic<FileSpan>> {
+        let size = self.values().size();
+        nbe::strengthen_level(self.prims(), metas, self.budget(), size, amount, level)
     }
 
     /// Expect that `ty1` is a subtype of `ty2` in the current context
+    ///
+    /// If unification is blocked on an unsolved metavariable, the problem is
+    /// postponed until more metavariables have been solved.
     pub fn unify_values(
         &self,
         metas: &mut meta::Env,
@@ -224,7 +434,50 @@ impl Context {
         value1: &Rc<domain::Value>,
         value2: &Rc<domain::Value>,
     ) -> Result<(), Diagnostic<FileSpan>> {
-        unify::unify_values(self.prims(), metas, self.values(), span, value1, value2)
+        match unify::unify_values(
+            self.prims(),
+            metas,
+            self.budget(),
+            self.values(),
+            span,
+            value1,
+            value2,
+        ) {
+            Ok(()) => unify::retry_postponed(self.prims(), metas, self.budget()),
+            Err(UnifyError::Blocked) => {
+                log::trace!("postponing unification problem");
+
+                metas.add_postponed(meta::Problem {
+                    span,
+                    names: self.names.clone(),
+                    values: self.values.clone(),
+                    tys: self.tys.clone(),
+                    value1: value1.clone(),
+                    value2: value2.clone(),
+                });
+
+                Ok(())
+            },
+            Err(UnifyError::Mismatch(mismatch)) => Err(unify::mismatch_to_diagnostic(
+                self, metas, span, value1, value2, &mismatch,
+            )),
+            Err(UnifyError::Failed(diagnostic)) => Err(diagnostic),
+        }
+    }
+
+    /// Report any postponed unification problems that could not be solved.
+    pub fn check_postponed(&self, metas: &meta::Env) -> Result<(), Diagnostic<FileSpan>> {
+        self.check_postponed_since(metas, meta::ProblemId::default())
+    }
+
+    /// Report any unification problems that were postponed since the problem
+    /// with the given id, and that could not be solved.
+    pub fn check_postponed_since(
+        &self,
+        metas: &meta::Env,
+        since: meta::ProblemId,
+    ) -> Result<(), Diagnostic<FileSpan>> {
+        unify::check_postponed(self.prims(), metas, self.budget(), since)
     }
 
     /// Convert a term to a pretty printable document.
@@ -277,6 +530,178 @@ impl Default for Context {
         context.add_defn("S64", Rc::from(Value::literal_ty(LitType::S64)), u0.clone());
         context.add_defn("F32", Rc::from(Value::literal_ty(LitType::F32)), u0.clone());
         context.add_defn("F64", Rc::from(Value::literal_ty(LitType::F64)), u0.clone());
+        context.add_defn("Level", Rc::from(Value::LevelType), u0.clone());
+
+        // Propositional equality
+        {
+            use mltt_core::syntax::Term;
+
+            let metas = meta::Env::new();
+            let eval = |term: Rc<Term>| {
+                mltt_core::nbe::eval_term(
+                    &prim::Env::new(),
+                    &metas,
+                    &mltt_core::nbe::Budget::default(),
+                    &var::Env::new(),
+                    &term,
+                )
+                .unwrap()
+            };
+            let var = |index: u32| Rc::from(Term::var(index));
+            let level_ty = || Rc::from(Term::LevelType);
+            let universe = |index: u32| Rc::from(Term::Universe(var(index)));
+            let implicit = |name: &str| AppMode::Implicit(Label::new(name));
+            let fun_ty = |app_mode, name: Option<&str>, param_ty, body_ty| {
+                let name_hint = name.map(Symbol::intern);
+                Rc::from(Term::FunType(app_mode, name_hint, param_ty, body_ty))
+            };
+            let fun_intro = |app_mode, name: &str, body| {
+                Rc::from(Term::FunIntro(app_mode, Some(Symbol::intern(name)), body))
+            };
+            let app = |fun, arg| Rc::from(Term::FunElim(fun, AppMode::Explicit, arg));
+            let eq_ty = |ty, lhs, rhs| Rc::from(Term::EqType(ty, lhs, rhs));
+
+            // Eq : Fun {l : Level} {A : Type^l} -> A -> A -> Type^l
+            let eq_ty_ty = fun_ty(
+                implicit("l"),
+                Some("l"),
+                level_ty(),
+                fun_ty(
+                    implicit("A"),
+                    Some("A"),
+                    universe(0),
+                    fun_ty(
+                        AppMode::Explicit,
+                        None,
+                        var(0),
+                        fun_ty(AppMode::Explicit, None, var(1), universe(3)),
+                    ),
+                ),
+            );
+            let eq = fun_intro(
+                implicit("l"),
+                "l",
+                fun_intro(
+                    implicit("A"),
+                    "A",
+                    fun_intro(
+                        AppMode::Explicit,
+                        "a",
+                        fun_intro(AppMode::Explicit, "b", eq_ty(var(2), var(1), var(0))),
+                    ),
+                ),
+            );
+            context.add_defn("Eq", eval(eq), eval(eq_ty_ty));
+
+            // refl : Fun {l : Level} {A : Type^l} {a : A} -> Eq a a
+            let refl_ty = fun_ty(
+                implicit("l"),
+                Some("l"),
+                level_ty(),
+                fun_ty(
+                    implicit("A"),
+                    Some("A"),
+                    universe(0),
+                    fun_ty(
+                        implicit("a"),
+                        Some("a"),
+                        var(0),
+                        eq_ty(var(1), var(0), var(0)),
+                    ),
+                ),
+            );
+            let refl = fun_intro(
+                implicit("l"),
+                "l",
+                fun_intro(
+                    implicit("A"),
+                    "A",
+                    fun_intro(implicit("a"), "a", Rc::from(Term::EqIntro)),
+                ),
+            );
+            context.add_defn("refl", eval(refl), eval(refl_ty));
+
+            // J : Fun {l : Level} {A : Type^l} {a : A}
+            //     (P : Fun (b : A) -> Eq a b -> Type^l) -> P a refl ->
+            //     Fun {b : A} (p : Eq a b) -> P b p
+            let motive_ty = fun_ty(
+                AppMode::Explicit,
+                Some("b"),
+                var(1),
+                fun_ty(
+                    AppMode::Explicit,
+                    None,
+                    eq_ty(var(2), var(1), var(0)),
+                    universe(4),
+                ),
+            );
+            let j_ty = fun_ty(
+                implicit("l"),
+                Some("l"),
+                level_ty(),
+                fun_ty(
+                    implicit("A"),
+                    Some("A"),
+                    universe(0),
+                    fun_ty(
+                        implicit("a"),
+                        Some("a"),
+                        var(0),
+                        fun_ty(
+                            AppMode::Explicit,
+                            Some("P"),
+                            motive_ty,
+                            fun_ty(
+                                AppMode::Explicit,
+                                None,
+                                app(app(var(0), var(1)), Rc::from(Term::EqIntro)),
+                                fun_ty(
+                                    implicit("b"),
+                                    Some("b"),
+                                    var(3),
+                                    fun_ty(
+                                        AppMode::Explicit,
+                                        Some("p"),
+                                        eq_ty(var(4), var(3), var(0)),
+                                        app(app(var(3), var(1)), var(0)),
+                                    ),
+                                ),
+                            ),
+                        ),
+                    ),
+                ),
+            );
+            let j = fun_intro(
+                implicit("l"),
+                "l",
+                fun_intro(
+                    implicit("A"),
+                    "A",
+                    fun_intro(
+                        implicit("a"),
+                        "a",
+                        fun_intro(
+                            AppMode::Explicit,
+                            "P",
+                            fun_intro(
+                                AppMode::Explicit,
+                                "r",
+                                fun_intro(
+                                    implicit("b"),
+                                    "b",
+                                    fun_intro(
+                                        AppMode::Explicit,
+                                        "p",
+                                        Rc::from(Term::EqElim(var(0), var(3), var(2))),
+                                    ),
+                                ),
+                            ),
+                        ),
+                    ),
+                ),
+            );
+            context.add_defn("J", eval(j), eval(j_ty));
+        }
 
         context.prims = prim::Env::default();
 
@@ -352,4 +777,26 @@ mod test {
 
         assert_eq!(context.lookup_binder("x").unwrap().1, &ty1);
     }
+
+    #[test]
+    fn add_defn_glued() {
+        use mltt_core::domain::Value;
+
+        let mut context = Context::empty();
+        let metas = meta::Env::new();
+
+        let u0 = Rc::from(Value::universe(0));
+        let u1 = Rc::from(Value::universe(1));
+
+        context.add_defn("A", u0.clone(), u1.clone());
+
+        let (var_index, _) = context.lookup_binder("A").unwrap();
+        let value = context.values().lookup_entry(var_index).unwrap().clone();
+
+        assert_eq!(
+            context.read_back_value(&metas, None, &value).unwrap(),
+            Rc::from(syntax::Term::var(0)),
+        );
+        assert_eq!(context.force_value(&metas, None, &value).unwrap(), u0);
+    }
 }
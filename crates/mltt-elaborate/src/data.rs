@@ -0,0 +1,303 @@
+//! Elaboration of inductive data type declarations.
+
+use language_reporting::{Diagnostic, Label as DiagnosticLabel};
+use mltt_concrete::Data;
+use mltt_core::intern::Symbol;
+use mltt_core::nbe::Unfold;
+use mltt_core::{domain, meta, syntax, var, AppMode, Label};
+use mltt_span::FileSpan;
+use std::rc::Rc;
+
+use super::{concat_docs, nbe, synth_type_params, synth_universe, Context};
+
+/// Check an inductive data type declaration, adding its type former and its
+/// constructors to the context.
+///
+/// Returns the elaborated definitions of the type former and constructors.
+pub fn check_data(
+    context: &mut Context,
+    metas: &mut meta::Env,
+    concrete_data: &Data<'_>,
+) -> Result<Vec<syntax::Item>, Diagnostic<FileSpan>> {
+    use mltt_core::syntax::Item::Definition;
+
+    let label = Label::new(concrete_data.label.slice);
+
+    // Elaborate the type of the type former
+    let former_ty = {
+        let mut param_context = context.clone();
+        let (params, _) = synth_type_params(&mut param_context, metas, &concrete_data.params)?;
+        let (body_ty, _) = synth_data_body_ty(&param_context, metas, concrete_data)?;
+
+        params
+            .into_iter()
+            .rev()
+            .fold(body_ty, |acc, (app_mode, param_name, param_ty)| {
+                let name_hint = Some(Symbol::intern(param_name.slice));
+                Rc::from(syntax::Term::FunType(app_mode, name_hint, param_ty, acc))
+            })
+    };
+    let former_ty_value = context.eval_term(metas, concrete_data.label.span(), &former_ty)?;
+
+    // The constructors are elaborated with the type former and the parameters
+    // of the data type in scope
+    let self_level = context.values().size().next_level();
+    let mut intro_context = context.clone();
+    intro_context.add_param(concrete_data.label, former_ty_value.clone());
+    let (params, _) = synth_type_params(&mut intro_context, metas, &concrete_data.params)?;
+    let param_values = (0..params.len() as u32)
+        .map(|offset| Rc::from(domain::Value::var(self_level + (offset + 1))))
+        .collect::<Vec<_>>();
+
+    // Find the universe that the data type lives in, along with the number of
+    // indices that it takes. This is done in the same context as the
+    // constructors, so that the level can refer to the parameters.
+    let (level, index_count) = {
+        let (body_ty, span) = synth_data_body_ty(&intro_context, metas, concrete_data)?;
+        let body_ty_value = intro_context.eval_term(metas, span, &body_ty)?;
+        synth_indices(&intro_context, metas, span, &body_ty_value)?
+    };
+
+    let mut intros = Vec::with_capacity(concrete_data.intros.len());
+    for concrete_intro in &concrete_data.intros {
+        let intro_label = Label::new(concrete_intro.label.slice);
+        let intro_span = concrete_intro.body_ty.span();
+
+        log::trace!("checking constructor:\t\t{}", intro_label);
+
+        let (intro_ty, intro_level) =
+            synth_universe(&intro_context, metas, &concrete_intro.body_ty)?;
+        if !intro_level.is_le(&level) {
+            return Err(
+                Diagnostic::new_error("constructor does not fit in its data type").with_label(
+                    DiagnosticLabel::new_primary(intro_span).with_message(format!(
+                        "the fields of this constructor are too large for `{}`",
+                        label,
+                    )),
+                ),
+            );
+        }
+
+        // Check that the constructor returns the data type, applied to its
+        // parameters and some indices
+        let intro_ty_value = intro_context.eval_term(metas, intro_span, &intro_ty)?;
+        let mut field_context = intro_context.clone();
+        let mut return_ty = intro_ty_value.clone();
+        while let domain::Value::FunType(_, _, param_ty, body_ty) = field_context
+            .force_value(metas, intro_span, &return_ty)?
+            .as_ref()
+        {
+            let param = field_context.add_fresh_param(param_ty.clone());
+            return_ty = field_context.app_closure(metas, body_ty, param)?;
+        }
+        match field_context
+            .force_value(metas, intro_span, &return_ty)?
+            .as_ref()
+        {
+            domain::Value::Neutral(domain::Head::Var(var_level), spine)
+                if *var_level == self_level
+                    && spine.len() == params.len() + index_count
+                    && Iterator::zip(spine.iter(), param_values.iter()).all(|(elim, param)| {
+                        match elim {
+                            domain::Elim::Fun(_, arg) => arg == param,
+                            _ => false,
+                        }
+                    }) => {},
+            _ => {
+                return Err(
+                    Diagnostic::new_error("unexpected constructor type").with_label(
+                        DiagnosticLabel::new_primary(intro_span).with_message(format!(
+                            "constructors must return `{}`, applied to its parameters",
+                            label,
+                        )),
+                    ),
+                );
+            },
+        }
+
+        // Check that the data type only occurs strictly positively in the
+        // fields of the constructor
+        let normal_intro_ty = nbe::read_back_value(
+            intro_context.prims(),
+            metas,
+            intro_context.budget(),
+            intro_context.values().size(),
+            Unfold::All,
+            intro_span,
+            &intro_ty_value,
+        )?;
+        if !is_strictly_positive_intro(var::Index(params.len() as u32), &normal_intro_ty) {
+            return Err(
+                Diagnostic::new_error("non strictly positive occurrence of data type").with_label(
+                    DiagnosticLabel::new_primary(intro_span).with_message(format!(
+                        "`{}` must not appear to the left of a function arrow or in an index",
+                        label,
+                    )),
+                ),
+            );
+        }
+
+        // The constructor takes the parameters of the data type as implicit
+        // arguments
+        let intro_ty = params
+            .iter()
+            .rev()
+            .fold(intro_ty, |acc, (_, param_name, param_ty)| {
+                let app_mode = AppMode::Implicit(Label::new(param_name.slice));
+                let name_hint = Some(Symbol::intern(param_name.slice));
+                Rc::from(syntax::Term::FunType(
+                    app_mode,
+                    name_hint,
+                    param_ty.clone(),
+                    acc,
+                ))
+            });
+
+        let docs = concat_docs(&concrete_intro.docs);
+        intros.push((docs, intro_label, intro_ty));
+    }
+
+    let data = Rc::from(syntax::Data {
+        label: label.clone(),
+        param_count: params.len(),
+        ty: former_ty,
+        intros,
+    });
+
+    log::trace!("elaborated data type:\t{}\t= {:?}", label, data);
+
+    let closure = domain::DataClosure::new(data.clone(), context.values().clone());
+    let mut core_items = Vec::with_capacity(data.intros.len() + 1);
+
+    let docs = concat_docs(&concrete_data.docs);
+    let term = Rc::from(syntax::Term::DataType(data.clone()));
+    let value = context.eval_term(metas, concrete_data.span, &term)?;
+    context.add_defn(label.to_string(), value, former_ty_value);
+    core_items.push(Definition(docs, label, term));
+
+    for (index, (docs, intro_label, _)) in data.intros.iter().enumerate() {
+        let intro_ty = nbe::intro_ty(
+            context.prims(),
+            metas,
+            context.budget(),
+            &closure,
+            intro_label,
+        )?;
+        // Move the constructor past the definitions that were added before it
+        let term =
+            syntax::Term::DataIntro(data.clone(), intro_label.clone()).shift(index as u32 + 1);
+        let value = context.eval_term(metas, concrete_data.span, &term)?;
+
+        context.add_defn(intro_label.to_string(), value, intro_ty);
+        core_items.push(Definition(docs.clone(), intro_label.clone(), term));
+    }
+
+    Ok(core_items)
+}
+
+/// Elaborate the type that follows the parameters of a data type declaration,
+/// defaulting to `Type` if it was omitted.
+fn synth_data_body_ty(
+    context: &Context,
+    metas: &mut meta::Env,
+    concrete_data: &Data<'_>,
+) -> Result<(Rc<syntax::Term>, FileSpan), Diagnostic<FileSpan>> {
+    match &concrete_data.body_ty {
+        None => Ok((
+            Rc::from(syntax::Term::universe(0)),
+            concrete_data.label.span(),
+        )),
+        Some(concrete_body_ty) => {
+            let (body_ty, _) = synth_universe(context, metas, concrete_body_ty)?;
+            Ok((body_ty, concrete_body_ty.span()))
+        },
+    }
+}
+
+/// Find the universe at the end of the type of a type former, returning its
+/// level, along with the number of indices that are taken before reaching it.
+///
+/// The level of the universe must not depend on the indices.
+fn synth_indices(
+    context: &Context,
+    metas: &meta::Env,
+    span: FileSpan,
+    ty: &Rc<domain::Type>,
+) -> Result<(domain::Level, usize), Diagnostic<FileSpan>> {
+    let mut context = context.clone();
+    let mut ty = ty.clone();
+    let mut index_count = 0;
+
+    loop {
+        match context.force_value(metas, span, &ty)?.as_ref() {
+            domain::Value::FunType(_, _, param_ty, body_ty) => {
+                let param = context.add_fresh_param(param_ty.clone());
+                ty = context.app_closure(metas, body_ty, param)?;
+                index_count += 1;
+            },
+            domain::Value::Universe(level) => {
+                return match context.strengthen_level(metas, index_count as u32, level)? {
+                    Some(level) => Ok((level, index_count)),
+                    None => Err(
+                        Diagnostic::new_error("universe depends on indices").with_label(
+                            DiagnosticLabel::new_primary(span).with_message(
+                                "the universe level of a data type must not depend on its indices",
+                            ),
+                        ),
+                    ),
+                };
+            },
+            _ => {
+                return Err(Diagnostic::new_error("type expected").with_label(
+                    DiagnosticLabel::new_primary(span)
+                        .with_message("data types must be in a universe"),
+                ));
+            },
+        }
+    }
+}
+
+/// Split a term into the head of a spine of function eliminations, and the
+/// arguments that were applied to it.
+fn split_spine(term: &Rc<syntax::Term>) -> (&Rc<syntax::Term>, Vec<&Rc<syntax::Term>>) {
+    let mut head = term;
+    let mut args = Vec::new();
+    while let syntax::Term::FunElim(fun, _, arg) = head.as_ref() {
+        head = fun;
+        args.push(arg);
+    }
+    args.reverse();
+    (head, args)
+}
+
+/// Check that the data type at `self_index` only occurs strictly positively in
+/// the fields of a normalized constructor type, and that it does not occur in
+/// the arguments of the type that the constructor returns.
+fn is_strictly_positive_intro(self_index: var::Index, intro_ty: &Rc<syntax::Term>) -> bool {
+    match intro_ty.as_ref() {
+        syntax::Term::FunType(_, _, field_ty, body_ty) => {
+            is_strictly_positive(self_index, field_ty)
+                && is_strictly_positive_intro(self_index + 1, body_ty)
+        },
+        _ => {
+            let (_, args) = split_spine(intro_ty);
+            args.iter().all(|arg| !arg.has_free_var(self_index))
+        },
+    }
+}
+
+/// Check that the data type at `self_index` only occurs strictly positively in
+/// the type of a field.
+fn is_strictly_positive(self_index: var::Index, field_ty: &Rc<syntax::Term>) -> bool {
+    match field_ty.as_ref() {
+        syntax::Term::FunType(_, _, param_ty, body_ty) => {
+            !param_ty.has_free_var(self_index) && is_strictly_positive(self_index + 1, body_ty)
+        },
+        _ => match split_spine(field_ty) {
+            (head, args) if head.as_ref() == &syntax::Term::Var(self_index) => {
+                args.iter().all(|arg| !arg.has_free_var(self_index))
+            },
+            _ => !field_ty.has_free_var(self_index),
+        },
+    }
+}
@@ -0,0 +1,220 @@
+//! Generalization of `auto` declarations.
+//!
+//! While elaborating the type of an `auto` declaration, names that are not
+//! bound in the context are bound to fresh metavariables. Once the type has
+//! been elaborated, any metavariables that remain unsolved are turned into
+//! implicit parameters, ordered so that each parameter comes after the
+//! parameters that its type depends on. For example:
+//!
+//! ```text
+//! auto const : A -> B -> A;
+//! ```
+//!
+//! is elaborated to:
+//!
+//! ```text
+//! const : Fun {l : Level} {A : Type^l} {l1 : Level} {B : Type^l1} -> A -> B -> A;
+//! ```
+
+use language_reporting::{Diagnostic, Label as DiagnosticLabel};
+use mltt_concrete::{SpannedString, Term};
+use mltt_core::intern::Symbol;
+use mltt_core::{domain, meta, syntax, var, AppMode, Label};
+use mltt_span::FileSpan;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{new_ty_meta, synth_universe, zonk, Context};
+
+/// Names that have been bound automatically while elaborating the type of an
+/// `auto` declaration.
+#[derive(Debug)]
+pub struct AutoBinds {
+    /// The context that the declaration is being elaborated in.
+    context: Context,
+    /// The names that have been bound so far, along with the metavariables
+    /// that stand for them and their types.
+    names: RefCell<Vec<(String, meta::Index, Rc<domain::Type>)>>,
+}
+
+impl AutoBinds {
+    /// Lookup the metavariable that stands for an automatically bound name,
+    /// creating a fresh one if the name has not been seen before.
+    ///
+    /// Returns the metavariable and its type.
+    pub fn lookup(
+        &self,
+        metas: &mut meta::Env,
+        name: &SpannedString<'_>,
+    ) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
+        let existing = self.names.borrow().iter().find_map(|(n, meta_index, ty)| {
+            if n == name.slice {
+                Some((*meta_index, ty.clone()))
+            } else {
+                None
+            }
+        });
+
+        let (meta_index, ty) = match existing {
+            Some(existing) => existing,
+            None => {
+                log::trace!("auto bind: {}", name);
+
+                let (_, ty) = new_ty_meta(&self.context, metas, name.span())?;
+                // No parameters are bound in the context of the declaration,
+                // so the metavariable does not need to be applied to a spine
+                let meta_index = metas.add_unsolved(name.span(), var::Size(0), ty.clone());
+                let entry = (name.slice.to_owned(), meta_index, ty.clone());
+                self.names.borrow_mut().push(entry);
+                (meta_index, ty)
+            },
+        };
+
+        Ok((Rc::from(syntax::Term::Meta(meta_index)), ty))
+    }
+
+    /// Lookup the name that was automatically bound to a metavariable.
+    fn lookup_name(&self, meta_index: meta::Index) -> Option<String> {
+        let names = self.names.borrow();
+        let (name, _, _) = names.iter().find(|(_, m, _)| *m == meta_index)?;
+        Some(name.clone())
+    }
+}
+
+/// Elaborate the type of an `auto` declaration, turning unbound names and
+/// unsolved metavariables into implicit parameters.
+///
+/// Returns the elaborated type.
+pub fn synth_auto_ty(
+    context: &Context,
+    metas: &mut meta::Env,
+    concrete_ty: &Term<'_>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    let span = concrete_ty.span();
+
+    if context.has_bound_params() {
+        return Err(
+            Diagnostic::new_error("unsupported auto declaration").with_label(
+                DiagnosticLabel::new_primary(span)
+                    .with_message("auto declarations cannot refer to local parameters"),
+            ),
+        );
+    }
+
+    let auto_binds = Rc::new(AutoBinds {
+        context: context.clone(),
+        names: RefCell::new(Vec::new()),
+    });
+    let (ty, _) = {
+        let mut context = context.clone();
+        context.set_auto_binds(auto_binds.clone());
+        synth_universe(&context, metas, concrete_ty)?
+    };
+
+    let (ty, unsolved) = zonk::zonk_term_unsolved(context.prims(), metas, context.budget(), &ty)?;
+
+    let mut params = Vec::new();
+    for meta_index in unsolved {
+        add_param(context, metas, span, meta_index, &mut params)?;
+    }
+
+    let meta_indices = params.iter().map(|(m, _)| *m).collect::<Vec<_>>();
+    let abstract_error = || {
+        Diagnostic::new_error("could not generalize type").with_label(
+            DiagnosticLabel::new_primary(span)
+                .with_message("the type contains unknown terms that depend on local parameters"),
+        )
+    };
+
+    let mut used_names = Vec::with_capacity(params.len());
+    let mut param_tys = Vec::with_capacity(params.len());
+    for (position, (meta_index, param_ty)) in params.iter().enumerate() {
+        let name = match auto_binds.lookup_name(*meta_index) {
+            Some(name) => name,
+            None => {
+                let base = match param_ty.as_ref() {
+                    syntax::Term::LevelType => "l",
+                    _ => "a",
+                };
+                fresh_name(context, &auto_binds, &used_names, base)
+            },
+        };
+        let param_ty = param_ty
+            .abstract_metas(&meta_indices[..position])
+            .ok_or_else(abstract_error)?;
+
+        used_names.push(name.clone());
+        param_tys.push((name, param_ty));
+    }
+
+    let body_ty = ty
+        .abstract_metas(&meta_indices)
+        .ok_or_else(abstract_error)?;
+
+    Ok(param_tys
+        .into_iter()
+        .rev()
+        .fold(body_ty, |acc, (name, param_ty)| {
+            let app_mode = AppMode::Implicit(Label::new(&name));
+            let name_hint = Some(Symbol::intern(&name));
+            Rc::from(syntax::Term::FunType(app_mode, name_hint, param_ty, acc))
+        }))
+}
+
+/// Add an unsolved metavariable to the parameters, after the metavariables
+/// that its type depends on.
+fn add_param(
+    context: &Context,
+    metas: &meta::Env,
+    span: FileSpan,
+    meta_index: meta::Index,
+    params: &mut Vec<(meta::Index, Rc<syntax::Term>)>,
+) -> Result<(), Diagnostic<FileSpan>> {
+    if params.iter().any(|(m, _)| *m == meta_index) {
+        return Ok(());
+    }
+
+    let ty = match metas.lookup_solution(meta_index) {
+        Some((_, meta::Solution::Unsolved, _, ty)) => ty.clone(),
+        Some((_, meta::Solution::Solved(_), _, _)) | None => {
+            let message = format!(
+                "generalize: unsolved metavariable not found: `{}`",
+                meta_index
+            );
+            return Err(Diagnostic::new_bug(message));
+        },
+    };
+
+    let ty = context.read_back_value(metas, span, &ty)?;
+    let (ty, unsolved) = zonk::zonk_term_unsolved(context.prims(), metas, context.budget(), &ty)?;
+    for dependency in unsolved {
+        add_param(context, metas, span, dependency, params)?;
+    }
+
+    params.push((meta_index, ty));
+
+    Ok(())
+}
+
+/// Choose a name for a generalized metavariable that does not clash with the
+/// other parameters, or with the names in the context.
+fn fresh_name(
+    context: &Context,
+    auto_binds: &AutoBinds,
+    used_names: &[String],
+    base: &str,
+) -> String {
+    let is_used = |name: &str| {
+        used_names.iter().any(|used| used == name)
+            || auto_binds.names.borrow().iter().any(|(n, _, _)| n == name)
+            || context.lookup_binder(name).is_some()
+    };
+
+    (0..)
+        .map(|count| match count {
+            0 => base.to_owned(),
+            _ => format!("{}{}", base, count),
+        })
+        .find(|name| !is_used(name))
+        .expect("ran out of fresh names")
+}
@@ -0,0 +1,509 @@
+//! Incremental re-checking of modules.
+//!
+//! The top-level items of a module are checked in the same groups as in
+//! `check_module`. Successfully elaborated groups are cached, keyed on their
+//! source text. Each cached group also records the definitions and forward
+//! declarations that it refers to, along with the revisions at which they
+//! were elaborated. When a module is checked again, a group is only
+//! re-elaborated if its source text changed, or if one of the items that it
+//! refers to was re-elaborated - otherwise its cached items are added to the
+//! context directly.
+//!
+//! The definitions that a group refers to are looked up by their labels, so
+//! inserting or removing unrelated definitions before a group does not cause
+//! it to be re-checked. Instead the variables in its cached items are renamed
+//! to point to the new positions of the definitions. Groups starting with an
+//! `auto` declaration bind the names that are not in scope, so these are still
+//! re-checked whenever the labels in scope before them change.
+
+use language_reporting::Diagnostic;
+use mltt_concrete::Item;
+use mltt_core::nbe::Unfold;
+use mltt_core::{meta, syntax, var, Label};
+use mltt_span::{File, FileSpan};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use crate::{check_item_group, item_group_len, item_labels, nbe, zonk};
+use crate::{Context, ForwardDeclarations};
+
+/// The revision at which a group of items was elaborated.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct Revision(u64);
+
+/// A definition added to the context by a group, identified by the revision
+/// of the group, and its position in the group.
+type DefnId = (Revision, u32);
+
+/// The key that cached groups are looked up with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    /// The source text of the group, including its documentation.
+    source: String,
+    /// A hash of the labels of the definitions in scope before the group, if
+    /// it starts with an `auto` declaration.
+    scope: Option<u64>,
+}
+
+/// A definition that a cached group refers to.
+#[derive(Debug, Clone)]
+struct DefnDep {
+    /// The level of the definition when the group was elaborated.
+    level: var::Level,
+    /// The name that referred to the definition when the group was elaborated,
+    /// or `None` if the definition had been shadowed.
+    name: Option<String>,
+    /// The definition, or `None` if it is in the initial context.
+    id: Option<DefnId>,
+}
+
+/// A cached group of items.
+#[derive(Debug)]
+struct Entry {
+    /// The revision at which the group was elaborated.
+    revision: Revision,
+    /// The size of the context that the group was elaborated in.
+    start_size: var::Size,
+    /// The elaborated items, with all of their metavariables solved.
+    items: Vec<syntax::Item>,
+    /// The types of the definitions in the group, each in the context that the
+    /// definition was added to.
+    defn_tys: Vec<Rc<syntax::Term>>,
+    /// The definitions that the group refers to.
+    defn_deps: Vec<DefnDep>,
+    /// The revisions of the pending forward declarations for the labels in
+    /// the group.
+    declaration_deps: Vec<(String, Option<Revision>)>,
+}
+
+/// Statistics about the last module that was checked.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of groups of items that were elaborated.
+    pub checked: usize,
+    /// The number of groups of items that were reused from the cache.
+    pub reused: usize,
+}
+
+/// A cache of elaborated items, allowing modules to be re-checked
+/// incrementally after they have been edited.
+///
+/// The cache should always be used with the same initial context.
+#[derive(Debug, Default)]
+pub struct Cache {
+    /// The revision to give to the next group that is elaborated.
+    next_revision: u64,
+    /// The cached groups.
+    entries: HashMap<Key, Rc<Entry>>,
+    /// Statistics about the last module that was checked.
+    stats: Stats,
+}
+
+impl Cache {
+    /// Create a new, empty cache.
+    pub fn new() -> Cache {
+        Cache::default()
+    }
+
+    /// Statistics about the last module that was checked.
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Check that this is a valid module, reusing the items that were cached
+    /// when checking previous versions of it.
+    ///
+    /// `file` is the file that the concrete items were parsed from. Returns the
+    /// same module as `check_module` would.
+    pub fn check_module(
+        &mut self,
+        context: &Context,
+        metas: &mut meta::Env,
+        file: &File,
+        concrete_items: &[Item<'_>],
+    ) -> Result<syntax::Module, Diagnostic<FileSpan>> {
+        let mut entries = HashMap::new();
+        let result = self.check_items(&mut entries, context, metas, file, concrete_items);
+
+        match result {
+            // Forget about the groups that are no longer in the module
+            Ok(_) => self.entries = entries,
+            // Hold on to the old groups, in case the rest of the module can be
+            // reused once the error has been fixed
+            Err(_) => self.entries.extend(entries),
+        }
+
+        result
+    }
+
+    fn check_items<'file>(
+        &mut self,
+        entries: &mut HashMap<Key, Rc<Entry>>,
+        context: &Context,
+        metas: &mut meta::Env,
+        file: &File,
+        concrete_items: &[Item<'file>],
+    ) -> Result<syntax::Module, Diagnostic<FileSpan>> {
+        // The local elaboration context
+        let mut context = context.clone();
+        let initial_size = context.values().size();
+        let mut forward_declarations = ForwardDeclarations::new();
+        // The definitions added to the context, starting from the initial
+        // size of the context, along with their levels
+        let mut defn_ids = Vec::<DefnId>::new();
+        let mut defn_levels = HashMap::<DefnId, var::Level>::new();
+        // The revisions of the pending forward declarations
+        let mut declaration_revisions = HashMap::<&'file str, Revision>::new();
+        let mut scope = 0;
+        let mut core_items = Vec::new();
+        // Whether the solutions to the metavariables have been substituted in
+        // all of the elaborated items
+        let mut is_zonked = true;
+
+        self.stats = Stats::default();
+
+        let mut concrete_items = concrete_items;
+        while !concrete_items.is_empty() {
+            let group_len = item_group_len(&forward_declarations, concrete_items);
+            let (group, rest) = concrete_items.split_at(group_len);
+            concrete_items = rest;
+
+            let start_size = context.values().size();
+            let labels = group
+                .iter()
+                .flat_map(item_labels)
+                .map(|label| label.slice)
+                .collect::<Vec<_>>();
+            let declaration_deps = labels
+                .iter()
+                .map(|label| (label.to_string(), declaration_revisions.get(label).cloned()))
+                .collect::<Vec<_>>();
+
+            let key = Key {
+                source: group_source(file, group),
+                scope: match &group[0] {
+                    Item::Declaration(declaration) if declaration.auto.is_some() => Some(scope),
+                    Item::Declaration(_) | Item::Definition(_) | Item::Data(_) => None,
+                },
+            };
+            let cached_entry = self.entries.get(&key).and_then(|entry| {
+                if entry.declaration_deps != declaration_deps {
+                    return None;
+                }
+                let dep_levels = resolve_defn_deps(&context, &defn_levels, &entry.defn_deps)?;
+                Some((entry, dep_levels))
+            });
+
+            let (revision, items) = match cached_entry {
+                Some((entry, dep_levels)) => {
+                    log::debug!("reusing cached items:\t{}", labels.join(", "));
+
+                    let (items, defn_tys) = rename_entry(entry, start_size, &dep_levels);
+                    add_cached_items(
+                        &mut context,
+                        metas,
+                        &mut forward_declarations,
+                        group,
+                        &items,
+                        &defn_tys,
+                    )?;
+                    entries.insert(key, entry.clone());
+                    self.stats.reused += 1;
+
+                    (entry.revision, items)
+                },
+                None => {
+                    log::debug!("checking items:\t{}", labels.join(", "));
+
+                    let start_context = context.clone();
+                    let items =
+                        check_item_group(&mut context, metas, &mut forward_declarations, group)?;
+                    let revision = Revision(self.next_revision);
+                    self.next_revision += 1;
+                    self.stats.checked += 1;
+
+                    match zonk_group(&context, metas, start_size, &items) {
+                        Some((items, defn_tys)) => {
+                            let defn_deps = free_levels(start_size, &items, &defn_tys)
+                                .into_iter()
+                                .map(|level| DefnDep {
+                                    level,
+                                    name: start_context.lookup_name(level).map(str::to_owned),
+                                    id: level
+                                        .0
+                                        .checked_sub(initial_size.0)
+                                        .map(|offset| defn_ids[offset as usize]),
+                                })
+                                .collect();
+                            let entry = Rc::new(Entry {
+                                revision,
+                                start_size,
+                                items: items.clone(),
+                                defn_tys,
+                                defn_deps,
+                                declaration_deps,
+                            });
+                            entries.insert(key, entry);
+
+                            (revision, items)
+                        },
+                        // Some metavariables are still unsolved, so we can't
+                        // cache the group yet
+                        None => {
+                            is_zonked = false;
+                            (revision, items)
+                        },
+                    }
+                },
+            };
+
+            let defn_count = context.values().size().0 - start_size.0;
+            for position in 0..defn_count {
+                let id = (revision, position);
+                defn_ids.push(id);
+                defn_levels.insert(id, start_size.next_level() + position);
+            }
+            match &group[0] {
+                Item::Declaration(declaration) => {
+                    declaration_revisions.insert(declaration.label.slice, revision);
+                },
+                Item::Definition(_) | Item::Data(_) => {
+                    for label in &labels {
+                        declaration_revisions.remove(label);
+                    }
+                },
+            }
+            for item in &items {
+                if let syntax::Item::Definition(_, label, _) = item {
+                    scope = extend_scope(scope, label);
+                }
+            }
+
+            core_items.extend(items);
+        }
+        context.check_postponed(metas)?;
+
+        let mut module = syntax::Module { items: core_items };
+        if !is_zonked {
+            module = zonk::zonk_module(context.prims(), metas, context.budget(), module)?;
+        }
+
+        Ok(syntax::Arena::new().share_module(&module))
+    }
+}
+
+/// Add the label of a definition to the hash of the labels in scope.
+fn extend_scope(scope: u64, label: &Label) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (scope, label.as_str()).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The source text of a group of items, including their documentation.
+fn group_source(file: &File, concrete_items: &[Item<'_>]) -> String {
+    let mut source = String::new();
+    for concrete_item in concrete_items {
+        let docs = match concrete_item {
+            Item::Declaration(declaration) => &declaration.docs,
+            Item::Definition(definition) => &definition.docs,
+            Item::Data(data) => &data.docs,
+        };
+        for doc_line in docs {
+            source.push_str(doc_line.slice);
+        }
+
+        let span = concrete_item.span();
+        source.push_str(&file.contents()[span.start().to_usize()..span.end().to_usize()]);
+        source.push('\n');
+    }
+    source
+}
+
+/// Find the current levels of the definitions that a cached group refers to.
+///
+/// Returns `None` if any of the definitions are no longer in scope, or if
+/// their names now refer to different definitions.
+fn resolve_defn_deps(
+    context: &Context,
+    defn_levels: &HashMap<DefnId, var::Level>,
+    defn_deps: &[DefnDep],
+) -> Option<Vec<var::Level>> {
+    defn_deps
+        .iter()
+        .map(|defn_dep| {
+            let level = match defn_dep.id {
+                None => defn_dep.level,
+                Some(id) => *defn_levels.get(&id)?,
+            };
+            if let Some(name) = &defn_dep.name {
+                let (index, _) = context.lookup_binder(name)?;
+                if context.values().size().level(index) != level {
+                    return None;
+                }
+            }
+            Some(level)
+        })
+        .collect()
+}
+
+/// Rename the variables in the items of a cached group, and in the types of
+/// its definitions, to point to the current levels of the definitions that
+/// the group refers to.
+fn rename_entry(
+    entry: &Entry,
+    start_size: var::Size,
+    dep_levels: &[var::Level],
+) -> (Vec<syntax::Item>, Vec<Rc<syntax::Term>>) {
+    let is_unchanged = start_size == entry.start_size
+        && Iterator::zip(entry.defn_deps.iter(), dep_levels)
+            .all(|(defn_dep, level)| defn_dep.level == *level);
+    if is_unchanged {
+        return (entry.items.clone(), entry.defn_tys.clone());
+    }
+
+    let renamed_levels = Iterator::zip(entry.defn_deps.iter(), dep_levels)
+        .map(|(defn_dep, level)| (defn_dep.level, *level))
+        .collect::<BTreeMap<_, _>>();
+    // Rename a term that is in the scope of `defn_count` definitions in the
+    // group, leaving the variables that point to these definitions alone
+    let rename = |defn_count: u32, term: &Rc<syntax::Term>| {
+        term.rename_free_vars(0, |index| {
+            if index.0 < defn_count {
+                return index;
+            }
+            let level = (entry.start_size + defn_count).level(index);
+            let level = renamed_levels[&level];
+            (start_size + defn_count).index(level)
+        })
+    };
+
+    let mut defn_count = 0;
+    let items = entry
+        .items
+        .iter()
+        .map(|item| match item {
+            syntax::Item::Declaration(docs, label, body_ty) => {
+                syntax::Item::Declaration(docs.clone(), label.clone(), rename(defn_count, body_ty))
+            },
+            syntax::Item::Definition(docs, label, term) => {
+                let term = rename(defn_count, term);
+                defn_count += 1;
+                syntax::Item::Definition(docs.clone(), label.clone(), term)
+            },
+        })
+        .collect();
+    let defn_tys = (entry.defn_tys.iter().enumerate())
+        .map(|(defn_count, ty)| rename(defn_count as u32, ty))
+        .collect();
+
+    (items, defn_tys)
+}
+
+/// Add the items of a cached group to the context, as if they had just been
+/// checked by `check_item_group`.
+fn add_cached_items<'file>(
+    context: &mut Context,
+    metas: &meta::Env,
+    forward_declarations: &mut ForwardDeclarations<'file>,
+    concrete_items: &[Item<'file>],
+    items: &[syntax::Item],
+    defn_tys: &[Rc<syntax::Term>],
+) -> Result<(), Diagnostic<FileSpan>> {
+    let span = concrete_items[0].span();
+    let mut defn_tys = defn_tys.iter();
+
+    for item in items {
+        match item {
+            syntax::Item::Declaration(_, _, body_ty) => {
+                let body_ty_value = context.eval_term(metas, span, body_ty)?;
+                if let Item::Declaration(declaration) = &concrete_items[0] {
+                    forward_declarations.insert(declaration.label.slice, Some(body_ty_value));
+                }
+            },
+            syntax::Item::Definition(_, label, term) => {
+                let ty = defn_tys.next().expect("missing type for cached definition");
+                let ty = context.eval_term(metas, span, ty)?;
+                let value = context.eval_term(metas, span, term)?;
+
+                context.add_defn(label.to_string(), value, ty);
+            },
+        }
+    }
+
+    match &concrete_items[0] {
+        Item::Declaration(_) => {},
+        Item::Definition(_) | Item::Data(_) => {
+            for concrete_item in concrete_items {
+                for label in item_labels(concrete_item) {
+                    forward_declarations.insert(label.slice, None);
+                }
+            }
+        },
+    }
+
+    Ok(())
+}
+
+/// Substitute the solutions to the metavariables in a newly checked group of
+/// items, and read back the types of its definitions.
+///
+/// Returns `None` if the group still refers to unsolved metavariables.
+fn zonk_group(
+    context: &Context,
+    metas: &meta::Env,
+    start_size: var::Size,
+    items: &[syntax::Item],
+) -> Option<(Vec<syntax::Item>, Vec<Rc<syntax::Term>>)> {
+    let (prims, budget) = (context.prims(), context.budget());
+    let module = syntax::Module {
+        items: items.to_vec(),
+    };
+    let module = zonk::zonk_module(prims, metas, budget, module).ok()?;
+
+    let end_size = context.values().size();
+    let defn_tys = (start_size.0..end_size.0)
+        .map(|level| {
+            let size = var::Size(level);
+            let ty = context
+                .tys()
+                .lookup_entry(end_size.index(var::Level(level)))?;
+            let ty = nbe::read_back_value(prims, metas, budget, size, Unfold::None, None, ty);
+            zonk::zonk_term(prims, metas, budget, &ty.ok()?).ok()
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some((module.items, defn_tys))
+}
+
+/// The levels of the variables that are referred to by a group of items and
+/// the types of its definitions, excluding the definitions in the group.
+fn free_levels(
+    start_size: var::Size,
+    items: &[syntax::Item],
+    defn_tys: &[Rc<syntax::Term>],
+) -> BTreeSet<var::Level> {
+    let mut levels = BTreeSet::new();
+    let mut size = start_size;
+    let mut add_free_levels = |size: var::Size, term: &Rc<syntax::Term>| {
+        let free_levels = term.free_vars(0).into_iter().map(|index| size.level(index));
+        levels.extend(free_levels.filter(|level| level.0 < start_size.0));
+    };
+
+    let mut defn_tys = defn_tys.iter();
+    for item in items {
+        match item {
+            syntax::Item::Declaration(_, _, body_ty) => add_free_levels(size, body_ty),
+            syntax::Item::Definition(_, _, term) => {
+                add_free_levels(size, term);
+                if let Some(ty) = defn_tys.next() {
+                    add_free_levels(size, ty);
+                }
+                size += 1;
+            },
+        }
+    }
+
+    levels
+}
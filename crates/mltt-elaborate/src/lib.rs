@@ -7,11 +7,15 @@
 //! - pattern compilation (TODO)
 //! - bidirectional type checking
 //! - unification of metavariables
+//! - termination checking of recursive definitions
+//! - zonking of metavariable solutions
 
 #![warn(rust_2018_idioms)]
 
 use language_reporting::{Diagnostic, Label as DiagnosticLabel};
-use mltt_concrete::{Arg, Item, SpannedString, Term, TypeParam};
+use mltt_concrete::{
+    Arg, Definition, Item, Level, LiteralKind, RecordTypeField, SpannedString, Term, TypeParam,
+};
 use mltt_core::literal::{LiteralIntro, LiteralType};
 use mltt_core::{domain, meta, prim, syntax, AppMode, DocString, Label, UniverseLevel};
 use mltt_span::FileSpan;
@@ -19,16 +23,24 @@ use std::rc::Rc;
 
 use crate::clause::{CaseClause, Clause};
 pub use crate::context::Context;
+pub use crate::zonk::zonk_term;
 
 mod clause;
 mod context;
+mod data;
+mod generalize;
+pub mod incremental;
 mod literal;
 mod nbe;
+mod record;
+mod termination;
 mod unify;
+mod zonk;
 
 /// Check that this is a valid module.
 ///
-/// Returns the elaborated module.
+/// Returns the elaborated module, with the solutions to its metavariables
+/// substituted in. An error is returned if any metavariables remain unsolved.
 pub fn check_module(
     context: &Context,
     metas: &mut meta::Env,
@@ -37,8 +49,17 @@ pub fn check_module(
     // The local elaboration context
     let mut context = context.clone();
     let items = check_items(&mut context, metas, concrete_items)?;
+    context.check_postponed(metas)?;
 
-    Ok(syntax::Module { items })
+    let module = zonk::zonk_module(
+        context.prims(),
+        metas,
+        context.budget(),
+        syntax::Module { items },
+    )?;
+
+    // Share structurally identical subterms, now that no more metas remain
+    Ok(syntax::Arena::new().share_module(&module))
 }
 
 /// Concatenate a bunch of lines of documentation into a single string, removing
@@ -57,6 +78,10 @@ fn concat_docs(doc_lines: &[SpannedString<'_>]) -> DocString {
     DocString::from(doc)
 }
 
+/// Declarations that may be waiting to be defined, along with the labels that
+/// have already been defined.
+type ForwardDeclarations<'file> = im::HashMap<&'file str, Option<Rc<domain::Type>>>;
+
 /// Check the given items and add them to the context.
 ///
 /// Returns the elaborated items.
@@ -65,7 +90,6 @@ fn check_items(
     metas: &mut meta::Env,
     concrete_items: &[Item<'_>],
 ) -> Result<Vec<syntax::Item>, Diagnostic<FileSpan>> {
-    // Declarations that may be waiting to be defined
     let mut forward_declarations = im::HashMap::new();
     // The elaborated items
     let mut core_items = {
@@ -73,97 +97,302 @@ fn check_items(
         Vec::with_capacity(expected_defn_count)
     };
 
-    for concrete_item in concrete_items {
-        use im::hashmap::Entry;
+    let mut concrete_items = concrete_items;
+    while !concrete_items.is_empty() {
+        let group_len = item_group_len(&forward_declarations, concrete_items);
+        let (group, rest) = concrete_items.split_at(group_len);
+
+        core_items.extend(check_item_group(
+            context,
+            metas,
+            &mut forward_declarations,
+            group,
+        )?);
+        concrete_items = rest;
+    }
+
+    Ok(core_items)
+}
+
+/// Find the number of items at the start of `concrete_items` that need to be
+/// checked together.
+///
+/// Definitions with a pending forward declaration are grouped with any
+/// definitions immediately following them that were also declared, allowing
+/// them to refer to each other recursively. All other items are checked on
+/// their own.
+fn item_group_len(
+    forward_declarations: &ForwardDeclarations<'_>,
+    concrete_items: &[Item<'_>],
+) -> usize {
+    let definition = match concrete_items.first() {
+        Some(Item::Definition(definition)) => definition,
+        Some(Item::Declaration(_)) | Some(Item::Data(_)) => return 1,
+        None => return 0,
+    };
+    let is_pending = |label: &str| match forward_declarations.get(label) {
+        Some(Some(_)) => true,
+        Some(None) | None => false,
+    };
+    if !is_pending(definition.label.slice) {
+        return 1;
+    }
+
+    let mut labels = vec![definition.label.slice];
+    for next_item in &concrete_items[1..] {
+        let next_label = match next_item {
+            Item::Definition(next_definition) => next_definition.label.slice,
+            Item::Declaration(_) | Item::Data(_) => break,
+        };
+        if !is_pending(next_label) || labels.contains(&next_label) {
+            break;
+        }
+        labels.push(next_label);
+    }
+
+    labels.len()
+}
+
+/// Check a group of items, as found by `item_group_len`, and add them to the
+/// context.
+///
+/// Returns the elaborated items. An error is returned if any of the unification
+/// problems postponed while checking the group remain unsolved, but problems
+/// that were postponed before the group was started are left to the enclosing
+/// items.
+fn check_item_group<'file>(
+    context: &mut Context,
+    metas: &mut meta::Env,
+    forward_declarations: &mut ForwardDeclarations<'file>,
+    concrete_items: &[Item<'file>],
+) -> Result<Vec<syntax::Item>, Diagnostic<FileSpan>> {
+    use im::hashmap::Entry;
+
+    let since = metas.next_problem_id();
+    let items = match &concrete_items[0] {
+        Item::Declaration(declaration) => {
+            let label = declaration.label.slice;
+            let concrete_body_ty = &declaration.body_ty;
+
+            log::trace!("checking declaration:\t\t{}\t: {}", label, concrete_body_ty);
+
+            match forward_declarations.entry(label) {
+                // No previous declaration for this name was seen, so we can
+                // go-ahead and type check, elaborate, and then add it to
+                // the context
+                Entry::Vacant(entry) => {
+                    let docs = concat_docs(&declaration.docs);
+                    let label = Label::new(label);
+                    let body_ty = match declaration.auto {
+                        None => synth_universe(&context, metas, &concrete_body_ty)?.0,
+                        Some(_) => generalize::synth_auto_ty(&context, metas, concrete_body_ty)?,
+                    };
+                    // Ensure that we evaluate the forward declaration in
+                    // the current context - if we wait until later more
+                    // definitions might have come in to scope!
+                    let body_ty_value =
+                        context.eval_term(metas, concrete_body_ty.span(), &body_ty)?;
+
+                    log::trace!("elaborated declaration:\t{}\t: {:?}", label, body_ty);
+
+                    entry.insert(Some(body_ty_value));
+
+                    Ok(vec![syntax::Item::Declaration(docs, label, body_ty)])
+                },
+                // There's a declaration for this name already pending - we
+                // can't add a new one!
+                Entry::Occupied(_) => Err(Diagnostic::new_error("already declared")
+                    .with_label(DiagnosticLabel::new_primary(declaration.label.span()))),
+            }
+        },
+        Item::Definition(definition) => {
+            let label = definition.label.slice;
+            let params = &definition.params;
+            let body_ty = definition.body_ty.as_ref();
+            let body = &definition.body;
+
+            log::trace!("checking definition:\t\t{}\t= {}", label, body);
+
+            match forward_declarations.get(label) {
+                // No prior declaration was found, so we'll try synthesizing
+                // its type instead
+                None => {
+                    let clause = Clause::new(params, body_ty, body);
+                    let (term, ty) = clause::synth_clause(&context, metas, clause)?;
+
+                    forward_declarations.insert(label, None);
+
+                    log::trace!("elaborated definition:\t{}\t= {:?}", label, term);
 
-        match concrete_item {
-            Item::Declaration(declaration) => {
-                let label = declaration.label.slice;
-                let concrete_body_ty = &declaration.body_ty;
+                    let label = Label::new(label);
+                    let docs = concat_docs(&definition.docs);
+                    let value = context.eval_term(metas, body.span(), &term)?;
 
-                log::trace!("checking declaration:\t\t{}\t: {}", label, concrete_body_ty);
+                    context.add_defn(label.to_string(), value, ty);
 
-                match forward_declarations.entry(label) {
-                    // No previous declaration for this name was seen, so we can
-                    // go-ahead and type check, elaborate, and then add it to
-                    // the context
+                    Ok(vec![syntax::Item::Definition(docs, label, term)])
+                },
+                // We found a prior declaration, so we'll use it as a basis
+                // for checking the definitions of the group, allowing them to
+                // refer to each other recursively.
+                Some(Some(_)) => {
+                    let definitions = concrete_items
+                        .iter()
+                        .map(|item| match item {
+                            Item::Definition(definition) => Ok(definition),
+                            Item::Declaration(_) | Item::Data(_) => {
+                                Err(Diagnostic::new_bug("unexpected item in recursive group")
+                                    .with_label(DiagnosticLabel::new_primary(item.span())))
+                            },
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    check_recursive_defns(context, metas, forward_declarations, &definitions)
+                },
+                // This declaration was already given a definition, so
+                // this is an error!
+                //
+                // NOTE: Some languages (eg. Haskell, Agda, Idris, and
+                // Erlang) turn duplicate definitions into case matches.
+                // Languages like Elm don't. What should we do here?
+                Some(None) => Err(Diagnostic::new_error("already defined")
+                    .with_label(DiagnosticLabel::new_primary(definition.label.span()))),
+            }
+        },
+        Item::Data(data) => {
+            log::trace!("checking data type:\t\t{}", data.label);
+
+            for label in item_labels(&concrete_items[0]) {
+                match forward_declarations.entry(label.slice) {
                     Entry::Vacant(entry) => {
-                        let docs = concat_docs(&declaration.docs);
-                        let label = Label(label.to_owned());
-                        let (body_ty, _) = synth_universe(&context, metas, &concrete_body_ty)?;
-                        // Ensure that we evaluate the forward declaration in
-                        // the current context - if we wait until later more
-                        // definitions might have come in to scope!
-                        let body_ty_value =
-                            context.eval_term(metas, concrete_body_ty.span(), &body_ty)?;
-
-                        log::trace!("elaborated declaration:\t{}\t: {:?}", label, body_ty);
-
-                        core_items.push(syntax::Item::Declaration(docs, label, body_ty));
-                        entry.insert(Some(body_ty_value));
+                        entry.insert(None);
                     },
-                    // There's a declaration for this name already pending - we
-                    // can't add a new one!
-                    Entry::Occupied(_) => {
-                        return Err(Diagnostic::new_error("already declared")
-                            .with_label(DiagnosticLabel::new_primary(declaration.label.span())));
+                    Entry::Occupied(entry) => {
+                        let message = match entry.get() {
+                            Some(_) => "already declared",
+                            None => "already defined",
+                        };
+                        return Err(Diagnostic::new_error(message)
+                            .with_label(DiagnosticLabel::new_primary(label.span())));
                     },
                 }
-            },
-            Item::Definition(definition) => {
-                let label = definition.label.slice;
-                let params = &definition.params;
-                let body_ty = definition.body_ty.as_ref();
-                let body = &definition.body;
+            }
 
-                log::trace!("checking definition:\t\t{}\t= {}", label, body);
+            data::check_data(context, metas, data)
+        },
+    }?;
+    context.check_postponed_since(metas, since)?;
 
-                let (term, term_span, ty) = match forward_declarations.entry(label) {
-                    // No prior declaration was found, so we'll try synthesizing
-                    // its type instead
-                    Entry::Vacant(entry) => {
-                        let clause = Clause::new(params, body_ty, body);
-                        let (term, ty) = clause::synth_clause(&context, metas, clause)?;
+    Ok(items)
+}
 
-                        entry.insert(None);
+/// The labels that are declared or defined by an item.
+fn item_labels<'a, 'file>(
+    concrete_item: &'a Item<'file>,
+) -> impl Iterator<Item = &'a SpannedString<'file>> {
+    let (label, intros) = match concrete_item {
+        Item::Declaration(declaration) => (&declaration.label, &[][..]),
+        Item::Definition(definition) => (&definition.label, &[][..]),
+        Item::Data(data) => (&data.label, &data.intros[..]),
+    };
 
-                        (term, body.span(), ty)
-                    },
-                    // Something has happened with this declaration, let's
-                    // 'take' a look!
-                    Entry::Occupied(mut entry) => match entry.get_mut().take() {
-                        // We found a prior declaration, so we'll use it as a
-                        // basis for checking the definition
-                        Some(ty) => {
-                            let clause = Clause::new(params, body_ty, body);
-                            let term = clause::check_clause(&context, metas, clause, &ty)?;
-
-                            (term, body.span(), ty)
-                        },
-                        // This declaration was already given a definition, so
-                        // this is an error!
-                        //
-                        // NOTE: Some languages (eg. Haskell, Agda, Idris, and
-                        // Erlang) turn duplicate definitions into case matches.
-                        // Languages like Elm don't. What should we do here?
-                        None => {
-                            return Err(Diagnostic::new_error("already defined").with_label(
-                                DiagnosticLabel::new_primary(definition.label.span()),
-                            ));
-                        },
-                    },
-                };
+    std::iter::once(label).chain(intros.iter().map(|intro| &intro.label))
+}
+
+/// Check a group of forward declared definitions, allowing them to refer to
+/// themselves and to each other recursively.
+///
+/// If none of the definitions turn out to be recursive, they are elaborated to
+/// plain definitions. Otherwise they are checked for termination and
+/// elaborated to a group of mutually recursive definitions. The definitions
+/// are added to the context as they are elaborated.
+fn check_recursive_defns(
+    context: &mut Context,
+    metas: &mut meta::Env,
+    forward_declarations: &mut ForwardDeclarations<'_>,
+    definitions: &[&Definition<'_>],
+) -> Result<Vec<syntax::Item>, Diagnostic<FileSpan>> {
+    let tys = definitions
+        .iter()
+        .map(|definition| {
+            let label = definition.label.slice;
+            match forward_declarations.get_mut(label).and_then(Option::take) {
+                Some(ty) => Ok(ty),
+                None => Err(Diagnostic::new_bug("missing forward declaration")
+                    .with_label(DiagnosticLabel::new_primary(definition.label.span()))),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
-                log::trace!("elaborated definition:\t{}\t= {:?}", label, term);
+    // Elaborate the bodies with the definitions of the group in scope
+    let mut body_context = context.clone();
+    for (definition, ty) in Iterator::zip(definitions.iter(), tys.iter()) {
+        body_context.add_recursive_defn(definition.label.slice, ty.clone());
+    }
+    let bodies = Iterator::zip(definitions.iter(), tys.iter())
+        .map(|(definition, ty)| {
+            let params = &definition.params;
+            let body_ty = definition.body_ty.as_ref();
+            let clause = Clause::new(params, body_ty, &definition.body);
+            clause::check_clause(&body_context, metas, clause, ty)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let group_len = definitions.len() as u32;
+    let terms = match bodies
+        .iter()
+        .map(|body| body.strengthen(group_len))
+        .collect::<Option<Vec<_>>>()
+    {
+        // None of the definitions refer back to the group, so we don't need
+        // to make them recursive
+        Some(terms) => terms,
+        None => {
+            let recursive_defns = Iterator::zip(definitions.iter(), bodies.iter())
+                .map(|(definition, body)| termination::RecursiveDefn {
+                    span: definition.label.span(),
+                    is_partial: definition.partial.is_some(),
+                    body,
+                })
+                .collect::<Vec<_>>();
+            let guards = termination::check_group(&recursive_defns)?;
+
+            let defns = Iterator::zip(definitions.iter(), tys.iter())
+                .zip(Iterator::zip(guards.into_iter(), bodies.into_iter()))
+                .map(|((definition, ty), (guard, body))| {
+                    let label = Label::new(definition.label.slice);
+                    let ty = context.read_back_value(metas, definition.label.span(), ty)?;
+                    Ok((label, guard, ty, body))
+                })
+                .collect::<Result<Vec<_>, Diagnostic<FileSpan>>>()?;
+            let defns = Rc::<[_]>::from(defns);
 
-                let label = Label(label.to_owned());
-                let docs = concat_docs(&definition.docs);
-                let value = context.eval_term(metas, term_span, &term)?;
+            (0..definitions.len())
+                .map(|index| Rc::from(syntax::Term::Fix(index, defns.clone())))
+                .collect()
+        },
+    };
 
-                context.add_defn(label.to_string(), value, ty);
-                core_items.push(syntax::Item::Definition(docs, label, term));
-            },
-        }
+    let mut core_items = Vec::with_capacity(definitions.len());
+    for (index, ((definition, ty), term)) in Iterator::zip(definitions.iter(), tys)
+        .zip(terms)
+        .enumerate()
+    {
+        // Move the term past the definitions that were added before it
+        let term = term.shift(index as u32);
+
+        log::trace!(
+            "elaborated definition:\t{}\t= {:?}",
+            definition.label.slice,
+            term,
+        );
+
+        let label = Label::new(definition.label.slice);
+        let docs = concat_docs(&definition.docs);
+        let value = context.eval_term(metas, definition.body.span(), &term)?;
+
+        context.add_defn(label.to_string(), value, ty);
+        core_items.push(syntax::Item::Definition(docs, label, term));
     }
 
     Ok(core_items)
@@ -175,10 +404,20 @@ pub fn synth_universe(
     context: &Context,
     metas: &mut meta::Env,
     concrete_term: &Term<'_>,
-) -> Result<(Rc<syntax::Term>, UniverseLevel), Diagnostic<FileSpan>> {
+) -> Result<(Rc<syntax::Term>, domain::Level), Diagnostic<FileSpan>> {
     let (term, ty) = synth_term(MetaInsertion::Yes, context, metas, concrete_term)?;
-    match ty.as_ref() {
-        domain::Value::Universe(level) => Ok((term, *level)),
+    match context.force_value(metas, None, &ty)?.as_ref() {
+        domain::Value::Universe(level) => Ok((term, level.clone())),
+        // The type is not known yet, so we expect it to be a universe of an
+        // unknown level
+        domain::Value::Neutral(domain::Head::Meta(_), _) => {
+            let span = concrete_term.span();
+            let level = new_level_meta(context, metas, span)?;
+            let universe = Rc::from(domain::Value::Universe(level.clone()));
+            context.unify_values(metas, span, &ty, &universe)?;
+
+            Ok((term, level))
+        },
         _ => Err(Diagnostic::new_error("type expected").with_label(
             DiagnosticLabel::new_primary(concrete_term.span()).with_message(format!(
                 "found `{}`",
@@ -188,6 +427,75 @@ pub fn synth_universe(
     }
 }
 
+/// Elaborate a telescope of type parameters, adding them to the context.
+///
+/// Returns the elaborated parameter types along with their names, and the
+/// maximum universe level of the parameter types. This level may refer to the
+/// parameters themselves, so it might need to be strengthened before it can be
+/// used outside of the telescope.
+fn synth_type_params<'file>(
+    context: &mut Context,
+    metas: &mut meta::Env,
+    concrete_params: &[TypeParam<'file>],
+) -> Result<
+    (
+        Vec<(AppMode, SpannedString<'file>, Rc<syntax::Term>)>,
+        domain::Level,
+    ),
+    Diagnostic<FileSpan>,
+> {
+    let mut param_tys = Vec::new();
+    let mut max_level = domain::Level::constant(0);
+
+    for param in concrete_params {
+        match param {
+            TypeParam::Explicit(_, param_names, concrete_param_ty) => {
+                for param_name in param_names {
+                    let app_mode = AppMode::Explicit;
+                    let param_ty_span = concrete_param_ty.span();
+                    let (param_ty, level) = synth_universe(context, metas, concrete_param_ty)?;
+                    let param_ty_value = context.eval_term(metas, param_ty_span, &param_ty)?;
+
+                    context.add_param(*param_name, param_ty_value);
+                    param_tys.push((app_mode, *param_name, param_ty));
+                    max_level = max_level.max(&level);
+                }
+            },
+            TypeParam::Implicit(param_span, param_labels, concrete_param_ty) => {
+                let concrete_param_ty = concrete_param_ty.as_ref().ok_or_else(|| {
+                    Diagnostic::new_error("implicit parameter is missing a type parameter")
+                        .with_label(DiagnosticLabel::new_primary(*param_span).with_message(
+                            "inference of parameter annotations is not yet supported",
+                        ))
+                })?;
+
+                for param_label in param_labels {
+                    let app_mode = AppMode::Implicit(Label::new(param_label.slice));
+                    let param_ty_span = concrete_param_ty.span();
+                    let (param_ty, level) = synth_universe(context, metas, concrete_param_ty)?;
+                    let param_ty_value = context.eval_term(metas, param_ty_span, &param_ty)?;
+
+                    context.add_param(*param_label, param_ty_value);
+                    param_tys.push((app_mode, *param_label, param_ty));
+                    max_level = max_level.max(&level);
+                }
+            },
+            TypeParam::Instance(_, param_label, concrete_param_ty) => {
+                let app_mode = AppMode::Instance(Label::new(param_label.slice));
+                let param_ty_span = concrete_param_ty.span();
+                let (param_ty, level) = synth_universe(context, metas, concrete_param_ty)?;
+                let param_ty_value = context.eval_term(metas, param_ty_span, &param_ty)?;
+
+                context.add_param(*param_label, param_ty_value);
+                param_tys.push((app_mode, *param_label, param_ty));
+                max_level = max_level.max(&level);
+            },
+        }
+    }
+
+    Ok((param_tys, max_level))
+}
+
 /// Check that a given term conforms to an expected type.
 ///
 /// Returns the elaborated term.
@@ -199,16 +507,43 @@ pub fn check_term(
 ) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
     log::trace!("checking term:\t\t{}", concrete_term);
 
+    // Insert implicit and instance lambdas when checking against implicit
+    // and instance function types. Primitives take their type from the
+    // expected type, and function introductions insert their own parameters
+    // when checking their clause, so we leave them alone. Parenthesized terms
+    // are handled once the parentheses have been removed.
+    match concrete_term {
+        Term::Prim(_, _) | Term::FunIntro(_, _, _) | Term::Parens(_, _) => {},
+        _ => {
+            if let domain::Value::FunType(app_mode, name_hint, param_ty, body_ty) =
+                context.force_value(metas, None, expected_ty)?.as_ref()
+            {
+                if let AppMode::Implicit(_) | AppMode::Instance(_) = app_mode {
+                    let mut context = context.clone();
+                    let param = context.add_fresh_param(param_ty.clone());
+                    let body_ty = context.app_closure(metas, body_ty, param)?;
+                    let body = check_term(&context, metas, concrete_term, &body_ty)?;
+
+                    return Ok(Rc::from(syntax::Term::FunIntro(
+                        app_mode.clone(),
+                        name_hint.clone(),
+                        body,
+                    )));
+                }
+            }
+        },
+    }
+
     match concrete_term {
         Term::Prim(_, name) => {
-            let prim_name = prim::Name(literal::parse_string(name)?);
+            let prim_name = prim::Name::from(literal::parse_string(name)?);
             match context.prims().lookup_entry(&prim_name) {
                 None => Err(Diagnostic::new_error("unknown primitive")
                     .with_label(DiagnosticLabel::new_primary(name.span()))),
                 Some(_) => Ok(Rc::from(syntax::Term::prim(prim_name))),
             }
         },
-        Term::Hole(span) => Ok(context.new_meta(metas, *span, expected_ty.clone())),
+        Term::Hole(span) => context.new_meta(metas, *span, expected_ty.clone()),
         Term::Parens(_, concrete_term) => check_term(context, metas, concrete_term, expected_ty),
         Term::Let(_, concrete_items, concrete_body) => {
             let mut context = context.clone();
@@ -239,8 +574,29 @@ pub fn check_term(
         },
 
         Term::LiteralIntro(kind, literal) => {
-            let literal_intro = literal::check(context, metas, *kind, literal, expected_ty)?;
-            Ok(Rc::from(syntax::Term::literal_intro(literal_intro)))
+            match (
+                kind,
+                context.force_value(metas, None, expected_ty)?.as_ref(),
+            ) {
+                // Integer literals can also be used as universe levels
+                (LiteralKind::Int, domain::Value::LevelType) => {
+                    let level = UniverseLevel(literal::parse_int(literal)?);
+                    Ok(Rc::from(syntax::Term::LevelIntro(level)))
+                },
+                // String and character literals have unambiguous types, so we
+                // can use them to solve an unknown expected type
+                (LiteralKind::String, domain::Value::Neutral(domain::Head::Meta(_), _))
+                | (LiteralKind::Char, domain::Value::Neutral(domain::Head::Meta(_), _)) => {
+                    let (literal_intro, ty) = literal::synth(*kind, literal)?;
+                    context.unify_values(metas, literal.span(), &ty, expected_ty)?;
+                    Ok(Rc::from(syntax::Term::literal_intro(literal_intro)))
+                },
+                (_, _) => {
+                    let literal_intro =
+                        literal::check(context, metas, *kind, literal, expected_ty)?;
+                    Ok(Rc::from(syntax::Term::literal_intro(literal_intro)))
+                },
+            }
         },
 
         Term::FunIntro(_, concrete_params, concrete_body) => {
@@ -249,28 +605,50 @@ pub fn check_term(
         },
 
         Term::RecordIntro(span, concrete_intro_fields) => {
-            let mut context = context.clone();
+            // The fields of a record are not in scope of each other, but the
+            // types of later fields are instantiated with the earlier values
             let mut fields = Vec::new();
             let mut expected_ty = expected_ty.clone();
-
-            for concrete_intro_field in concrete_intro_fields {
-                let (expected_label, expected_term_ty, rest) = match expected_ty.as_ref() {
-                    domain::Value::RecordTypeExtend(_, label, _, ty, rest) => Ok((label, ty, rest)),
-                    _ => Err(Diagnostic::new_error("too many fields found")
-                        .with_label(DiagnosticLabel::new_primary(*span))),
-                }?;
+            let mut concrete_intro_fields = concrete_intro_fields.iter().peekable();
+
+            while let Some(&concrete_intro_field) = concrete_intro_fields.peek() {
+                let forced_ty = context.force_value(metas, None, &expected_ty)?;
+                let (expected_label, expected_term_ty, expected_manifest, rest) =
+                    match forced_ty.as_ref() {
+                        domain::Value::RecordTypeExtend(_, label, _, ty, manifest, rest) => {
+                            Ok((label, ty, manifest, rest))
+                        },
+                        _ => Err(Diagnostic::new_error("too many fields found")
+                            .with_label(DiagnosticLabel::new_primary(*span))),
+                    }?;
 
                 let (found_label, params, body_ty, body) = concrete_intro_field.desugar();
 
-                if found_label.slice == expected_label.0 {
+                // Manifest fields can be omitted, in which case we fill them
+                // in using the value from the type
+                if let Some(expected_manifest) = expected_manifest {
+                    if found_label.slice != expected_label.as_str() {
+                        let term = context.read_back_value(metas, *span, expected_manifest)?;
+
+                        fields.push((expected_label.clone(), term));
+                        expected_ty =
+                            context.app_closure(metas, &rest, expected_manifest.clone())?;
+                        continue;
+                    }
+                }
+
+                concrete_intro_fields.next();
+
+                if found_label.slice == expected_label.as_str() {
                     let clause = Clause::new(params, body_ty, &body);
-                    let term = clause::check_clause(&context, metas, clause, expected_term_ty)?;
+                    let term = clause::check_clause(context, metas, clause, expected_term_ty)?;
 
                     let term_value = context.eval_term(metas, body.span(), &term)?;
-                    let term_ty = expected_term_ty.clone();
+                    if let Some(expected_manifest) = expected_manifest {
+                        context.unify_values(metas, body.span(), &term_value, expected_manifest)?;
+                    }
 
                     fields.push((expected_label.clone(), term));
-                    context.add_defn(found_label, term_value.clone(), term_ty);
                     expected_ty = context.app_closure(metas, &rest, term_value)?;
                 } else {
                     return Err(Diagnostic::new_error("field not found").with_label(
@@ -282,7 +660,19 @@ pub fn check_term(
                 }
             }
 
-            if let domain::Value::RecordTypeEmpty = expected_ty.as_ref() {
+            // Fill in any trailing manifest fields
+            while let domain::Value::RecordTypeExtend(_, label, _, _, Some(manifest), rest) =
+                context.force_value(metas, None, &expected_ty)?.as_ref()
+            {
+                let term = context.read_back_value(metas, *span, manifest)?;
+
+                fields.push((label.clone(), term));
+                expected_ty = context.app_closure(metas, rest, manifest.clone())?;
+            }
+
+            if let domain::Value::RecordTypeEmpty =
+                context.force_value(metas, None, &expected_ty)?.as_ref()
+            {
                 Ok(Rc::from(syntax::Term::RecordIntro(fields)))
             } else {
                 Err(Diagnostic::new_error("not enough fields provided")
@@ -290,6 +680,25 @@ pub fn check_term(
             }
         },
 
+        Term::RecordUpdate(span, concrete_record, concrete_intro_fields) => {
+            let (record, record_ty) =
+                synth_term(MetaInsertion::Yes, context, metas, concrete_record)?;
+
+            record::check_update(
+                context,
+                metas,
+                *span,
+                &record,
+                &record_ty,
+                concrete_intro_fields,
+                expected_ty,
+            )
+        },
+
+        Term::TupleIntro(span, concrete_elements) => {
+            record::check_tuple_intro(context, metas, *span, concrete_elements, expected_ty)
+        },
+
         _ => {
             let (synth, synth_ty) = synth_term(MetaInsertion::Yes, context, metas, concrete_term)?;
             context.unify_values(metas, concrete_term.span(), &synth_ty, expected_ty)?;
@@ -324,7 +733,13 @@ fn insert_metas(
 
     let mut term_ty = term_ty.clone();
 
-    while let FunType(app_mode, _, param_ty, body_ty) = term_ty.as_ref() {
+    loop {
+        let forced_ty = context.force_value(metas, None, &term_ty)?;
+        let (app_mode, param_ty, body_ty) = match forced_ty.as_ref() {
+            FunType(app_mode, _, param_ty, body_ty) => (app_mode, param_ty, body_ty),
+            _ => break,
+        };
+
         match (meta_insertion, app_mode) {
             // The user requested we stop inserting metavariables, or
             // we have seen an explicit argument, so we stop inserting
@@ -336,14 +751,18 @@ fn insert_metas(
             // In these cases we see a matching implicit or instance argument
             // being passed, corresponding to the expected parameter in the
             // type. Therefore we stop inserting metavariables.
-            (MetaInsertion::UntilImplicit(l), AppMode::Implicit(label)) if l == label.0 => break,
-            (MetaInsertion::UntilInstance(l), AppMode::Instance(label)) if l == label.0 => break,
+            (MetaInsertion::UntilImplicit(l), AppMode::Implicit(label)) if l == label.as_str() => {
+                break
+            },
+            (MetaInsertion::UntilInstance(l), AppMode::Instance(label)) if l == label.as_str() => {
+                break
+            },
 
             // Based on the given type, we expected an implicit argument to be
             // applied. Instead, let's apply a metavariable argument in its
             // place, to be solved later (during unification).
             (_, AppMode::Implicit(_)) => {
-                let arg = context.new_meta(metas, span, param_ty.clone());
+                let arg = context.new_meta(metas, span, param_ty.clone())?;
                 let arg_value = context.eval_term(metas, None, &arg)?;
                 term = Rc::from(syntax::Term::FunElim(term, app_mode.clone(), arg));
                 term_ty = context.app_closure(metas, body_ty, arg_value)?;
@@ -374,14 +793,15 @@ pub fn synth_term(
     metas: &mut meta::Env,
     concrete_term: &Term<'_>,
 ) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
-    use std::cmp;
-
     log::trace!("synthesizing term:\t\t{}", concrete_term);
 
     match concrete_term {
         Term::Var(name) => match context.lookup_binder(name.slice) {
-            None => Err(Diagnostic::new_error("unbound variable")
-                .with_label(DiagnosticLabel::new_primary(name.span()))),
+            None => match context.auto_binds() {
+                Some(auto_binds) => auto_binds.lookup(metas, name),
+                None => Err(Diagnostic::new_error("unbound variable")
+                    .with_label(DiagnosticLabel::new_primary(name.span()))),
+            },
             Some((index, var_ty)) => {
                 let span = concrete_term.span().end_span();
                 let var = Rc::from(syntax::Term::var(index));
@@ -390,7 +810,7 @@ pub fn synth_term(
         },
         Term::Prim(span, name) => match context
             .prims()
-            .lookup_entry(&prim::Name(literal::parse_string(name)?))
+            .lookup_entry(&prim::Name::from(literal::parse_string(name)?))
         {
             None => Err(Diagnostic::new_error("unknown primitive")
                 .with_label(DiagnosticLabel::new_primary(name.span()))),
@@ -417,12 +837,15 @@ pub fn synth_term(
 
             Ok((Rc::from(syntax::Term::Let(items, body)), body_ty))
         },
-        Term::If(span, _, _, _) => Err(Diagnostic::new_error("ambiguous term").with_label(
-            DiagnosticLabel::new_primary(*span).with_message("type annotations needed here"),
-        )),
-        Term::Case(span, _, _) => Err(Diagnostic::new_error("ambiguous term").with_label(
-            DiagnosticLabel::new_primary(*span).with_message("type annotations needed here"),
-        )),
+        Term::If(span, _, _, _) | Term::Case(span, _, _) => {
+            // Check the branches against a fresh metavariable, allowing the
+            // type of the branches to be inferred
+            let (ty, ty_value) = new_ty_meta(context, metas, *span)?;
+            let term = check_term(context, metas, concrete_term, &ty_value)?;
+            let ty_value = context.force_value(metas, *span, &ty_value)?;
+
+            Ok((Rc::from(syntax::Term::ann(term, ty)), ty_value))
+        },
 
         Term::LiteralIntro(kind, literal) => {
             let (literal_intro, ty) = literal::synth(*kind, literal)?;
@@ -433,70 +856,27 @@ pub fn synth_term(
 
         Term::FunType(_, concrete_params, concrete_body_ty) => {
             let mut context = context.clone();
-            let mut param_tys = Vec::new();
-            let mut max_level = UniverseLevel(0);
-
-            for param in concrete_params {
-                match param {
-                    TypeParam::Explicit(_, param_names, concrete_param_ty) => {
-                        for param_name in param_names {
-                            let app_mode = AppMode::Explicit;
-                            let param_ty_span = concrete_param_ty.span();
-                            let (param_ty, level) =
-                                synth_universe(&context, metas, concrete_param_ty)?;
-                            let param_ty_value =
-                                context.eval_term(metas, param_ty_span, &param_ty)?;
-
-                            context.add_param(param_name, param_ty_value);
-                            param_tys.push((app_mode, param_ty));
-                            max_level = cmp::max(max_level, level);
-                        }
-                    },
-                    TypeParam::Implicit(param_span, param_labels, concrete_param_ty) => {
-                        let concrete_param_ty = concrete_param_ty.as_ref().ok_or_else(|| {
-                            Diagnostic::new_error("implicit parameter is missing a type parameter")
-                                .with_label(DiagnosticLabel::new_primary(*param_span).with_message(
-                                    "inference of parameter annotations is not yet supported",
-                                ))
-                        })?;
-
-                        for param_label in param_labels {
-                            let app_mode = AppMode::Implicit(Label(param_label.to_string()));
-                            let param_ty_span = concrete_param_ty.span();
-                            let (param_ty, level) =
-                                synth_universe(&context, metas, concrete_param_ty)?;
-                            let param_ty_value =
-                                context.eval_term(metas, param_ty_span, &param_ty)?;
-
-                            context.add_param(param_label, param_ty_value);
-                            param_tys.push((app_mode, param_ty));
-                            max_level = cmp::max(max_level, level);
-                        }
-                    },
-                    TypeParam::Instance(_, param_label, concrete_param_ty) => {
-                        let app_mode = AppMode::Instance(Label(param_label.to_string()));
-                        let param_ty_span = concrete_param_ty.span();
-                        let (param_ty, level) = synth_universe(&context, metas, concrete_param_ty)?;
-                        let param_ty_value = context.eval_term(metas, param_ty_span, &param_ty)?;
-
-                        context.add_param(param_label, param_ty_value);
-                        param_tys.push((app_mode, param_ty));
-                        max_level = cmp::max(max_level, level);
-                    },
-                }
-            }
-
+            let (param_tys, params_level) =
+                synth_type_params(&mut context, metas, concrete_params)?;
             let (body_ty, body_level) = synth_universe(&context, metas, concrete_body_ty)?;
-            max_level = cmp::max(max_level, body_level);
+            // Function types that quantify over universe levels are placed in
+            // the largest universe
+            let max_level = context
+                .strengthen_level(
+                    metas,
+                    param_tys.len() as u32,
+                    &params_level.max(&body_level),
+                )?
+                .unwrap_or_else(domain::Level::omega);
 
             Ok((
                 param_tys
                     .into_iter()
                     .rev()
-                    .fold(body_ty, |acc, (app_mode, param_ty)| {
+                    .fold(body_ty, |acc, (app_mode, _, param_ty)| {
                         Rc::from(syntax::Term::FunType(app_mode, None, param_ty, acc))
                     }),
-                Rc::from(domain::Value::universe(max_level)),
+                Rc::from(domain::Value::Universe(max_level)),
             ))
         },
         Term::FunArrowType(concrete_param_ty, concrete_body_ty) => {
@@ -505,15 +885,19 @@ pub fn synth_term(
                 let mut context = context.clone();
                 let param_ty = context.eval_term(metas, concrete_param_ty.span(), &param_ty)?;
                 context.add_fresh_param(param_ty);
-                synth_universe(&context, metas, concrete_body_ty)?
+                let (body_ty, body_level) = synth_universe(&context, metas, concrete_body_ty)?;
+                let body_level = context
+                    .strengthen_level(metas, 1, &body_level)?
+                    .unwrap_or_else(domain::Level::omega);
+                (body_ty, body_level)
             };
 
             let fun_ty = syntax::Term::FunType(AppMode::Explicit, None, param_ty, body_ty);
-            let max_level = cmp::max(param_level, body_level);
+            let max_level = param_level.max(&body_level);
 
             Ok((
                 Rc::from(fun_ty),
-                Rc::from(domain::Value::universe(max_level)),
+                Rc::from(domain::Value::Universe(max_level)),
             ))
         },
         Term::FunIntro(_, concrete_params, concrete_body) => {
@@ -593,25 +977,120 @@ pub fn synth_term(
 
         Term::RecordType(_, concrete_ty_fields) => {
             let mut context = context.clone();
-            let mut max_level = UniverseLevel(0);
+            let mut max_level = domain::Level::constant(0);
+            let mut ty_fields = Vec::new();
+
+            let check_duplicate =
+                |ty_fields: &[(DocString, Label, _, _, _)], label: &str, span: FileSpan| {
+                    if ty_fields.iter().any(|(_, l, _, _, _)| l.as_str() == label) {
+                        let message = format!("duplicate field: `{}`", label);
+                        Err(Diagnostic::new_error(message)
+                            .with_label(DiagnosticLabel::new_primary(span)))
+                    } else {
+                        Ok(())
+                    }
+                };
 
-            let ty_fields = concrete_ty_fields
-                .iter()
-                .map(|concrete_ty_field| {
-                    let docs = concat_docs(&concrete_ty_field.docs);
-                    let (ty, ty_level) = synth_universe(&context, metas, &concrete_ty_field.ann)?;
-                    let ty_value = context.eval_term(metas, concrete_ty_field.ann.span(), &ty)?;
+            for concrete_ty_field in concrete_ty_fields {
+                match concrete_ty_field {
+                    RecordTypeField::Explicit {
+                        docs,
+                        label,
+                        ann,
+                        value,
+                    } => {
+                        check_duplicate(&ty_fields, label.slice, label.span())?;
+
+                        let docs = concat_docs(docs);
+                        let (ty, ty_level) = synth_universe(&context, metas, ann)?;
+                        let ty_level = context
+                            .strengthen_level(metas, ty_fields.len() as u32, &ty_level)?
+                            .unwrap_or_else(domain::Level::omega);
+                        let ty_value = context.eval_term(metas, ann.span(), &ty)?;
+
+                        let value = match value {
+                            None => {
+                                context.add_param(*label, ty_value);
+                                None
+                            },
+                            Some(concrete_value) => {
+                                let value = check_term(&context, metas, concrete_value, &ty_value)?;
+                                let value_value =
+                                    context.eval_term(metas, concrete_value.span(), &value)?;
+                                context.add_defn(*label, value_value, ty_value);
+                                Some(value)
+                            },
+                        };
+                        max_level = max_level.max(&ty_level);
+
+                        ty_fields.push((docs, Label::new(label.slice), None, ty, value));
+                    },
+                    RecordTypeField::Include { record_ty } => {
+                        let span = record_ty.span();
+                        let (ty, ty_level) = synth_universe(&context, metas, record_ty)?;
+                        let ty_level = context
+                            .strengthen_level(metas, ty_fields.len() as u32, &ty_level)?
+                            .unwrap_or_else(domain::Level::omega);
+                        let mut ty_value = context.eval_term(metas, span, &ty)?;
+
+                        // Copy the fields of the included record type, reading
+                        // them back in the scope of the fields added so far
+                        while let domain::Value::RecordTypeExtend(
+                            docs,
+                            label,
+                            name_hint,
+                            field_ty,
+                            manifest,
+                            rest,
+                        ) = context.force_value(metas, None, &ty_value)?.as_ref()
+                        {
+                            check_duplicate(&ty_fields, label.as_str(), span)?;
+
+                            let field_ty_term = context.read_back_value(metas, span, field_ty)?;
+                            let (field_value, manifest_term) = match manifest {
+                                None => (context.add_param(label.as_str(), field_ty.clone()), None),
+                                Some(manifest) => {
+                                    let manifest_term =
+                                        context.read_back_value(metas, span, manifest)?;
+                                    let field_value = manifest.clone();
+                                    context.add_defn(
+                                        label.as_str(),
+                                        field_value.clone(),
+                                        field_ty.clone(),
+                                    );
+                                    (field_value, Some(manifest_term))
+                                },
+                            };
+
+                            ty_fields.push((
+                                docs.clone(),
+                                label.clone(),
+                                name_hint.clone(),
+                                field_ty_term,
+                                manifest_term,
+                            ));
+                            ty_value = context.app_closure(metas, rest, field_value)?;
+                        }
 
-                    context.add_param(concrete_ty_field.label, ty_value);
-                    max_level = cmp::max(max_level, ty_level);
+                        match context.force_value(metas, None, &ty_value)?.as_ref() {
+                            domain::Value::RecordTypeEmpty => {},
+                            _ => {
+                                return Err(Diagnostic::new_error("expected a record type")
+                                    .with_label(
+                                        DiagnosticLabel::new_primary(span)
+                                            .with_message("only record types can be included"),
+                                    ));
+                            },
+                        }
 
-                    Ok((docs, Label(concrete_ty_field.label.to_string()), None, ty))
-                })
-                .collect::<Result<_, Diagnostic<FileSpan>>>()?;
+                        max_level = max_level.max(&ty_level);
+                    },
+                }
+            }
 
             Ok((
                 Rc::from(syntax::Term::RecordType(ty_fields)),
-                Rc::from(domain::Value::universe(max_level)),
+                Rc::from(domain::Value::Universe(max_level)),
             ))
         },
         Term::RecordIntro(span, intro_fields) => {
@@ -627,35 +1106,62 @@ pub fn synth_term(
                 ))
             }
         },
+        Term::RecordUpdate(span, concrete_record, concrete_intro_fields) => {
+            let (record, record_ty) =
+                synth_term(MetaInsertion::Yes, context, metas, concrete_record)?;
+            let term = record::check_update(
+                context,
+                metas,
+                *span,
+                &record,
+                &record_ty,
+                concrete_intro_fields,
+                &record_ty,
+            )?;
+            // Annotate the updated record with its type, so that the
+            // elaborated term can be synthesized as well
+            let ty = context.read_back_value(metas, *span, &record_ty)?;
+
+            Ok((Rc::from(syntax::Term::Ann(term, ty)), record_ty))
+        },
         Term::RecordElim(concrete_record, label) => {
-            let (record, mut record_ty) =
+            let (record, record_ty) =
                 synth_term(MetaInsertion::Yes, context, metas, concrete_record)?;
 
-            while let domain::Value::RecordTypeExtend(_, current_label, _, current_ty, rest) =
-                record_ty.as_ref()
-            {
-                let expr = Rc::from(syntax::Term::RecordElim(
-                    record.clone(),
-                    current_label.clone(),
-                ));
-
-                if current_label.0 == label.slice {
+            match record::synth_elim(context, metas, &record, &record_ty, label.slice)? {
+                Some((expr, expr_ty)) => {
                     let span = concrete_term.span().end_span();
-                    return insert_metas(meta_insertion, context, metas, span, expr, &current_ty);
-                } else {
-                    let expr = context.eval_term(metas, None, &expr)?;
-                    record_ty = context.app_closure(metas, rest, expr)?;
-                }
+                    insert_metas(meta_insertion, context, metas, span, expr, &expr_ty)
+                },
+                None => {
+                    let message = format!("field not found: `{}`", label);
+                    Err(Diagnostic::new_error(message)
+                        .with_label(DiagnosticLabel::new_primary(label.span())))
+                },
             }
+        },
 
-            let message = format!("field not found: `{}`", label);
-            Err(Diagnostic::new_error(message)
-                .with_label(DiagnosticLabel::new_primary(label.span())))
+        Term::TupleType(_, concrete_components) => {
+            record::synth_tuple_ty(context, metas, concrete_components)
+        },
+        Term::TupleIntro(_, concrete_elements) => {
+            record::synth_tuple_intro(context, metas, concrete_elements)
         },
 
-        Term::Universe(span, level) => {
-            let level = UniverseLevel(level.as_ref().map_or(Ok(0), literal::parse_int)?);
-            let ty_level = level.shift(1).ok_or_else(|| {
+        Term::Universe(span, concrete_level) => {
+            let level = match concrete_level {
+                None => Rc::from(syntax::Term::LevelIntro(UniverseLevel(0))),
+                Some(concrete_level) => check_level(context, metas, concrete_level)?,
+            };
+            let level_value = context.eval_term(metas, *span, &level)?;
+            let level_value = nbe::value_to_level(
+                context.prims(),
+                metas,
+                context.budget(),
+                *span,
+                &level_value,
+            )?;
+            let ty_level = level_value.shift(1).ok_or_else(|| {
                 Diagnostic::new_error("maximum universe level reached").with_label(
                     DiagnosticLabel::new_primary(*span).with_message(format!(
                         "cannot represent universes greater than `{}`",
@@ -665,9 +1171,68 @@ pub fn synth_term(
             })?;
 
             Ok((
-                Rc::from(syntax::Term::universe(level)),
-                Rc::from(domain::Value::universe(ty_level)),
+                Rc::from(syntax::Term::Universe(level)),
+                Rc::from(domain::Value::Universe(ty_level)),
             ))
         },
     }
 }
+
+/// Create a fresh metavariable that stands for an unknown universe level.
+fn new_level_meta(
+    context: &Context,
+    metas: &mut meta::Env,
+    span: FileSpan,
+) -> Result<domain::Level, Diagnostic<FileSpan>> {
+    let level = context.new_meta(metas, span, Rc::from(domain::Value::LevelType))?;
+    let level = context.eval_term(metas, span, &level)?;
+    nbe::value_to_level(context.prims(), metas, context.budget(), span, &level)
+}
+
+/// Create a fresh metavariable that stands for a type in a universe of an
+/// unknown level.
+///
+/// Returns the metavariable and its value.
+fn new_ty_meta(
+    context: &Context,
+    metas: &mut meta::Env,
+    span: FileSpan,
+) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
+    let level = new_level_meta(context, metas, span)?;
+    let ty = context.new_meta(metas, span, Rc::from(domain::Value::Universe(level)))?;
+    let ty_value = context.eval_term(metas, span, &ty)?;
+
+    Ok((ty, ty_value))
+}
+
+/// Check that a concrete level is a well formed universe level.
+///
+/// Returns the elaborated level.
+fn check_level(
+    context: &Context,
+    metas: &mut meta::Env,
+    concrete_level: &Level<'_>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    match concrete_level {
+        Level::Literal(literal) => Ok(Rc::from(syntax::Term::LevelIntro(UniverseLevel(
+            literal::parse_int(literal)?,
+        )))),
+        Level::Var(name) => {
+            let level_ty = Rc::from(domain::Value::LevelType);
+            check_term(context, metas, &Term::Var(*name), &level_ty)
+        },
+        Level::Shift(concrete_level, shift) => {
+            let level = check_level(context, metas, concrete_level)?;
+            let shift = literal::parse_int(shift)?;
+
+            Ok(Rc::from(syntax::Term::LevelShift(level, shift)))
+        },
+        Level::Max(_, concrete_lhs, concrete_rhs) => {
+            let lhs = check_level(context, metas, concrete_lhs)?;
+            let rhs = check_level(context, metas, concrete_rhs)?;
+
+            Ok(Rc::from(syntax::Term::LevelMax(lhs, rhs)))
+        },
+        Level::Parens(_, concrete_level) => check_level(context, metas, concrete_level),
+    }
+}
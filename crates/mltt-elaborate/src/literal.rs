@@ -31,7 +31,10 @@ pub fn check(
     use mltt_core::domain::Value::LiteralType;
     use mltt_core::literal::{LiteralIntro as LitIntro, LiteralType as LitType};
 
-    match (kind, expected_ty.as_ref()) {
+    match (
+        kind,
+        context.force_value(metas, None, expected_ty)?.as_ref(),
+    ) {
         (LitKind::String, LiteralType(LitType::String)) => {
             parse_string(src).map(Rc::from).map(LitIntro::String)
         },
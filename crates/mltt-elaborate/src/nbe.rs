@@ -1,29 +1,32 @@
 //! Wrappers around the core NBE functions that return diagnostics on errors.
 
 use language_reporting::{Diagnostic, Label as DiagnosticLabel};
-use mltt_core::{domain, meta, nbe, prim, syntax, var, AppMode, Label};
+use mltt_core::nbe::{self, Budget, EvalError, Limit};
+use mltt_core::{domain, meta, prim, syntax, var, AppMode, Label, UniverseLevel};
 use mltt_span::FileSpan;
 use std::rc::Rc;
 
 pub fn eval_fun_elim(
     prims: &prim::Env,
     metas: &meta::Env,
+    budget: &Budget,
     fun: Rc<domain::Value>,
     app_mode: &AppMode,
     arg: Rc<domain::Value>,
 ) -> Result<Rc<domain::Value>, Diagnostic<FileSpan>> {
-    nbe::eval_fun_elim(prims, metas, fun, app_mode, arg)
-        .map_err(|error| Diagnostic::new_bug(format!("failed function elimination: {}", error)))
+    nbe::eval_fun_elim(prims, metas, budget, fun, app_mode, arg)
+        .map_err(|error| eval_error("failed function elimination", None, error))
 }
 
 pub fn eval_literal_elim(
     prims: &prim::Env,
     metas: &meta::Env,
+    budget: &Budget,
     scrutinee: Rc<domain::Value>,
     closure: domain::LiteralClosure,
 ) -> Result<Rc<domain::Value>, Diagnostic<FileSpan>> {
-    nbe::eval_literal_elim(prims, metas, scrutinee, closure)
-        .map_err(|error| Diagnostic::new_bug(format!("failed literal elimination: {}", error)))
+    nbe::eval_literal_elim(prims, metas, budget, scrutinee, closure)
+        .map_err(|error| eval_error("failed literal elimination", None, error))
 }
 
 pub fn eval_record_elim(
@@ -31,70 +34,181 @@ pub fn eval_record_elim(
     label: &Label,
 ) -> Result<Rc<domain::Value>, Diagnostic<FileSpan>> {
     nbe::eval_record_elim(term, label)
-        .map_err(|error| Diagnostic::new_bug(format!("failed record elimination: {}", error)))
+        .map_err(|error| eval_error("failed record elimination", None, error))
+}
+
+pub fn intro_ty(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    closure: &domain::DataClosure,
+    label: &Label,
+) -> Result<Rc<domain::Type>, Diagnostic<FileSpan>> {
+    nbe::intro_ty(prims, metas, budget, closure, label)
+        .map_err(|error| eval_error("failed to evaluate constructor type", None, error))
+}
+
+pub fn check_data(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    env_size: var::Size,
+    closure1: &domain::DataClosure,
+    closure2: &domain::DataClosure,
+) -> Result<bool, Diagnostic<FileSpan>> {
+    nbe::check_data(prims, metas, budget, env_size, closure1, closure2)
+        .map_err(|error| eval_error("failed to compare data types", None, error))
 }
 
 pub fn app_closure(
     prims: &prim::Env,
     metas: &meta::Env,
+    budget: &Budget,
     closure: &domain::AppClosure,
     arg: Rc<domain::Value>,
 ) -> Result<Rc<domain::Value>, Diagnostic<FileSpan>> {
-    nbe::app_closure(prims, metas, closure, arg)
-        .map_err(|error| Diagnostic::new_bug(format!("failed closure application: {}", error)))
+    nbe::app_closure(prims, metas, budget, closure, arg)
+        .map_err(|error| eval_error("failed closure application", None, error))
 }
 
 pub fn eval_term(
     prims: &prim::Env,
     metas: &meta::Env,
+    budget: &Budget,
     values: &var::Env<Rc<domain::Value>>,
     span: impl Into<Option<FileSpan>>,
     term: &Rc<syntax::Term>,
 ) -> Result<Rc<domain::Value>, Diagnostic<FileSpan>> {
-    nbe::eval_term(prims, metas, values, term).map_err(|error| match span.into() {
-        None => Diagnostic::new_bug(format!("failed to evaluate term: {}", error)),
-        Some(span) => Diagnostic::new_bug("failed to evaluate term")
-            .with_label(DiagnosticLabel::new_primary(span).with_message(error)),
-    })
+    nbe::eval_term(prims, metas, budget, values, term)
+        .map_err(|error| eval_error("failed to evaluate term", span.into(), error))
 }
 
 pub fn read_back_value(
     prims: &prim::Env,
     metas: &meta::Env,
+    budget: &Budget,
     env_size: var::Size,
+    unfold: nbe::Unfold,
     span: impl Into<Option<FileSpan>>,
     value: &Rc<domain::Value>,
 ) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
-    nbe::read_back_value(prims, metas, env_size, value).map_err(|error| match span.into() {
-        None => Diagnostic::new_bug(format!("failed to read-back value: {}", error)),
-        Some(span) => Diagnostic::new_bug("failed to read-back value")
-            .with_label(DiagnosticLabel::new_primary(span).with_message(error)),
-    })
+    nbe::read_back_value(prims, metas, budget, env_size, unfold, value)
+        .map_err(|error| eval_error("failed to read-back value", span.into(), error))
 }
 
 pub fn normalize_term(
     prims: &prim::Env,
     metas: &meta::Env,
+    budget: &Budget,
     values: &var::Env<Rc<domain::Value>>,
     span: impl Into<Option<FileSpan>>,
     term: &Rc<syntax::Term>,
 ) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
-    nbe::normalize_term(prims, metas, values, term).map_err(|error| match span.into() {
-        None => Diagnostic::new_bug(format!("failed to normalize term: {}", error)),
-        Some(span) => Diagnostic::new_bug("failed to normalize term")
-            .with_label(DiagnosticLabel::new_primary(span).with_message(error)),
-    })
+    nbe::normalize_term(prims, metas, budget, values, term)
+        .map_err(|error| eval_error("failed to normalize term", span.into(), error))
 }
 
 pub fn force_value(
     prims: &prim::Env,
     metas: &meta::Env,
+    budget: &Budget,
     span: impl Into<Option<FileSpan>>,
     value: &Rc<domain::Value>,
 ) -> Result<Rc<domain::Value>, Diagnostic<FileSpan>> {
-    nbe::force_value(prims, metas, value).map_err(|error| match span.into() {
-        None => Diagnostic::new_bug(format!("failed to force value: {}", error)),
-        Some(span) => Diagnostic::new_bug("failed to force value")
-            .with_label(DiagnosticLabel::new_primary(span).with_message(error)),
-    })
+    nbe::force_value(prims, metas, budget, value)
+        .map_err(|error| eval_error("failed to force value", span.into(), error))
+}
+
+pub fn value_to_level(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    span: impl Into<Option<FileSpan>>,
+    value: &Rc<domain::Value>,
+) -> Result<domain::Level, Diagnostic<FileSpan>> {
+    nbe::value_to_level(prims, metas, budget, value)
+        .map_err(|error| eval_error("failed to evaluate level", span.into(), error))
+}
+
+pub fn strengthen_level(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    env_size: var::Size,
+    amount: u32,
+    level: &domain::Level,
+) -> Result<Option<domain::Level>, Diagnostic<FileSpan>> {
+    nbe::strengthen_level(prims, metas, budget, env_size, amount, level)
+        .map_err(|error| eval_error("failed to strengthen level", None, error))
+}
+
+pub fn force_level(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    span: impl Into<Option<FileSpan>>,
+    level: &domain::Level,
+) -> Result<domain::Level, Diagnostic<FileSpan>> {
+    nbe::force_level(prims, metas, budget, level)
+        .map_err(|error| eval_error("failed to force level", span.into(), error))
+}
+
+/// Convert an evaluation error into a diagnostic. Errors that the user could
+/// have caused, like calls to `abort` or running out of evaluation budget, are
+/// reported as errors, and everything else is reported as a bug in the
+/// elaborator.
+fn eval_error(message: &str, span: Option<FileSpan>, error: EvalError) -> Diagnostic<FileSpan> {
+    let (diagnostic, label_message) = match &error {
+        EvalError::Abort(abort_message) => (
+            Diagnostic::new_error(format!("evaluation aborted: {}", abort_message)),
+            "aborted while evaluating this term".to_owned(),
+        ),
+        EvalError::OverflowingUniverseLevel => (
+            Diagnostic::new_error("maximum universe level reached"),
+            format!(
+                "cannot represent universes greater than `{}`",
+                UniverseLevel::MAX,
+            ),
+        ),
+        EvalError::LimitExceeded(Limit::Steps(steps)) => (
+            Diagnostic::new_error("evaluation limit exceeded"),
+            format!("evaluation took more than {} steps", steps),
+        ),
+        EvalError::LimitExceeded(Limit::Depth(depth)) => (
+            Diagnostic::new_error("evaluation limit exceeded"),
+            format!("evaluation nested deeper than {} levels", depth),
+        ),
+        EvalError::UnboundVariable(_)
+        | EvalError::UnboundMeta(_)
+        | EvalError::UnknownPrim(_)
+        | EvalError::NoDefinitionInGroup(_)
+        | EvalError::NoIntroInData(_)
+        | EvalError::ForcingThunk => (
+            Diagnostic::new_bug(format!("{}: {}", message, error)),
+            "found while evaluating this term".to_owned(),
+        ),
+        EvalError::NoFieldInRecord { found, .. }
+        | EvalError::NoClauseForIntro { found, .. }
+        | EvalError::UnexpectedIntroFields { found, .. }
+        | EvalError::ExpectedFunction { found }
+        | EvalError::ExpectedRecord { found }
+        | EvalError::ExpectedLiteral { found }
+        | EvalError::ExpectedDataIntro { found }
+        | EvalError::ExpectedEqIntro { found }
+        | EvalError::ExpectedLevel { found } => (
+            Diagnostic::new_bug(format!("{}: {}", message, error)),
+            format!("found `{:?}`", found),
+        ),
+        EvalError::UnexpectedAppMode { .. } => (
+            Diagnostic::new_bug(format!("{}: {}", message, error)),
+            "found while evaluating this term".to_owned(),
+        ),
+    };
+
+    match span {
+        None => diagnostic,
+        Some(span) => {
+            diagnostic.with_label(DiagnosticLabel::new_primary(span).with_message(label_message))
+        },
+    }
 }
@@ -0,0 +1,304 @@
+//! Elaboration of record projections, record updates, and tuples.
+//!
+//! Tuples are sugar for records with the labels `_0`, `_1`, ..., so for
+//! example the type `(x : A) * B x` is elaborated to:
+//!
+//! ```text
+//! Record { _0 : A; _1 : B _0 }
+//! ```
+
+use language_reporting::{Diagnostic, Label as DiagnosticLabel};
+use mltt_concrete::{RecordIntroField, SpannedString, Term};
+use mltt_core::intern::Symbol;
+use mltt_core::{domain, meta, syntax, DocString, Label};
+use mltt_span::FileSpan;
+use std::rc::Rc;
+
+use crate::clause::{self, Clause};
+use crate::{check_term, synth_term, synth_universe, Context, MetaInsertion};
+
+/// Project a field from a record, returning the projection along with the
+/// type of the field, or `None` if the field is not in the record type.
+///
+/// Projections of manifest fields are replaced by their known values.
+pub fn synth_elim(
+    context: &Context,
+    metas: &meta::Env,
+    record: &Rc<syntax::Term>,
+    record_ty: &Rc<domain::Type>,
+    label: &str,
+) -> Result<Option<(Rc<syntax::Term>, Rc<domain::Type>)>, Diagnostic<FileSpan>> {
+    let mut record_ty = record_ty.clone();
+
+    while let domain::Value::RecordTypeExtend(_, current_label, _, current_ty, manifest, rest) =
+        context.force_value(metas, None, &record_ty)?.as_ref()
+    {
+        let expr = match manifest {
+            Some(manifest) => context.read_back_value(metas, None, manifest)?,
+            None => Rc::from(syntax::Term::RecordElim(
+                record.clone(),
+                current_label.clone(),
+            )),
+        };
+
+        if current_label.as_str() == label {
+            return Ok(Some((expr, current_ty.clone())));
+        } else {
+            let expr = match manifest {
+                Some(manifest) => manifest.clone(),
+                None => context.eval_term(metas, None, &expr)?,
+            };
+            record_ty = context.app_closure(metas, rest, expr)?;
+        }
+    }
+
+    Ok(None)
+}
+
+/// Check a record update against the expected type.
+///
+/// The fields that were given are checked against the expected type, and the
+/// remaining fields are projected from the original record. Because the
+/// expected type is instantiated with the new field values as we go, fields
+/// that depend on an updated field are checked again.
+pub fn check_update(
+    context: &Context,
+    metas: &mut meta::Env,
+    span: FileSpan,
+    record: &Rc<syntax::Term>,
+    record_ty: &Rc<domain::Type>,
+    concrete_intro_fields: &[RecordIntroField<'_>],
+    expected_ty: &Rc<domain::Type>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    let mut fields = Vec::<(Label, Rc<syntax::Term>)>::new();
+    let mut expected_ty = expected_ty.clone();
+
+    while let domain::Value::RecordTypeExtend(
+        _,
+        expected_label,
+        _,
+        expected_term_ty,
+        expected_manifest,
+        rest,
+    ) = context.force_value(metas, None, &expected_ty)?.as_ref()
+    {
+        let concrete_intro_field = concrete_intro_fields
+            .iter()
+            .find(|field| field.desugar().0.slice == expected_label.as_str());
+
+        let (term, term_value) = match (concrete_intro_field, expected_manifest) {
+            (Some(concrete_intro_field), _) => {
+                let (_, params, body_ty, body) = concrete_intro_field.desugar();
+                let clause = Clause::new(params, body_ty, &body);
+                let term = clause::check_clause(&context, metas, clause, expected_term_ty)?;
+                let term_value = context.eval_term(metas, body.span(), &term)?;
+                if let Some(expected_manifest) = expected_manifest {
+                    context.unify_values(metas, body.span(), &term_value, expected_manifest)?;
+                }
+
+                (term, term_value)
+            },
+            (None, Some(expected_manifest)) => {
+                let term = context.read_back_value(metas, span, expected_manifest)?;
+                (term, expected_manifest.clone())
+            },
+            (None, None) => {
+                let (term, term_ty) =
+                    match synth_elim(context, metas, record, record_ty, expected_label.as_str())? {
+                        Some((term, term_ty)) => (term, term_ty),
+                        None => {
+                            let message = format!("missing field: `{}`", expected_label);
+                            return Err(Diagnostic::new_error(message).with_label(
+                                DiagnosticLabel::new_primary(span).with_message(format!(
+                                    "the updated record does not have a field named `{}`",
+                                    expected_label,
+                                )),
+                            ));
+                        },
+                    };
+
+                // The type of the original field might differ from the
+                // expected type if it depends on a field that was updated
+                context.unify_values(metas, span, &term_ty, expected_term_ty)?;
+                let term_value = context.eval_term(metas, span, &term)?;
+
+                (term, term_value)
+            },
+        };
+
+        fields.push((expected_label.clone(), term));
+        expected_ty = context.app_closure(metas, rest, term_value)?;
+    }
+
+    match context.force_value(metas, None, &expected_ty)?.as_ref() {
+        domain::Value::RecordTypeEmpty => {},
+        _ => {
+            return Err(Diagnostic::new_error("expected a record type")
+                .with_label(DiagnosticLabel::new_primary(span)));
+        },
+    }
+
+    for (index, concrete_intro_field) in concrete_intro_fields.iter().enumerate() {
+        let found_label = concrete_intro_field.desugar().0;
+        let is_duplicate = concrete_intro_fields[..index]
+            .iter()
+            .any(|field| field.desugar().0.slice == found_label.slice);
+
+        if is_duplicate {
+            let message = format!("duplicate field: `{}`", found_label);
+            return Err(Diagnostic::new_error(message)
+                .with_label(DiagnosticLabel::new_primary(found_label.span())));
+        } else if fields
+            .iter()
+            .all(|(label, _)| label.as_str() != found_label.slice)
+        {
+            let message = format!("field not found: `{}`", found_label);
+            return Err(Diagnostic::new_error(message)
+                .with_label(DiagnosticLabel::new_primary(found_label.span())));
+        }
+    }
+
+    Ok(Rc::from(syntax::Term::RecordIntro(fields)))
+}
+
+/// Synthesize the type of a tuple type, returning the elaborated record type
+/// along with the universe that it lives in.
+pub fn synth_tuple_ty(
+    context: &Context,
+    metas: &mut meta::Env,
+    concrete_components: &[(Option<SpannedString<'_>>, Term<'_>)],
+) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
+    let mut context = context.clone();
+    let mut max_level = domain::Level::constant(0);
+    let mut ty_fields = Vec::new();
+
+    for (index, (name, concrete_ty)) in concrete_components.iter().enumerate() {
+        let (ty, ty_level) = synth_universe(&context, metas, concrete_ty)?;
+        let ty_level = context
+            .strengthen_level(metas, index as u32, &ty_level)?
+            .unwrap_or_else(domain::Level::omega);
+        let ty_value = context.eval_term(metas, concrete_ty.span(), &ty)?;
+
+        match name {
+            Some(name) => context.add_param(*name, ty_value),
+            None => context.add_fresh_param(ty_value),
+        };
+        max_level = max_level.max(&ty_level);
+
+        let name_hint = name.map(|name| Symbol::intern(name.slice));
+        ty_fields.push((
+            DocString::from(""),
+            Label::tuple(index),
+            name_hint,
+            ty,
+            None,
+        ));
+    }
+
+    Ok((
+        Rc::from(syntax::Term::RecordType(ty_fields)),
+        Rc::from(domain::Value::Universe(max_level)),
+    ))
+}
+
+/// Check a tuple against the expected type.
+///
+/// If the expected type is not yet known to be a record type, we fall back to
+/// synthesizing the type of the tuple.
+pub fn check_tuple_intro(
+    context: &Context,
+    metas: &mut meta::Env,
+    span: FileSpan,
+    concrete_elements: &[Term<'_>],
+    expected_ty: &Rc<domain::Type>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    match context.force_value(metas, None, expected_ty)?.as_ref() {
+        domain::Value::RecordTypeExtend(..) => {},
+        _ => {
+            let (term, term_ty) = synth_tuple_intro(context, metas, concrete_elements)?;
+            context.unify_values(metas, span, &term_ty, expected_ty)?;
+            return Ok(term);
+        },
+    }
+
+    let mut fields = Vec::new();
+    let mut expected_ty = expected_ty.clone();
+
+    for (index, concrete_element) in concrete_elements.iter().enumerate() {
+        let forced_ty = context.force_value(metas, None, &expected_ty)?;
+        let (expected_label, expected_element_ty, expected_manifest, rest) = match forced_ty
+            .as_ref()
+        {
+            domain::Value::RecordTypeExtend(_, label, _, ty, manifest, rest)
+                if *label == Label::tuple(index) =>
+            {
+                Ok((label, ty, manifest, rest))
+            },
+            domain::Value::RecordTypeEmpty => Err(Diagnostic::new_error("too many elements found")
+                .with_label(
+                    DiagnosticLabel::new_primary(concrete_element.span())
+                        .with_message("unexpected element"),
+                )),
+            _ => Err(Diagnostic::new_error("mismatched tuple").with_label(
+                DiagnosticLabel::new_primary(span)
+                    .with_message("the expected type is not a tuple type"),
+            )),
+        }?;
+
+        let term = check_term(context, metas, concrete_element, expected_element_ty)?;
+        let term_value = context.eval_term(metas, concrete_element.span(), &term)?;
+        if let Some(expected_manifest) = expected_manifest {
+            context.unify_values(
+                metas,
+                concrete_element.span(),
+                &term_value,
+                expected_manifest,
+            )?;
+        }
+
+        fields.push((expected_label.clone(), term));
+        expected_ty = context.app_closure(metas, rest, term_value)?;
+    }
+
+    match context.force_value(metas, None, &expected_ty)?.as_ref() {
+        domain::Value::RecordTypeEmpty => Ok(Rc::from(syntax::Term::RecordIntro(fields))),
+        _ => Err(Diagnostic::new_error("not enough elements provided")
+            .with_label(DiagnosticLabel::new_primary(span))),
+    }
+}
+
+/// Synthesize the type of a tuple, returning the elaborated record along with
+/// a non-dependent record type.
+pub fn synth_tuple_intro(
+    context: &Context,
+    metas: &mut meta::Env,
+    concrete_elements: &[Term<'_>],
+) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
+    let mut fields = Vec::new();
+    let mut ty_fields = Vec::new();
+
+    for (index, concrete_element) in concrete_elements.iter().enumerate() {
+        let (term, term_ty) = synth_term(MetaInsertion::Yes, context, metas, concrete_element)?;
+        // The type of each field is in the scope of the earlier fields
+        let term_ty = context
+            .read_back_value(metas, concrete_element.span(), &term_ty)?
+            .shift(index as u32);
+
+        fields.push((Label::tuple(index), term));
+        ty_fields.push((
+            DocString::from(""),
+            Label::tuple(index),
+            None,
+            term_ty,
+            None,
+        ));
+    }
+
+    // Annotate the record with its type, so that the elaborated term can be
+    // synthesized as well
+    let ty = Rc::from(syntax::Term::RecordType(ty_fields));
+    let ty_value = context.eval_term(metas, None, &ty)?;
+    let term = Rc::from(syntax::Term::RecordIntro(fields));
+
+    Ok((Rc::from(syntax::Term::Ann(term, ty)), ty_value))
+}
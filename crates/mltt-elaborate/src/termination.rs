@@ -0,0 +1,653 @@
+//! Termination checking of recursive definitions.
+//!
+//! We use a simple syntactic check, where each recursive call must be made on
+//! an argument that is a strict subterm of one of the caller's parameters.
+//! Strict subterms are found by projecting fields out of records, or by
+//! binding the fields of a constructor in a case split.
+//! Mutually recursive definitions must agree on the position of the parameter
+//! that decreases, so that the recursion is guaranteed to bottom out.
+//!
+//! Definitions that are marked as `partial` are exempt from this check, but
+//! other definitions in their group may not call them recursively.
+
+use language_reporting::{Diagnostic, Label as DiagnosticLabel};
+use mltt_core::syntax::{Guard, Item, Term};
+use mltt_core::var;
+use mltt_span::FileSpan;
+use std::rc::Rc;
+
+/// A definition in a group of mutually recursive definitions.
+pub struct RecursiveDefn<'a> {
+    /// The span of the definition's label, used for reporting errors.
+    pub span: FileSpan,
+    /// Whether the definition was marked as `partial`.
+    pub is_partial: bool,
+    /// The elaborated body of the definition, with each of the definitions in
+    /// the group bound as variables.
+    pub body: &'a Rc<Term>,
+}
+
+/// A recursive call, found in the body of a definition.
+struct Call {
+    /// The position of the definition being called in the group.
+    callee: usize,
+    /// For each of the arguments of the call, the positions of the caller's
+    /// parameters that it is structurally smaller than.
+    args: Vec<Vec<usize>>,
+}
+
+/// Check that the definitions in a group terminate, returning the guards that
+/// restrict when they can be unfolded during evaluation.
+pub fn check_group(defns: &[RecursiveDefn<'_>]) -> Result<Vec<Guard>, Diagnostic<FileSpan>> {
+    let calls = defns
+        .iter()
+        .map(|defn| collect_calls(defns.len(), defn.body))
+        .collect::<Vec<_>>();
+
+    // Find the definitions that each definition can reach through some chain
+    // of calls, so that we know which definitions are in a cycle together.
+    let mut reaches = calls
+        .iter()
+        .map(|calls| {
+            let mut reaches = vec![false; defns.len()];
+            for call in calls {
+                reaches[call.callee] = true;
+            }
+            reaches
+        })
+        .collect::<Vec<_>>();
+    for middle in 0..defns.len() {
+        let middle_reaches = reaches[middle].clone();
+        for start_reaches in &mut reaches {
+            if start_reaches[middle] {
+                for (reach, middle_reach) in
+                    Iterator::zip(start_reaches.iter_mut(), &middle_reaches)
+                {
+                    *reach |= *middle_reach;
+                }
+            }
+        }
+    }
+
+    let mut guards = defns
+        .iter()
+        .map(|defn| {
+            if defn.is_partial {
+                Guard::Partial
+            } else {
+                Guard::Always
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Check each cycle of definitions, finding the positions of the
+    // parameters that decrease on each recursive call
+    let mut checked = vec![false; defns.len()];
+    for (index, defn) in defns.iter().enumerate() {
+        if checked[index] || defn.is_partial || !reaches[index][index] {
+            continue;
+        }
+
+        let cycle = (0..defns.len())
+            .filter(|&other| reaches[index][other] && reaches[other][index])
+            .collect::<Vec<_>>();
+        for &member in &cycle {
+            checked[member] = true;
+        }
+
+        match find_decreasing_params(defns, &calls, &cycle) {
+            Some(positions) => {
+                for (&member, position) in Iterator::zip(cycle.iter(), positions) {
+                    if !defns[member].is_partial {
+                        guards[member] = Guard::Structural(position);
+                    }
+                }
+            },
+            None => {
+                return Err(Diagnostic::new_error(
+                    "recursive definition is not structurally decreasing",
+                )
+                .with_label(
+                    DiagnosticLabel::new_primary(defn.span).with_message(
+                        "mark the definition as `partial` to skip termination checking",
+                    ),
+                ));
+            },
+        }
+    }
+
+    Ok(guards)
+}
+
+/// Search for an assignment of decreasing parameter positions to the members
+/// of a cycle of definitions, such that every recursive call within the cycle
+/// is made on a structurally smaller argument.
+fn find_decreasing_params(
+    defns: &[RecursiveDefn<'_>],
+    calls: &[Vec<Call>],
+    cycle: &[usize],
+) -> Option<Vec<usize>> {
+    let arities = cycle
+        .iter()
+        .map(|&member| {
+            if defns[member].is_partial {
+                1 // Partial definitions don't need a decreasing parameter
+            } else {
+                fun_arity(defns[member].body)
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut positions = vec![0; cycle.len()];
+
+    // Try every combination of positions, stopping at the first one that works
+    if arities.iter().any(|&arity| arity == 0) {
+        return None;
+    }
+    loop {
+        let position_of = |defn_index: usize| {
+            let member = cycle.iter().position(|&member| member == defn_index)?;
+            if defns[defn_index].is_partial {
+                None
+            } else {
+                Some(positions[member])
+            }
+        };
+
+        let is_decreasing = cycle.iter().all(|&caller| {
+            defns[caller].is_partial
+                || calls[caller]
+                    .iter()
+                    .filter(|call| cycle.contains(&call.callee))
+                    .all(
+                        |call| match (position_of(caller), position_of(call.callee)) {
+                            (Some(caller_position), Some(callee_position)) => {
+                                match call.args.get(callee_position) {
+                                    Some(smaller_than) => smaller_than.contains(&caller_position),
+                                    None => false,
+                                }
+                            },
+                            (_, _) => false,
+                        },
+                    )
+        });
+        if is_decreasing {
+            return Some(positions);
+        }
+
+        // Move on to the next combination of positions
+        let mut member = 0;
+        loop {
+            if member == cycle.len() {
+                return None;
+            }
+            positions[member] += 1;
+            if positions[member] < arities[member] {
+                break;
+            }
+            positions[member] = 0;
+            member += 1;
+        }
+    }
+}
+
+/// The number of parameters that a definition introduces up-front.
+fn fun_arity(term: &Rc<Term>) -> usize {
+    match term.as_ref() {
+        Term::FunIntro(_, _, body) => 1 + fun_arity(body),
+        _ => 0,
+    }
+}
+
+/// Collect the recursive calls made in the body of a definition.
+fn collect_calls(group_len: usize, body: &Rc<Term>) -> Vec<Call> {
+    let mut body = body;
+    let mut arity = 0;
+    while let Term::FunIntro(_, _, next_body) = body.as_ref() {
+        arity += 1;
+        body = next_body;
+    }
+
+    let mut collector = CallCollector {
+        group_len: group_len as u32,
+        arity,
+        aliases: Vec::new(),
+        fields: Vec::new(),
+        calls: Vec::new(),
+    };
+    collector.term(arity, body);
+    collector.calls
+}
+
+/// Walks the body of a definition, collecting the recursive calls that it
+/// makes to the definitions in its group.
+struct CallCollector {
+    /// The number of definitions in the group.
+    group_len: u32,
+    /// The number of parameters that the definition introduces up-front.
+    arity: u32,
+    /// The levels of let-bound variables that refer directly to one of the
+    /// parameters, along with the position of that parameter.
+    aliases: Vec<(u32, usize)>,
+    /// The levels of variables bound to the fields of a constructor, along
+    /// with the positions of the parameters that they are smaller than.
+    fields: Vec<(u32, Vec<usize>)>,
+    /// The recursive calls that have been found so far.
+    calls: Vec<Call>,
+}
+
+impl CallCollector {
+    /// Lookup the definition in the group that a variable refers to, where
+    /// `depth` is the number of binders between the variable and the start of
+    /// the definition's body.
+    fn lookup_defn(&self, depth: u32, var_index: var::Index) -> Option<usize> {
+        let offset = var_index.0.checked_sub(depth)?;
+        if offset < self.group_len {
+            // The last definition of the group is bound innermost
+            Some((self.group_len - (offset + 1)) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Lookup the position of the parameter of the definition that a variable
+    /// refers to.
+    fn lookup_param(&self, depth: u32, var_index: var::Index) -> Option<usize> {
+        let level = depth.checked_sub(var_index.0 + 1)?;
+        if level < self.arity {
+            Some(level as usize)
+        } else {
+            let alias = self.aliases.iter().rev().find(|(l, _)| *l == level);
+            alias.map(|(_, position)| *position)
+        }
+    }
+
+    /// Lookup the positions of the parameters that a variable bound to the
+    /// field of a constructor is smaller than.
+    fn lookup_field(&self, depth: u32, var_index: var::Index) -> Vec<usize> {
+        let level = depth.checked_sub(var_index.0 + 1);
+        let field = self.fields.iter().rev().find(|(l, _)| Some(*l) == level);
+        field.map_or(Vec::new(), |(_, positions)| positions.clone())
+    }
+
+    /// The positions of the parameters that a term is structurally smaller
+    /// than.
+    ///
+    /// Projecting a field out of a record results in a strict subterm of
+    /// that record, as do the fields of a constructor.
+    fn smaller_than(&self, depth: u32, term: &Rc<Term>) -> Vec<usize> {
+        let mut term = term;
+        let mut projections = 0;
+        while let Term::RecordElim(record, _) = term.as_ref() {
+            projections += 1;
+            term = record;
+        }
+
+        match term.as_ref() {
+            Term::Var(var_index) => {
+                let mut positions = self.lookup_field(depth, *var_index);
+                if projections > 0 {
+                    positions.extend(self.lookup_param(depth, *var_index));
+                }
+                positions
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// The positions of the parameters that a term is either equal to, or
+    /// structurally smaller than.
+    fn subterm_of(&self, depth: u32, term: &Rc<Term>) -> Vec<usize> {
+        let mut positions = self.smaller_than(depth, term);
+        if let Term::Var(var_index) = term.as_ref() {
+            positions.extend(self.lookup_param(depth, *var_index));
+        }
+        positions
+    }
+
+    fn items(&mut self, depth: u32, items: &[Item]) -> u32 {
+        let mut depth = depth;
+        for item in items {
+            match item {
+                Item::Declaration(_, _, ty) => self.term(depth, ty),
+                Item::Definition(_, _, term) => {
+                    self.term(depth, term);
+                    // Keep track of definitions that alias the parameters, or
+                    // their subterms, like the scrutinees of case expressions
+                    if let Term::Var(var_index) = term.as_ref() {
+                        match self.lookup_param(depth, *var_index) {
+                            Some(position) => self.aliases.push((depth, position)),
+                            None => {
+                                let positions = self.lookup_field(depth, *var_index);
+                                if !positions.is_empty() {
+                                    self.fields.push((depth, positions));
+                                }
+                            },
+                        }
+                    }
+                    depth += 1;
+                },
+            }
+        }
+        depth
+    }
+
+    fn term(&mut self, depth: u32, term: &Rc<Term>) {
+        match term.as_ref() {
+            Term::Var(var_index) => {
+                if let Some(callee) = self.lookup_defn(depth, *var_index) {
+                    let args = Vec::new();
+                    self.calls.push(Call { callee, args });
+                }
+            },
+            Term::Meta(_) | Term::Prim(_) => {},
+
+            Term::Ann(term, term_ty) => {
+                self.term(depth, term);
+                self.term(depth, term_ty);
+            },
+            Term::Let(items, body) => {
+                let (aliases_len, fields_len) = (self.aliases.len(), self.fields.len());
+                let body_depth = self.items(depth, items);
+                self.term(body_depth, body);
+                self.aliases.truncate(aliases_len);
+                self.fields.truncate(fields_len);
+            },
+            Term::Fix(_, defns) => {
+                let body_depth = depth + defns.len() as u32;
+                for (_, _, ty, body) in defns.iter() {
+                    self.term(depth, ty);
+                    self.term(body_depth, body);
+                }
+            },
+
+            Term::LiteralType(_) | Term::LiteralIntro(_) => {},
+            Term::LiteralElim(scrutinee, clauses, default_body) => {
+                self.term(depth, scrutinee);
+                for (_, body) in clauses.iter() {
+                    self.term(depth, body);
+                }
+                self.term(depth, default_body);
+            },
+
+            Term::FunType(_, _, param_ty, body_ty) => {
+                self.term(depth, param_ty);
+                self.term(depth + 1, body_ty);
+            },
+            Term::FunIntro(_, _, body) => self.term(depth + 1, body),
+            Term::FunElim(_, _, _) => {
+                let mut head = term;
+                let mut args = Vec::new();
+                while let Term::FunElim(fun, _, arg) = head.as_ref() {
+                    args.push(arg);
+                    head = fun;
+                }
+                args.reverse();
+
+                match head.as_ref() {
+                    Term::Var(var_index) => {
+                        if let Some(callee) = self.lookup_defn(depth, *var_index) {
+                            let args = args
+                                .iter()
+                                .map(|arg| self.smaller_than(depth, arg))
+                                .collect();
+                            self.calls.push(Call { callee, args });
+                        }
+                    },
+                    _ => self.term(depth, head),
+                }
+
+                for arg in args {
+                    self.term(depth, arg);
+                }
+            },
+
+            Term::RecordType(ty_fields) => {
+                for (index, (_, _, _, ty, manifest)) in ty_fields.iter().enumerate() {
+                    self.term(depth + index as u32, ty);
+                    if let Some(manifest) = manifest {
+                        self.term(depth + index as u32, manifest);
+                    }
+                }
+            },
+            Term::RecordIntro(intro_fields) => {
+                for (_, term) in intro_fields {
+                    self.term(depth, term);
+                }
+            },
+            Term::RecordElim(record, _) => self.term(depth, record),
+
+            Term::DataType(_) | Term::DataIntro(_, _) => {},
+            Term::DataElim(scrutinee, clauses) => {
+                self.term(depth, scrutinee);
+                let positions = self.subterm_of(depth, scrutinee);
+                for (_, name_hints, body) in clauses.iter() {
+                    let fields_len = self.fields.len();
+                    let field_count = name_hints.len() as u32;
+                    if !positions.is_empty() {
+                        for level in depth..depth + field_count {
+                            self.fields.push((level, positions.clone()));
+                        }
+                    }
+                    self.term(depth + field_count, body);
+                    self.fields.truncate(fields_len);
+                }
+            },
+
+            Term::EqType(ty, lhs, rhs) => {
+                self.term(depth, ty);
+                self.term(depth, lhs);
+                self.term(depth, rhs);
+            },
+            Term::EqIntro => {},
+            Term::EqElim(proof, motive, body) => {
+                self.term(depth, proof);
+                self.term(depth, motive);
+                self.term(depth, body);
+            },
+
+            Term::LevelType | Term::LevelIntro(_) | Term::LevelOmega => {},
+            Term::LevelShift(level, _) => self.term(depth, level),
+            Term::LevelMax(lhs, rhs) => {
+                self.term(depth, lhs);
+                self.term(depth, rhs);
+            },
+
+            Term::Universe(level) => self.term(depth, level),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mltt_core::syntax::Item::Definition;
+    use mltt_core::{AppMode, DocString, Label};
+    use mltt_span::Files;
+
+    fn span() -> FileSpan {
+        let mut files = Files::new();
+        let file_id = files.add("test", "");
+        FileSpan::initial(file_id)
+    }
+
+    fn fun_intro(body: Term) -> Term {
+        Term::FunIntro(AppMode::Explicit, None, Rc::from(body))
+    }
+
+    fn fun_elim(fun: Term, arg: Term) -> Term {
+        Term::FunElim(Rc::from(fun), AppMode::Explicit, Rc::from(arg))
+    }
+
+    fn record_elim(record: Term, label: &str) -> Term {
+        Term::RecordElim(Rc::from(record), mltt_core::Label::new(label))
+    }
+
+    fn case(scrutinee: Term, clauses: Vec<(&str, usize, Term)>) -> Term {
+        let scrutinee = Definition(DocString::from(""), Label::new("_"), Rc::from(scrutinee));
+        let clauses = clauses
+            .into_iter()
+            .map(|(label, field_count, body)| {
+                (Label::new(label), vec![None; field_count], Rc::from(body))
+            })
+            .collect::<Vec<_>>();
+        let body = Term::DataElim(Rc::from(Term::var(0)), Rc::from(clauses));
+
+        Term::Let(vec![scrutinee], Rc::from(body))
+    }
+
+    #[test]
+    fn non_recursive() {
+        // fun x => x
+        let body = Rc::from(fun_intro(Term::var(0)));
+        let defns = [RecursiveDefn {
+            span: span(),
+            is_partial: false,
+            body: &body,
+        }];
+
+        assert_eq!(check_group(&defns).unwrap(), vec![Guard::Always]);
+    }
+
+    #[test]
+    fn non_decreasing() {
+        // fun x => loop x
+        let body = Rc::from(fun_intro(fun_elim(Term::var(1), Term::var(0))));
+        let defns = [RecursiveDefn {
+            span: span(),
+            is_partial: false,
+            body: &body,
+        }];
+
+        assert!(check_group(&defns).is_err());
+    }
+
+    #[test]
+    fn non_decreasing_partial() {
+        // fun x => loop x
+        let body = Rc::from(fun_intro(fun_elim(Term::var(1), Term::var(0))));
+        let defns = [RecursiveDefn {
+            span: span(),
+            is_partial: true,
+            body: &body,
+        }];
+
+        assert_eq!(check_group(&defns).unwrap(), vec![Guard::Partial]);
+    }
+
+    #[test]
+    fn decreasing_projection() {
+        // fun x y => f x y.next
+        let body = Rc::from(fun_intro(fun_intro(fun_elim(
+            fun_elim(Term::var(2), Term::var(1)),
+            record_elim(Term::var(0), "next"),
+        ))));
+        let defns = [RecursiveDefn {
+            span: span(),
+            is_partial: false,
+            body: &body,
+        }];
+
+        assert_eq!(check_group(&defns).unwrap(), vec![Guard::Structural(1)]);
+    }
+
+    #[test]
+    fn decreasing_data_field() {
+        // fun x => case x { nil => x; cons y ys => f ys }
+        let body = Rc::from(fun_intro(case(
+            Term::var(0),
+            vec![
+                ("nil", 0, Term::var(0)),
+                ("cons", 2, fun_elim(Term::var(4), Term::var(0))),
+            ],
+        )));
+        let defns = [RecursiveDefn {
+            span: span(),
+            is_partial: false,
+            body: &body,
+        }];
+
+        assert_eq!(check_group(&defns).unwrap(), vec![Guard::Structural(0)]);
+    }
+
+    #[test]
+    fn non_decreasing_data_scrutinee() {
+        // fun x => case x { nil => x; cons y ys => f x }
+        let body = Rc::from(fun_intro(case(
+            Term::var(0),
+            vec![
+                ("nil", 0, Term::var(0)),
+                ("cons", 2, fun_elim(Term::var(4), Term::var(2))),
+            ],
+        )));
+        let defns = [RecursiveDefn {
+            span: span(),
+            is_partial: false,
+            body: &body,
+        }];
+
+        assert!(check_group(&defns).is_err());
+    }
+
+    #[test]
+    fn mutual_decreasing_projection() {
+        // fun x => g x.next
+        let body1 = Rc::from(fun_intro(fun_elim(
+            Term::var(2),
+            record_elim(Term::var(0), "next"),
+        )));
+        // fun x => f x.next
+        let body2 = Rc::from(fun_intro(fun_elim(
+            Term::var(3),
+            record_elim(Term::var(0), "next"),
+        )));
+        // fun x => f x
+        let body3 = Rc::from(fun_intro(fun_elim(Term::var(3), Term::var(0))));
+        let defns = [
+            RecursiveDefn {
+                span: span(),
+                is_partial: false,
+                body: &body1,
+            },
+            RecursiveDefn {
+                span: span(),
+                is_partial: false,
+                body: &body2,
+            },
+            RecursiveDefn {
+                span: span(),
+                is_partial: false,
+                body: &body3,
+            },
+        ];
+
+        assert_eq!(
+            check_group(&defns).unwrap(),
+            vec![Guard::Structural(0), Guard::Structural(0), Guard::Always],
+        );
+    }
+
+    #[test]
+    fn call_to_partial() {
+        // fun x => g x.next
+        let body1 = Rc::from(fun_intro(fun_elim(
+            Term::var(1),
+            record_elim(Term::var(0), "next"),
+        )));
+        // fun x => f x
+        let body2 = Rc::from(fun_intro(fun_elim(Term::var(2), Term::var(0))));
+        let defns = [
+            RecursiveDefn {
+                span: span(),
+                is_partial: false,
+                body: &body1,
+            },
+            RecursiveDefn {
+                span: span(),
+                is_partial: true,
+                body: &body2,
+            },
+        ];
+
+        assert!(check_group(&defns).is_err());
+    }
+}
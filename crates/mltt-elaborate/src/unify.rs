@@ -2,126 +2,703 @@
 
 use language_reporting::{Diagnostic, Label as DiagnosticLabel};
 use mltt_core::literal::{LiteralIntro, LiteralType};
-use mltt_core::{domain, meta, prim, syntax, var, AppMode};
+use mltt_core::nbe::{Budget, Unfold};
+use mltt_core::{domain, meta, prim, syntax, validate, var, AppMode, Label, UniverseLevel};
 use mltt_span::FileSpan;
 use std::rc::Rc;
 
-use crate::nbe;
+use crate::{nbe, zonk, Context};
 
-/// Check that all entries in a spine are bound variables.
+/// The reason that unification did not succeed.
+#[derive(Debug)]
+pub enum UnifyError {
+    /// Unification was blocked on an unsolved metavariable, and might succeed
+    /// if it is retried after more metavariables have been solved.
+    Blocked,
+    /// A mismatch was found between the two values.
+    Mismatch(Mismatch),
+    /// Unification failed for some other reason.
+    Failed(Diagnostic<FileSpan>),
+}
+
+impl From<Diagnostic<FileSpan>> for UnifyError {
+    fn from(src: Diagnostic<FileSpan>) -> UnifyError {
+        UnifyError::Failed(src)
+    }
+}
+
+/// A mismatch that was found during unification.
+#[derive(Debug)]
+pub struct Mismatch {
+    /// The size of the context that the mismatch was found in. This might be
+    /// larger than the size of the context that unification started in, if the
+    /// mismatch was found underneath some binders.
+    size: var::Size,
+    /// The location of the subterm that the mismatch was found in.
+    span: FileSpan,
+    /// The kind of mismatch.
+    kind: MismatchKind,
+}
+
+/// The kinds of mismatches that can be found during unification.
+#[derive(Debug)]
+enum MismatchKind {
+    /// The values were not equal.
+    Values(Rc<domain::Value>, Rc<domain::Value>),
+    /// The application modes of two functions were not equal.
+    AppMode(AppMode, AppMode),
+    /// The labels of two record fields were not equal.
+    Label(Label, Label),
+    /// The expected record field had a manifest value, but the found field
+    /// did not.
+    ManifestField(Label),
+    /// The first universe level was larger than the second.
+    UniverseLevel(domain::Level, domain::Level),
+    /// A variable was not in the scope of a metavariable solution.
+    ScopeEscape(meta::Index, var::Level),
+    /// A metavariable occurred in its own solution.
+    Occurs(meta::Index),
+}
+
+/// Create a mismatch error.
+fn mismatch<T>(size: var::Size, span: FileSpan, kind: MismatchKind) -> Result<T, UnifyError> {
+    Err(UnifyError::Mismatch(Mismatch { size, span, kind }))
+}
+
+/// Describe an application mode, for use in error messages.
+fn describe_app_mode(app_mode: &AppMode) -> String {
+    match app_mode {
+        AppMode::Explicit => "an explicit application".to_owned(),
+        AppMode::Implicit(label) => format!("an implicit application of `{}`", label),
+        AppMode::Instance(label) => format!("an instance application of `{}`", label),
+    }
+}
+
+/// Pretty print a value that was found in a mismatch, unfolding any solved
+/// metavariables.
+fn value_to_string(context: &Context, metas: &meta::Env, value: &Rc<domain::Value>) -> String {
+    context
+        .value_to_doc(metas, value)
+        .pretty(1_000_000_000)
+        .to_string()
+}
+
+/// Convert a unification mismatch into a diagnostic, pretty printing both
+/// sides of the original problem, along with the offending subterms.
+pub fn mismatch_to_diagnostic(
+    context: &Context,
+    metas: &meta::Env,
+    span: FileSpan,
+    value1: &Rc<domain::Value>,
+    value2: &Rc<domain::Value>,
+    mismatch: &Mismatch,
+) -> Diagnostic<FileSpan> {
+    // The mismatch might have been found underneath some binders, so we give
+    // them fresh names when pretty printing the offending subterms
+    let mut mismatch_context = context.clone();
+    mismatch_context.add_unknown_params(mismatch.size);
+    let mismatch_to_string = |value| value_to_string(&mismatch_context, metas, value);
+
+    let (message, detail) = match &mismatch.kind {
+        MismatchKind::Values(found, expected) => (
+            "mismatched types",
+            format!(
+                "`{}` is not equal to `{}`",
+                mismatch_to_string(found),
+                mismatch_to_string(expected),
+            ),
+        ),
+        MismatchKind::AppMode(found, expected) => (
+            "mismatched application modes",
+            format!(
+                "expected {}, found {}",
+                describe_app_mode(expected),
+                describe_app_mode(found),
+            ),
+        ),
+        MismatchKind::Label(found, expected) => (
+            "mismatched record labels",
+            format!("expected field `{}`, found field `{}`", expected, found),
+        ),
+        MismatchKind::ManifestField(label) => (
+            "mismatched record fields",
+            format!("expected field `{}` to have a known value", label),
+        ),
+        MismatchKind::UniverseLevel(found, expected) => {
+            let found = Rc::from(domain::Value::Universe(found.clone()));
+            let expected = Rc::from(domain::Value::Universe(expected.clone()));
+            (
+                "mismatched universe levels",
+                format!(
+                    "`{}` is not a subtype of `{}`",
+                    mismatch_to_string(&found),
+                    mismatch_to_string(&expected),
+                ),
+            )
+        },
+        MismatchKind::ScopeEscape(meta_index, var_level) => {
+            let var = Rc::from(domain::Value::var(*var_level));
+            (
+                "variable escapes the scope of a metavariable",
+                format!(
+                    "`{}` is not in the scope of `{}`",
+                    mismatch_to_string(&var),
+                    meta_index,
+                ),
+            )
+        },
+        MismatchKind::Occurs(meta_index) => (
+            "occurs check failed",
+            format!(
+                "the metavariable `{}` would occur in its own solution",
+                meta_index
+            ),
+        ),
+    };
+
+    Diagnostic::new_error(message)
+        .with_label(DiagnosticLabel::new_primary(span).with_message(format!(
+            "expected `{}`, found `{}`",
+            value_to_string(context, metas, value2),
+            value_to_string(context, metas, value1),
+        )))
+        .with_label(DiagnosticLabel::new_secondary(mismatch.span).with_message(detail))
+}
+
+/// The parameters of a metavariable solution, derived from the spine that the
+/// metavariable was applied to.
+///
+/// Parameters that can't be referred to in the solution are set to `None`.
+type Params = Vec<(AppMode, Option<var::Level>)>;
+
+/// Check that all entries in a spine are bound variables, returning `None` if
+/// the spine is not a pattern.
+///
+/// Variables that occur more than once in the spine (ie. non-linear spines) are
+/// ambiguous, so we drop them from the resulting parameters, preventing them
+/// from being referred to in the solution.
 fn check_spine(
     prims: &prim::Env,
     metas: &meta::Env,
+    budget: &Budget,
     span: FileSpan,
     spine: &domain::Spine,
-) -> Result<im::Vector<var::Level>, Diagnostic<FileSpan>> {
-    spine
-        .iter()
-        .map(|elim| {
-            if let domain::Elim::Fun(_, arg) = elim {
-                if let domain::Value::Neutral(domain::Head::Var(var_level), spine) =
-                    nbe::force_value(prims, metas, span, arg)?.as_ref()
-                {
-                    if spine.is_empty() {
-                        return Ok(*var_level);
-                    }
+) -> Result<Option<Params>, Diagnostic<FileSpan>> {
+    let mut params = Vec::with_capacity(spine.len());
+    for elim in spine {
+        match elim {
+            domain::Elim::Fun(app_mode, arg) => {
+                match nbe::force_value(prims, metas, budget, span, arg)?.as_ref() {
+                    domain::Value::Neutral(domain::Head::Var(var_level), spine)
+                        if spine.is_empty() =>
+                    {
+                        params.push((app_mode.clone(), Some(*var_level)));
+                    },
+                    _ => return Ok(None),
                 }
-            }
+            },
+            domain::Elim::Literal(_)
+            | domain::Elim::Record(_)
+            | domain::Elim::Data(_)
+            | domain::Elim::Eq(_, _) => {
+                return Ok(None);
+            },
+        }
+    }
+
+    let var_levels = params
+        .iter()
+        .map(|(_, var_level)| *var_level)
+        .collect::<Vec<_>>();
+    for (_, param) in &mut params {
+        if var_levels
+            .iter()
+            .filter(|var_level| **var_level == *param)
+            .count()
+            > 1
+        {
+            *param = None;
+        }
+    }
+
+    Ok(Some(params))
+}
+
+/// A partial renaming from the variables in the context of a unification
+/// problem to the parameters of a metavariable solution.
+struct Renaming<'params> {
+    /// The size of the context of the unification problem.
+    size: var::Size,
+    /// The parameters of the metavariable solution.
+    params: &'params [(AppMode, Option<var::Level>)],
+}
+
+impl<'params> Renaming<'params> {
+    /// Rename a variable that was found under `local_size` binders in the
+    /// solution candidate, returning `None` if it is out of scope.
+    fn rename_var(&self, local_size: u32, var_index: var::Index) -> Option<var::Index> {
+        if var_index.0 < local_size {
+            // Bound inside the solution candidate, so no renaming is needed
+            return Some(var_index);
+        }
+
+        let var_level = (self.size + local_size).level(var_index);
+        let position = self
+            .params
+            .iter()
+            .position(|(_, param)| *param == Some(var_level))?;
+
+        Some(var::Index(
+            local_size + (self.params.len() - (position + 1)) as u32,
+        ))
+    }
+}
+
+/// Split a term into a metavariable and a spine of function arguments, if it
+/// is a metavariable application.
+fn meta_spine(term: &Rc<syntax::Term>) -> Option<(meta::Index, Vec<(AppMode, Rc<syntax::Term>)>)> {
+    match term.as_ref() {
+        syntax::Term::Meta(meta_index) => Some((*meta_index, Vec::new())),
+        syntax::Term::FunElim(fun, app_mode, arg) => {
+            let (meta_index, mut args) = meta_spine(fun)?;
+            args.push((app_mode.clone(), arg.clone()));
+            Some((meta_index, args))
+        },
+        _ => None,
+    }
+}
+
+/// Prune the parameters of an unsolved metavariable that are not marked as
+/// kept, by solving it with a fresh metavariable that does not depend on them.
+///
+/// The parameters are given by their application modes, along with whether
+/// they should be kept. The type of the fresh metavariable is the type of the
+/// original metavariable, strengthened over the kept parameters. If it depends
+/// on any of the pruned parameters, then pruning is blocked until the
+/// metavariables in the type are solved.
+///
+/// Returns the fresh metavariable.
+fn prune_meta(
+    prims: &prim::Env,
+    metas: &mut meta::Env,
+    budget: &Budget,
+    meta_index: meta::Index,
+    keep: &[(AppMode, bool)],
+) -> Result<meta::Index, UnifyError> {
+    let (span, arity, ty) = match metas.lookup_solution(meta_index) {
+        Some((span, meta::Solution::Unsolved, arity, ty)) => (*span, *arity, ty.clone()),
+        Some((_, meta::Solution::Solved(_), _, _)) | None => {
+            let message = format!("attempted to prune a solved metavariable: `{}`", meta_index);
+            return Err(UnifyError::Failed(Diagnostic::new_bug(message)));
+        },
+    };
+
+    log::trace!("pruning metavariable: {}", meta_index);
+
+    // Only the parameters that the metavariable was created with can be
+    // pruned, because the other arguments are bound by its type
+    if keep.len() != arity.0 as usize {
+        log::trace!("pruning blocked by arity: {}", meta_index);
+        return Err(UnifyError::Blocked);
+    }
+
+    // Strengthen the type of the metavariable over the kept parameters. The
+    // type is in the scope of the parameters of the metavariable, so it must
+    // not refer to any of the pruned parameters.
+    let ty_term = nbe::read_back_value(prims, metas, budget, arity, Unfold::All, span, &ty)?;
+    let free_vars = ty_term.free_vars(0);
+    let is_pruned = |position: usize| !keep[position].1;
+    if free_vars
+        .iter()
+        .any(|var_index| is_pruned(arity.level(*var_index).0 as usize))
+    {
+        log::trace!("pruning blocked by type: {}", meta_index);
+        return Err(UnifyError::Blocked);
+    }
+    let pruned_arity = var::Size(keep.iter().filter(|(_, keep)| *keep).count() as u32);
+    let pruned_ty = {
+        let ty_term = ty_term.rename_free_vars(0, |var_index| {
+            let position = arity.level(var_index).0 as usize;
+            let pruned_position = (0..position).filter(|p| !is_pruned(*p)).count();
+            pruned_arity.index(var::Level(pruned_position as u32))
+        });
+        let values = zonk::neutral_env(pruned_arity);
+        nbe::eval_term(prims, metas, budget, &values, span, &ty_term)?
+    };
+    let pruned_meta_index = metas.add_unsolved(span, pruned_arity, pruned_ty);
+
+    let len = keep.len();
+    let body = keep.iter().enumerate().filter(|(_, (_, keep))| *keep).fold(
+        Rc::from(syntax::Term::Meta(pruned_meta_index)),
+        |acc, (position, (app_mode, _))| {
+            let arg = Rc::from(syntax::Term::var((len - (position + 1)) as u32));
+            Rc::from(syntax::Term::FunElim(acc, app_mode.clone(), arg))
+        },
+    );
+    let solution = keep.iter().rev().fold(body, |acc, (app_mode, _)| {
+        Rc::from(syntax::Term::FunIntro(app_mode.clone(), None, acc))
+    });
+
+    let solution = nbe::eval_term(prims, metas, budget, &var::Env::new(), None, &solution)?;
+    metas.add_solved(meta_index, solution);
+
+    Ok(pruned_meta_index)
+}
 
-            // TODO: is this a bug?
-            // FIXME: really confusing error?
-            Err(Diagnostic::new_error("non-variable in meta spine")
-                .with_label(DiagnosticLabel::new_primary(span)))
+/// Rename a data type declaration that was found in a solution candidate.
+fn rename_data(
+    prims: &prim::Env,
+    metas: &mut meta::Env,
+    budget: &Budget,
+    span: FileSpan,
+    head: meta::Index,
+    renaming: &Renaming<'_>,
+    local_size: u32,
+    data: &syntax::Data,
+) -> Result<Rc<syntax::Data>, UnifyError> {
+    let ty = rename_solution(
+        prims, metas, budget, span, head, renaming, local_size, &data.ty,
+    )?;
+    let intros = data
+        .intros
+        .iter()
+        .map(|(docs, label, ty)| {
+            let ty = rename_solution(
+                prims,
+                metas,
+                budget,
+                span,
+                head,
+                renaming,
+                local_size + 1,
+                ty,
+            )?;
+            Ok((docs.clone(), label.clone(), ty))
         })
-        .collect()
+        .collect::<Result<_, UnifyError>>()?;
+
+    Ok(Rc::from(syntax::Data {
+        label: data.label.clone(),
+        param_count: data.param_count,
+        ty,
+        intros,
+    }))
 }
 
-/// Scope check + occurs check a solution candidate.
-fn check_solution(
-    env_size: var::Size,
+/// Scope check + occurs check a solution candidate, renaming it so that it
+/// can be used as the body of the metavariable solution.
+///
+/// If an out of scope variable is found in the spine of another unsolved
+/// metavariable, then that metavariable is pruned, rather than failing the
+/// scope check.
+fn rename_solution(
+    prims: &prim::Env,
+    metas: &mut meta::Env,
+    budget: &Budget,
     span: FileSpan,
     head: meta::Index,
-    bound_levels: &im::Vector<var::Level>,
+    renaming: &Renaming<'_>,
+    local_size: u32,
     rhs: &Rc<syntax::Term>,
-) -> Result<(), Diagnostic<FileSpan>> {
-    match rhs.as_ref() {
-        // Scope check
-        syntax::Term::Var(rhs_var_index) => {
-            if bound_levels
+) -> Result<Rc<syntax::Term>, UnifyError> {
+    // Occurs check and pruning
+    if let Some((rhs_meta_index, args)) = meta_spine(rhs) {
+        if rhs_meta_index == head {
+            return mismatch(renaming.size + local_size, span, MismatchKind::Occurs(head));
+        }
+
+        let is_unsolved = match metas.lookup_solution(rhs_meta_index) {
+            Some((_, meta::Solution::Unsolved, _, _)) => true,
+            Some((_, meta::Solution::Solved(_), _, _)) | None => false,
+        };
+        let arg_vars = args
+            .iter()
+            .map(|(app_mode, arg)| match arg.as_ref() {
+                syntax::Term::Var(var_index) => Some((app_mode, *var_index)),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>();
+
+        if let Some(arg_vars) = arg_vars {
+            let renamed_args = arg_vars
                 .iter()
-                .any(|var_level| env_size.index(*var_level) == *rhs_var_index)
-            {
-                // FIXME: Better error message
-                let message = format!("solution scope error: `?{}`", head.0);
-                Err(Diagnostic::new_error(message).with_label(DiagnosticLabel::new_primary(span)))
-            } else {
-                Ok(())
-            }
-        },
-        // Occurs check
-        syntax::Term::Meta(rhs_meta_level) => {
-            if *rhs_meta_level == head {
-                // FIXME: Better error message
-                let message = format!("occurs check: `?{}`", head.0);
-                Err(Diagnostic::new_error(message).with_label(DiagnosticLabel::new_primary(span)))
-            } else {
-                Ok(())
+                .map(|(_, var_index)| renaming.rename_var(local_size, *var_index))
+                .collect::<Vec<_>>();
+
+            if is_unsolved && renamed_args.iter().any(Option::is_none) {
+                let keep = Iterator::zip(arg_vars.iter(), renamed_args.iter())
+                    .map(|((app_mode, _), renamed_arg)| {
+                        ((*app_mode).clone(), renamed_arg.is_some())
+                    })
+                    .collect::<Vec<_>>();
+                let pruned_meta_index = prune_meta(prims, metas, budget, rhs_meta_index, &keep)?;
+
+                return Ok(Iterator::zip(args.iter(), renamed_args.iter())
+                    .filter_map(|((app_mode, _), renamed_arg)| Some((app_mode, (*renamed_arg)?)))
+                    .fold(
+                        Rc::from(syntax::Term::Meta(pruned_meta_index)),
+                        |acc, (app_mode, var_index)| {
+                            let arg = Rc::from(syntax::Term::var(var_index));
+                            Rc::from(syntax::Term::FunElim(acc, app_mode.clone(), arg))
+                        },
+                    ));
             }
+        }
+    }
+
+    match rhs.as_ref() {
+        // Scope check
+        syntax::Term::Var(rhs_var_index) => match renaming.rename_var(local_size, *rhs_var_index) {
+            Some(var_index) => Ok(Rc::from(syntax::Term::var(var_index))),
+            None => {
+                let size = renaming.size + local_size;
+                mismatch(
+                    size,
+                    span,
+                    MismatchKind::ScopeEscape(head, size.level(*rhs_var_index)),
+                )
+            },
         },
-        syntax::Term::Prim(_) => Ok(()),
+        syntax::Term::Meta(_) | syntax::Term::Prim(_) => Ok(rhs.clone()),
 
         syntax::Term::Ann(term, term_ty) => {
-            check_solution(env_size, span, head, bound_levels, term)?;
-            check_solution(env_size, span, head, bound_levels, term_ty)?;
+            let term =
+                rename_solution(prims, metas, budget, span, head, renaming, local_size, term)?;
+            let term_ty = rename_solution(
+                prims, metas, budget, span, head, renaming, local_size, term_ty,
+            )?;
 
-            Ok(())
+            Ok(Rc::from(syntax::Term::Ann(term, term_ty)))
+        },
+        syntax::Term::Let(_, _) => Err(UnifyError::Failed(
+            Diagnostic::new_bug("attempted to unify let expressions")
+                .with_label(DiagnosticLabel::new_primary(span)),
+        )),
+        syntax::Term::Fix(index, defns) => {
+            let body_size = local_size + defns.len() as u32;
+            let defns = defns
+                .iter()
+                .map(|(label, guard, ty, body)| {
+                    let ty = rename_solution(
+                        prims, metas, budget, span, head, renaming, local_size, ty,
+                    )?;
+                    let body = rename_solution(
+                        prims, metas, budget, span, head, renaming, body_size, body,
+                    )?;
+                    Ok((label.clone(), guard.clone(), ty, body))
+                })
+                .collect::<Result<Vec<_>, UnifyError>>()?;
+
+            Ok(Rc::from(syntax::Term::Fix(*index, Rc::from(defns))))
         },
-        syntax::Term::Let(_, _) => Err(Diagnostic::new_bug("attempted to unify let expressions")
-            .with_label(DiagnosticLabel::new_primary(span))),
 
-        syntax::Term::LiteralType(_) => Ok(()),
-        syntax::Term::LiteralIntro(_) => Ok(()),
+        syntax::Term::LiteralType(_) => Ok(rhs.clone()),
+        syntax::Term::LiteralIntro(_) => Ok(rhs.clone()),
         syntax::Term::LiteralElim(scrutinee, clauses, default_clause) => {
-            check_solution(env_size, span, head, bound_levels, scrutinee)?;
-            for (_, body) in clauses.iter() {
-                check_solution(env_size, span, head, bound_levels, body)?;
-            }
-            check_solution(env_size, span, head, bound_levels, default_clause)?;
-            Ok(())
+            let scrutinee = rename_solution(
+                prims, metas, budget, span, head, renaming, local_size, scrutinee,
+            )?;
+            let clauses = clauses
+                .iter()
+                .map(|(literal_intro, body)| {
+                    let body = rename_solution(
+                        prims, metas, budget, span, head, renaming, local_size, body,
+                    )?;
+                    Ok((literal_intro.clone(), body))
+                })
+                .collect::<Result<Vec<_>, UnifyError>>()?;
+            let default_clause = rename_solution(
+                prims,
+                metas,
+                budget,
+                span,
+                head,
+                renaming,
+                local_size,
+                default_clause,
+            )?;
+
+            Ok(Rc::from(syntax::Term::LiteralElim(
+                scrutinee,
+                Rc::from(clauses),
+                default_clause,
+            )))
         },
 
-        syntax::Term::FunType(_, _, param_ty, body_ty) => {
-            check_solution(env_size, span, head, bound_levels, param_ty)?;
-            check_solution(env_size + 1, span, head, bound_levels, body_ty)?;
-            Ok(())
+        syntax::Term::FunType(app_mode, name_hint, param_ty, body_ty) => {
+            let param_ty = rename_solution(
+                prims, metas, budget, span, head, renaming, local_size, param_ty,
+            )?;
+            let body_ty = rename_solution(
+                prims,
+                metas,
+                budget,
+                span,
+                head,
+                renaming,
+                local_size + 1,
+                body_ty,
+            )?;
+
+            Ok(Rc::from(syntax::Term::FunType(
+                app_mode.clone(),
+                name_hint.clone(),
+                param_ty,
+                body_ty,
+            )))
         },
-        syntax::Term::FunIntro(_, _, body) => {
-            check_solution(env_size + 1, span, head, bound_levels, body)?;
-            Ok(())
+        syntax::Term::FunIntro(app_mode, name_hint, body) => {
+            let body = rename_solution(
+                prims,
+                metas,
+                budget,
+                span,
+                head,
+                renaming,
+                local_size + 1,
+                body,
+            )?;
+
+            Ok(Rc::from(syntax::Term::FunIntro(
+                app_mode.clone(),
+                name_hint.clone(),
+                body,
+            )))
         },
-        syntax::Term::FunElim(fun, _, arg) => {
-            check_solution(env_size, span, head, bound_levels, fun)?;
-            check_solution(env_size, span, head, bound_levels, arg)?;
-            Ok(())
+        syntax::Term::FunElim(fun, app_mode, arg) => {
+            let fun = rename_solution(prims, metas, budget, span, head, renaming, local_size, fun)?;
+            let arg = rename_solution(prims, metas, budget, span, head, renaming, local_size, arg)?;
+
+            Ok(Rc::from(syntax::Term::FunElim(fun, app_mode.clone(), arg)))
         },
 
         syntax::Term::RecordType(ty_fields) => {
-            for (i, (_, _, _, term)) in ty_fields.iter().enumerate() {
-                check_solution(env_size + i as u32, span, head, bound_levels, term)?;
-            }
-            Ok(())
+            let ty_fields = ty_fields
+                .iter()
+                .enumerate()
+                .map(|(i, (doc, label, name_hint, term, manifest))| {
+                    let local_size = local_size + i as u32;
+                    let term = rename_solution(
+                        prims, metas, budget, span, head, renaming, local_size, term,
+                    )?;
+                    let manifest = match manifest {
+                        None => None,
+                        Some(manifest) => Some(rename_solution(
+                            prims, metas, budget, span, head, renaming, local_size, manifest,
+                        )?),
+                    };
+                    Ok((
+                        doc.clone(),
+                        label.clone(),
+                        name_hint.clone(),
+                        term,
+                        manifest,
+                    ))
+                })
+                .collect::<Result<_, UnifyError>>()?;
+
+            Ok(Rc::from(syntax::Term::RecordType(ty_fields)))
         },
         syntax::Term::RecordIntro(intro_fields) => {
-            for (_, term) in intro_fields.iter() {
-                check_solution(env_size, span, head, bound_levels, term)?;
-            }
-            Ok(())
+            let intro_fields = intro_fields
+                .iter()
+                .map(|(label, term)| {
+                    let term = rename_solution(
+                        prims, metas, budget, span, head, renaming, local_size, term,
+                    )?;
+                    Ok((label.clone(), term))
+                })
+                .collect::<Result<_, UnifyError>>()?;
+
+            Ok(Rc::from(syntax::Term::RecordIntro(intro_fields)))
+        },
+        syntax::Term::RecordElim(record, label) => {
+            let record = rename_solution(
+                prims, metas, budget, span, head, renaming, local_size, record,
+            )?;
+
+            Ok(Rc::from(syntax::Term::RecordElim(record, label.clone())))
         },
-        syntax::Term::RecordElim(record, _) => {
-            check_solution(env_size, span, head, bound_levels, record)
+
+        syntax::Term::DataType(data) => {
+            let data = rename_data(prims, metas, budget, span, head, renaming, local_size, data)?;
+
+            Ok(Rc::from(syntax::Term::DataType(data)))
+        },
+        syntax::Term::DataIntro(data, label) => {
+            let data = rename_data(prims, metas, budget, span, head, renaming, local_size, data)?;
+
+            Ok(Rc::from(syntax::Term::DataIntro(data, label.clone())))
+        },
+        syntax::Term::DataElim(scrutinee, clauses) => {
+            let scrutinee = rename_solution(
+                prims, metas, budget, span, head, renaming, local_size, scrutinee,
+            )?;
+            let clauses = clauses
+                .iter()
+                .map(|(label, name_hints, body)| {
+                    let body_size = local_size + name_hints.len() as u32;
+                    let body = rename_solution(
+                        prims, metas, budget, span, head, renaming, body_size, body,
+                    )?;
+                    Ok((label.clone(), name_hints.clone(), body))
+                })
+                .collect::<Result<Vec<_>, UnifyError>>()?;
+
+            Ok(Rc::from(syntax::Term::DataElim(
+                scrutinee,
+                Rc::from(clauses),
+            )))
+        },
+
+        syntax::Term::EqType(ty, eq_lhs, eq_rhs) => {
+            let ty = rename_solution(prims, metas, budget, span, head, renaming, local_size, ty)?;
+            let eq_lhs = rename_solution(
+                prims, metas, budget, span, head, renaming, local_size, eq_lhs,
+            )?;
+            let eq_rhs = rename_solution(
+                prims, metas, budget, span, head, renaming, local_size, eq_rhs,
+            )?;
+
+            Ok(Rc::from(syntax::Term::EqType(ty, eq_lhs, eq_rhs)))
+        },
+        syntax::Term::EqIntro => Ok(rhs.clone()),
+        syntax::Term::EqElim(proof, motive, body) => {
+            let proof = rename_solution(
+                prims, metas, budget, span, head, renaming, local_size, proof,
+            )?;
+            let motive = rename_solution(
+                prims, metas, budget, span, head, renaming, local_size, motive,
+            )?;
+            let body =
+                rename_solution(prims, metas, budget, span, head, renaming, local_size, body)?;
+
+            Ok(Rc::from(syntax::Term::EqElim(proof, motive, body)))
         },
 
-        syntax::Term::Universe(_) => Ok(()),
+        syntax::Term::LevelType => Ok(rhs.clone()),
+        syntax::Term::LevelIntro(_) | syntax::Term::LevelOmega => Ok(rhs.clone()),
+        syntax::Term::LevelShift(level, shift) => {
+            let level = rename_solution(
+                prims, metas, budget, span, head, renaming, local_size, level,
+            )?;
+
+            Ok(Rc::from(syntax::Term::LevelShift(level, *shift)))
+        },
+        syntax::Term::LevelMax(lhs, rhs) => {
+            let lhs = rename_solution(prims, metas, budget, span, head, renaming, local_size, lhs)?;
+            let rhs = rename_solution(prims, metas, budget, span, head, renaming, local_size, rhs)?;
+
+            Ok(Rc::from(syntax::Term::LevelMax(lhs, rhs)))
+        },
+
+        syntax::Term::Universe(level) => {
+            let level = rename_solution(
+                prims, metas, budget, span, head, renaming, local_size, level,
+            )?;
+
+            Ok(Rc::from(syntax::Term::Universe(level)))
+        },
     }
 }
 
@@ -130,91 +707,297 @@ fn check_solution(
 fn solve_neutral(
     prims: &prim::Env,
     metas: &mut meta::Env,
+    budget: &Budget,
     values: &var::Env<Rc<domain::Value>>,
     span: FileSpan,
     head: meta::Index,
-    spine: &domain::Spine,
+    params: Params,
     rhs: &Rc<domain::Value>,
-) -> Result<(), Diagnostic<FileSpan>> {
-    let bound_levels = check_spine(prims, metas, span, spine)?;
-    let rhs = nbe::read_back_value(prims, metas, values.size(), None, rhs)?;
+) -> Result<(), UnifyError> {
+    // Unfold global definitions, because they won't be in the scope of the
+    // metavariable solution
+    let rhs = nbe::read_back_value(prims, metas, budget, values.size(), Unfold::All, None, rhs)?;
 
-    check_solution(values.size(), span, head, &bound_levels, &rhs)?;
+    let renaming = Renaming {
+        size: values.size(),
+        params: &params,
+    };
+    let rhs = rename_solution(prims, metas, budget, span, head, &renaming, 0, &rhs)?;
 
-    let rhs = bound_levels.iter().rev().fold(rhs, |acc, _| {
-        Rc::from(syntax::Term::FunIntro(AppMode::Explicit, None, acc))
+    let rhs = params.iter().rev().fold(rhs, |acc, (app_mode, _)| {
+        Rc::from(syntax::Term::FunIntro(app_mode.clone(), None, acc))
     });
 
-    let rhs_value = nbe::eval_term(prims, metas, &var::Env::new(), None, &rhs)?;
+    let rhs_value = nbe::eval_term(prims, metas, budget, &var::Env::new(), None, &rhs)?;
 
     metas.add_solved(head, rhs_value);
 
     Ok(())
 }
 
+/// Add a fresh variable to the environment, returning the variable.
+fn instantiate_value(
+    values: &var::Env<Rc<domain::Value>>,
+) -> (Rc<domain::Value>, var::Env<Rc<domain::Value>>) {
+    let mut values = values.clone();
+    let value = Rc::from(domain::Value::var(values.size().next_level()));
+    values.add_entry(value.clone());
+    (value, values)
+}
+
+/// Unify the spines of two neutral values, assuming that their heads are
+/// equal.
+fn unify_spines(
+    prims: &prim::Env,
+    metas: &mut meta::Env,
+    budget: &Budget,
+    values: &var::Env<Rc<domain::Value>>,
+    span: FileSpan,
+    value1: &Rc<domain::Value>,
+    value2: &Rc<domain::Value>,
+) -> Result<(), UnifyError> {
+    let (spine1, spine2) = match (value1.as_ref(), value2.as_ref()) {
+        (domain::Value::Neutral(_, spine1), domain::Value::Neutral(_, spine2))
+            if spine1.len() == spine2.len() =>
+        {
+            (spine1, spine2)
+        },
+        (_, _) => {
+            let kind = MismatchKind::Values(value1.clone(), value2.clone());
+            return mismatch(values.size(), span, kind);
+        },
+    };
+
+    for (elim1, elim2) in Iterator::zip(spine1.iter(), spine2.iter()) {
+        match (elim1, elim2) {
+            (domain::Elim::Fun(app_mode1, arg1), domain::Elim::Fun(app_mode2, arg2))
+                if app_mode1 == app_mode2 =>
+            {
+                unify_values(prims, metas, budget, values, span, arg1, arg2)?;
+            },
+            (domain::Elim::Record(l1), domain::Elim::Record(l2)) if l1 == l2 => {},
+            (domain::Elim::Literal(lc1), domain::Elim::Literal(lc2)) => {
+                // Hum, guessing here??
+                let (sc, values) = instantiate_value(values);
+                let val1 = nbe::eval_literal_elim(prims, metas, budget, sc.clone(), lc1.clone())?;
+                let val2 = nbe::eval_literal_elim(prims, metas, budget, sc.clone(), lc2.clone())?;
+                unify_values(prims, metas, budget, &values, span, &val1, &val2)?;
+            },
+            (domain::Elim::Data(closure1), domain::Elim::Data(closure2))
+                if closure1.clauses.len() == closure2.clauses.len() =>
+            {
+                let clauses = Iterator::zip(closure1.clauses.iter(), closure2.clauses.iter());
+                for ((label1, name_hints1, body1), (label2, name_hints2, body2)) in clauses {
+                    if label1 != label2 || name_hints1.len() != name_hints2.len() {
+                        let kind = MismatchKind::Label(label1.clone(), label2.clone());
+                        return mismatch(values.size(), span, kind);
+                    }
+
+                    // Compare the bodies of the clauses, with their fields
+                    // bound to fresh variables
+                    let mut body_values = values.clone();
+                    let mut values1 = closure1.values.clone();
+                    let mut values2 = closure2.values.clone();
+                    for _ in name_hints1 {
+                        let (field, next_values) = instantiate_value(&body_values);
+                        body_values = next_values;
+                        values1.add_entry(field.clone());
+                        values2.add_entry(field);
+                    }
+                    let body1 = nbe::eval_term(prims, metas, budget, &values1, None, body1)?;
+                    let body2 = nbe::eval_term(prims, metas, budget, &values2, None, body2)?;
+                    unify_values(prims, metas, budget, &body_values, span, &body1, &body2)?;
+                }
+            },
+            (domain::Elim::Eq(motive1, body1), domain::Elim::Eq(motive2, body2)) => {
+                unify_values(prims, metas, budget, values, span, motive1, motive2)?;
+                unify_values(prims, metas, budget, values, span, body1, body2)?;
+            },
+            (domain::Elim::Fun(app_mode1, _), domain::Elim::Fun(app_mode2, _)) => {
+                let kind = MismatchKind::AppMode(app_mode1.clone(), app_mode2.clone());
+                return mismatch(values.size(), span, kind);
+            },
+            (domain::Elim::Record(label1), domain::Elim::Record(label2)) => {
+                let kind = MismatchKind::Label(label1.clone(), label2.clone());
+                return mismatch(values.size(), span, kind);
+            },
+            (_, _) => {
+                let kind = MismatchKind::Values(value1.clone(), value2.clone());
+                return mismatch(values.size(), span, kind);
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// Unify the arguments that have been applied to two data types or
+/// constructors, assuming that they are from the same declaration.
+fn unify_data_args(
+    prims: &prim::Env,
+    metas: &mut meta::Env,
+    budget: &Budget,
+    values: &var::Env<Rc<domain::Value>>,
+    span: FileSpan,
+    args1: &[(AppMode, Rc<domain::Value>)],
+    args2: &[(AppMode, Rc<domain::Value>)],
+) -> Result<(), UnifyError> {
+    for ((app_mode1, arg1), (app_mode2, arg2)) in Iterator::zip(args1.iter(), args2.iter()) {
+        if app_mode1 != app_mode2 {
+            let kind = MismatchKind::AppMode(app_mode1.clone(), app_mode2.clone());
+            return mismatch(values.size(), span, kind);
+        }
+        unify_values(prims, metas, budget, values, span, arg1, arg2)?;
+    }
+
+    Ok(())
+}
+
+/// Check if two closures refer to the same data type declaration.
+fn is_same_data(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    values: &var::Env<Rc<domain::Value>>,
+    closure1: &domain::DataClosure,
+    closure2: &domain::DataClosure,
+) -> Result<bool, UnifyError> {
+    let size = values.size();
+    Ok(nbe::check_data(
+        prims, metas, budget, size, closure1, closure2,
+    )?)
+}
+
+/// Returns the location of the metavariable that a value is headed by, if it
+/// can be found within the given span.
+fn subterm_span(metas: &meta::Env, span: FileSpan, value: &domain::Value) -> Option<FileSpan> {
+    match value {
+        domain::Value::Neutral(domain::Head::Meta(meta_index), _) => {
+            let (meta_span, _, _, _) = metas.lookup_solution(*meta_index)?;
+            let is_within = meta_span.source() == span.source()
+                && span.start() <= meta_span.start()
+                && meta_span.end() <= span.end();
+            if is_within {
+                Some(*meta_span)
+            } else {
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
 /// Unify two values. If unification succeeds, the `value1` should be
 /// definitionally equal to, or a subtype of of `value2` in the updated
 /// metavariable environment.
 pub fn unify_values(
     prims: &prim::Env,
     metas: &mut meta::Env,
+    budget: &Budget,
     values: &var::Env<Rc<domain::Value>>,
     span: FileSpan,
     value1: &Rc<domain::Value>,
     value2: &Rc<domain::Value>,
-) -> Result<(), Diagnostic<FileSpan>> {
+) -> Result<(), UnifyError> {
     log::trace!("unifying values");
 
-    fn instantiate_value(
-        values: &var::Env<Rc<domain::Value>>,
-    ) -> (Rc<domain::Value>, var::Env<Rc<domain::Value>>) {
-        let mut values = values.clone();
-        let value = Rc::from(domain::Value::var(values.size().next_level()));
-        values.add_entry(value.clone());
-        (value, values)
-    }
+    // Point mismatches at the subterm that one of the values was created for
+    let span = subterm_span(metas, span, value1)
+        .or_else(|| subterm_span(metas, span, value2))
+        .unwrap_or(span);
 
-    fn unification_error(
-        span: FileSpan,
-        _value1: &Rc<domain::Value>,
-        _value2: &Rc<domain::Value>,
-    ) -> Result<(), Diagnostic<FileSpan>> {
-        // FIXME: Better error message
-        Err(Diagnostic::new_error("can't unify").with_label(DiagnosticLabel::new_primary(span)))
+    // Try to avoid unfolding global definitions, by first checking if both
+    // sides refer to the same definition, applied to unifiable spines
+    if let (
+        domain::Value::Neutral(domain::Head::Global(level1, _), _),
+        domain::Value::Neutral(domain::Head::Global(level2, _), _),
+    ) = (value1.as_ref(), value2.as_ref())
+    {
+        if level1 == level2 {
+            // Unifying the spines might solve some metavariables before
+            // failing, so we take a checkpoint to roll back to
+            let checkpoint = metas.checkpoint();
+            match unify_spines(prims, metas, budget, values, span, value1, value2) {
+                Ok(()) => return Ok(()),
+                // Fall back to unfolding the definitions
+                Err(UnifyError::Blocked) | Err(UnifyError::Mismatch(_)) => {
+                    metas.rollback(checkpoint)
+                },
+                Err(UnifyError::Failed(diagnostic)) => return Err(UnifyError::Failed(diagnostic)),
+            }
+        }
     }
 
-    match (
-        nbe::force_value(prims, metas, span, value1)?.as_ref(),
-        nbe::force_value(prims, metas, span, value2)?.as_ref(),
-    ) {
+    let values_mismatch = || {
+        let kind = MismatchKind::Values(value1.clone(), value2.clone());
+        mismatch(values.size(), span, kind)
+    };
+    let value1 = &nbe::force_value(prims, metas, budget, span, value1)?;
+    let value2 = &nbe::force_value(prims, metas, budget, span, value2)?;
+
+    match (value1.as_ref(), value2.as_ref()) {
         (domain::Value::Neutral(head1, spine1), domain::Value::Neutral(head2, spine2))
             if head1 == head2 && spine1.len() == spine2.len() =>
         {
-            for (elim1, elim2) in Iterator::zip(spine1.iter(), spine2.iter()) {
-                match (elim1, elim2) {
-                    (domain::Elim::Fun(app_mode1, arg1), domain::Elim::Fun(app_mode2, arg2))
-                        if app_mode1 == app_mode2 =>
-                    {
-                        unify_values(prims, metas, values, span, arg1, arg2)?;
-                    }
-                    (domain::Elim::Record(l1), domain::Elim::Record(l2)) if l1 == l2 => {},
-                    (domain::Elim::Literal(lc1), domain::Elim::Literal(lc2)) => {
-                        // Hum, guessing here??
-                        let (sc, values) = instantiate_value(values);
-                        let val1 = nbe::eval_literal_elim(prims, metas, sc.clone(), lc1.clone())?;
-                        let val2 = nbe::eval_literal_elim(prims, metas, sc.clone(), lc2.clone())?;
-                        unify_values(prims, metas, &values, span, &val1, &val2)?;
-                    },
-                    (_, _) => unification_error(span, value1, value2)?,
-                }
-            }
-            Ok(())
-        }
+            unify_spines(prims, metas, budget, values, span, value1, value2)
+        },
+        (
+            domain::Value::Neutral(domain::Head::Meta(meta_level1), spine1),
+            domain::Value::Neutral(domain::Head::Meta(meta_level2), spine2),
+        ) => match check_spine(prims, metas, budget, span, spine1)? {
+            Some(params) => solve_neutral(
+                prims,
+                metas,
+                budget,
+                values,
+                span,
+                *meta_level1,
+                params,
+                value2,
+            ),
+            None => match check_spine(prims, metas, budget, span, spine2)? {
+                Some(params) => solve_neutral(
+                    prims,
+                    metas,
+                    budget,
+                    values,
+                    span,
+                    *meta_level2,
+                    params,
+                    value1,
+                ),
+                None => Err(UnifyError::Blocked),
+            },
+        },
         (domain::Value::Neutral(domain::Head::Meta(meta_level), spine), _) => {
-            solve_neutral(prims, metas, values, span, *meta_level, spine, value2)
+            match check_spine(prims, metas, budget, span, spine)? {
+                Some(params) => solve_neutral(
+                    prims,
+                    metas,
+                    budget,
+                    values,
+                    span,
+                    *meta_level,
+                    params,
+                    value2,
+                ),
+                None => Err(UnifyError::Blocked),
+            }
         },
         (_, domain::Value::Neutral(domain::Head::Meta(meta_level), spine)) => {
-            solve_neutral(prims, metas, values, span, *meta_level, spine, value1)
+            match check_spine(prims, metas, budget, span, spine)? {
+                Some(params) => solve_neutral(
+                    prims,
+                    metas,
+                    budget,
+                    values,
+                    span,
+                    *meta_level,
+                    params,
+                    value1,
+                ),
+                None => Err(UnifyError::Blocked),
+            }
         },
 
         (
@@ -231,28 +1014,39 @@ pub fn unify_values(
             domain::Value::FunType(app_mode1, _, param_ty1, body_ty1),
             domain::Value::FunType(app_mode2, _, param_ty2, body_ty2),
         ) if app_mode1 == app_mode2 => {
-            unify_values(prims, metas, values, span, param_ty1, param_ty2)?;
+            unify_values(prims, metas, budget, values, span, param_ty1, param_ty2)?;
 
             let (param, values) = instantiate_value(values);
-            let body_ty1 = nbe::app_closure(prims, metas, body_ty1, param.clone())?;
-            let body_ty2 = nbe::app_closure(prims, metas, body_ty2, param.clone())?;
+            let body_ty1 = nbe::app_closure(prims, metas, budget, body_ty1, param.clone())?;
+            let body_ty2 = nbe::app_closure(prims, metas, budget, body_ty2, param.clone())?;
 
-            unify_values(prims, metas, &values, span, &body_ty1, &body_ty2)?;
+            unify_values(prims, metas, budget, &values, span, &body_ty1, &body_ty2)?;
 
             Ok(())
         },
+        (
+            domain::Value::FunType(app_mode1, _, _, _),
+            domain::Value::FunType(app_mode2, _, _, _),
+        ) => {
+            let kind = MismatchKind::AppMode(app_mode1.clone(), app_mode2.clone());
+            mismatch(values.size(), span, kind)
+        },
         (
             domain::Value::FunIntro(app_mode1, _, body1),
             domain::Value::FunIntro(app_mode2, _, body2),
         ) if app_mode1 == app_mode2 => {
             let (param, values) = instantiate_value(values);
-            let body1 = nbe::app_closure(prims, metas, body1, param.clone())?;
-            let body2 = nbe::app_closure(prims, metas, body2, param.clone())?;
+            let body1 = nbe::app_closure(prims, metas, budget, body1, param.clone())?;
+            let body2 = nbe::app_closure(prims, metas, budget, body2, param.clone())?;
 
-            unify_values(prims, metas, &values, span, &body1, &body2)?;
+            unify_values(prims, metas, budget, &values, span, &body1, &body2)?;
 
             Ok(())
         },
+        (domain::Value::FunIntro(app_mode1, _, _), domain::Value::FunIntro(app_mode2, _, _)) => {
+            let kind = MismatchKind::AppMode(app_mode1.clone(), app_mode2.clone());
+            mismatch(values.size(), span, kind)
+        },
 
         // Eta conversion (η-conversion) for functions:
         //
@@ -266,37 +1060,55 @@ pub fn unify_values(
         // - https://en.wikipedia.org/wiki/Lambda_calculus#%CE%B7-conversion
         (domain::Value::FunIntro(app_mode1, _, body1), _) => {
             let (param, values) = instantiate_value(values);
-            let body1 = nbe::app_closure(prims, metas, body1, param.clone())?;
-            let body2 = nbe::eval_fun_elim(prims, metas, value2.clone(), app_mode1, param)?;
+            let body1 = nbe::app_closure(prims, metas, budget, body1, param.clone())?;
+            let body2 = nbe::eval_fun_elim(prims, metas, budget, value2.clone(), app_mode1, param)?;
 
-            unify_values(prims, metas, &values, span, &body1, &body2)?;
+            unify_values(prims, metas, budget, &values, span, &body1, &body2)?;
 
             Ok(())
         },
         (_, domain::Value::FunIntro(app_mode2, _, body2)) => {
             let (param, values) = instantiate_value(values);
-            let body2 = nbe::app_closure(prims, metas, body2, param.clone())?;
-            let body1 = nbe::eval_fun_elim(prims, metas, value1.clone(), app_mode2, param)?;
+            let body2 = nbe::app_closure(prims, metas, budget, body2, param.clone())?;
+            let body1 = nbe::eval_fun_elim(prims, metas, budget, value1.clone(), app_mode2, param)?;
 
-            unify_values(prims, metas, &values, span, &body1, &body2)?;
+            unify_values(prims, metas, budget, &values, span, &body1, &body2)?;
 
             Ok(())
         },
 
         (
-            domain::Value::RecordTypeExtend(_, label1, _, value_ty1, rest_ty1),
-            domain::Value::RecordTypeExtend(_, label2, _, value_ty2, rest_ty2),
+            domain::Value::RecordTypeExtend(_, label1, _, value_ty1, manifest1, rest_ty1),
+            domain::Value::RecordTypeExtend(_, label2, _, value_ty2, manifest2, rest_ty2),
         ) if label1 == label2 => {
-            unify_values(prims, metas, values, span, value_ty1, value_ty2)?;
+            unify_values(prims, metas, budget, values, span, value_ty1, value_ty2)?;
+            match (manifest1, manifest2) {
+                (Some(manifest1), Some(manifest2)) => {
+                    unify_values(prims, metas, budget, values, span, manifest1, manifest2)?;
+                },
+                // The value of a manifest field may be forgotten
+                (_, None) => {},
+                (None, Some(_)) => {
+                    let kind = MismatchKind::ManifestField(label2.clone());
+                    return mismatch(values.size(), span, kind);
+                },
+            }
 
             let (value, values) = instantiate_value(values);
-            let rest_ty1 = nbe::app_closure(prims, metas, rest_ty1, value.clone())?;
-            let rest_ty2 = nbe::app_closure(prims, metas, rest_ty2, value.clone())?;
+            let rest_ty1 = nbe::app_closure(prims, metas, budget, rest_ty1, value.clone())?;
+            let rest_ty2 = nbe::app_closure(prims, metas, budget, rest_ty2, value.clone())?;
 
-            unify_values(prims, metas, &values, span, &rest_ty1, &rest_ty2)?;
+            unify_values(prims, metas, budget, &values, span, &rest_ty1, &rest_ty2)?;
 
             Ok(())
         },
+        (
+            domain::Value::RecordTypeExtend(_, label1, _, _, _, _),
+            domain::Value::RecordTypeExtend(_, label2, _, _, _, _),
+        ) => {
+            let kind = MismatchKind::Label(label1.clone(), label2.clone());
+            mismatch(values.size(), span, kind)
+        },
         (domain::Value::RecordTypeEmpty, domain::Value::RecordTypeEmpty) => Ok(()),
         (domain::Value::RecordIntro(fields1), domain::Value::RecordIntro(fields2))
             if fields1.len() == fields2.len() =>
@@ -306,14 +1118,15 @@ pub fn unify_values(
                 Iterator::zip(fields1.iter(), fields2.iter())
             {
                 if label1 == label2 {
-                    unify_values(prims, metas, &values, span, value1, value2)?;
+                    unify_values(prims, metas, budget, &values, span, value1, value2)?;
                     values.add_entry(Rc::from(domain::Value::var(values.size().next_level())));
                 } else {
-                    unification_error(span, value1, value2)?;
+                    let kind = MismatchKind::Label(label1.clone(), label2.clone());
+                    return mismatch(values.size(), span, kind);
                 }
             }
             Ok(())
-        }
+        },
 
         // Eta conversion (η-conversion) for records:
         //
@@ -330,7 +1143,7 @@ pub fn unify_values(
             let mut values = values.clone();
             for (label1, value1) in fields1 {
                 let value2 = nbe::eval_record_elim(value2.clone(), label1)?;
-                unify_values(prims, metas, &values, span, value1, &value2)?;
+                unify_values(prims, metas, budget, &values, span, value1, &value2)?;
                 values.add_entry(Rc::from(domain::Value::var(values.size().next_level())));
             }
             Ok(())
@@ -339,16 +1152,882 @@ pub fn unify_values(
             let mut values = values.clone();
             for (label2, value2) in fields2 {
                 let value1 = nbe::eval_record_elim(value1.clone(), label2)?;
-                unify_values(prims, metas, &values, span, &value1, value2)?;
+                unify_values(prims, metas, budget, &values, span, &value1, value2)?;
                 values.add_entry(Rc::from(domain::Value::var(values.size().next_level())));
             }
             Ok(())
         },
 
-        (domain::Value::Universe(level1), domain::Value::Universe(level2)) if level1 <= level2 => {
+        (domain::Value::DataType(closure1, args1), domain::Value::DataType(closure2, args2))
+            if args1.len() == args2.len()
+                && is_same_data(prims, metas, budget, values, closure1, closure2)? =>
+        {
+            unify_data_args(prims, metas, budget, values, span, args1, args2)
+        },
+        (
+            domain::Value::DataIntro(closure1, label1, args1),
+            domain::Value::DataIntro(closure2, label2, args2),
+        ) if label1 == label2
+            && args1.len() == args2.len()
+            && is_same_data(prims, metas, budget, values, closure1, closure2)? =>
+        {
+            unify_data_args(prims, metas, budget, values, span, args1, args2)
+        },
+        (domain::Value::DataIntro(_, label1, _), domain::Value::DataIntro(_, label2, _))
+            if label1 != label2 =>
+        {
+            let kind = MismatchKind::Label(label1.clone(), label2.clone());
+            mismatch(values.size(), span, kind)
+        },
+
+        (domain::Value::EqType(ty1, lhs1, rhs1), domain::Value::EqType(ty2, lhs2, rhs2)) => {
+            unify_values(prims, metas, budget, values, span, ty1, ty2)?;
+            unify_values(prims, metas, budget, values, span, lhs1, lhs2)?;
+            unify_values(prims, metas, budget, values, span, rhs1, rhs2)?;
+
             Ok(())
         },
+        (domain::Value::EqIntro, domain::Value::EqIntro) => Ok(()),
+
+        (domain::Value::LevelType, domain::Value::LevelType) => Ok(()),
+        (domain::Value::Level(level1), domain::Value::Level(level2)) => {
+            unify_levels(prims, metas, budget, values, span, level1, level2, false)
+        },
+
+        (domain::Value::Universe(level1), domain::Value::Universe(level2)) => {
+            unify_levels(prims, metas, budget, values, span, level1, level2, true)
+        },
+
+        (_, _) => values_mismatch(),
+    }
+}
+
+/// Returns the metavariable neutral and its offset if the level is a single,
+/// shifted, unsolved metavariable.
+fn meta_level(level: &domain::Level) -> Option<(&Rc<domain::Value>, u16)> {
+    match level {
+        domain::Level::Finite(UniverseLevel(0), neutrals) => match neutrals.as_slice() {
+            [(value, shift)] => match value.as_ref() {
+                domain::Value::Neutral(domain::Head::Meta(_), _) => Some((value, *shift)),
+                _ => None,
+            },
+            _ => None,
+        },
+        domain::Level::Finite(_, _) | domain::Level::Omega => None,
+    }
+}
+
+/// Returns `true` if the level refers to the given metavariable neutral.
+fn mentions_meta(level: &domain::Level, meta: &Rc<domain::Value>) -> bool {
+    level.neutrals().iter().any(|(value, _)| value == meta)
+}
+
+/// Returns `true` if the level refers to any unsolved metavariables.
+fn is_blocked_level(level: &domain::Level) -> bool {
+    level
+        .neutrals()
+        .iter()
+        .any(|(value, _)| match value.as_ref() {
+            domain::Value::Neutral(domain::Head::Meta(_), _) => true,
+            _ => false,
+        })
+}
+
+/// Unify two universe levels. If `is_cumulative` is set, then `level1` only
+/// needs to be less than or equal to `level2`.
+///
+/// Constraints on metavariables are solved by equating the levels, which
+/// might not result in the smallest possible universe, but should be enough
+/// for most programs.
+fn unify_levels(
+    prims: &prim::Env,
+    metas: &mut meta::Env,
+    budget: &Budget,
+    values: &var::Env<Rc<domain::Value>>,
+    span: FileSpan,
+    level1: &domain::Level,
+    level2: &domain::Level,
+    is_cumulative: bool,
+) -> Result<(), UnifyError> {
+    let level1 = nbe::force_level(prims, metas, budget, span, level1)?;
+    let level2 = nbe::force_level(prims, metas, budget, span, level2)?;
+
+    if level1 == level2 {
+        return Ok(());
+    }
+
+    // Solve metavariables before checking for cumulativity, otherwise they
+    // would be left unsolved whenever the constraint is trivially satisfied
+    if let Some((meta, shift)) = meta_level(&level1) {
+        if let Some(solution) = level2.unshift(shift) {
+            if !mentions_meta(&solution, meta) {
+                let solution = mltt_core::nbe::level_to_value(solution);
+                return unify_values(prims, metas, budget, values, span, meta, &solution);
+            }
+        }
+    }
+    if let Some((meta, shift)) = meta_level(&level2) {
+        if let Some(solution) = level1.unshift(shift) {
+            if !mentions_meta(&solution, meta) {
+                let solution = mltt_core::nbe::level_to_value(solution);
+                return unify_values(prims, metas, budget, values, span, &solution, meta);
+            }
+        }
+    }
+
+    if is_cumulative && level1.is_le(&level2) {
+        Ok(())
+    } else if is_blocked_level(&level1) || is_blocked_level(&level2) {
+        Err(UnifyError::Blocked)
+    } else {
+        mismatch(
+            values.size(),
+            span,
+            MismatchKind::UniverseLevel(level1, level2),
+        )
+    }
+}
+
+/// Create a context for reporting errors in a postponed unification problem.
+fn problem_context(prims: &prim::Env, budget: &Budget, problem: &meta::Problem) -> Context {
+    Context::from_entries(
+        prims.clone(),
+        budget.clone(),
+        problem.names.clone(),
+        problem.values.clone(),
+        problem.tys.clone(),
+    )
+}
+
+/// Retry the postponed unification problems, until no more progress can be
+/// made. This should be called whenever new metavariables have been solved.
+pub fn retry_postponed(
+    prims: &prim::Env,
+    metas: &mut meta::Env,
+    budget: &Budget,
+) -> Result<(), Diagnostic<FileSpan>> {
+    loop {
+        let universe_progress = check_universe_problems(prims, metas, budget)?;
+
+        let problems = metas.take_postponed();
+        let problem_count = problems.len();
+
+        for (id, problem) in problems {
+            log::trace!("retrying postponed problem");
+
+            let values = &problem.values;
+            let (span, value1, value2) = (problem.span, &problem.value1, &problem.value2);
+            match unify_values(prims, metas, budget, values, span, value1, value2) {
+                Ok(()) => {},
+                Err(UnifyError::Blocked) => metas.restore_postponed(id, problem),
+                Err(UnifyError::Mismatch(mismatch)) => {
+                    let context = problem_context(prims, budget, &problem);
+                    return Err(mismatch_to_diagnostic(
+                        &context, metas, span, value1, value2, &mismatch,
+                    ));
+                },
+                Err(UnifyError::Failed(diagnostic)) => return Err(diagnostic),
+            }
+        }
+
+        if metas.postponed().len() >= problem_count && !universe_progress {
+            return Ok(());
+        }
+    }
+}
+
+/// Check the universes of the solutions to any metavariables that were waiting
+/// to be solved, returning `true` if any progress was made.
+fn check_universe_problems(
+    prims: &prim::Env,
+    metas: &mut meta::Env,
+    budget: &Budget,
+) -> Result<bool, Diagnostic<FileSpan>> {
+    let problems = metas.take_universe_problems();
+    let problem_count = problems.len();
+
+    for problem in problems {
+        let (span, values) = (problem.span, &problem.values);
+        let term = nbe::eval_term(prims, metas, budget, values, span, &problem.term)?;
+        let term = match nbe::force_value(prims, metas, budget, span, &term)?.as_ref() {
+            domain::Value::Neutral(domain::Head::Meta(_), _) => {
+                metas.add_universe_problem(problem);
+                continue;
+            },
+            _ => nbe::read_back_value(
+                prims,
+                metas,
+                budget,
+                values.size(),
+                Unfold::None,
+                span,
+                &term,
+            )?,
+        };
+
+        let mut context =
+            validate::Context::new(prims.clone(), values.clone(), problem.tys.clone());
+        context.set_budget(budget.clone());
+        let level = match validate::synth_universe(&context, metas, &term) {
+            Ok(level) => level,
+            Err(validate::TypeError::UnsolvedMeta(_)) => {
+                metas.add_universe_problem(problem);
+                continue;
+            },
+            // Ill-typed solutions will be reported when validating the
+            // elaborated terms, so we can ignore them here
+            Err(_) => continue,
+        };
+
+        let found = Rc::from(domain::Value::Universe(level));
+        let expected = &problem.universe;
+        match unify_values(prims, metas, budget, values, span, &found, expected) {
+            Ok(()) => {},
+            Err(UnifyError::Blocked) => metas.add_postponed(meta::Problem {
+                span,
+                names: problem.names.clone(),
+                values: values.clone(),
+                tys: problem.tys.clone(),
+                value1: found,
+                value2: expected.clone(),
+            }),
+            Err(UnifyError::Mismatch(mismatch)) => {
+                let context = Context::from_entries(
+                    prims.clone(),
+                    budget.clone(),
+                    problem.names.clone(),
+                    values.clone(),
+                    problem.tys.clone(),
+                );
+                return Err(mismatch_to_diagnostic(
+                    &context, metas, span, &found, expected, &mismatch,
+                ));
+            },
+            Err(UnifyError::Failed(diagnostic)) => return Err(diagnostic),
+        }
+    }
+
+    Ok(metas.universe_problems().len() < problem_count)
+}
+
+/// Report any unification problems that were postponed since the problem with
+/// the given id, and that could not be solved.
+pub fn check_postponed(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    since: meta::ProblemId,
+) -> Result<(), Diagnostic<FileSpan>> {
+    let problems = metas.postponed_since(since).collect::<Vec<_>>();
+    if problems.is_empty() {
+        return Ok(());
+    }
+
+    let message = match problems.len() {
+        1 => "unsolved constraint",
+        _ => "unsolved constraints",
+    };
+
+    Err(problems
+        .iter()
+        .fold(Diagnostic::new_error(message), |diagnostic, problem| {
+            let context = problem_context(prims, budget, problem);
+            let value1 = value_to_string(&context, metas, &problem.value1);
+            let value2 = value_to_string(&context, metas, &problem.value2);
+
+            diagnostic.with_label(
+                DiagnosticLabel::new_primary(problem.span)
+                    .with_message(format!("could not unify `{}` with `{}`", value1, value2)),
+            )
+        }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mltt_span::Files;
+
+    fn span() -> FileSpan {
+        let mut files = Files::new();
+        let file_id = files.add("test", "");
+        FileSpan::initial(file_id)
+    }
+
+    fn params(size: u32) -> var::Env<Rc<domain::Value>> {
+        let mut values = var::Env::new();
+        for level in 0..size {
+            values.add_entry(Rc::from(domain::Value::var(level)));
+        }
+        values
+    }
+
+    fn meta_app(meta_index: meta::Index, var_levels: &[u32]) -> Rc<domain::Value> {
+        let spine = var_levels
+            .iter()
+            .map(|var_level| {
+                let arg = Rc::from(domain::Value::var(*var_level));
+                domain::Elim::Fun(AppMode::Explicit, arg)
+            })
+            .collect();
+
+        Rc::from(domain::Value::Neutral(
+            domain::Head::Meta(meta_index),
+            spine,
+        ))
+    }
+
+    fn is_solved(metas: &meta::Env, meta_index: meta::Index) -> bool {
+        match metas.lookup_solution(meta_index) {
+            Some((_, meta::Solution::Solved(_), _, _)) => true,
+            Some((_, meta::Solution::Unsolved, _, _)) | None => false,
+        }
+    }
+
+    #[test]
+    fn solve_non_linear_spine() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(2);
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let meta_index = metas.add_unsolved(span(), var::Size(2), u0.clone());
+
+        // ?0 %0 %0 == Type
+        let lhs = meta_app(meta_index, &[0, 0]);
+        unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &lhs,
+            &u0,
+        )
+        .unwrap();
+
+        assert!(is_solved(&metas, meta_index));
+    }
+
+    #[test]
+    fn solve_non_linear_spine_scope_error() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(2);
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let meta_index = metas.add_unsolved(span(), var::Size(2), u0);
+
+        // ?0 %0 %0 == %0
+        let lhs = meta_app(meta_index, &[0, 0]);
+        let rhs = Rc::from(domain::Value::var(0));
+        assert!(unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &lhs,
+            &rhs
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn solve_pruned() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(2);
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let meta_index0 = metas.add_unsolved(span(), var::Size(1), u0.clone());
+        let meta_index1 = metas.add_unsolved(span(), var::Size(2), u0);
+
+        // ?0 %0 == ?1 %0 %1
+        let lhs = meta_app(meta_index0, &[0]);
+        let rhs = meta_app(meta_index1, &[0, 1]);
+        unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &lhs,
+            &rhs,
+        )
+        .unwrap();
+
+        // ?1 should have been pruned to a fresh metavariable, `?2`
+        let pruned_meta_index = meta::Index(2);
+        assert!(is_solved(&metas, meta_index0));
+        assert!(is_solved(&metas, meta_index1));
+        assert!(!is_solved(&metas, pruned_meta_index));
+
+        let lhs = nbe::force_value(&prims, &metas, &Budget::default(), span(), &lhs).unwrap();
+        let rhs = nbe::force_value(&prims, &metas, &Budget::default(), span(), &rhs).unwrap();
+        assert_eq!(lhs, meta_app(pruned_meta_index, &[0]));
+        assert_eq!(rhs, meta_app(pruned_meta_index, &[0]));
+    }
+
+    #[test]
+    fn prune_strengthens_type() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(2);
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let meta_index0 = metas.add_unsolved(span(), var::Size(1), u0);
+        // The type of `?1` depends on the parameter that is kept
+        let meta_index1 = metas.add_unsolved(span(), var::Size(2), Rc::from(domain::Value::var(1)));
+
+        // ?0 %1 == ?1 %0 %1
+        let lhs = meta_app(meta_index0, &[1]);
+        let rhs = meta_app(meta_index1, &[0, 1]);
+        unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &lhs,
+            &rhs,
+        )
+        .unwrap();
+
+        // The kept parameter is the first parameter of the pruned meta, `?2`
+        match metas.lookup_solution(meta::Index(2)) {
+            Some((_, meta::Solution::Unsolved, arity, ty)) => {
+                assert_eq!(*arity, var::Size(1));
+                assert_eq!(*ty, Rc::from(domain::Value::var(0)));
+            },
+            _ => panic!("expected an unsolved metavariable"),
+        }
+    }
+
+    #[test]
+    fn prune_blocked_by_type() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(2);
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let meta_index0 = metas.add_unsolved(span(), var::Size(1), u0);
+        // The type of `?1` depends on the parameter that would be pruned
+        let meta_index1 = metas.add_unsolved(span(), var::Size(2), Rc::from(domain::Value::var(1)));
+
+        // ?0 %0 == ?1 %0 %1
+        let lhs = meta_app(meta_index0, &[0]);
+        let rhs = meta_app(meta_index1, &[0, 1]);
+        match unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &lhs,
+            &rhs,
+        ) {
+            Err(UnifyError::Blocked) => {},
+            _ => panic!("expected unification to be blocked"),
+        }
+
+        assert!(!is_solved(&metas, meta_index0));
+        assert!(!is_solved(&metas, meta_index1));
+    }
+
+    #[test]
+    fn blocked_non_pattern() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(0);
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let meta_index = metas.add_unsolved(span(), var::Size(0), u0.clone());
+
+        // ?0 Type == Type
+        let spine = vec![domain::Elim::Fun(AppMode::Explicit, u0.clone())];
+        let lhs = Rc::from(domain::Value::Neutral(
+            domain::Head::Meta(meta_index),
+            spine,
+        ));
+        match unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &lhs,
+            &u0,
+        ) {
+            Err(UnifyError::Blocked) => {},
+            _ => panic!("expected unification to be blocked"),
+        }
+    }
+
+    #[test]
+    fn retry_postponed_after_solution() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(1);
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let meta_index0 = metas.add_unsolved(span(), var::Size(0), u0.clone());
+        let meta_index1 = metas.add_unsolved(span(), var::Size(1), u0.clone());
+
+        // ?0 (?1 %0) == Type
+        let spine = vec![domain::Elim::Fun(
+            AppMode::Explicit,
+            meta_app(meta_index1, &[0]),
+        )];
+        let lhs = Rc::from(domain::Value::Neutral(
+            domain::Head::Meta(meta_index0),
+            spine,
+        ));
+        metas.add_postponed(meta::Problem {
+            span: span(),
+            names: var::Env::new(),
+            values: values.clone(),
+            tys: var::Env::new(),
+            value1: lhs,
+            value2: u0,
+        });
+
+        // ?1 %0 == %0
+        let lhs = meta_app(meta_index1, &[0]);
+        let rhs = Rc::from(domain::Value::var(0));
+        unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &lhs,
+            &rhs,
+        )
+        .unwrap();
+        retry_postponed(&prims, &mut metas, &Budget::default()).unwrap();
+
+        assert_eq!(metas.postponed().len(), 0);
+        assert!(is_solved(&metas, meta_index0));
+        assert!(check_postponed(
+            &prims,
+            &metas,
+            &Budget::default(),
+            meta::ProblemId::default()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn report_unsolved_postponed() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(0);
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let meta_index = metas.add_unsolved(span(), var::Size(0), u0.clone());
+
+        // ?0 Type == Type
+        let spine = vec![domain::Elim::Fun(AppMode::Explicit, u0.clone())];
+        let lhs = Rc::from(domain::Value::Neutral(
+            domain::Head::Meta(meta_index),
+            spine,
+        ));
+        metas.add_postponed(meta::Problem {
+            span: span(),
+            names: var::Env::new(),
+            values,
+            tys: var::Env::new(),
+            value1: lhs,
+            value2: u0,
+        });
+        retry_postponed(&prims, &mut metas, &Budget::default()).unwrap();
+
+        assert_eq!(metas.postponed().len(), 1);
+        assert!(!is_solved(&metas, meta_index));
+        match check_postponed(
+            &prims,
+            &metas,
+            &Budget::default(),
+            meta::ProblemId::default(),
+        ) {
+            Err(diagnostic) => {
+                assert_eq!(diagnostic.message, "unsolved constraint");
+                assert_eq!(diagnostic.labels.len(), 1);
+            },
+            Ok(()) => panic!("expected the constraint to be reported"),
+        }
+    }
+
+    #[test]
+    fn mismatch_universe_level() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(0);
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let u1 = Rc::from(domain::Value::universe(1));
+
+        match unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &u1,
+            &u0,
+        ) {
+            Err(UnifyError::Mismatch(Mismatch {
+                kind: MismatchKind::UniverseLevel(level1, level2),
+                ..
+            })) => {
+                assert_eq!(level1, domain::Level::constant(1));
+                assert_eq!(level2, domain::Level::constant(0));
+            },
+            _ => panic!("expected a universe level mismatch"),
+        }
+    }
+
+    #[test]
+    fn mismatch_omega_universe_level() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(0);
+
+        let u_max = Rc::from(domain::Value::universe(UniverseLevel::MAX));
+        let u_omega = Rc::from(domain::Value::Universe(domain::Level::omega()));
+
+        match unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &u_omega,
+            &u_max,
+        ) {
+            Err(UnifyError::Mismatch(Mismatch {
+                kind: MismatchKind::UniverseLevel(level1, level2),
+                ..
+            })) => {
+                assert_eq!(level1, domain::Level::omega());
+                assert_eq!(level2, domain::Level::constant(UniverseLevel::MAX));
+            },
+            _ => panic!("expected a universe level mismatch"),
+        }
+    }
+
+    #[test]
+    fn mismatch_points_at_subterm() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(0);
+
+        let mut files = Files::new();
+        let file_id = files.add("test", "id {A = ?} \"hello\"");
+        let term_span = FileSpan::new(file_id, 0, 18);
+        let hole_span = FileSpan::new(file_id, 8, 9);
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let string = Rc::from(domain::Value::literal_ty(LiteralType::String));
+        let meta_index = metas.add_unsolved(hole_span, var::Size(0), u0.clone());
+        metas.add_solved(meta_index, string);
+
+        // ?0 == Type, where ?0 has been solved with `String`
+        let lhs = meta_app(meta_index, &[]);
+        let mismatch = match unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            term_span,
+            &lhs,
+            &u0,
+        ) {
+            Err(UnifyError::Mismatch(mismatch)) => mismatch,
+            _ => panic!("expected a mismatch"),
+        };
+        assert_eq!(mismatch.span, hole_span);
+
+        let context = Context::empty();
+        let diagnostic = mismatch_to_diagnostic(&context, &metas, term_span, &lhs, &u0, &mismatch);
+        assert_eq!(diagnostic.message, "mismatched types");
+        assert_eq!(diagnostic.labels[0].span, term_span);
+        assert_eq!(diagnostic.labels[1].span, hole_span);
+        assert_eq!(
+            diagnostic.labels[1].message.as_ref().map(String::as_str),
+            Some("`String` is not equal to `Type`"),
+        );
+    }
+
+    #[test]
+    fn solve_universe_level() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(0);
+
+        let level_ty = Rc::from(domain::Value::LevelType);
+        let meta_index = metas.add_unsolved(span(), var::Size(0), level_ty);
+
+        // Type^1 <: Type^(?0 + 1)
+        let u1 = Rc::from(domain::Value::universe(1));
+        let meta_level = domain::Level::neutral(meta_app(meta_index, &[]));
+        let meta_universe = Rc::from(domain::Value::Universe(meta_level.shift(1).unwrap()));
+        unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &u1,
+            &meta_universe,
+        )
+        .unwrap();
+
+        match metas.lookup_solution(meta_index) {
+            Some((_, meta::Solution::Solved(solution), _, _)) => {
+                assert_eq!(
+                    *solution,
+                    Rc::from(domain::Value::Level(domain::Level::constant(0)))
+                );
+            },
+            _ => panic!("expected the level to be solved"),
+        }
+    }
+
+    #[test]
+    fn mismatch_occurs() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(1);
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let meta_index = metas.add_unsolved(span(), var::Size(1), u0.clone());
+
+        // ?0 %0 == (?0 %0 -> Type)
+        let lhs = meta_app(meta_index, &[0]);
+        let rhs = Rc::from(domain::Value::FunType(
+            AppMode::Explicit,
+            None,
+            lhs.clone(),
+            domain::AppClosure::new(Rc::from(syntax::Term::universe(0)), values.clone()),
+        ));
+
+        match unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &lhs,
+            &rhs,
+        ) {
+            Err(UnifyError::Mismatch(Mismatch {
+                kind: MismatchKind::Occurs(occurs_meta_index),
+                ..
+            })) => assert_eq!(occurs_meta_index, meta_index),
+            _ => panic!("expected the occurs check to fail"),
+        }
+    }
+
+    #[test]
+    fn unify_globals() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(0);
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let u1 = Rc::from(domain::Value::universe(1));
+        let global0 = Rc::from(domain::Value::global(0, u0.clone()));
+        let global1 = Rc::from(domain::Value::global(1, u0.clone()));
+        let global2 = Rc::from(domain::Value::global(2, u1.clone()));
+
+        unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &global0,
+            &global0,
+        )
+        .unwrap();
+        unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &global0,
+            &global1,
+        )
+        .unwrap();
+        unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &global0,
+            &u0,
+        )
+        .unwrap();
+        assert!(unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &global2,
+            &global0
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn unify_globals_rolls_back_spines() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+        let values = params(0);
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let u1 = Rc::from(domain::Value::universe(1));
+        let meta_index = metas.add_unsolved(span(), var::Size(0), u1.clone());
+
+        // fun _ _ => Type
+        let term = Rc::from(syntax::Term::FunIntro(
+            AppMode::Explicit,
+            None,
+            Rc::from(syntax::Term::FunIntro(
+                AppMode::Explicit,
+                None,
+                Rc::from(syntax::Term::universe(0)),
+            )),
+        ));
+        let value =
+            nbe::eval_term(&prims, &metas, &Budget::default(), &values, span(), &term).unwrap();
+        let global = |arg1: Rc<domain::Value>, arg2: Rc<domain::Value>| {
+            Rc::from(domain::Value::Neutral(
+                domain::Head::Global(var::Level(0), value.clone()),
+                vec![
+                    domain::Elim::Fun(AppMode::Explicit, arg1),
+                    domain::Elim::Fun(AppMode::Explicit, arg2),
+                ],
+            ))
+        };
+
+        // The spines fail to unify after solving `?0`, but the definitions
+        // unify once they are unfolded
+        unify_values(
+            &prims,
+            &mut metas,
+            &Budget::default(),
+            &values,
+            span(),
+            &global(meta_app(meta_index, &[]), u1),
+            &global(u0.clone(), u0),
+        )
+        .unwrap();
 
-        (_, _) => unification_error(span, value1, value2),
+        assert!(!is_solved(&metas, meta_index));
     }
 }
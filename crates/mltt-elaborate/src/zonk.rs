@@ -0,0 +1,528 @@
+//! Zonking of metavariable solutions.
+//!
+//! Once elaboration has completed, the core terms may still contain references
+//! to metavariables whose solutions only live in the metavariable environment.
+//! Zonking substitutes these solutions back into the core syntax, producing
+//! terms that are ready to be validated and serialized without needing to
+//! carry the metavariable environment around with them.
+
+use language_reporting::{Diagnostic, Label as DiagnosticLabel};
+use mltt_core::nbe::{Budget, Unfold};
+use mltt_core::{domain, meta, prim, syntax, var, AppMode};
+use mltt_span::FileSpan;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use crate::nbe;
+
+/// Substitute the solved metavariables in a module, returning an error if any
+/// unsolved metavariables remain.
+pub fn zonk_module(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    module: syntax::Module,
+) -> Result<syntax::Module, Diagnostic<FileSpan>> {
+    let mut unsolved = BTreeSet::new();
+    let items = zonk_items(prims, metas, budget, &mut unsolved, &module.items)?;
+    unsolved_metas_error(metas, &unsolved)?;
+
+    Ok(syntax::Module { items })
+}
+
+/// Substitute the solved metavariables in a term, returning an error if any
+/// unsolved metavariables remain.
+pub fn zonk_term(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    term: &Rc<syntax::Term>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    let (term, unsolved) = zonk_term_unsolved(prims, metas, budget, term)?;
+    unsolved_metas_error(metas, &unsolved)?;
+
+    Ok(term)
+}
+
+/// Substitute the solved metavariables in a term, returning the unsolved
+/// metavariables that remain.
+pub fn zonk_term_unsolved(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    term: &Rc<syntax::Term>,
+) -> Result<(Rc<syntax::Term>, BTreeSet<meta::Index>), Diagnostic<FileSpan>> {
+    let mut unsolved = BTreeSet::new();
+    let term = zonk_term_inner(prims, metas, budget, &mut unsolved, term)?;
+
+    Ok((term, unsolved))
+}
+
+/// Create an environment of variables of the given size.
+///
+/// This is used in place of the values from the elaboration context when we
+/// don't want to unfold the definitions that are bound in it.
+pub fn neutral_env(size: var::Size) -> var::Env<Rc<domain::Value>> {
+    let mut values = var::Env::new();
+    for level in 0..size.0 {
+        values.add_entry(Rc::from(domain::Value::var(level)));
+    }
+    values
+}
+
+/// Report the metavariables that could not be solved during elaboration.
+fn unsolved_metas_error(
+    metas: &meta::Env,
+    unsolved: &BTreeSet<meta::Index>,
+) -> Result<(), Diagnostic<FileSpan>> {
+    if unsolved.is_empty() {
+        return Ok(());
+    }
+
+    let message = match unsolved.len() {
+        1 => "unsolved metavariable",
+        _ => "unsolved metavariables",
+    };
+
+    Err(unsolved.iter().fold(
+        Diagnostic::new_error(message),
+        |diagnostic, meta_index| match metas.lookup_solution(*meta_index) {
+            Some((span, _, _, _)) => diagnostic.with_label(
+                DiagnosticLabel::new_primary(*span)
+                    .with_message(format!("could not infer the term for `{}`", meta_index)),
+            ),
+            None => diagnostic,
+        },
+    ))
+}
+
+/// Split a spine of function eliminations into its head and its arguments,
+/// with the arguments in the order that they are applied.
+fn split_spine(term: &Rc<syntax::Term>) -> (&Rc<syntax::Term>, Vec<(&AppMode, &Rc<syntax::Term>)>) {
+    let mut head = term;
+    let mut args = Vec::new();
+    while let syntax::Term::FunElim(fun, app_mode, arg) = head.as_ref() {
+        args.push((app_mode, arg));
+        head = fun;
+    }
+    args.reverse();
+
+    (head, args)
+}
+
+/// Substitute the solution of a metavariable, returning `None` if the
+/// metavariable is unsolved.
+///
+/// Solutions are closed, so they can be substituted into any scope.
+fn zonk_meta(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    unsolved: &mut BTreeSet<meta::Index>,
+    meta_index: meta::Index,
+) -> Result<Option<Rc<syntax::Term>>, Diagnostic<FileSpan>> {
+    match metas.lookup_solution(meta_index) {
+        Some((_, meta::Solution::Solved(value), _, _)) => {
+            // Zonk the solution again, in case it mentions metas that were
+            // solved later on
+            let size = var::Size(0);
+            let term = nbe::read_back_value(prims, metas, budget, size, Unfold::None, None, value)?;
+            let term = zonk_term_inner(prims, metas, budget, unsolved, &term)?;
+            Ok(Some(term))
+        },
+        Some((_, meta::Solution::Unsolved, _, _)) => {
+            unsolved.insert(meta_index);
+            Ok(None)
+        },
+        None => Err(Diagnostic::new_bug(format!(
+            "zonk: metavariable not found: `{}`",
+            meta_index,
+        ))),
+    }
+}
+
+/// Apply a metavariable solution to some arguments, substituting them for the
+/// parameters of the solution rather than normalizing the result.
+fn apply_solution(
+    solution: Rc<syntax::Term>,
+    args: Vec<(AppMode, Rc<syntax::Term>)>,
+) -> Rc<syntax::Term> {
+    args.into_iter()
+        .fold(solution, |fun, (app_mode, arg)| match fun.as_ref() {
+            syntax::Term::FunIntro(_, _, body) => body.subst(&arg),
+            _ => Rc::from(syntax::Term::FunElim(fun, app_mode, arg)),
+        })
+}
+
+fn zonk_items(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    unsolved: &mut BTreeSet<meta::Index>,
+    items: &[syntax::Item],
+) -> Result<Vec<syntax::Item>, Diagnostic<FileSpan>> {
+    items
+        .iter()
+        .map(|item| match item {
+            syntax::Item::Declaration(docs, label, body_ty) => {
+                let body_ty = zonk_term_inner(prims, metas, budget, unsolved, body_ty)?;
+                Ok(syntax::Item::Declaration(
+                    docs.clone(),
+                    label.clone(),
+                    body_ty,
+                ))
+            },
+            syntax::Item::Definition(docs, label, body) => {
+                let body = zonk_term_inner(prims, metas, budget, unsolved, body)?;
+                Ok(syntax::Item::Definition(docs.clone(), label.clone(), body))
+            },
+        })
+        .collect()
+}
+
+fn zonk_data(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    unsolved: &mut BTreeSet<meta::Index>,
+    data: &syntax::Data,
+) -> Result<Rc<syntax::Data>, Diagnostic<FileSpan>> {
+    let ty = zonk_term_inner(prims, metas, budget, unsolved, &data.ty)?;
+    let intros = data
+        .intros
+        .iter()
+        .map(|(docs, label, ty)| {
+            let ty = zonk_term_inner(prims, metas, budget, unsolved, ty)?;
+            Ok((docs.clone(), label.clone(), ty))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Rc::from(syntax::Data {
+        label: data.label.clone(),
+        param_count: data.param_count,
+        ty,
+        intros,
+    }))
+}
+
+fn zonk_term_inner(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    budget: &Budget,
+    unsolved: &mut BTreeSet<meta::Index>,
+    term: &Rc<syntax::Term>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    use mltt_core::syntax::Term;
+
+    match term.as_ref() {
+        Term::Var(_) | Term::Prim(_) => Ok(term.clone()),
+        Term::Ann(term, term_ty) => {
+            let term = zonk_term_inner(prims, metas, budget, unsolved, term)?;
+            let term_ty = zonk_term_inner(prims, metas, budget, unsolved, term_ty)?;
+
+            Ok(Rc::from(Term::Ann(term, term_ty)))
+        },
+        Term::Let(items, body) => {
+            let items = zonk_items(prims, metas, budget, unsolved, items)?;
+            let body = zonk_term_inner(prims, metas, budget, unsolved, body)?;
+
+            Ok(Rc::from(Term::Let(items, body)))
+        },
+        Term::Fix(index, defns) => {
+            let defns = defns
+                .iter()
+                .map(|(label, guard, ty, body)| {
+                    let ty = zonk_term_inner(prims, metas, budget, unsolved, ty)?;
+                    let body = zonk_term_inner(prims, metas, budget, unsolved, body)?;
+                    Ok((label.clone(), guard.clone(), ty, body))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(Rc::from(Term::Fix(*index, Rc::from(defns))))
+        },
+
+        // Literals
+        Term::LiteralType(_) | Term::LiteralIntro(_) => Ok(term.clone()),
+        Term::LiteralElim(scrutinee, clauses, default_body) => {
+            let scrutinee = zonk_term_inner(prims, metas, budget, unsolved, scrutinee)?;
+            let clauses = clauses
+                .iter()
+                .map(|(literal_intro, body)| {
+                    let body = zonk_term_inner(prims, metas, budget, unsolved, body)?;
+                    Ok((literal_intro.clone(), body))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let default_body = zonk_term_inner(prims, metas, budget, unsolved, default_body)?;
+
+            Ok(Rc::from(Term::LiteralElim(
+                scrutinee,
+                Rc::from(clauses),
+                default_body,
+            )))
+        },
+
+        // Functions
+        Term::FunType(app_mode, name_hint, param_ty, body_ty) => {
+            let param_ty = zonk_term_inner(prims, metas, budget, unsolved, param_ty)?;
+            let body_ty = zonk_term_inner(prims, metas, budget, unsolved, body_ty)?;
+
+            Ok(Rc::from(Term::FunType(
+                app_mode.clone(),
+                name_hint.clone(),
+                param_ty,
+                body_ty,
+            )))
+        },
+        Term::FunIntro(app_mode, name_hint, body) => {
+            let body = zonk_term_inner(prims, metas, budget, unsolved, body)?;
+
+            Ok(Rc::from(Term::FunIntro(
+                app_mode.clone(),
+                name_hint.clone(),
+                body,
+            )))
+        },
+        Term::Meta(_) | Term::FunElim(_, _, _) => {
+            // Peel the spine once, rather than looking for a metavariable at
+            // the head of every nested elimination
+            let (head, args) = split_spine(term);
+            let args = args
+                .into_iter()
+                .map(|(app_mode, arg)| {
+                    let arg = zonk_term_inner(prims, metas, budget, unsolved, arg)?;
+                    Ok((app_mode.clone(), arg))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let head = match head.as_ref() {
+                Term::Meta(meta_index) => {
+                    match zonk_meta(prims, metas, budget, unsolved, *meta_index)? {
+                        Some(solution) => return Ok(apply_solution(solution, args)),
+                        None => head.clone(),
+                    }
+                },
+                _ => zonk_term_inner(prims, metas, budget, unsolved, head)?,
+            };
+
+            Ok(args.into_iter().fold(head, |fun, (app_mode, arg)| {
+                Rc::from(Term::FunElim(fun, app_mode, arg))
+            }))
+        },
+
+        // Records
+        Term::RecordType(ty_fields) => {
+            let ty_fields = ty_fields
+                .iter()
+                .map(|(docs, label, name_hint, ty, manifest)| {
+                    let ty = zonk_term_inner(prims, metas, budget, unsolved, ty)?;
+                    let manifest = match manifest {
+                        None => None,
+                        Some(manifest) => {
+                            Some(zonk_term_inner(prims, metas, budget, unsolved, manifest)?)
+                        },
+                    };
+                    Ok((docs.clone(), label.clone(), name_hint.clone(), ty, manifest))
+                })
+                .collect::<Result<_, _>>()?;
+
+            Ok(Rc::from(Term::RecordType(ty_fields)))
+        },
+        Term::RecordIntro(intro_fields) => {
+            let intro_fields = intro_fields
+                .iter()
+                .map(|(label, term)| {
+                    let term = zonk_term_inner(prims, metas, budget, unsolved, term)?;
+                    Ok((label.clone(), term))
+                })
+                .collect::<Result<_, _>>()?;
+
+            Ok(Rc::from(Term::RecordIntro(intro_fields)))
+        },
+        Term::RecordElim(record, label) => {
+            let record = zonk_term_inner(prims, metas, budget, unsolved, record)?;
+
+            Ok(Rc::from(Term::RecordElim(record, label.clone())))
+        },
+
+        // Data types
+        Term::DataType(data) => {
+            let data = zonk_data(prims, metas, budget, unsolved, data)?;
+
+            Ok(Rc::from(Term::DataType(data)))
+        },
+        Term::DataIntro(data, label) => {
+            let data = zonk_data(prims, metas, budget, unsolved, data)?;
+
+            Ok(Rc::from(Term::DataIntro(data, label.clone())))
+        },
+        Term::DataElim(scrutinee, clauses) => {
+            let scrutinee = zonk_term_inner(prims, metas, budget, unsolved, scrutinee)?;
+            let clauses = clauses
+                .iter()
+                .map(|(label, name_hints, body)| {
+                    let body = zonk_term_inner(prims, metas, budget, unsolved, body)?;
+                    Ok((label.clone(), name_hints.clone(), body))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(Rc::from(Term::DataElim(scrutinee, Rc::from(clauses))))
+        },
+
+        // Equality
+        Term::EqType(ty, lhs, rhs) => {
+            let ty = zonk_term_inner(prims, metas, budget, unsolved, ty)?;
+            let lhs = zonk_term_inner(prims, metas, budget, unsolved, lhs)?;
+            let rhs = zonk_term_inner(prims, metas, budget, unsolved, rhs)?;
+
+            Ok(Rc::from(Term::EqType(ty, lhs, rhs)))
+        },
+        Term::EqIntro => Ok(term.clone()),
+        Term::EqElim(proof, motive, body) => {
+            let proof = zonk_term_inner(prims, metas, budget, unsolved, proof)?;
+            let motive = zonk_term_inner(prims, metas, budget, unsolved, motive)?;
+            let body = zonk_term_inner(prims, metas, budget, unsolved, body)?;
+
+            Ok(Rc::from(Term::EqElim(proof, motive, body)))
+        },
+
+        // Universe levels
+        Term::LevelType => Ok(term.clone()),
+        Term::LevelIntro(_) | Term::LevelOmega => Ok(term.clone()),
+        Term::LevelShift(level, shift) => {
+            let level = zonk_term_inner(prims, metas, budget, unsolved, level)?;
+
+            Ok(Rc::from(Term::LevelShift(level, *shift)))
+        },
+        Term::LevelMax(lhs, rhs) => {
+            let lhs = zonk_term_inner(prims, metas, budget, unsolved, lhs)?;
+            let rhs = zonk_term_inner(prims, metas, budget, unsolved, rhs)?;
+
+            Ok(Rc::from(Term::LevelMax(lhs, rhs)))
+        },
+
+        // Universes
+        Term::Universe(level) => {
+            let level = zonk_term_inner(prims, metas, budget, unsolved, level)?;
+
+            Ok(Rc::from(Term::Universe(level)))
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mltt_core::syntax::Term;
+    use mltt_core::AppMode;
+    use mltt_span::Files;
+
+    fn span() -> FileSpan {
+        let mut files = Files::new();
+        let file_id = files.add("test", "");
+        FileSpan::initial(file_id)
+    }
+
+    #[test]
+    fn zonk_solved_spine() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let meta_index = metas.add_unsolved(span(), var::Size(1), u0);
+        // ?0 := fun x => x
+        let solution = Rc::from(Term::FunIntro(
+            AppMode::Explicit,
+            None,
+            Rc::from(Term::var(0)),
+        ));
+        let solution = nbe::eval_term(
+            &prims,
+            &metas,
+            &Budget::default(),
+            &var::Env::new(),
+            None,
+            &solution,
+        )
+        .unwrap();
+        metas.add_solved(meta_index, solution);
+
+        // ?0 @0
+        let term = Rc::from(Term::FunElim(
+            Rc::from(Term::meta(meta_index)),
+            AppMode::Explicit,
+            Rc::from(Term::var(0)),
+        ));
+
+        let zonked = zonk_term(&prims, &metas, &Budget::default(), &term).unwrap();
+        assert_eq!(zonked, Rc::from(Term::var(0)));
+    }
+
+    #[test]
+    fn zonk_solved_spine_preserves_args() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let meta_index = metas.add_unsolved(span(), var::Size(2), u0);
+        // ?0 := fun x y => y
+        let solution = Rc::from(Term::FunIntro(
+            AppMode::Explicit,
+            None,
+            Rc::from(Term::FunIntro(
+                AppMode::Explicit,
+                None,
+                Rc::from(Term::var(0)),
+            )),
+        ));
+        let solution = nbe::eval_term(
+            &prims,
+            &metas,
+            &Budget::default(),
+            &var::Env::new(),
+            None,
+            &solution,
+        )
+        .unwrap();
+        metas.add_solved(meta_index, solution);
+
+        // (fun z => z) @0
+        let arg = Rc::from(Term::FunElim(
+            Rc::from(Term::FunIntro(
+                AppMode::Explicit,
+                None,
+                Rc::from(Term::var(0)),
+            )),
+            AppMode::Explicit,
+            Rc::from(Term::var(0)),
+        ));
+        // ?0 @1 ((fun z => z) @0) @1
+        let term = [Rc::from(Term::var(1)), arg.clone(), Rc::from(Term::var(1))]
+            .iter()
+            .fold(Rc::from(Term::meta(meta_index)), |fun, arg| {
+                Rc::from(Term::FunElim(fun, AppMode::Explicit, arg.clone()))
+            });
+
+        // The argument is substituted without being normalized
+        let zonked = zonk_term(&prims, &metas, &Budget::default(), &term).unwrap();
+        assert_eq!(
+            zonked,
+            Rc::from(Term::FunElim(
+                arg,
+                AppMode::Explicit,
+                Rc::from(Term::var(1))
+            )),
+        );
+    }
+
+    #[test]
+    fn zonk_unsolved() {
+        let prims = prim::Env::new();
+        let mut metas = meta::Env::new();
+
+        let u0 = Rc::from(domain::Value::universe(0));
+        let meta_index = metas.add_unsolved(span(), var::Size(0), u0);
+        let term = Rc::from(Term::ann(Term::meta(meta_index), Term::universe(0)));
+
+        assert!(zonk_term(&prims, &metas, &Budget::default(), &term).is_err());
+    }
+}
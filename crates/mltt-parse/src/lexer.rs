@@ -7,11 +7,14 @@ use crate::token::{DelimKind, Token, TokenKind};
 
 /// The keywords used in the language.
 pub const KEYWORDS: &[&str] = &[
+    "auto",
     "case",
+    "data",
     "else",
     "if",
     "in",
     "let",
+    "partial",
     "then",
     "Type",
     "Fun",
@@ -19,6 +22,7 @@ pub const KEYWORDS: &[&str] = &[
     "primitive",
     "Record",
     "record",
+    "with",
 ];
 
 fn is_whitespace(ch: char) -> bool {
@@ -7,18 +7,22 @@
 //! ```text
 //! module  ::= item* EOF
 //!
-//! item    ::= DOC_COMMENT* IDENTIFIER ":" term ";"
-//!           | DOC_COMMENT* IDENTIFIER intro-param* (":" term)? "=" term ";"
+//! item    ::= DOC_COMMENT* "auto"? IDENTIFIER ":" term ";"
+//!           | DOC_COMMENT* "partial"? IDENTIFIER intro-param* (":" term)? "=" term ";"
+//!           | DOC_COMMENT* "data" IDENTIFIER explicit-type-param* (":" term)? "{" (data-intro ";")* "}" ";"
 //!
 //! pattern ::= IDENTIFIER
+//!           | IDENTIFIER pattern+
 //!           | STRING_LITERAL
 //!           | CHAR_LITERAL
 //!           | INT_LITERAL
 //!           | FLOAT_LITERAL
+//!           | "(" pattern ("," pattern)+ ")"
 //!
 //! term    ::= IDENTIFIER
 //!           | "?"
 //!           | "(" term ")"
+//!           | "(" term ("," term)+ ")"
 //!           | term ":" term
 //!           | "let" item+ "in" term
 //!           | "if" term "then" term "else" term
@@ -33,11 +37,19 @@
 //!           | "fun" intro-param+ "=>" term
 //!           | term arg
 //!           | "Record" "{" (record-type-field ";")* record-type-field? "}"
-//!           | "record" "{" (record-intro-field ";")* record-intro-field? "}"
+//!           | "record" "{" (".." term ";")? (record-intro-field ";")* record-intro-field? "}"
+//!           | term "with" "{" (record-intro-field ";")* record-intro-field? "}"
 //!           | term "." IDENTIFIER
-//!           | "Type" ("^" INT_LITERAL)?
+//!           | term "*" term
+//!           | "Type" ("^" level-atom)?
 //!
-//! type-param  ::= "(" IDENTIFIER+ ":" term ")"
+//! level       ::= "max" level-atom level-atom
+//!               | level-atom ("+" INT_LITERAL)*
+//! level-atom  ::= INT_LITERAL
+//!               | IDENTIFIER
+//!               | "(" level ")"
+//!
+//! type-param  ::= explicit-type-param
 //!               | "{" IDENTIFIER+ (":" term)? "}"
 //!               | "{{" IDENTIFIER ":" term "}}"
 //! intro-param ::= pattern
@@ -47,8 +59,11 @@
 //!               | "{" IDENTIFIER ("=" term)? "}"
 //!               | "{{" IDENTIFIER ("=" term)? "}}"
 //!
+//! explicit-type-param ::= "(" IDENTIFIER+ ":" term ")"
+//! data-intro          ::= DOC_COMMENT* IDENTIFIER ":" term
 //! case-clause         ::= pattern "=>" term
-//! record-type-field   ::= DOC_COMMENT* IDENTIFIER ":" term
+//! record-type-field   ::= DOC_COMMENT* IDENTIFIER ":" term ("=" term)?
+//!                       | ".." term
 //! record-intro-field  ::= IDENTIFIER
 //!                       | IDENTIFIER intro-param* (":" term)? "=" term
 //! ```
@@ -62,8 +77,8 @@
 
 use language_reporting::{Diagnostic, Label};
 use mltt_concrete::{
-    Arg, Declaration, Definition, IntroParam, Item, LiteralKind, Pattern, RecordIntroField,
-    RecordTypeField, SpannedString, Term, TypeParam,
+    Arg, Data, DataIntro, Declaration, Definition, IntroParam, Item, Level, LiteralKind, Pattern,
+    RecordIntroField, RecordTypeField, SpannedString, Term, TypeParam,
 };
 use mltt_span::FileSpan;
 
@@ -114,12 +129,39 @@ impl Matcher<Token<'_>> for Keyword<'_> {
     }
 }
 
+struct Symbol<'a>(pub &'a str);
+
+impl Matcher<Token<'_>> for Symbol<'_> {
+    fn is_match(&self, given: &Token<'_>) -> bool {
+        given.kind == TokenKind::Symbol && given.src.slice == self.0
+    }
+}
+
 struct ItemStart;
 
 impl Matcher<Token<'_>> for ItemStart {
     fn is_match(&self, given: &Token<'_>) -> bool {
         match given.kind {
             TokenKind::LineDoc | TokenKind::Identifier => true,
+            TokenKind::Keyword if given.src.slice == "auto" => true,
+            TokenKind::Keyword if given.src.slice == "partial" => true,
+            TokenKind::Keyword if given.src.slice == "data" => true,
+            _ => false,
+        }
+    }
+}
+
+struct PatternStart;
+
+impl Matcher<Token<'_>> for PatternStart {
+    fn is_match(&self, given: &Token<'_>) -> bool {
+        match given.kind {
+            TokenKind::Identifier
+            | TokenKind::StringLiteral
+            | TokenKind::CharLiteral
+            | TokenKind::IntLiteral
+            | TokenKind::FloatLiteral
+            | TokenKind::Open(DelimKind::Paren) => true,
             _ => false,
         }
     }
@@ -295,13 +337,20 @@ where
     /// Parse an item.
     ///
     /// ```text
-    /// item ::= DOC_COMMENT* IDENTIFIER ":" term(0) ";"
-    ///        | DOC_COMMENT* IDENTIFIER intro-param* (":" term(0))? "=" term(0) ";"
+    /// item ::= DOC_COMMENT* "auto"? IDENTIFIER ":" term(0) ";"
+    ///        | DOC_COMMENT* "partial"? IDENTIFIER intro-param* (":" term(0))? "=" term(0) ";"
+    ///        | DOC_COMMENT* "data" data
     /// ```
     fn parse_item(&mut self) -> Result<Item<'file>, Diagnostic<FileSpan>> {
         log::trace!("expecting item");
 
         let docs = self.expect_doc_comments();
+        if let Some(start_token) = self.try_match(Keyword("data")) {
+            return self.parse_data(docs, start_token);
+        }
+
+        let auto = self.try_match(Keyword("auto")).map(|token| token.src);
+        let partial = self.try_match(Keyword("partial")).map(|token| token.src);
         let label = self.expect_identifier()?;
 
         log::trace!("item label: {:?}", label);
@@ -312,8 +361,14 @@ where
             let body_ty = self.parse_term(Prec(0))?;
 
             if params.is_empty() && self.try_match(TokenKind::Semicolon).is_some() {
+                if let Some(partial) = partial {
+                    return Err(Diagnostic::new_error("declarations cannot be partial")
+                        .with_label(Label::new_primary(partial.span())));
+                }
+
                 let declaration = Declaration {
                     docs,
+                    auto,
                     label,
                     body_ty,
                 };
@@ -326,12 +381,18 @@ where
             None
         };
 
+        if let Some(auto) = auto {
+            return Err(Diagnostic::new_error("definitions cannot be auto")
+                .with_label(Label::new_primary(auto.span())));
+        }
+
         if self.try_match(TokenKind::Equals).is_some() {
             let body = self.parse_term(Prec(0))?;
             self.expect_match(TokenKind::Semicolon)?;
 
             let definition = Definition {
                 docs,
+                partial,
                 label,
                 params,
                 body_ty,
@@ -350,6 +411,71 @@ where
         }
     }
 
+    /// Parse the trailing part of a data type definition.
+    ///
+    /// ```text
+    /// data        ::= IDENTIFIER explicit-type-param* (":" term(0))? "{" (data-intro ";")* "}" ";"
+    /// data-intro  ::= DOC_COMMENT* IDENTIFIER ":" term(0)
+    /// ```
+    ///
+    /// Only explicit parameters are allowed, so that the opening brace of the
+    /// constructors is not mistaken for an implicit parameter.
+    fn parse_data(
+        &mut self,
+        docs: Vec<SpannedString<'file>>,
+        start_token: Token<'file>,
+    ) -> Result<Item<'file>, Diagnostic<FileSpan>> {
+        let label = self.expect_identifier()?;
+
+        log::trace!("data label: {:?}", label);
+
+        let mut params = Vec::new();
+        while let Some(start_param_token) = self.try_match(TokenKind::Open(DelimKind::Paren)) {
+            params.push(self.parse_explicit_type_param(start_param_token)?);
+        }
+        let body_ty = match self.try_match(TokenKind::Colon) {
+            Some(_) => Some(self.parse_term(Prec(0))?),
+            None => None,
+        };
+
+        self.expect_match(TokenKind::Open(DelimKind::Brace))?;
+
+        let mut intros = Vec::new();
+        loop {
+            let docs = self.expect_doc_comments();
+
+            if let Some(label) = self.try_identifier() {
+                self.expect_match(TokenKind::Colon)?;
+                let body_ty = self.parse_term(Prec(0))?;
+
+                intros.push(DataIntro {
+                    docs,
+                    label,
+                    body_ty,
+                });
+
+                if self.try_match(TokenKind::Semicolon).is_some() {
+                    continue;
+                }
+            }
+
+            break;
+        }
+
+        self.expect_match(TokenKind::Close(DelimKind::Brace))?;
+        let end_token = self.expect_match(TokenKind::Semicolon)?;
+        let span = FileSpan::merge(start_token.span(), end_token.span());
+
+        Ok(Item::Data(Data {
+            span,
+            docs,
+            label,
+            params,
+            body_ty,
+            intros,
+        }))
+    }
+
     /// Parse zero-or-more function introduction parameters.
     ///
     /// ```text
@@ -366,7 +492,7 @@ where
     /// Parse a function introduction parameter.
     ///
     /// ```text
-    /// intro-param ::= pattern(0)
+    /// intro-param ::= pattern(100)
     ///               | "{" IDENTIFIER ("=" pattern(0))? "}"
     ///               | "{{" IDENTIFIER ("=" pattern(0))? "}}"
     /// ```
@@ -395,7 +521,7 @@ where
                 IntroParam::Implicit(span, label, term)
             })
         } else {
-            Ok(IntroParam::Explicit(self.parse_pattern(Prec(0))?))
+            Ok(IntroParam::Explicit(self.parse_pattern(Prec(100))?))
         }
     }
 
@@ -439,14 +565,15 @@ where
     ///
     /// ```text
     /// pattern(prec) ::= operators(prec) {
-    ///     nilfix  IDENTIFIER
+    ///     prefix  IDENTIFIER          ::= data-intro-pattern
     ///     nilfix  STRING_LITERAL
     ///     nilfix  CHAR_LITERAL
     ///     nilfix  INT_LITERAL
     ///     nilfix  FLOAT_LITERAL
+    ///     prefix  "("                 ::= tuple-intro-pattern
     /// }
     /// ```
-    fn parse_pattern(&mut self, _right_prec: Prec) -> Result<Pattern<'file>, Diagnostic<FileSpan>> {
+    fn parse_pattern(&mut self, right_prec: Prec) -> Result<Pattern<'file>, Diagnostic<FileSpan>> {
         // Use Top-Down Operator Precedence Parsing (a.k.a. Pratt Parsing) to
         // recognise the term syntax. This is not yet abstracted out into a more
         // general form.
@@ -457,7 +584,10 @@ where
 
         // Prefix operators
         let pattern = match (token.kind, token.src.slice) {
-            (TokenKind::Identifier, _) => Ok(Pattern::Var(self.parse_var(token)?)),
+            (TokenKind::Identifier, _) => {
+                let label = self.parse_var(token)?;
+                self.parse_data_intro_pattern(label, right_prec)
+            },
             (TokenKind::StringLiteral, _) => {
                 let (kind, literal) = self.parse_string_literal(token)?;
                 Ok(Pattern::LiteralIntro(kind, literal))
@@ -474,6 +604,7 @@ where
                 let (kind, literal) = self.parse_float_literal(token)?;
                 Ok(Pattern::LiteralIntro(kind, literal))
             },
+            (TokenKind::Open(DelimKind::Paren), _) => self.parse_tuple_intro_pattern(token),
             (_, _) => Err(Diagnostic::new_error("expected a pattern").with_label(
                 Label::new_primary(token.span()).with_message("pattern expected here"),
             )),
@@ -489,6 +620,60 @@ where
         Ok(pattern)
     }
 
+    /// Parse the trailing part of a constructor pattern. If no argument
+    /// patterns follow the label, then we treat it as a variable pattern.
+    ///
+    /// ```text
+    /// data-intro-pattern ::= pattern(100)*
+    /// ```
+    fn parse_data_intro_pattern(
+        &mut self,
+        label: SpannedString<'file>,
+        right_prec: Prec,
+    ) -> Result<Pattern<'file>, Diagnostic<FileSpan>> {
+        let mut arg_patterns = Vec::new();
+        if right_prec < 100 {
+            while self.is_peek_match(PatternStart) {
+                arg_patterns.push(self.parse_pattern(Prec(100))?);
+            }
+        }
+
+        if arg_patterns.is_empty() {
+            Ok(Pattern::Var(label))
+        } else {
+            Ok(Pattern::DataIntro(label, arg_patterns))
+        }
+    }
+
+    /// Parse the trailing part of a tuple pattern.
+    ///
+    /// ```text
+    /// tuple-intro-pattern ::= pattern(0) ("," pattern(0))+ ")"
+    /// ```
+    fn parse_tuple_intro_pattern(
+        &mut self,
+        start_token: Token<'file>,
+    ) -> Result<Pattern<'file>, Diagnostic<FileSpan>> {
+        let mut patterns = vec![self.parse_pattern(Prec(0))?];
+        while self.try_match(TokenKind::Comma).is_some() {
+            patterns.push(self.parse_pattern(Prec(0))?);
+        }
+
+        let end_token = self.expect_match(TokenKind::Close(DelimKind::Paren))?;
+        let span = FileSpan::merge(start_token.span(), end_token.span());
+
+        if patterns.len() < 2 {
+            return Err(
+                Diagnostic::new_error("expected a tuple pattern").with_label(
+                    Label::new_primary(span)
+                        .with_message("tuple patterns must have at least two components"),
+                ),
+            );
+        }
+
+        Ok(Pattern::TupleIntro(span, patterns))
+    }
+
     /// Parse a term.
     ///
     /// ```text
@@ -511,6 +696,8 @@ where
     ///     nilfix  FLOAT_LITERAL
     ///
     ///     infixr  "."             80  ::= record-elim fun-elim
+    ///     infixl  "with"          80  ::= record-update
+    ///     infixr  "*"             60  ::= tuple-type
     ///     infixr  ":"             20  ::= ann
     ///     infixr  "->"            50  ::= fun-arrow-type
     /// }
@@ -575,6 +762,14 @@ where
                     term = self.parse_record_elim(term, token)?;
                     term = self.parse_fun_elim(term)?;
                 },
+                TokenKind::Keyword if token.src.slice == "with" && right_prec < 80 => {
+                    let token = self.advance().unwrap();
+                    term = self.parse_record_update(term, token)?;
+                },
+                TokenKind::Symbol if token.src.slice == "*" && right_prec < 60 => {
+                    let token = self.advance().unwrap();
+                    term = self.parse_tuple_ty(term, token)?;
+                },
                 TokenKind::Colon if right_prec < 20 => {
                     let token = self.advance().unwrap();
                     term = self.parse_ann(term, token)?;
@@ -700,40 +895,21 @@ where
         Ok((LiteralKind::Float, token.src))
     }
 
-    /// Parse the trailing part of a function introduction.
+    /// Parse zero-or-more function type parameters.
     ///
     /// ```text
-    /// fun-ty  ::= type-param+ "->" term(50 - 1)
+    /// type-params ::= type-param*
     ///
     /// type-param  ::= "(" IDENTIFIER+ ":" term(0) ")"
     ///               | "{" IDENTIFIER+ (":" term(0))? "}"
     ///               | "{{" IDENTIFIER ":" term(0) "}}"
     /// ```
-    fn parse_fun_ty(
-        &mut self,
-        start_token: Token<'file>,
-    ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
+    fn parse_type_params(&mut self) -> Result<Vec<TypeParam<'file>>, Diagnostic<FileSpan>> {
         let mut params = Vec::new();
 
         loop {
             if let Some(start_param_token) = self.try_match(TokenKind::Open(DelimKind::Paren)) {
-                let mut param_names = Vec::new();
-                while let Some(param_name) = self.try_identifier() {
-                    param_names.push(param_name);
-                }
-                if param_names.is_empty() {
-                    return Err(Diagnostic::new_error("expected at least one parameter")
-                        .with_label(Label::new_primary(start_param_token.span()).with_message(
-                            "at least one parameter was expected after this parenthesis",
-                        )));
-                }
-
-                self.expect_match(TokenKind::Colon)?;
-                let param_ty = self.parse_term(Prec(0))?;
-                let end_param_token = self.expect_match(TokenKind::Close(DelimKind::Paren))?;
-                let param_span = FileSpan::merge(start_param_token.span(), end_param_token.span());
-
-                params.push(TypeParam::Explicit(param_span, param_names, param_ty));
+                params.push(self.parse_explicit_type_param(start_param_token)?);
             } else if let Some(start_param_token) =
                 self.try_match(TokenKind::Open(DelimKind::Brace))
             {
@@ -777,6 +953,50 @@ where
             }
         }
 
+        Ok(params)
+    }
+
+    /// Parse the trailing part of an explicit function type parameter.
+    ///
+    /// ```text
+    /// explicit-type-param ::= "(" IDENTIFIER+ ":" term(0) ")"
+    /// ```
+    fn parse_explicit_type_param(
+        &mut self,
+        start_param_token: Token<'file>,
+    ) -> Result<TypeParam<'file>, Diagnostic<FileSpan>> {
+        let mut param_names = Vec::new();
+        while let Some(param_name) = self.try_identifier() {
+            param_names.push(param_name);
+        }
+        if param_names.is_empty() {
+            return Err(
+                Diagnostic::new_error("expected at least one parameter")
+                    .with_label(Label::new_primary(start_param_token.span()).with_message(
+                        "at least one parameter was expected after this parenthesis",
+                    )),
+            );
+        }
+
+        self.expect_match(TokenKind::Colon)?;
+        let param_ty = self.parse_term(Prec(0))?;
+        let end_param_token = self.expect_match(TokenKind::Close(DelimKind::Paren))?;
+        let param_span = FileSpan::merge(start_param_token.span(), end_param_token.span());
+
+        Ok(TypeParam::Explicit(param_span, param_names, param_ty))
+    }
+
+    /// Parse the trailing part of a function introduction.
+    ///
+    /// ```text
+    /// fun-ty  ::= type-param+ "->" term(50 - 1)
+    /// ```
+    fn parse_fun_ty(
+        &mut self,
+        start_token: Token<'file>,
+    ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
+        let params = self.parse_type_params()?;
+
         if params.is_empty() {
             return Err(
                 Diagnostic::new_error("expected at least one parameter").with_label(
@@ -818,27 +1038,41 @@ where
         Ok(Term::FunIntro(span, params, Box::new(body)))
     }
 
-    /// Parse the trailing part of a parenthesis grouping.
+    /// Parse the trailing part of a parenthesis grouping or a tuple.
     ///
     /// ```text
     /// parens ::= term(0) ")"
+    ///          | term(0) ("," term(0))+ ")"
     /// ```
     fn parse_parens(
         &mut self,
         start_token: Token<'file>,
     ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
         let term = self.parse_term(Prec(0))?;
-        let end_token = self.expect_match(TokenKind::Close(DelimKind::Paren))?;
-        let span = FileSpan::merge(start_token.span(), end_token.span());
 
-        Ok(Term::Parens(span, Box::new(term)))
+        if self.is_peek_match(TokenKind::Comma) {
+            let mut terms = vec![term];
+            while self.try_match(TokenKind::Comma).is_some() {
+                terms.push(self.parse_term(Prec(0))?);
+            }
+            let end_token = self.expect_match(TokenKind::Close(DelimKind::Paren))?;
+            let span = FileSpan::merge(start_token.span(), end_token.span());
+
+            Ok(Term::TupleIntro(span, terms))
+        } else {
+            let end_token = self.expect_match(TokenKind::Close(DelimKind::Paren))?;
+            let span = FileSpan::merge(start_token.span(), end_token.span());
+
+            Ok(Term::Parens(span, Box::new(term)))
+        }
     }
 
     /// Parse the trailing part of a record type.
     ///
     /// ```text
     /// record-type         ::= "{" (record-type-field ";")* record-type-field? "}"
-    /// record-type-field   ::= DOC_COMMENT* IDENTIFIER ":" term(0)
+    /// record-type-field   ::= DOC_COMMENT* IDENTIFIER ":" term(0) ("=" term(0))?
+    ///                       | ".." term(0)
     /// ```
     fn parse_record_ty(
         &mut self,
@@ -851,11 +1085,30 @@ where
         loop {
             let docs = self.expect_doc_comments();
 
-            if let Some(label) = self.try_identifier() {
+            let field = if let Some(label) = self.try_identifier() {
                 self.expect_match(TokenKind::Colon)?;
                 let ann = self.parse_term(Prec(0))?;
+                let value = match self.try_match(TokenKind::Equals) {
+                    None => None,
+                    Some(_) => Some(self.parse_term(Prec(0))?),
+                };
 
-                fields.push(RecordTypeField { docs, label, ann });
+                Some(RecordTypeField::Explicit {
+                    docs,
+                    label,
+                    ann,
+                    value,
+                })
+            } else if docs.is_empty() && self.try_match(Symbol("..")).is_some() {
+                let record_ty = self.parse_term(Prec(0))?;
+
+                Some(RecordTypeField::Include { record_ty })
+            } else {
+                None
+            };
+
+            if let Some(field) = field {
+                fields.push(field);
 
                 if self.try_match(TokenKind::Semicolon).is_some() {
                     continue;
@@ -879,7 +1132,8 @@ where
     /// Parse the trailing part of a record introduction.
     ///
     /// ```text
-    /// record-intro        ::= "{" (record-intro-field ";")* record-intro-field? "}"
+    /// record-intro        ::= "{" (".." term(0) ";")? record-intro-fields
+    /// record-intro-fields ::= (record-intro-field ";")* record-intro-field? "}"
     /// record-intro-field  ::= IDENTIFIER
     ///                       | IDENTIFIER intro-param* (":" term(0))? "=" term(0)
     /// ```
@@ -887,9 +1141,51 @@ where
         &mut self,
         start_token: Token<'file>,
     ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
-        let mut fields = Vec::new();
+        self.expect_match(TokenKind::Open(DelimKind::Brace))?;
+
+        if self.try_match(Symbol("..")).is_some() {
+            let record = self.parse_term(Prec(0))?;
+            let (end_token, fields) = match self.try_match(TokenKind::Semicolon) {
+                Some(_) => self.parse_record_intro_fields()?,
+                None => (
+                    self.expect_match(TokenKind::Close(DelimKind::Brace))?,
+                    Vec::new(),
+                ),
+            };
+            let span = FileSpan::merge(start_token.span(), end_token.span());
+
+            Ok(Term::RecordUpdate(span, Box::new(record), fields))
+        } else {
+            let (end_token, fields) = self.parse_record_intro_fields()?;
+            let span = FileSpan::merge(start_token.span(), end_token.span());
+
+            Ok(Term::RecordIntro(span, fields))
+        }
+    }
 
+    /// Parse the trailing part of a record update.
+    ///
+    /// ```text
+    /// record-update ::= "{" record-intro-fields
+    /// ```
+    fn parse_record_update(
+        &mut self,
+        lhs: Term<'file>,
+        _start_token: Token<'file>,
+    ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
         self.expect_match(TokenKind::Open(DelimKind::Brace))?;
+        let (end_token, fields) = self.parse_record_intro_fields()?;
+        let span = FileSpan::merge(lhs.span(), end_token.span());
+
+        Ok(Term::RecordUpdate(span, Box::new(lhs), fields))
+    }
+
+    /// Parse the fields of a record introduction, up to and including the
+    /// closing brace.
+    fn parse_record_intro_fields(
+        &mut self,
+    ) -> Result<(Token<'file>, Vec<RecordIntroField<'file>>), Diagnostic<FileSpan>> {
+        let mut fields = Vec::new();
 
         while let Some(label) = self.try_identifier() {
             let params = self.parse_intro_params()?;
@@ -914,17 +1210,13 @@ where
             if self.try_match(TokenKind::Semicolon).is_some() {
                 continue;
             } else {
-                let end_token = self.expect_match(TokenKind::Close(DelimKind::Brace))?;
-                let span = FileSpan::merge(start_token.span(), end_token.span());
-
-                return Ok(Term::RecordIntro(span, fields));
+                break;
             }
         }
 
         let end_token = self.expect_match(TokenKind::Close(DelimKind::Brace))?;
-        let span = FileSpan::merge(start_token.span(), end_token.span());
 
-        Ok(Term::RecordIntro(span, fields))
+        Ok((end_token, fields))
     }
 
     /// Parse the trailing part of a let expression.
@@ -1025,22 +1317,73 @@ where
     /// Parse the trailing part of a universe.
     ///
     /// ```text
-    /// universe ::= ("^" INT_LITERAL)?
+    /// universe ::= ("^" level-atom)?
     /// ```
     fn parse_universe(
         &mut self,
         start_token: Token<'file>,
     ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
         if self.try_match(TokenKind::Caret).is_some() {
-            let level_token = self.expect_match(TokenKind::IntLiteral)?;
-            let span = FileSpan::merge(start_token.span(), level_token.span());
+            let level = self.parse_level_atom()?;
+            let span = FileSpan::merge(start_token.span(), level.span());
 
-            Ok(Term::Universe(span, Some(level_token.src)))
+            Ok(Term::Universe(span, Some(level)))
         } else {
             Ok(Term::Universe(start_token.span(), None))
         }
     }
 
+    /// Parse a universe level.
+    ///
+    /// ```text
+    /// level ::= "max" level-atom level-atom
+    ///         | level-atom ("+" INT_LITERAL)*
+    /// ```
+    fn parse_level(&mut self) -> Result<Level<'file>, Diagnostic<FileSpan>> {
+        let is_max = match self.peek() {
+            Some(token) => token.kind == TokenKind::Identifier && token.src.slice == "max",
+            None => false,
+        };
+
+        if is_max {
+            let start_token = self.advance().unwrap();
+            let lhs = self.parse_level_atom()?;
+            let rhs = self.parse_level_atom()?;
+            let span = FileSpan::merge(start_token.span(), rhs.span());
+
+            Ok(Level::Max(span, Box::new(lhs), Box::new(rhs)))
+        } else {
+            let mut level = self.parse_level_atom()?;
+            while self.try_match(Symbol("+")).is_some() {
+                let shift_token = self.expect_match(TokenKind::IntLiteral)?;
+                level = Level::Shift(Box::new(level), shift_token.src);
+            }
+
+            Ok(level)
+        }
+    }
+
+    /// Parse an atomic universe level.
+    ///
+    /// ```text
+    /// level-atom ::= INT_LITERAL
+    ///              | IDENTIFIER
+    ///              | "(" level ")"
+    /// ```
+    fn parse_level_atom(&mut self) -> Result<Level<'file>, Diagnostic<FileSpan>> {
+        if let Some(token) = self.try_match(TokenKind::IntLiteral) {
+            Ok(Level::Literal(token.src))
+        } else if let Some(start_token) = self.try_match(TokenKind::Open(DelimKind::Paren)) {
+            let level = self.parse_level()?;
+            let end_token = self.expect_match(TokenKind::Close(DelimKind::Paren))?;
+            let span = FileSpan::merge(start_token.span(), end_token.span());
+
+            Ok(Level::Parens(span, Box::new(level)))
+        } else {
+            Ok(Level::Var(self.expect_identifier()?))
+        }
+    }
+
     /// Parse the trailing part of a primitive.
     ///
     /// ```text
@@ -1085,6 +1428,44 @@ where
         Ok(Term::Ann(Box::new(lhs), Box::new(rhs)))
     }
 
+    /// Parse the trailing part of a tuple type. If the left hand side is an
+    /// annotated variable in parentheses, like `(x : A)`, then the component is
+    /// named, and can be referred to in the types of the later components.
+    ///
+    /// ```text
+    /// tuple-type ::= term(60 - 1)
+    /// ```
+    fn parse_tuple_ty(
+        &mut self,
+        lhs: Term<'file>,
+        _start_token: Token<'file>,
+    ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
+        let rhs = self.parse_term(Prec(60 - 1))?;
+        let span = FileSpan::merge(lhs.span(), rhs.span());
+
+        let lhs = match lhs {
+            Term::Parens(parens_span, term) => match *term {
+                Term::Ann(term, ann) => match *term {
+                    Term::Var(name) => (Some(name), *ann),
+                    term => {
+                        let term = Term::Ann(Box::new(term), ann);
+                        (None, Term::Parens(parens_span, Box::new(term)))
+                    },
+                },
+                term => (None, Term::Parens(parens_span, Box::new(term))),
+            },
+            lhs => (None, lhs),
+        };
+
+        let mut components = vec![lhs];
+        match rhs {
+            Term::TupleType(_, rhs_components) => components.extend(rhs_components),
+            rhs => components.push((None, rhs)),
+        }
+
+        Ok(Term::TupleType(span, components))
+    }
+
     /// Parse the trailing part of a function arrow.
     ///
     /// ```text
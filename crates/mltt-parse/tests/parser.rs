@@ -1,7 +1,7 @@
 use language_reporting::termcolor::{ColorChoice, StandardStream};
 use mltt_concrete::{
-    Arg, Definition, IntroParam, Item, LiteralKind, Pattern, RecordIntroField, RecordTypeField,
-    SpannedString, Term, TypeParam,
+    Arg, Declaration, Definition, IntroParam, Item, Level, LiteralKind, Pattern, RecordIntroField,
+    RecordTypeField, SpannedString, Term, TypeParam,
 };
 use mltt_parse::lexer::Lexer;
 use mltt_parse::parser::parse_term;
@@ -87,6 +87,7 @@ fn let_expr() {
         FileSpan::new(file_id, 0, 22),
         vec![Item::Definition(Definition {
             docs: Vec::new(),
+            partial: None,
             label: SpannedString::new(file_id, 4, "var"),
             params: Vec::new(),
             body_ty: None,
@@ -96,6 +97,38 @@ fn let_expr() {
     ),);
 }
 
+#[test]
+fn let_expr_auto_declaration() {
+    test_term!(
+        "let auto id : A -> A; id a = a; in id",
+        |file_id| Term::Let(
+            FileSpan::new(file_id, 0, 37),
+            vec![
+                Item::Declaration(Declaration {
+                    docs: Vec::new(),
+                    auto: Some(SpannedString::new(file_id, 4, "auto")),
+                    label: SpannedString::new(file_id, 9, "id"),
+                    body_ty: Term::FunArrowType(
+                        Box::new(Term::Var(SpannedString::new(file_id, 14, "A"))),
+                        Box::new(Term::Var(SpannedString::new(file_id, 19, "A"))),
+                    ),
+                }),
+                Item::Definition(Definition {
+                    docs: Vec::new(),
+                    partial: None,
+                    label: SpannedString::new(file_id, 22, "id"),
+                    params: vec![IntroParam::Explicit(Pattern::Var(SpannedString::new(
+                        file_id, 25, "a",
+                    )))],
+                    body_ty: None,
+                    body: Term::Var(SpannedString::new(file_id, 29, "a")),
+                }),
+            ],
+            Box::new(Term::Var(SpannedString::new(file_id, 35, "id"))),
+        ),
+    );
+}
+
 #[test]
 fn if_expr() {
     test_term!("if foo then bar else baz", |file_id| Term::If(
@@ -374,15 +407,17 @@ fn record_type() {
     test_term!("Record { x : Type; y : Type }", |file_id| Term::RecordType(
         FileSpan::new(file_id, 0, 29),
         vec![
-            RecordTypeField {
+            RecordTypeField::Explicit {
                 docs: Vec::new(),
                 label: SpannedString::new(file_id, 9, "x"),
                 ann: Term::Universe(FileSpan::new(file_id, 13, 17), None),
+                value: None,
             },
-            RecordTypeField {
+            RecordTypeField::Explicit {
                 docs: Vec::new(),
                 label: SpannedString::new(file_id, 19, "y"),
                 ann: Term::Universe(FileSpan::new(file_id, 23, 27), None),
+                value: None,
             },
         ]
     ));
@@ -395,21 +430,41 @@ fn record_type_trailing_semicolon() {
         |file_id| Term::RecordType(
             FileSpan::new(file_id, 0, 30),
             vec![
-                RecordTypeField {
+                RecordTypeField::Explicit {
                     docs: Vec::new(),
                     label: SpannedString::new(file_id, 9, "x"),
                     ann: Term::Universe(FileSpan::new(file_id, 13, 17), None),
+                    value: None,
                 },
-                RecordTypeField {
+                RecordTypeField::Explicit {
                     docs: Vec::new(),
                     label: SpannedString::new(file_id, 19, "y"),
                     ann: Term::Universe(FileSpan::new(file_id, 23, 27), None),
+                    value: None,
                 },
             ]
         ),
     );
 }
 
+#[test]
+fn record_type_manifest_include() {
+    test_term!("Record { ..R; x : Type = U8 }", |file_id| Term::RecordType(
+        FileSpan::new(file_id, 0, 29),
+        vec![
+            RecordTypeField::Include {
+                record_ty: Term::Var(SpannedString::new(file_id, 11, "R")),
+            },
+            RecordTypeField::Explicit {
+                docs: Vec::new(),
+                label: SpannedString::new(file_id, 14, "x"),
+                ann: Term::Universe(FileSpan::new(file_id, 18, 22), None),
+                value: Some(Term::Var(SpannedString::new(file_id, 25, "U8"))),
+            },
+        ]
+    ));
+}
+
 #[test]
 fn record_intro() {
     test_term!("record { x = x; y = y }", |file_id| Term::RecordIntro(
@@ -483,6 +538,94 @@ fn record_intro_trailing_semicolon() {
     });
 }
 
+#[test]
+fn record_intro_update() {
+    test_term!("record { ..r; x = y }", |file_id| Term::RecordUpdate(
+        FileSpan::new(file_id, 0, 21),
+        Box::new(Term::Var(SpannedString::new(file_id, 11, "r"))),
+        vec![RecordIntroField::Explicit {
+            label: SpannedString::new(file_id, 14, "x"),
+            params: Vec::new(),
+            body_ty: None,
+            body: Term::Var(SpannedString::new(file_id, 18, "y")),
+        }],
+    ));
+}
+
+#[test]
+fn record_update() {
+    test_term!("r with { x = y }", |file_id| Term::RecordUpdate(
+        FileSpan::new(file_id, 0, 16),
+        Box::new(Term::Var(SpannedString::new(file_id, 0, "r"))),
+        vec![RecordIntroField::Explicit {
+            label: SpannedString::new(file_id, 9, "x"),
+            params: Vec::new(),
+            body_ty: None,
+            body: Term::Var(SpannedString::new(file_id, 13, "y")),
+        }],
+    ));
+}
+
+#[test]
+fn tuple_ty() {
+    test_term!("A * B * C", |file_id| Term::TupleType(
+        FileSpan::new(file_id, 0, 9),
+        vec![
+            (None, Term::Var(SpannedString::new(file_id, 0, "A"))),
+            (None, Term::Var(SpannedString::new(file_id, 4, "B"))),
+            (None, Term::Var(SpannedString::new(file_id, 8, "C"))),
+        ],
+    ));
+}
+
+#[test]
+fn tuple_ty_dependent() {
+    test_term!("(x : A) * B x", |file_id| Term::TupleType(
+        FileSpan::new(file_id, 0, 13),
+        vec![
+            (
+                Some(SpannedString::new(file_id, 1, "x")),
+                Term::Var(SpannedString::new(file_id, 5, "A")),
+            ),
+            (
+                None,
+                Term::FunElim(
+                    Box::new(Term::Var(SpannedString::new(file_id, 10, "B"))),
+                    vec![Arg::Explicit(Term::Var(SpannedString::new(
+                        file_id, 12, "x"
+                    )))],
+                ),
+            ),
+        ],
+    ));
+}
+
+#[test]
+fn tuple_intro() {
+    test_term!("(x, y)", |file_id| Term::TupleIntro(
+        FileSpan::new(file_id, 0, 6),
+        vec![
+            Term::Var(SpannedString::new(file_id, 1, "x")),
+            Term::Var(SpannedString::new(file_id, 4, "y")),
+        ],
+    ));
+}
+
+#[test]
+fn fun_intro_tuple_pattern() {
+    test_term!("fun (x, y) => x", |file_id| Term::FunIntro(
+        FileSpan::new(file_id, 0, 15),
+        vec![IntroParam::Explicit(Pattern::TupleIntro(
+            FileSpan::new(file_id, 4, 10),
+            vec![
+                Pattern::Var(SpannedString::new(file_id, 5, "x")),
+                Pattern::Var(SpannedString::new(file_id, 8, "y")),
+            ],
+        ))],
+        Box::new(Term::Var(SpannedString::new(file_id, 14, "x"))),
+    ));
+}
+
 #[test]
 fn record_proj() {
     test_term!("foo.bar", |file_id| Term::RecordElim(
@@ -542,7 +685,7 @@ fn universe() {
 fn universe_level_0() {
     test_term!("Type^0", |file_id| Term::Universe(
         FileSpan::new(file_id, 0, 6),
-        Some(SpannedString::new(file_id, 5, "0")),
+        Some(Level::Literal(SpannedString::new(file_id, 5, "0"))),
     ));
 }
 
@@ -550,6 +693,69 @@ fn universe_level_0() {
 fn universe_level_23() {
     test_term!("Type^23", |file_id| Term::Universe(
         FileSpan::new(file_id, 0, 7),
-        Some(SpannedString::new(file_id, 5, "23")),
+        Some(Level::Literal(SpannedString::new(file_id, 5, "23"))),
+    ));
+}
+
+#[test]
+fn universe_level_var() {
+    test_term!("Type^l", |file_id| Term::Universe(
+        FileSpan::new(file_id, 0, 6),
+        Some(Level::Var(SpannedString::new(file_id, 5, "l"))),
     ));
 }
+
+#[test]
+fn universe_level_shift() {
+    test_term!("Type^(l + 1)", |file_id| Term::Universe(
+        FileSpan::new(file_id, 0, 12),
+        Some(Level::Parens(
+            FileSpan::new(file_id, 5, 12),
+            Box::new(Level::Shift(
+                Box::new(Level::Var(SpannedString::new(file_id, 6, "l"))),
+                SpannedString::new(file_id, 10, "1"),
+            )),
+        )),
+    ));
+}
+
+#[test]
+fn universe_level_max() {
+    test_term!("Type^(max l 1)", |file_id| Term::Universe(
+        FileSpan::new(file_id, 0, 14),
+        Some(Level::Parens(
+            FileSpan::new(file_id, 5, 14),
+            Box::new(Level::Max(
+                FileSpan::new(file_id, 6, 13),
+                Box::new(Level::Var(SpannedString::new(file_id, 10, "l"))),
+                Box::new(Level::Literal(SpannedString::new(file_id, 12, "1"))),
+            )),
+        )),
+    ));
+}
+
+#[test]
+fn case_data_intro_patterns() {
+    test_term!("case xs { nil => 0; cons x rest => 1 }", |file_id| {
+        Term::Case(
+            FileSpan::new(file_id, 0, 38),
+            Box::new(Term::Var(SpannedString::new(file_id, 5, "xs"))),
+            vec![
+                (
+                    Pattern::Var(SpannedString::new(file_id, 10, "nil")),
+                    Term::LiteralIntro(LiteralKind::Int, SpannedString::new(file_id, 17, "0")),
+                ),
+                (
+                    Pattern::DataIntro(
+                        SpannedString::new(file_id, 20, "cons"),
+                        vec![
+                            Pattern::Var(SpannedString::new(file_id, 25, "x")),
+                            Pattern::Var(SpannedString::new(file_id, 27, "rest")),
+                        ],
+                    ),
+                    Term::LiteralIntro(LiteralKind::Int, SpannedString::new(file_id, 35, "1")),
+                ),
+            ],
+        )
+    });
+}
@@ -0,0 +1,139 @@
+//! Benchmarks for elaborating and validating the sample modules.
+//!
+//! Run with `cargo bench -p mltt-test`, optionally passing a filter on the
+//! sample names, for example `cargo bench -p mltt-test -- records`.
+//!
+//! Each sample is checked with closure environments trimmed, and again with
+//! trimming disabled as a baseline. Along with the time taken, we report the
+//! peak memory used while checking, and the memory retained by the checked
+//! module and metavariables once checking has finished.
+
+use mltt_core::{meta, trim, validate};
+use mltt_parse::lexer::Lexer;
+use mltt_parse::parser;
+use mltt_span::{FileId, Files};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+const SAMPLES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../tests/samples");
+const ITERATIONS: u32 = 100;
+
+/// An allocator that keeps track of the number of bytes allocated.
+struct CountingAlloc;
+
+/// The number of bytes currently allocated.
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+/// The largest number of bytes allocated since the peak was last reset.
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Measurements taken while checking a sample.
+#[derive(Default)]
+struct Measurements {
+    /// The total time spent checking.
+    time: Duration,
+    /// The largest number of bytes used while checking.
+    peak_bytes: usize,
+    /// The number of bytes retained by the checked module and metavariables.
+    retained_bytes: usize,
+}
+
+fn main() {
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with('-'));
+
+    let mut paths = fs::read_dir(SAMPLES_DIR)
+        .unwrap_or_else(|error| panic!("{}", error))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "mltt"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    println!(
+        "{:<24} {:>10} {:>14} {:>12} {:>14}",
+        "sample", "mode", "time", "peak", "retained",
+    );
+
+    for path in paths {
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        if filter
+            .as_ref()
+            .map_or(false, |filter| !name.contains(filter.as_str()))
+        {
+            continue;
+        }
+
+        let src = fs::read_to_string(&path).unwrap_or_else(|error| panic!("{}", error));
+        let mut files = Files::new();
+        let file_id = files.add(name.clone(), src);
+
+        for &(mode, is_trimmed) in &[("trimmed", true), ("untrimmed", false)] {
+            let measurements = {
+                let _scope = trim::scope(is_trimmed);
+                measure(&name, &files, file_id)
+            };
+
+            let per_iteration = measurements.time / ITERATIONS;
+            println!(
+                "{:<24} {:>10} {:>8.3} ms/iter {:>9} KiB {:>11} KiB",
+                name,
+                mode,
+                per_iteration.as_secs() as f64 * 1e3
+                    + f64::from(per_iteration.subsec_nanos()) / 1e6,
+                measurements.peak_bytes / 1024,
+                measurements.retained_bytes / 1024,
+            );
+        }
+    }
+}
+
+/// Check a sample repeatedly, measuring the time and memory used.
+fn measure(name: &str, files: &Files, file_id: FileId) -> Measurements {
+    let mut measurements = Measurements::default();
+
+    for _ in 0..ITERATIONS {
+        let start_bytes = ALLOCATED.load(Ordering::SeqCst);
+        PEAK.store(start_bytes, Ordering::SeqCst);
+        let start = Instant::now();
+
+        let concrete_module = parser::parse_module(Lexer::new(&files[file_id]))
+            .unwrap_or_else(|diagnostic| panic!("{}: {:?}", name, diagnostic));
+        let context = mltt_elaborate::Context::default();
+        let mut metas = meta::Env::new();
+        let module = mltt_elaborate::check_module(&context, &mut metas, &concrete_module)
+            .unwrap_or_else(|diagnostic| panic!("{}: {:?}", name, diagnostic));
+        validate::check_module(&context.validation_context(), &meta::Env::new(), &module)
+            .unwrap_or_else(|error| panic!("{}: {}", name, error));
+
+        measurements.time += start.elapsed();
+        drop((concrete_module, context));
+        let end_bytes = ALLOCATED.load(Ordering::SeqCst);
+        measurements.peak_bytes = usize::max(
+            measurements.peak_bytes,
+            PEAK.load(Ordering::SeqCst) - start_bytes,
+        );
+        measurements.retained_bytes = end_bytes.saturating_sub(start_bytes);
+        drop((module, metas));
+    }
+
+    measurements
+}
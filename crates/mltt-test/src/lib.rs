@@ -18,9 +18,166 @@ mod samples {
     test!(combinators, "combinators");
     test!(connectives, "connectives");
     test!(cumulativity, "cumulativity");
+    test!(data, "data");
     test!(empty, "empty");
+    test!(equality, "equality");
     test!(primitives, "primitives");
     test!(records, "records");
+    test!(universe_polymorphism, "universe-polymorphism");
+}
+
+mod incremental {
+    use mltt_elaborate::incremental::Stats;
+
+    const MODULE: &str = r"
+Unit : Type;
+Unit = Record {};
+
+unit : Unit;
+unit = record {};
+
+Id : Type -> Type;
+Id A = A;
+
+test-id : Id Unit;
+test-id = unit;
+
+other : Unit;
+other = unit;
+";
+
+    #[test]
+    fn unchanged() {
+        let stats = crate::support::run_incremental_edit(MODULE, MODULE);
+
+        assert_eq!(
+            stats,
+            Stats {
+                checked: 0,
+                reused: 10
+            }
+        );
+    }
+
+    #[test]
+    fn edit_rechecks_dependents() {
+        let edited = MODULE.replace("Id A = A;", "Id B = B;");
+        let stats = crate::support::run_incremental_edit(MODULE, &edited);
+
+        // `Id`, along with the declaration and definition of `test-id`
+        assert_eq!(
+            stats,
+            Stats {
+                checked: 3,
+                reused: 7
+            }
+        );
+    }
+
+    #[test]
+    fn edit_without_dependents() {
+        let edited = MODULE.replace("other = unit;", "other =\n    unit;");
+        let stats = crate::support::run_incremental_edit(MODULE, &edited);
+
+        assert_eq!(
+            stats,
+            Stats {
+                checked: 1,
+                reused: 9
+            }
+        );
+    }
+
+    #[test]
+    fn insert_reuses_following_items() {
+        let edited = MODULE.replace("test-id :", "unit2 : Unit;\nunit2 = unit;\n\ntest-id :");
+        let stats = crate::support::run_incremental_edit(MODULE, &edited);
+
+        // Only the new items
+        assert_eq!(
+            stats,
+            Stats {
+                checked: 2,
+                reused: 10
+            }
+        );
+    }
+
+    #[test]
+    fn insert_unrelated_definition_at_top() {
+        let edited = format!("Bool : Type;\nBool = Record {{}};\n{}", MODULE);
+        let stats = crate::support::run_incremental_edit(MODULE, &edited);
+
+        // Only the new items
+        assert_eq!(
+            stats,
+            Stats {
+                checked: 2,
+                reused: 10
+            }
+        );
+    }
+
+    #[test]
+    fn remove_unrelated_definition() {
+        let before = MODULE.replace(
+            "unit : Unit;",
+            "Bool : Type;\nBool = Record {};\n\nunit : Unit;",
+        );
+        let stats = crate::support::run_incremental_edit(&before, MODULE);
+
+        assert_eq!(
+            stats,
+            Stats {
+                checked: 0,
+                reused: 10
+            }
+        );
+    }
+
+    mod samples {
+        macro_rules! test {
+            ($test_name:ident, $file_name:literal) => {
+                #[test]
+                fn $test_name() {
+                    $crate::support::run_incremental_sample($file_name);
+                }
+            };
+        }
+
+        test!(categories, "categories");
+        test!(combinators, "combinators");
+        test!(connectives, "connectives");
+        test!(cumulativity, "cumulativity");
+        test!(data, "data");
+        test!(empty, "empty");
+        test!(equality, "equality");
+        test!(primitives, "primitives");
+        test!(records, "records");
+        test!(universe_polymorphism, "universe-polymorphism");
+    }
+}
+
+mod serialize {
+    macro_rules! test {
+        ($test_name:ident, $file_name:literal) => {
+            #[test]
+            fn $test_name() {
+                $crate::support::run_serialize_sample($file_name);
+            }
+        };
+    }
+
+    test!(categories, "categories");
+    test!(combinators, "combinators");
+    test!(connectives, "connectives");
+    test!(cumulativity, "cumulativity");
+    test!(data, "data");
+    test!(empty, "empty");
+    test!(equality, "equality");
+    test!(primitives, "primitives");
+    test!(records, "records");
+    test!(universe_polymorphism, "universe-polymorphism");
 }
 
 mod elaborate {
@@ -34,6 +191,10 @@ mod elaborate {
             };
         }
 
+        mod eq_intro {
+            test!(not_equal, "eq-intro/not-equal");
+        }
+
         mod literal_intro {
             mod int {
                 mod u8 {
@@ -49,9 +210,15 @@ mod elaborate {
         }
 
         mod record_intro {
+            test!(manifest_mismatch, "record-intro/manifest-mismatch");
             test!(superfluous_field, "record-intro/superfluous-field");
             test!(unexpected_field, "record-intro/unexpected-field");
         }
+
+        mod tuple_intro {
+            test!(not_enough_elements, "tuple-intro/not-enough-elements");
+            test!(too_many_elements, "tuple-intro/too-many-elements");
+        }
     }
 
     mod check_pass {
@@ -73,12 +240,24 @@ mod elaborate {
             test!(default, "case/default");
             test!(overlapping, "case/overlapping");
             test!(simple, "case/simple");
+            test!(tuple, "case/tuple");
+        }
+
+        mod eq_intro {
+            test!(refl, "eq-intro/refl");
         }
 
         mod fun_intro {
             test!(explicit, "fun-intro/explicit");
             test!(implicit, "fun-intro/implicit");
+            test!(implicit_inserted, "fun-intro/implicit-inserted");
+            test!(implicit_inserted_var, "fun-intro/implicit-inserted-var");
             test!(instance, "fun-intro/instance");
+            test!(tuple_pattern, "fun-intro/tuple-pattern");
+        }
+
+        mod let_ {
+            test!(postponed, "let/postponed");
         }
 
         mod literal_intro {
@@ -174,11 +353,25 @@ mod elaborate {
             }
         }
 
+        mod record_elim {
+            test!(manifest, "record-elim/manifest");
+        }
+
         mod record_intro {
             test!(dependent_pair, "record-intro/dependent-pair");
+            test!(manifest_omitted, "record-intro/manifest-omitted");
             test!(singleton, "record-intro/singleton");
             test!(singleton1, "record-intro/singleton1");
         }
+
+        mod record_update {
+            test!(extend, "record-update/extend");
+        }
+
+        mod tuple_intro {
+            test!(dependent, "tuple-intro/dependent");
+            test!(nested, "tuple-intro/nested");
+        }
     }
 
     mod synth_fail {
@@ -191,6 +384,10 @@ mod elaborate {
             };
         }
 
+        mod data {
+            test!(non_positive, "data/non-positive");
+        }
+
         mod fun_intro {
             test!(ambiguous, "fun-intro/ambiguous");
         }
@@ -201,6 +398,7 @@ mod elaborate {
 
         mod let_ {
             test!(already_defined, "let/already-defined");
+            test!(non_terminating, "let/non-terminating");
             test!(not_yet_declared, "let/not-yet-declared");
         }
 
@@ -227,6 +425,14 @@ mod elaborate {
             test!(ambiguous, "record-intro/ambiguous");
         }
 
+        mod record_update {
+            test!(
+                dependent_field_mismatch,
+                "record-update/dependent-field-mismatch"
+            );
+            test!(field_not_found, "record-update/field-not-found");
+        }
+
         mod universe {
             test!(overflow, "universe/overflow");
         }
@@ -248,6 +454,15 @@ mod elaborate {
 
         test!(parens, "parens");
 
+        mod case {
+            test!(literal, "case/literal");
+        }
+
+        mod data {
+            test!(list_length, "data/list-length");
+            test!(nat_to_u32, "data/nat-to-u32");
+        }
+
         mod fun_elim {
             test!(explicit, "fun-elim/explicit");
             test!(implicit, "fun-elim/implicit");
@@ -269,11 +484,19 @@ mod elaborate {
             test!(type1_term, "fun-type-arrow/type1-term");
         }
 
+        mod if_ {
+            test!(string, "if/string");
+        }
+
         mod let_ {
+            test!(auto_declaration, "let/auto-declaration");
             test!(complicated, "let/complicated");
             test!(definition, "let/definition");
+            test!(definition_if, "let/definition-if");
             test!(declaration_definition, "let/declaration-definition");
             test!(forward_declarations, "let/forward-declarations");
+            test!(recursive_mutual, "let/recursive-mutual");
+            test!(recursive_partial, "let/recursive-partial");
         }
 
         #[rustfmt::skip]
@@ -322,10 +545,26 @@ mod elaborate {
         mod record_type {
             test!(dependent_pair, "record-type/dependent-pair");
             test!(empty, "record-type/empty");
+            test!(include, "record-type/include");
+            test!(manifest, "record-type/manifest");
             test!(singleton, "record-type/singleton");
             test!(singleton1, "record-type/singleton1");
         }
 
+        mod record_update {
+            test!(dependent_field, "record-update/dependent-field");
+            test!(field, "record-update/field");
+        }
+
+        mod tuple_intro {
+            test!(simple, "tuple-intro/simple");
+        }
+
+        mod tuple_type {
+            test!(dependent, "tuple-type/dependent");
+            test!(simple, "tuple-type/simple");
+        }
+
         mod universe {
             test!(type_, "universe/type");
             test!(type0, "universe/type0");
@@ -1,7 +1,7 @@
 use language_reporting::termcolor::{ColorChoice, StandardStream};
 use language_reporting::Diagnostic;
-use mltt_core::{domain, nbe, syntax, validate};
-use mltt_elaborate::MetaInsertion;
+use mltt_core::{domain, nbe, serialize, syntax, validate};
+use mltt_elaborate::{incremental, MetaInsertion};
 use mltt_parse::lexer::Lexer;
 use mltt_parse::parser;
 use mltt_span::{File, FileId, FileSpan, Files};
@@ -52,6 +52,16 @@ fn synth_universe(
     context.eval_term(metas, concrete_ty.span(), &ty)
 }
 
+/// Substitute the solutions to the metavariables in an elaborated term, so
+/// that it can be validated.
+fn zonk_term(
+    context: &mltt_elaborate::Context,
+    metas: &mltt_core::meta::Env,
+    term: &Rc<syntax::Term>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    mltt_elaborate::zonk_term(context.prims(), metas, context.budget(), term)
+}
+
 fn check_term(
     context: &mltt_elaborate::Context,
     metas: &mut mltt_core::meta::Env,
@@ -63,6 +73,7 @@ fn check_term(
     // FIXME: check lexer for errors
 
     let term = mltt_elaborate::check_term(context, metas, &concrete_term, &expected_ty)?;
+    let term = zonk_term(context, metas, &term)?;
     validate::check_term(&context.validation_context(), &metas, &term, &expected_ty)
         .unwrap_or_else(|error| panic!("{}", error));
 
@@ -81,20 +92,30 @@ fn synth_term(
 
     let (term, term_ty) =
         mltt_elaborate::synth_term(MetaInsertion::Yes, context, metas, &concrete_term)?;
+    let term = zonk_term(context, metas, &term)?;
     validate::synth_term(&context.validation_context(), &metas, &term)
         .unwrap_or_else(|error| panic!("{}", error));
 
     // Verify that we got the expected type (sans subtyping)
     let prims = context.prims();
     let size = context.values().size();
-    if !nbe::check_ty(&prims, &metas, size, false, &term_ty, &expected_ty)
-        .unwrap_or_else(|error| panic!("{}", error))
+    if !nbe::check_ty(
+        &prims,
+        &metas,
+        context.budget(),
+        size,
+        false,
+        &term_ty,
+        &expected_ty,
+    )
+    .unwrap_or_else(|error| panic!("{}", error))
     {
         panic!("unequal types");
     }
 
     // Ensure that the checking also works
     let term2 = mltt_elaborate::check_term(context, metas, &concrete_term, expected_ty)?;
+    let term2 = zonk_term(context, metas, &term2)?;
     validate::check_term(&context.validation_context(), &metas, &term2, &expected_ty)
         .unwrap_or_else(|error| panic!("{}", error));
 
@@ -117,8 +138,148 @@ pub fn run_sample(name: &str) {
 
     let module = mltt_elaborate::check_module(&context, &mut metas, &concrete_module)
         .unwrap_or_else(emit_diagnostic(&writer, &files));
+    // Zonking should have removed all metavariables from the module
+    let metas = mltt_core::meta::Env::new();
     validate::check_module(&context.validation_context(), &metas, &module)
         .unwrap_or_else(|error| panic!("{}", error));
+    check_recursive_nbe(&context, &metas, &module);
+}
+
+/// Check that the stack-safe implementation of normalization agrees with the
+/// recursive reference implementation on the items of a module.
+fn check_recursive_nbe(
+    context: &mltt_elaborate::Context,
+    metas: &mltt_core::meta::Env,
+    module: &syntax::Module,
+) {
+    let prims = context.prims();
+    let budget = context.budget();
+    let mut values = context.values().clone();
+
+    for item in &module.items {
+        let (label, term) = match item {
+            syntax::Item::Declaration(_, label, term)
+            | syntax::Item::Definition(_, label, term) => (label, term),
+        };
+
+        let value = nbe::eval_term(prims, metas, budget, &values, term)
+            .unwrap_or_else(|error| panic!("{}", error));
+        let recursive_value = nbe::recursive::eval_term(prims, metas, budget, &values, term)
+            .unwrap_or_else(|error| panic!("{}", error));
+
+        for &unfold in &[nbe::Unfold::None, nbe::Unfold::All] {
+            let size = values.size();
+            let term = nbe::read_back_value(prims, metas, budget, size, unfold, &value)
+                .unwrap_or_else(|error| panic!("{}", error));
+            let recursive_term = nbe::recursive::read_back_value(
+                prims,
+                metas,
+                budget,
+                size,
+                unfold,
+                &recursive_value,
+            )
+            .unwrap_or_else(|error| panic!("{}", error));
+
+            // Use alpha equivalence so that `NaN` literals compare equal
+            assert!(
+                term.alpha_eq(&recursive_term),
+                "normal forms of `{}` differ with {:?}: `{:?}` and `{:?}`",
+                label,
+                unfold,
+                term,
+                recursive_term,
+            );
+        }
+
+        if let syntax::Item::Definition(_, _, _) = item {
+            values.add_entry(value);
+        }
+    }
+}
+
+/// Serialize an elaborated sample module, checking that it is loaded back
+/// unchanged from each of the supported formats.
+pub fn run_serialize_sample(name: &str) {
+    let _ = pretty_env_logger::try_init();
+    let writer = StandardStream::stdout(ColorChoice::Always);
+
+    let (mut files, mut metas, context) = setup();
+
+    let module_path = format!("{}/samples/{}.mltt", TESTS_DIR, name);
+    let module_file_id = load_file(&mut files, module_path);
+
+    let lexer = Lexer::new(&files[module_file_id]);
+    let concrete_module =
+        parser::parse_module(lexer).unwrap_or_else(emit_diagnostic(&writer, &files));
+
+    let module = mltt_elaborate::check_module(&context, &mut metas, &concrete_module)
+        .unwrap_or_else(emit_diagnostic(&writer, &files));
+    for &format in &[serialize::Format::Binary, serialize::Format::Json] {
+        let bytes = serialize::encode_module(format, &module);
+        let loaded_module = serialize::load_module(&context.validation_context(), format, &bytes)
+            .unwrap_or_else(|error| panic!("{}", error));
+        assert_eq!(loaded_module, module);
+    }
+}
+
+/// Check a sample module incrementally, comparing the result with checking
+/// the module from scratch. Checking it again should reuse all of its items.
+pub fn run_incremental_sample(name: &str) {
+    let _ = pretty_env_logger::try_init();
+    let writer = StandardStream::stdout(ColorChoice::Always);
+
+    let (mut files, mut metas, context) = setup();
+    let mut cache = incremental::Cache::new();
+
+    let module_path = format!("{}/samples/{}.mltt", TESTS_DIR, name);
+    let module_file_id = load_file(&mut files, module_path);
+    let file = &files[module_file_id];
+
+    let lexer = Lexer::new(file);
+    let concrete_module =
+        parser::parse_module(lexer).unwrap_or_else(emit_diagnostic(&writer, &files));
+
+    let expected_module = mltt_elaborate::check_module(&context, &mut metas, &concrete_module)
+        .unwrap_or_else(emit_diagnostic(&writer, &files));
+    for _ in 0..2 {
+        let module = cache
+            .check_module(&context, &mut metas, file, &concrete_module)
+            .unwrap_or_else(emit_diagnostic(&writer, &files));
+        assert_eq!(module, expected_module);
+    }
+
+    assert_eq!(cache.stats().checked, 0);
+}
+
+/// Check a module incrementally before and after an edit, comparing the
+/// results with checking each version of the module from scratch.
+///
+/// Returns the statistics from checking the edited module.
+pub fn run_incremental_edit(before: &str, after: &str) -> incremental::Stats {
+    let _ = pretty_env_logger::try_init();
+    let writer = StandardStream::stdout(ColorChoice::Always);
+
+    let (mut files, mut metas, context) = setup();
+    let mut cache = incremental::Cache::new();
+
+    for src in &[before, after] {
+        let file_id = files.add("incremental", *src);
+        let file = &files[file_id];
+
+        let lexer = Lexer::new(file);
+        let concrete_module =
+            parser::parse_module(lexer).unwrap_or_else(emit_diagnostic(&writer, &files));
+
+        let module = cache
+            .check_module(&context, &mut metas, file, &concrete_module)
+            .unwrap_or_else(emit_diagnostic(&writer, &files));
+        let expected_module = mltt_elaborate::check_module(&context, &mut metas, &concrete_module)
+            .unwrap_or_else(emit_diagnostic(&writer, &files));
+        assert_eq!(module, expected_module);
+    }
+
+    cache.stats()
 }
 
 pub fn run_elaborate_check_pass(name: &str) {